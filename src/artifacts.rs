@@ -0,0 +1,114 @@
+use crate::{
+    days::{DayType, PartType, YearType},
+    int_code::ComputerError,
+};
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+thread_local! {
+    static RECORDED: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/**
+ * Lets a day keep some of its intermediate state around. If the part fails
+ * in batch mode, everything recorded ends up in the failure artifacts.
+ * Recording the same name again replaces the older entry.
+ */
+pub fn record(name: &str, contents: impl Display) {
+    let contents = contents.to_string();
+    RECORDED.with(|recorded| {
+        let mut recorded = recorded.borrow_mut();
+        match recorded.iter_mut().find(|(known, _)| known == name) {
+            Some((_, old)) => *old = contents,
+            None => recorded.push((name.to_owned(), contents)),
+        }
+    });
+}
+
+pub fn take_recorded() -> Vec<(String, String)> {
+    RECORDED.with(|recorded| recorded.take())
+}
+
+/**
+ * FNV-1a, so the hash stays the same across builds and toolchains.
+ */
+pub fn input_hash(input: &str) -> String {
+    let hash = input.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+/**
+ * Writes everything known about a failed part into its own directory
+ * below `base`, laid out by year like the data, and returns its path.
+ */
+pub fn write_failure(
+    base: &Path,
+    year: YearType,
+    day: DayType,
+    part: PartType,
+    input: &str,
+    error: &anyhow::Error,
+) -> io::Result<PathBuf> {
+    let dir = base
+        .join(year.to_string())
+        .join(format!("day{day:02}-part{part}"));
+    fs::create_dir_all(&dir)?;
+
+    fs::write(dir.join("input_hash.txt"), input_hash(input) + "\n")?;
+
+    let mut chain = fs::File::create(dir.join("error.txt"))?;
+    for (level, cause) in error.chain().enumerate() {
+        writeln!(chain, "{level}: {cause}")?;
+    }
+
+    let crash = error
+        .chain()
+        .find_map(|cause| match cause.downcast_ref::<ComputerError>() {
+            Some(ComputerError::Crashed(crash)) => Some(crash),
+            _ => None,
+        });
+    if let Some(crash) = crash {
+        fs::write(dir.join("crash.txt"), crash.report() + "\n")?;
+    }
+
+    for (name, contents) in take_recorded() {
+        fs::write(dir.join(format!("{name}.txt")), contents)?;
+    }
+
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn failure_directory() -> io::Result<()> {
+        let base = std::env::temp_dir().join(format!("artifacts-{}", input_hash("test")));
+        record("state", 1);
+        record("state", "final");
+        let error = anyhow::anyhow!("inner").context("outer");
+        let dir = write_failure(&base, 2019, 3, 2, "input", &error)?;
+
+        assert_eq!(dir, base.join("2019").join("day03-part2"));
+        assert_eq!(
+            fs::read_to_string(dir.join("error.txt"))?,
+            "0: outer\n1: inner\n"
+        );
+        assert_eq!(fs::read_to_string(dir.join("state.txt"))?, "final");
+        assert_eq!(
+            fs::read_to_string(dir.join("input_hash.txt"))?,
+            input_hash("input") + "\n"
+        );
+        assert!(take_recorded().is_empty());
+
+        fs::remove_dir_all(base)
+    }
+}
@@ -0,0 +1,57 @@
+#[cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+#[derive(Debug, thiserror::Error)]
+pub enum ClipboardError {
+    #[cfg_attr(feature = "clipboard", allow(dead_code))]
+    #[error("Clipboard support is not compiled in, build with --features clipboard")]
+    Disabled,
+
+    #[error("No clipboard tool found (tried {0})")]
+    NoTool(String),
+
+    #[error("The clipboard is empty")]
+    Empty,
+}
+
+/**
+ * The external programs asked for the clipboard content, the first one that works wins.
+ */
+#[cfg(feature = "clipboard")]
+const TOOLS: [(&str, &[&str]); 5] = [
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("pbpaste", &[]),
+    ("powershell", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+/**
+ * Reads the clipboard as text, with line endings normalized the way
+ * the input files have them.
+ */
+#[cfg(feature = "clipboard")]
+pub fn read() -> Result<String, ClipboardError> {
+    use itertools::Itertools;
+    use std::process::Command;
+
+    let content = TOOLS
+        .iter()
+        .find_map(|(tool, args)| {
+            let output = Command::new(tool).args(*args).output().ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n"))
+        })
+        .ok_or_else(|| ClipboardError::NoTool(TOOLS.iter().map(|(tool, _)| tool).join(", ")))?;
+
+    if content.trim().is_empty() {
+        Err(ClipboardError::Empty)
+    } else {
+        Ok(content)
+    }
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn read() -> Result<String, ClipboardError> {
+    Err(ClipboardError::Disabled)
+}
@@ -1,12 +1,11 @@
 #![allow(dead_code)]
-use super::pos2::Pos2;
-use num_traits::{Num, NumAssignOps};
+use super::{pos2::Pos2, scalar::Scalar};
 use std::fmt::Display;
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Area<T>
 where
-    T: Num,
+    T: Scalar,
 {
     lower_left: Pos2<T>,
     upper_right: Pos2<T>,
@@ -14,7 +13,7 @@ where
 
 impl<T> Area<T>
 where
-    T: Num + Ord + Copy,
+    T: Scalar,
 {
     pub fn new(p1: Pos2<T>, p2: Pos2<T>) -> Area<T> {
         Area {
@@ -29,12 +28,7 @@ where
             upper_right: Pos2::new(x1.max(x2), y1.max(y2)),
         }
     }
-}
 
-impl<T> Area<T>
-where
-    T: Num + Ord + Copy,
-{
     pub fn extend(&self, pos: Pos2<T>) -> Area<T> {
         if self.contains(pos) {
             return *self;
@@ -86,7 +80,7 @@ where
 
 impl<'a, T> Area<T>
 where
-    T: Num + Ord + 'a + Copy,
+    T: Scalar + 'a,
 {
     pub fn from_iterator<I>(mut iter: I) -> Option<Self>
     where
@@ -103,7 +97,7 @@ where
 
 impl<T> Area<T>
 where
-    T: Num + Copy,
+    T: Scalar,
 {
     pub fn width(&self) -> T {
         self.upper_right.x() - self.lower_left.x() + T::one()
@@ -113,12 +107,7 @@ where
     pub fn height(&self) -> T {
         self.upper_right.y() - self.lower_left.y() + T::one()
     }
-}
 
-impl<T> Area<T>
-where
-    T: Num + Copy,
-{
     #[allow(dead_code)]
     pub fn area(&self) -> T {
         self.width() * self.height()
@@ -127,7 +116,7 @@ where
 
 impl<T> Display for Area<T>
 where
-    T: Num + Display,
+    T: Scalar + Display,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[{}-{}]", self.lower_left, self.upper_right)
@@ -136,7 +125,7 @@ where
 
 impl<T> Area<T>
 where
-    T: Num + Copy,
+    T: Scalar,
 {
     pub fn cells(&self, ascending: bool) -> CellIterator<'_, T> {
         CellIterator::new(self, ascending)
@@ -150,7 +139,7 @@ where
 #[derive(Debug)]
 pub struct RowIterator<'a, T>
 where
-    T: Num + Copy,
+    T: Scalar,
 {
     area: &'a Area<T>,
     row: T,
@@ -159,7 +148,7 @@ where
 
 impl<'a, T> RowIterator<'a, T>
 where
-    T: Num + Copy,
+    T: Scalar,
 {
     fn new(area: &'a Area<T>, ascending: bool) -> RowIterator<'a, T> {
         RowIterator {
@@ -176,7 +165,7 @@ where
 
 impl<'a, T> Iterator for RowIterator<'a, T>
 where
-    T: Num + Ord + NumAssignOps + Copy,
+    T: Scalar,
 {
     type Item = Row<'a, T>;
 
@@ -203,7 +192,7 @@ where
 #[derive(Debug)]
 pub struct Row<'a, T>
 where
-    T: Num,
+    T: Scalar,
 {
     area: &'a Area<T>,
     row: T,
@@ -211,7 +200,7 @@ where
 
 impl<'a, T> Row<'a, T>
 where
-    T: Num + Copy,
+    T: Scalar,
 {
     pub fn cols(&self, ascending: bool) -> ColIterator<'_, T> {
         ColIterator {
@@ -230,7 +219,7 @@ where
 #[derive(Debug)]
 pub struct ColIterator<'a, T>
 where
-    T: Num,
+    T: Scalar,
 {
     area: &'a Area<T>,
     row: T,
@@ -240,7 +229,7 @@ where
 
 impl<'a, T> Iterator for ColIterator<'a, T>
 where
-    T: Num + Ord + NumAssignOps + Copy,
+    T: Scalar,
 {
     type Item = Pos2<T>;
     fn next(&mut self) -> Option<Self::Item> {
@@ -263,7 +252,7 @@ where
 #[derive(Debug)]
 pub struct CellIterator<'a, T>
 where
-    T: Num,
+    T: Scalar,
 {
     area: &'a Area<T>,
     row: T,
@@ -273,7 +262,7 @@ where
 
 impl<'a, T> CellIterator<'a, T>
 where
-    T: Num + Copy,
+    T: Scalar,
 {
     pub fn new(area: &'a Area<T>, ascending: bool) -> CellIterator<'a, T> {
         let (col, row) = if ascending {
@@ -292,7 +281,7 @@ where
 
 impl<'a, T> Iterator for CellIterator<'a, T>
 where
-    T: Num + Ord + NumAssignOps + Copy,
+    T: Scalar,
 {
     type Item = Pos2<T>;
 
@@ -0,0 +1,38 @@
+use rustc_hash::FxHasher;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasherDefault,
+};
+
+/**
+ * FxHash is much faster than SipHash for the small keys used here, like coordinates.
+ * It is not resistant against collision attacks, which does not matter for puzzles,
+ * and it is not randomly seeded, so iteration orders are the same on every run.
+ */
+pub type FastBuildHasher = BuildHasherDefault<FxHasher>;
+
+pub type FastMap<K, V> = HashMap<K, V, FastBuildHasher>;
+
+pub type FastSet<T> = HashSet<T, FastBuildHasher>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn deterministic_order() {
+        let build = || {
+            (0..100)
+                .map(|n| (n * 7919 % 1000, n))
+                .collect::<FastMap<_, _>>()
+        };
+        assert_eq!(
+            build().into_iter().collect_vec(),
+            build().into_iter().collect_vec()
+        );
+
+        let set: FastSet<_> = (0..10).collect();
+        assert!(set.contains(&3));
+    }
+}
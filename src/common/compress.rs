@@ -0,0 +1,38 @@
+#![allow(dead_code)]
+use itertools::Itertools;
+use std::{collections::HashMap, hash::Hash};
+
+/**
+ * Maps sparse coordinates onto dense indices.
+ * Returns the mapping from coordinate to index and the sorted list of
+ * distinct coordinates, which doubles as the reverse mapping.
+ */
+pub fn compress_coords<T, I>(coords: I) -> (HashMap<T, usize>, Vec<T>)
+where
+    T: Ord + Hash + Copy,
+    I: IntoIterator<Item = T>,
+{
+    let reverse = coords.into_iter().sorted().dedup().collect_vec();
+    let mapping = reverse
+        .iter()
+        .enumerate()
+        .map(|(idx, coord)| (*coord, idx))
+        .collect();
+    (mapping, reverse)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compress() {
+        let (mapping, reverse) = compress_coords([1_000_000_i64, -5, 17, -5, 1_000_000]);
+
+        assert_eq!(reverse, vec![-5, 17, 1_000_000]);
+        assert_eq!(mapping.len(), 3);
+        assert_eq!(mapping[&-5], 0);
+        assert_eq!(mapping[&17], 1);
+        assert_eq!(mapping[&1_000_000], 2);
+    }
+}
@@ -0,0 +1,137 @@
+#![allow(dead_code)]
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp<'a, T> {
+    Equal(&'a T),
+    Delete(&'a T),
+    Insert(&'a T),
+}
+
+fn distance_table<T: PartialEq>(left: &[T], right: &[T]) -> Vec<Vec<usize>> {
+    let mut table = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (l, row) in table.iter_mut().enumerate() {
+        row[0] = l;
+    }
+    table[0] = (0..=right.len()).collect();
+    for l in 1..=left.len() {
+        for r in 1..=right.len() {
+            let replace = if left[l - 1] == right[r - 1] { 0 } else { 1 };
+            table[l][r] = (table[l - 1][r - 1] + replace)
+                .min(table[l - 1][r] + 1)
+                .min(table[l][r - 1] + 1);
+        }
+    }
+    table
+}
+
+/**
+ * The Levenshtein distance between both sequences.
+ */
+pub fn edit_distance<T: PartialEq>(left: &[T], right: &[T]) -> usize {
+    distance_table(left, right)[left.len()][right.len()]
+}
+
+/**
+ * Aligns both sequences with as few edits as possible.
+ * A replaced element shows up as a deletion followed by an insertion.
+ */
+pub fn diff<'a, T: PartialEq>(left: &'a [T], right: &'a [T]) -> Vec<DiffOp<'a, T>> {
+    let table = distance_table(left, right);
+    let mut ops = vec![];
+    let (mut l, mut r) = (left.len(), right.len());
+    while l > 0 || r > 0 {
+        if l > 0 && r > 0 && left[l - 1] == right[r - 1] && table[l][r] == table[l - 1][r - 1] {
+            ops.push(DiffOp::Equal(&left[l - 1]));
+            l -= 1;
+            r -= 1;
+        } else if r > 0 && (l == 0 || table[l][r] == table[l][r - 1] + 1) {
+            ops.push(DiffOp::Insert(&right[r - 1]));
+            r -= 1;
+        } else if l > 0 && r > 0 && table[l][r] == table[l - 1][r - 1] + 1 {
+            ops.push(DiffOp::Insert(&right[r - 1]));
+            ops.push(DiffOp::Delete(&left[l - 1]));
+            l -= 1;
+            r -= 1;
+        } else {
+            ops.push(DiffOp::Delete(&left[l - 1]));
+            l -= 1;
+        }
+    }
+    ops.reverse();
+    ops
+}
+
+/**
+ * Renders a diff line by line, prefixed like a unified diff.
+ * With `context` set, runs of equal lines longer than that are collapsed.
+ */
+pub fn render_diff<T: PartialEq + Display>(
+    left: &[T],
+    right: &[T],
+    context: Option<usize>,
+) -> String {
+    let ops = diff(left, right);
+    let is_change = |idx: usize| !matches!(ops[idx], DiffOp::Equal(_));
+    let mut lines = vec![];
+    let mut skipped = 0;
+    for (idx, op) in ops.iter().enumerate() {
+        let visible = match context {
+            None => true,
+            Some(context) => {
+                let from = idx.saturating_sub(context);
+                let to = (idx + context).min(ops.len() - 1);
+                (from..=to).any(is_change)
+            }
+        };
+        if !visible {
+            skipped += 1;
+            continue;
+        }
+        if skipped > 0 {
+            lines.push(format!("@ {skipped} equal lines"));
+            skipped = 0;
+        }
+        lines.push(match op {
+            DiffOp::Equal(item) => format!("  {item}"),
+            DiffOp::Delete(item) => format!("- {item}"),
+            DiffOp::Insert(item) => format!("+ {item}"),
+        });
+    }
+    if skipped > 0 {
+        lines.push(format!("@ {skipped} equal lines"));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distance_and_diff() {
+        let left = "kitten".chars().collect::<Vec<_>>();
+        let right = "sitting".chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&left, &right), 3);
+        assert_eq!(edit_distance(&left, &left), 0);
+        assert_eq!(edit_distance(&left, &[]), 6);
+
+        let ops = diff(&left, &right);
+        let equal = ops
+            .iter()
+            .filter(|op| matches!(op, DiffOp::Equal(_)))
+            .count();
+        assert_eq!(equal, 4);
+
+        let left = ["a", "b", "c", "d", "e"];
+        let right = ["a", "b", "x", "d", "e"];
+        assert_eq!(
+            render_diff(&left, &right, None),
+            "  a\n  b\n- c\n+ x\n  d\n  e"
+        );
+        assert_eq!(
+            render_diff(&left, &right, Some(0)),
+            "@ 2 equal lines\n- c\n+ x\n@ 2 equal lines"
+        );
+    }
+}
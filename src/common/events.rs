@@ -0,0 +1,232 @@
+#![allow(dead_code)]
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+/**
+ * Something worth showing while a day runs. Solvers only publish these,
+ * what becomes of them is up to whoever subscribed.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /** `done` steps of a task that takes `total` steps, if that is known */
+    Progress {
+        task: &'static str,
+        done: u64,
+        total: Option<u64>,
+    },
+    /** A picture of the current state, ready to print */
+    Frame { source: &'static str, text: String },
+    /** A search finished after looking at `expanded` states and skipping `skipped` */
+    SearchStats {
+        search: &'static str,
+        expanded: usize,
+        skipped: usize,
+        found: bool,
+    },
+    /** A line an ASCII program printed or was sent, see `IoLog::Events` */
+    AsciiLine { text: String },
+    /**
+     * What an IntCode computer did since it last published this, when it
+     * halts, is reset or dropped
+     */
+    ComputerMetrics {
+        halted: bool,
+        instructions: u64,
+        inputs: u64,
+        outputs: u64,
+        memory_cells: usize,
+    },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::Progress {
+                task,
+                done,
+                total: Some(total),
+            } => write!(f, "{task}: {done}/{total}"),
+            Event::Progress {
+                task,
+                done,
+                total: None,
+            } => write!(f, "{task}: {done}"),
+            Event::Frame { source, text } => write!(f, "{source}:\n{text}"),
+            Event::SearchStats {
+                search,
+                expanded,
+                skipped,
+                found,
+            } => write!(
+                f,
+                "{search}: {expanded} expanded, {skipped} skipped, {}",
+                if *found { "found" } else { "not found" }
+            ),
+            Event::AsciiLine { text } => write!(f, "ascii: {text}"),
+            Event::ComputerMetrics {
+                halted,
+                instructions,
+                inputs,
+                outputs,
+                memory_cells,
+            } => write!(
+                f,
+                "IntCode {} after {instructions} instructions, {inputs} inputs, {outputs} outputs, {memory_cells} memory cells",
+                if *halted { "halted" } else { "stopped" }
+            ),
+        }
+    }
+}
+
+/** Identifies a subscription, to end it again */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriberId(usize);
+
+type Subscriber = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/**
+ * Hands every published event to all subscribers, on the thread that
+ * published it. Without subscribers publishing costs next to nothing,
+ * so events can be published from hot code.
+ */
+pub struct EventBus {
+    subscribers: RwLock<Vec<(SubscriberId, Subscriber)>>,
+    active: AtomicBool,
+    next_id: AtomicUsize,
+}
+
+impl EventBus {
+    pub const fn new() -> Self {
+        Self {
+            subscribers: RwLock::new(Vec::new()),
+            active: AtomicBool::new(false),
+            next_id: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn subscribe(&self, subscriber: impl Fn(&Event) + Send + Sync + 'static) -> SubscriberId {
+        let id = SubscriberId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut subscribers = self.subscribers.write().unwrap();
+        subscribers.push((id, Arc::new(subscriber)));
+        self.active.store(true, Ordering::Release);
+        id
+    }
+
+    pub fn unsubscribe(&self, id: SubscriberId) {
+        let mut subscribers = self.subscribers.write().unwrap();
+        subscribers.retain(|(subscriber, _)| *subscriber != id);
+        self.active
+            .store(!subscribers.is_empty(), Ordering::Release);
+    }
+
+    #[inline]
+    pub fn has_subscribers(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn publish(&self, event: Event) {
+        self.publish_with(|| event);
+    }
+
+    /**
+     * Only creates the event if anybody listens.
+     */
+    #[inline]
+    pub fn publish_with(&self, event: impl FnOnce() -> Event) {
+        if !self.has_subscribers() {
+            return;
+        }
+        let event = event();
+        // Subscribers are called without the lock, so they may subscribe themselves
+        let subscribers = self
+            .subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(_, subscriber)| Arc::clone(subscriber))
+            .collect::<Vec<_>>();
+        for subscriber in subscribers {
+            subscriber(&event);
+        }
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * The name of a type without its path and generics, to name the task or
+ * search an event comes from.
+ */
+pub fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+static BUS: EventBus = EventBus::new();
+
+/** The bus days and the IntCode computer publish to */
+pub fn bus() -> &'static EventBus {
+    &BUS
+}
+
+#[inline]
+pub fn publish_with(event: impl FnOnce() -> Event) {
+    BUS.publish_with(event);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn subscribe_and_publish() {
+        let bus = EventBus::new();
+        bus.publish_with(|| panic!("nobody listens"));
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let id = {
+            let seen = Arc::clone(&seen);
+            bus.subscribe(move |event| seen.lock().unwrap().push(event.to_string()))
+        };
+        assert!(bus.has_subscribers());
+
+        bus.publish(Event::Progress {
+            task: "sim",
+            done: 3,
+            total: Some(10),
+        });
+        bus.publish(Event::SearchStats {
+            search: "maze",
+            expanded: 12,
+            skipped: 2,
+            found: true,
+        });
+        bus.unsubscribe(id);
+        assert!(!bus.has_subscribers());
+        bus.publish(Event::ComputerMetrics {
+            halted: true,
+            instructions: 1,
+            inputs: 0,
+            outputs: 0,
+            memory_cells: 1,
+        });
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            ["sim: 3/10", "maze: 12 expanded, 2 skipped, found"]
+        );
+        assert_eq!(short_type_name::<Vec<Event>>(), "Vec");
+    }
+}
@@ -0,0 +1,529 @@
+#![allow(dead_code)]
+use std::{
+    collections::{BTreeSet, HashMap},
+    hash::Hash,
+};
+
+/**
+ * The strongly connected components of a directed graph.
+ * They are numbered in topological order, so edges only ever lead
+ * from a component to itself or to one with a higher number.
+ */
+#[derive(Debug)]
+pub struct Components<N> {
+    members: Vec<Vec<N>>,
+    membership: HashMap<N, usize>,
+    cyclic: Vec<bool>,
+}
+
+impl<N: Eq + Hash> Components<N> {
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn component_of(&self, node: &N) -> Option<usize> {
+        self.membership.get(node).copied()
+    }
+
+    pub fn members(&self, component: usize) -> &[N] {
+        &self.members[component]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[N]> {
+        self.members.iter().map(Vec::as_slice)
+    }
+
+    /**
+     * A component contains a cycle if it has more than one node
+     * or its only node has an edge to itself.
+     */
+    pub fn is_cyclic(&self, component: usize) -> bool {
+        self.cyclic[component]
+    }
+
+    pub fn cycles(&self) -> impl Iterator<Item = &[N]> {
+        self.iter()
+            .enumerate()
+            .filter(|(component, _)| self.is_cyclic(*component))
+            .map(|(_, members)| members)
+    }
+}
+
+/**
+ * The graph with every strongly connected component collapsed into one node.
+ * This is always a DAG.
+ */
+#[derive(Debug)]
+pub struct Condensation<N> {
+    components: Components<N>,
+    edges: Vec<BTreeSet<usize>>,
+}
+
+impl<N: Eq + Hash> Condensation<N> {
+    pub fn components(&self) -> &Components<N> {
+        &self.components
+    }
+
+    pub fn into_components(self) -> Components<N> {
+        self.components
+    }
+
+    pub fn successors(&self, component: usize) -> &BTreeSet<usize> {
+        &self.edges[component]
+    }
+
+    /**
+     * The original graph is acyclic if no component contains a cycle.
+     */
+    pub fn is_acyclic(&self) -> bool {
+        self.components.cycles().next().is_none()
+    }
+}
+
+struct Graph<N> {
+    nodes: Vec<N>,
+    index: HashMap<N, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl<N: Eq + Hash + Clone> Graph<N> {
+    /**
+     * Collects all nodes reachable from the given ones.
+     */
+    fn build<F, I>(nodes: impl IntoIterator<Item = N>, successors: F) -> Self
+    where
+        F: Fn(&N) -> I,
+        I: IntoIterator<Item = N>,
+    {
+        let mut graph = Self {
+            nodes: vec![],
+            index: HashMap::new(),
+            edges: vec![],
+        };
+        for node in nodes {
+            graph.intern(node);
+        }
+        let mut next = 0;
+        while next < graph.nodes.len() {
+            let targets = successors(&graph.nodes[next])
+                .into_iter()
+                .map(|target| graph.intern(target))
+                .collect();
+            graph.edges[next] = targets;
+            next += 1;
+        }
+        graph
+    }
+
+    fn intern(&mut self, node: N) -> usize {
+        if let Some(idx) = self.index.get(&node) {
+            return *idx;
+        }
+        let idx = self.nodes.len();
+        self.index.insert(node.clone(), idx);
+        self.nodes.push(node);
+        self.edges.push(vec![]);
+        idx
+    }
+
+    /**
+     * Tarjan's algorithm, without recursion so deep graphs do not overflow the stack.
+     * Returns the components in topological order.
+     */
+    fn tarjan(&self) -> Vec<Vec<usize>> {
+        let len = self.nodes.len();
+        let mut order = vec![None; len];
+        let mut low = vec![0; len];
+        let mut on_stack = vec![false; len];
+        let mut stack = vec![];
+        let mut components = vec![];
+        let mut counter = 0;
+
+        for root in 0..len {
+            if order[root].is_some() {
+                continue;
+            }
+            let mut calls = vec![(root, 0)];
+            order[root] = Some(counter);
+            low[root] = counter;
+            counter += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some((node, next_edge)) = calls.last_mut() {
+                let node = *node;
+                if let Some(&target) = self.edges[node].get(*next_edge) {
+                    *next_edge += 1;
+                    match order[target] {
+                        None => {
+                            order[target] = Some(counter);
+                            low[target] = counter;
+                            counter += 1;
+                            stack.push(target);
+                            on_stack[target] = true;
+                            calls.push((target, 0));
+                        }
+                        Some(target_order) if on_stack[target] => {
+                            low[node] = low[node].min(target_order);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some((parent, _)) = calls.last() {
+                    low[*parent] = low[*parent].min(low[node]);
+                }
+                if Some(low[node]) == order[node] {
+                    let mut component = vec![];
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        components.reverse();
+        components
+    }
+}
+
+pub fn strongly_connected_components<N, F, I>(
+    nodes: impl IntoIterator<Item = N>,
+    successors: F,
+) -> Components<N>
+where
+    N: Eq + Hash + Clone,
+    F: Fn(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    condense(nodes, successors).into_components()
+}
+
+pub fn condense<N, F, I>(nodes: impl IntoIterator<Item = N>, successors: F) -> Condensation<N>
+where
+    N: Eq + Hash + Clone,
+    F: Fn(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let graph = Graph::build(nodes, successors);
+    let components = graph.tarjan();
+
+    let mut component_of = vec![0; graph.nodes.len()];
+    for (component, members) in components.iter().enumerate() {
+        for member in members {
+            component_of[*member] = component;
+        }
+    }
+
+    let mut edges = vec![BTreeSet::new(); components.len()];
+    let mut cyclic = components
+        .iter()
+        .map(|members| members.len() > 1)
+        .collect::<Vec<_>>();
+    for (from, targets) in graph.edges.iter().enumerate() {
+        for to in targets {
+            if from == *to {
+                cyclic[component_of[from]] = true;
+            } else if component_of[from] != component_of[*to] {
+                edges[component_of[from]].insert(component_of[*to]);
+            }
+        }
+    }
+
+    let membership = graph
+        .index
+        .into_iter()
+        .map(|(node, idx)| (node, component_of[idx]))
+        .collect();
+    let members = components
+        .into_iter()
+        .map(|members| {
+            members
+                .into_iter()
+                .map(|idx| graph.nodes[idx].clone())
+                .collect()
+        })
+        .collect();
+
+    Condensation {
+        components: Components {
+            members,
+            membership,
+            cyclic,
+        },
+        edges,
+    }
+}
+
+/**
+ * A symmetric matrix that only keeps the lower triangle, which is how the
+ * `Distances` of days 18 and 20 keep their connections: row `i - 1` holds
+ * the entries from node `i` to every node before it. There is nothing on
+ * the diagonal.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CondensedMatrix<T> {
+    len: usize,
+    rows: Vec<Vec<T>>,
+}
+
+impl<T: Clone> CondensedMatrix<T> {
+    pub fn new(len: usize, value: T) -> Self {
+        Self {
+            len,
+            rows: (1..len).map(|row| vec![value.clone(); row]).collect(),
+        }
+    }
+}
+
+impl<T> CondensedMatrix<T> {
+    pub fn from_fn(len: usize, entry: impl Fn(usize, usize) -> T) -> Self {
+        Self {
+            len,
+            rows: (1..len)
+                .map(|from| (0..from).map(|to| entry(from, to)).collect())
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn set(&mut self, from: usize, to: usize, value: T) {
+        assert!(from != to);
+        self.rows[from.max(to) - 1][from.min(to)] = value;
+    }
+
+    /** `None` on the diagonal */
+    pub fn get(&self, from: usize, to: usize) -> Option<&T> {
+        (from != to).then(|| &self.rows[from.max(to) - 1][from.min(to)])
+    }
+
+    pub fn rows(&self) -> &[Vec<T>] {
+        &self.rows
+    }
+
+    /** Keeps the first `len` nodes */
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+        self.rows.truncate(self.len.saturating_sub(1));
+    }
+}
+
+/**
+ * An entry of a matrix the tour heuristics can walk along.
+ */
+pub trait Distance {
+    /** `None` if there is no connection */
+    fn distance(&self) -> Option<usize>;
+}
+
+impl Distance for usize {
+    fn distance(&self) -> Option<usize> {
+        Some(*self)
+    }
+}
+
+impl Distance for Option<usize> {
+    fn distance(&self) -> Option<usize> {
+        *self
+    }
+}
+
+fn distance<T: Distance>(matrix: &CondensedMatrix<T>, from: usize, to: usize) -> Option<usize> {
+    match matrix.get(from, to) {
+        Some(entry) => entry.distance(),
+        None => Some(0),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    /** Ends at the last node visited */
+    Open,
+    /** Returns to the start after visiting every node */
+    Closed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour {
+    pub order: Vec<usize>,
+    pub length: usize,
+}
+
+fn tour_length<T: Distance>(
+    matrix: &CondensedMatrix<T>,
+    order: &[usize],
+    route: Route,
+) -> Option<usize> {
+    let back = match (route, order.first(), order.last()) {
+        (Route::Closed, Some(first), Some(last)) => distance(matrix, *last, *first)?,
+        _ => 0,
+    };
+    order.windows(2).try_fold(back, |length, step| {
+        Some(length + distance(matrix, step[0], step[1])?)
+    })
+}
+
+/**
+ * Always goes to the closest node not visited yet. Fast, but usually well
+ * above the optimum, so it is mostly good as a starting point for `two_opt`.
+ * Returns `None` if it gets stuck because no unvisited node is connected.
+ */
+pub fn nearest_neighbor<T: Distance>(
+    matrix: &CondensedMatrix<T>,
+    start: usize,
+    route: Route,
+) -> Option<Tour> {
+    let mut visited = vec![false; matrix.len()];
+    let mut order = vec![start];
+    visited[start] = true;
+    let mut current = start;
+    while order.len() < matrix.len() {
+        let (_, next) = (0..matrix.len())
+            .filter(|node| !visited[*node])
+            .filter_map(|node| Some((distance(matrix, current, node)?, node)))
+            .min()?;
+        visited[next] = true;
+        order.push(next);
+        current = next;
+    }
+    let length = tour_length(matrix, &order, route)?;
+    Some(Tour { order, length })
+}
+
+/**
+ * Reverses parts of the tour as long as that makes it shorter. The start
+ * stays where it is. The result is a local optimum, an upper bound for the
+ * exact answer.
+ */
+pub fn two_opt<T: Distance>(matrix: &CondensedMatrix<T>, tour: Tour, route: Route) -> Tour {
+    let mut best = tour;
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for first in 1..best.order.len() {
+            for last in first + 1..best.order.len() {
+                let mut order = best.order.clone();
+                order[first..=last].reverse();
+                if let Some(length) = tour_length(matrix, &order, route)
+                    && length < best.length
+                {
+                    best = Tour { order, length };
+                    improved = true;
+                }
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hashmap;
+
+    #[test]
+    fn components_and_condensation() {
+        let graph = hashmap! {
+            'a' => vec!['b'],
+            'b' => vec!['c', 'e'],
+            'c' => vec!['a', 'd'],
+            'd' => vec!['d'],
+            'e' => vec!['f'],
+            'f' => vec![],
+        };
+        let condensed = condense("abcdef".chars(), |node| graph[node].clone());
+        let components = condensed.components();
+
+        assert_eq!(components.len(), 4);
+        let cycle = components.component_of(&'a').unwrap();
+        assert_eq!(components.component_of(&'c'), Some(cycle));
+        let mut members = components.members(cycle).to_vec();
+        members.sort();
+        assert_eq!(members, vec!['a', 'b', 'c']);
+
+        let d = components.component_of(&'d').unwrap();
+        let e = components.component_of(&'e').unwrap();
+        let f = components.component_of(&'f').unwrap();
+        assert!(components.is_cyclic(d));
+        assert!(!components.is_cyclic(e));
+        assert_eq!(components.cycles().count(), 2);
+        assert!(!condensed.is_acyclic());
+
+        assert_eq!(
+            condensed
+                .successors(cycle)
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            {
+                let mut expected = vec![d, e];
+                expected.sort();
+                expected
+            }
+        );
+        assert!(cycle < e && e < f && cycle < d);
+
+        let chain = strongly_connected_components([1], |node| (*node < 5).then_some(node + 1));
+        assert_eq!(chain.len(), 5);
+        assert_eq!(chain.cycles().count(), 0);
+    }
+
+    #[test]
+    fn tours() {
+        // Four corners of a 1x3 rectangle, the diagonals are 4 long
+        let corners = [(0, 0), (3, 0), (0, 1), (3, 1)];
+        let matrix = CondensedMatrix::from_fn(corners.len(), |from, to| {
+            let (a, b): ((i32, i32), (i32, i32)) = (corners[from], corners[to]);
+            Some(((a.0 - b.0).abs() + (a.1 - b.1).abs()) as usize)
+        });
+        assert_eq!(matrix.len(), 4);
+        assert_eq!(matrix.get(1, 2), Some(&Some(4)));
+
+        let greedy = nearest_neighbor(&matrix, 0, Route::Closed).unwrap();
+        assert_eq!(greedy.order, vec![0, 2, 3, 1]);
+        assert_eq!(greedy.length, 8);
+        assert_eq!(two_opt(&matrix, greedy, Route::Closed).length, 8);
+
+        // Greedy is lured to the close node first and has to walk all the way back
+        let line: [i64; 4] = [0, 2, -3, 10];
+        let matrix = CondensedMatrix::from_fn(line.len(), |from, to| {
+            Some((line[from] - line[to]).unsigned_abs() as usize)
+        });
+        let greedy = nearest_neighbor(&matrix, 0, Route::Open).unwrap();
+        assert_eq!(greedy.order, vec![0, 1, 2, 3]);
+        assert_eq!(greedy.length, 20);
+        let improved = two_opt(&matrix, greedy, Route::Open);
+        assert_eq!(improved.order, vec![0, 2, 1, 3]);
+        assert_eq!(improved.length, 16);
+
+        let mut disconnected = CondensedMatrix::new(3, None);
+        disconnected.set(0, 1, Some(1));
+        assert_eq!(nearest_neighbor(&disconnected, 0, Route::Open), None);
+
+        let single = CondensedMatrix::<usize>::new(1, 0);
+        assert_eq!(single.len(), 1);
+        let alone = Tour {
+            order: vec![0],
+            length: 0,
+        };
+        assert_eq!(nearest_neighbor(&single, 0, Route::Closed), Some(alone));
+    }
+}
@@ -0,0 +1,538 @@
+#![allow(dead_code)]
+use super::{area::Area, pos2::Pos2};
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GridError {
+    #[error("Empty grids are not allowed")]
+    EmptyGrid,
+
+    #[error("All rows of a grid must have the same length")]
+    NotRectangular,
+}
+
+fn check_rectangular<T>(rows: &[Vec<T>]) -> Result<(), GridError> {
+    match rows.first() {
+        Some(first) if rows.iter().any(|row| row.len() != first.len()) => {
+            Err(GridError::NotRectangular)
+        }
+        _ => Ok(()),
+    }
+}
+
+/** Expects rectangular rows, see `check_rectangular` */
+fn transpose_rectangular<T: Clone>(rows: &[Vec<T>]) -> Vec<Vec<T>> {
+    let width = rows.first().map(|row| row.len()).unwrap_or_default();
+    (0..width)
+        .map(|x| rows.iter().map(|row| row[x].clone()).collect())
+        .collect()
+}
+
+/**
+ * Ragged rows cannot be transposed without moving cells into the wrong
+ * column, so they are an error. Use `pad_rows` first to fill them up.
+ */
+pub fn transpose<T: Clone>(rows: &[Vec<T>]) -> Result<Vec<Vec<T>>, GridError> {
+    check_rectangular(rows)?;
+    Ok(transpose_rectangular(rows))
+}
+
+pub fn rotate_cw<T: Clone>(rows: &[Vec<T>]) -> Result<Vec<Vec<T>>, GridError> {
+    let mut rotated = transpose(rows)?;
+    rotated.iter_mut().for_each(|row| row.reverse());
+    Ok(rotated)
+}
+
+pub fn rotate_ccw<T: Clone>(rows: &[Vec<T>]) -> Result<Vec<Vec<T>>, GridError> {
+    let mut rotated = transpose(rows)?;
+    rotated.reverse();
+    Ok(rotated)
+}
+
+pub fn flip_horizontal<T: Clone>(rows: &[Vec<T>]) -> Vec<Vec<T>> {
+    rows.iter()
+        .map(|row| row.iter().rev().cloned().collect())
+        .collect()
+}
+
+pub fn flip_vertical<T: Clone>(rows: &[Vec<T>]) -> Vec<Vec<T>> {
+    rows.iter().rev().cloned().collect()
+}
+
+pub fn pad_rows<T: Clone>(rows: &mut [Vec<T>], fill: T) {
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or_default();
+    rows.iter_mut()
+        .for_each(|row| row.resize(width, fill.clone()));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    rows: Vec<Vec<T>>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(rows: Vec<Vec<T>>) -> Result<Self, GridError> {
+        if rows.is_empty() || rows[0].is_empty() {
+            return Err(GridError::EmptyGrid);
+        }
+        check_rectangular(&rows)?;
+        Ok(Self { rows })
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn get(&self, pos: Pos2<usize>) -> Option<&T> {
+        self.rows.get(pos.y()).and_then(|row| row.get(pos.x()))
+    }
+
+    pub fn get_mut(&mut self, pos: Pos2<usize>) -> Option<&mut T> {
+        self.rows
+            .get_mut(pos.y())
+            .and_then(|row| row.get_mut(pos.x()))
+    }
+
+    pub fn rows(&self) -> &[Vec<T>] {
+        &self.rows
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<T>> {
+        self.rows
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = (Pos2<usize>, &T)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(x, cell)| (Pos2::new(x, y), cell))
+        })
+    }
+
+    pub fn map<U, F>(&self, mut func: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            rows: self
+                .rows
+                .iter()
+                .map(|row| row.iter().map(&mut func).collect())
+                .collect(),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn transpose(&self) -> Self {
+        Self {
+            rows: transpose_rectangular(&self.rows),
+        }
+    }
+
+    pub fn rotate_cw(&self) -> Self {
+        let mut rotated = self.transpose();
+        rotated.rows.iter_mut().for_each(|row| row.reverse());
+        rotated
+    }
+
+    pub fn rotate_ccw(&self) -> Self {
+        let mut rotated = self.transpose();
+        rotated.rows.reverse();
+        rotated
+    }
+
+    pub fn flip_horizontal(&self) -> Self {
+        Self {
+            rows: flip_horizontal(&self.rows),
+        }
+    }
+
+    pub fn flip_vertical(&self) -> Self {
+        Self {
+            rows: flip_vertical(&self.rows),
+        }
+    }
+}
+
+impl Grid<char> {
+    /**
+     * Parses a block of text into a grid of chars.
+     * Lines shorter than the longest one are padded with `fill`,
+     * so inputs with ragged right edges still become rectangular.
+     */
+    pub fn parse_padded(input: &str, fill: char) -> Result<Self, GridError> {
+        let mut rows = input
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect::<Vec<Vec<_>>>();
+        pad_rows(&mut rows, fill);
+        Self::new(rows)
+    }
+}
+
+impl<T> Index<Pos2<usize>> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos2<usize>) -> &Self::Output {
+        &self.rows[pos.y()][pos.x()]
+    }
+}
+
+impl<T> IndexMut<Pos2<usize>> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos2<usize>) -> &mut Self::Output {
+        &mut self.rows[pos.y()][pos.x()]
+    }
+}
+
+/**
+ * A grid without fixed bounds, for maps that get discovered while walking around.
+ * The area grows with every inserted cell.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Pos2<i64>, T>,
+    area: Option<Area<i64>>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            area: None,
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, pos: Pos2<i64>, value: T) -> Option<T> {
+        self.area = Some(match self.area {
+            Some(area) => area.extend(pos),
+            None => Area::new(pos, pos),
+        });
+        self.cells.insert(pos, value)
+    }
+
+    #[inline]
+    pub fn get(&self, pos: &Pos2<i64>) -> Option<&T> {
+        self.cells.get(pos)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    #[inline]
+    pub fn area(&self) -> Option<Area<i64>> {
+        self.area
+    }
+
+    /**
+     * Gets a cell by its offset from the lower left corner of the area.
+     */
+    pub fn get_relative(&self, x: usize, y: usize) -> Option<&T> {
+        let area = self.area?;
+        self.get(&(area.lower_left() + Pos2::new(x as i64, y as i64)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Pos2<i64>, &T)> + '_ {
+        self.cells.iter()
+    }
+}
+
+impl<T> FromIterator<(Pos2<i64>, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Pos2<i64>, T)>>(iter: I) -> Self {
+        let mut grid = Self::new();
+        for (pos, value) in iter {
+            grid.insert(pos, value);
+        }
+        grid
+    }
+}
+
+/**
+ * Cells an `OffsetGrid` reserves at least on a side it grows to.
+ */
+const MIN_GROWTH: i64 = 8;
+
+/**
+ * Like `SparseGrid`, but the cells are kept in one dense vector, which is a
+ * lot faster to look up. Any coordinate can be used: the grid remembers
+ * which position its first cell stands for and reallocates when a cell
+ * outside of the reserved space is inserted, reserving half its size
+ * again on that side.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetGrid<T> {
+    cells: Vec<Option<T>>,
+    /** The position of `cells[0]`, the lower left corner of the reserved space */
+    origin: Pos2<i64>,
+    width: usize,
+    height: usize,
+    len: usize,
+    area: Option<Area<i64>>,
+}
+
+impl<T> Default for OffsetGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: Vec::new(),
+            origin: Pos2::default(),
+            width: 0,
+            height: 0,
+            len: 0,
+            area: None,
+        }
+    }
+}
+
+impl<T> OffsetGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index(&self, pos: Pos2<i64>) -> Option<usize> {
+        let x = usize::try_from(pos.x() - self.origin.x()).ok()?;
+        let y = usize::try_from(pos.y() - self.origin.y()).ok()?;
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+
+    fn position(&self, index: usize) -> Pos2<i64> {
+        self.origin + Pos2::new((index % self.width) as i64, (index / self.width) as i64)
+    }
+
+    fn reserve(&mut self, pos: Pos2<i64>) {
+        if self.index(pos).is_some() {
+            return;
+        }
+        let (mut lower, mut upper) = if self.cells.is_empty() {
+            (pos, pos)
+        } else {
+            (
+                self.origin,
+                self.origin + Pos2::new(self.width as i64 - 1, self.height as i64 - 1),
+            )
+        };
+        let grow_x = (self.width as i64 / 2).max(MIN_GROWTH);
+        let grow_y = (self.height as i64 / 2).max(MIN_GROWTH);
+        if pos.x() < lower.x() {
+            lower = lower.set_x(pos.x() - grow_x);
+        } else if pos.x() > upper.x() {
+            upper = upper.set_x(pos.x() + grow_x);
+        }
+        if pos.y() < lower.y() {
+            lower = lower.set_y(pos.y() - grow_y);
+        } else if pos.y() > upper.y() {
+            upper = upper.set_y(pos.y() + grow_y);
+        }
+
+        let mut old = std::mem::replace(
+            self,
+            Self {
+                cells: Vec::new(),
+                origin: lower,
+                width: (upper.x() - lower.x() + 1) as usize,
+                height: (upper.y() - lower.y() + 1) as usize,
+                len: self.len,
+                area: self.area,
+            },
+        );
+        self.cells
+            .resize_with(self.width * self.height, Default::default);
+        for (index, cell) in std::mem::take(&mut old.cells).into_iter().enumerate() {
+            if cell.is_some() {
+                let new_index = self
+                    .index(old.position(index))
+                    .expect("the new space covers the old one");
+                self.cells[new_index] = cell;
+            }
+        }
+    }
+
+    pub fn insert(&mut self, pos: Pos2<i64>, value: T) -> Option<T> {
+        self.reserve(pos);
+        self.area = Some(match self.area {
+            Some(area) => area.extend(pos),
+            None => Area::new(pos, pos),
+        });
+        let index = self.index(pos).expect("space was reserved");
+        let old = self.cells[index].replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    #[inline]
+    pub fn get(&self, pos: &Pos2<i64>) -> Option<&T> {
+        self.index(*pos)
+            .and_then(|index| self.cells[index].as_ref())
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, pos: &Pos2<i64>) -> Option<&mut T> {
+        self.index(*pos)
+            .and_then(|index| self.cells[index].as_mut())
+    }
+
+    #[inline]
+    pub fn contains(&self, pos: &Pos2<i64>) -> bool {
+        self.get(pos).is_some()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * The smallest area holding all cells, the reserved space may be larger.
+     */
+    #[inline]
+    pub fn area(&self) -> Option<Area<i64>> {
+        self.area
+    }
+
+    /**
+     * Gets a cell by its offset from the lower left corner of the area.
+     */
+    pub fn get_relative(&self, x: usize, y: usize) -> Option<&T> {
+        let area = self.area?;
+        self.get(&(area.lower_left() + Pos2::new(x as i64, y as i64)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Pos2<i64>, &T)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(index, cell)| Some((self.position(index), cell.as_ref()?)))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.cells.iter().flatten()
+    }
+}
+
+impl<T> FromIterator<(Pos2<i64>, T)> for OffsetGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Pos2<i64>, T)>>(iter: I) -> Self {
+        let mut grid = Self::new();
+        for (pos, value) in iter {
+            grid.insert(pos, value);
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_transformations() -> Result<(), GridError> {
+        let grid = Grid::parse_padded("ab\ncd\nef", ' ')?;
+
+        assert_eq!(
+            grid.transpose(),
+            Grid::new(vec![vec!['a', 'c', 'e'], vec!['b', 'd', 'f']])?
+        );
+        assert_eq!(
+            grid.rotate_cw(),
+            Grid::new(vec![vec!['e', 'c', 'a'], vec!['f', 'd', 'b']])?
+        );
+        assert_eq!(
+            grid.rotate_ccw(),
+            Grid::new(vec![vec!['b', 'd', 'f'], vec!['a', 'c', 'e']])?
+        );
+        assert_eq!(
+            grid.flip_horizontal(),
+            Grid::new(vec![vec!['b', 'a'], vec!['d', 'c'], vec!['f', 'e']])?
+        );
+        assert_eq!(grid.rotate_cw().rotate_cw().rotate_cw().rotate_cw(), grid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_offset_grid() {
+        let mut grid = OffsetGrid::new();
+        assert_eq!(grid.insert(Pos2::new(0, 0), 'a'), None);
+        assert_eq!(grid.insert(Pos2::new(-20, 3), 'b'), None);
+        assert_eq!(grid.insert(Pos2::new(5, -100), 'c'), None);
+        assert_eq!(grid.insert(Pos2::new(0, 0), 'd'), Some('a'));
+
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.get(&Pos2::new(-20, 3)), Some(&'b'));
+        assert_eq!(grid.get(&Pos2::new(1000, 1000)), None);
+        assert_eq!(
+            grid.area(),
+            Some(Area::new(Pos2::new(-20, -100), Pos2::new(5, 3)))
+        );
+        assert_eq!(grid.get_relative(25, 0), Some(&'c'));
+        assert_eq!(
+            grid.iter()
+                .sorted_by_key(|(pos, _)| (pos.x(), pos.y()))
+                .collect_vec(),
+            vec![
+                (Pos2::new(-20, 3), &'b'),
+                (Pos2::new(0, 0), &'d'),
+                (Pos2::new(5, -100), &'c')
+            ]
+        );
+    }
+
+    #[test]
+    fn test_padding() -> Result<(), GridError> {
+        let grid = Grid::parse_padded("  #\n#\n ##", '.')?;
+
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 3);
+        assert_eq!(grid[Pos2::new(1, 1)], '.');
+        assert!(matches!(
+            Grid::new(vec![vec![1, 2], vec![3]]),
+            Err(GridError::NotRectangular)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ragged_rows() -> Result<(), GridError> {
+        let mut rows = vec![vec![1, 2, 3], vec![4], vec![5, 6]];
+        assert!(matches!(transpose(&rows), Err(GridError::NotRectangular)));
+        assert!(matches!(rotate_cw(&rows), Err(GridError::NotRectangular)));
+
+        pad_rows(&mut rows, 0);
+        assert_eq!(
+            transpose(&rows)?,
+            vec![vec![1, 4, 5], vec![2, 0, 6], vec![3, 0, 0]]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+#![allow(dead_code)]
+use super::{direction::Direction, pos2::Pos2, scalar::Scalar};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
+
+/**
+ * The cheapest way from the start to every cell a search reached: what it
+ * costs and the cell it comes from, so the way itself can be walked back.
+ */
+#[derive(Debug, Clone)]
+pub struct CostMap<T> {
+    start: Pos2<T>,
+    reached: HashMap<Pos2<T>, (usize, Pos2<T>)>,
+}
+
+impl<T: Scalar> CostMap<T> {
+    fn new(start: Pos2<T>) -> Self {
+        Self {
+            start,
+            reached: HashMap::from([(start, (0, start))]),
+        }
+    }
+
+    pub fn start(&self) -> Pos2<T> {
+        self.start
+    }
+
+    /** `None` if the cell cannot be reached */
+    pub fn cost(&self, pos: Pos2<T>) -> Option<usize> {
+        self.reached.get(&pos).map(|(cost, _)| *cost)
+    }
+
+    /** The number of cells reached, the start included */
+    pub fn len(&self) -> usize {
+        self.reached.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reached.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Pos2<T>, usize)> + '_ {
+        self.reached.iter().map(|(pos, (cost, _))| (*pos, *cost))
+    }
+
+    /** A cell that costs the most to reach, and what it costs */
+    pub fn farthest(&self) -> Option<(Pos2<T>, usize)> {
+        self.iter().max_by_key(|(_, cost)| *cost)
+    }
+
+    /** The cells of the cheapest way to `target`, start and target included */
+    pub fn path_to(&self, target: Pos2<T>) -> Option<Vec<Pos2<T>>> {
+        let mut path = vec![target];
+        let mut pos = target;
+        while pos != self.start {
+            pos = self.reached.get(&pos)?.1;
+            path.push(pos);
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/**
+ * Breadth first from `start` to every cell it can walk to, each step
+ * costing one. The area has to be closed off by cells that are not
+ * walkable, as the search goes on as long as it finds new ones.
+ */
+pub fn distance_map<T: Scalar>(start: Pos2<T>, walkable: impl Fn(Pos2<T>) -> bool) -> CostMap<T> {
+    let mut map = CostMap::new(start);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((pos, cost)) = queue.pop_front() {
+        for next in Direction::iter().filter_map(|direction| pos.check_add(direction)) {
+            if !map.reached.contains_key(&next) && walkable(next) {
+                map.reached.insert(next, (cost + 1, pos));
+                queue.push_back((next, cost + 1));
+            }
+        }
+    }
+    map
+}
+
+/**
+ * Like `distance_map`, but stepping onto a cell costs what `cost` says of
+ * it, `None` if it is blocked. Leaving the start costs nothing extra, and
+ * all costs must be positive for the result to be the cheapest.
+ */
+pub fn cost_map<T: Scalar>(start: Pos2<T>, cost: impl Fn(Pos2<T>) -> Option<usize>) -> CostMap<T> {
+    let mut map = CostMap::new(start);
+    // Positions are not ordered, the heap points into this list instead
+    let mut cells = vec![start];
+    let mut queue = BinaryHeap::from([Reverse((0, 0))]);
+    while let Some(Reverse((so_far, index))) = queue.pop() {
+        let pos = cells[index];
+        if map.cost(pos).is_some_and(|known| known < so_far) {
+            continue;
+        }
+        for next in Direction::iter().filter_map(|direction| pos.check_add(direction)) {
+            let Some(step) = cost(next) else {
+                continue;
+            };
+            let total = so_far + step;
+            if map.cost(next).is_none_or(|known| total < known) {
+                map.reached.insert(next, (total, pos));
+                cells.push(next);
+                queue.push(Reverse((total, cells.len() - 1)));
+            }
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::grid::Grid;
+
+    const MAZE: &str = "\
+#########
+#S..~~~E#
+#.#####.#
+#.......#
+#########";
+
+    fn maze() -> Grid<char> {
+        Grid::parse_padded(MAZE, '#').unwrap()
+    }
+
+    #[test]
+    fn distances() {
+        let maze = maze();
+        let start = Pos2::new(1usize, 1);
+        let walkable = |pos| maze.get(pos).is_some_and(|tile| *tile != '#');
+        let map = distance_map(start, walkable);
+        assert_eq!(map.len(), 16);
+        assert_eq!(map.cost(Pos2::new(7, 1)), Some(6));
+        assert_eq!(map.cost(Pos2::new(0, 0)), None);
+        assert_eq!(map.farthest().map(|(_, cost)| cost), Some(8));
+
+        let path = map.path_to(Pos2::new(4, 3)).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.first(), Some(&start));
+        assert!(map.path_to(Pos2::new(2, 2)).is_none());
+    }
+
+    #[test]
+    fn weighted() {
+        let maze = &maze();
+        let start = Pos2::new(1usize, 1);
+        let exit = Pos2::new(7, 1);
+        let terrain = |swamp| {
+            move |pos| match maze.get(pos) {
+                Some('#') | None => None,
+                Some('~') => Some(swamp),
+                Some(_) => Some(1),
+            }
+        };
+
+        // Through the swamp while it is cheap, around it once it is not
+        let map = cost_map(start, terrain(2));
+        assert_eq!(map.cost(exit), Some(9));
+        assert_eq!(map.path_to(exit).unwrap().len(), 7);
+        let map = cost_map(start, terrain(5));
+        assert_eq!(map.cost(exit), Some(10));
+        assert_eq!(map.path_to(exit).unwrap().len(), 11);
+
+        let plain = distance_map(start, |pos| terrain(1)(pos).is_some());
+        let same = cost_map(start, terrain(1));
+        assert!(plain.iter().all(|(pos, cost)| same.cost(pos) == Some(cost)));
+        assert_eq!(plain.len(), same.len());
+    }
+}
@@ -74,3 +74,39 @@ pub fn zip7<A, B, C, D, E, F, G>(
         _ => None,
     }
 }
+
+/**
+ * `len` elements of `pattern` repeated over and over, starting at `start`,
+ * copied in whole runs of the pattern. Empty if the pattern is.
+ */
+pub fn repeated<T: Copy>(pattern: &[T], start: usize, len: usize) -> Vec<T> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    let mut result = Vec::with_capacity(len);
+    let from = start % pattern.len();
+    result.extend_from_slice(&pattern[from..pattern.len().min(from + len)]);
+    while result.len() < len {
+        let run = pattern.len().min(len - result.len());
+        result.extend_from_slice(&pattern[..run]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn repeats() {
+        let pattern = [1, 2, 3];
+        assert_eq!(repeated(&pattern, 0, 7), vec![1, 2, 3, 1, 2, 3, 1]);
+        assert_eq!(repeated(&pattern, 5, 4), vec![3, 1, 2, 3]);
+        assert_eq!(repeated(&pattern, 1, 1), vec![2]);
+        assert!(repeated(&pattern, 2, 0).is_empty());
+        assert!(repeated::<u8>(&[], 3, 10).is_empty());
+
+        let same = pattern.iter().copied().cycle().skip(4).take(100);
+        assert_eq!(repeated(&pattern, 4, 100), same.collect::<Vec<_>>());
+    }
+}
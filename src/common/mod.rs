@@ -1,7 +1,14 @@
 pub mod abs;
 pub mod area;
 pub mod block;
+pub mod collections;
+pub mod compress;
+pub mod diff;
 pub mod direction;
+pub mod events;
+pub mod graph;
+pub mod grid;
+pub mod grid_search;
 pub mod helper;
 pub mod idx;
 pub mod math;
@@ -11,5 +18,12 @@ pub mod name;
 pub mod path_finder;
 pub mod pos2;
 pub mod pos3;
+pub mod scalar;
+pub mod segment;
+pub mod sim;
+pub mod testing;
 pub mod turn;
 pub mod unit_vector;
+pub mod units;
+pub mod unsafe_utils;
+pub mod viz;
@@ -1,4 +1,5 @@
-use std::{collections::HashSet, marker::PhantomData};
+use crate::common::collections::FastSet;
+use std::marker::PhantomData;
 
 pub trait ItemSkipper {
     type Item;
@@ -16,7 +17,7 @@ pub struct FingerprintSkipper<F>
 where
     F: FingerprintItem,
 {
-    fingerprints: HashSet<F::Fingerprint>,
+    fingerprints: FastSet<F::Fingerprint>,
 }
 
 impl<F: FingerprintItem> ItemSkipper for FingerprintSkipper<F> {
@@ -24,7 +25,7 @@ impl<F: FingerprintItem> ItemSkipper for FingerprintSkipper<F> {
 
     fn init() -> Self {
         Self {
-            fingerprints: HashSet::new(),
+            fingerprints: FastSet::default(),
         }
     }
 
@@ -5,6 +5,7 @@ mod path_queue;
 
 use self::item_skipper::NoneSkipper;
 pub use self::path_queue::PathQueue;
+use super::events::{self, Event};
 pub use item_skipper::{FingerprintItem, FingerprintSkipper, ItemSkipper};
 
 pub trait PathFinder {
@@ -20,25 +21,40 @@ pub trait PathFinder {
         -> impl Iterator<Item = Self::Item> + 'a;
 }
 
+/**
+ * Publishes how much work the search did once it is over.
+ */
 pub fn find_best_path<P: PathFinder>(path_finder: P) -> Option<P::Item> {
     let mut skipper = P::Skipper::init();
+    let mut expanded = 0;
+    let mut skipped = 0;
 
     let mut queue = P::Queue::create();
     queue.push(path_finder.get_start_item());
 
+    let mut found = None;
     while let Some(item) = queue.pop() {
         if path_finder.is_finished(&item) {
-            return Some(item);
+            found = Some(item);
+            break;
         }
 
         if skipper.skip_item(&item) {
+            skipped += 1;
             continue;
         }
 
+        expanded += 1;
         for next_item in path_finder.get_next_states(&item) {
             queue.push(next_item)
         }
     }
 
-    None
+    events::publish_with(|| Event::SearchStats {
+        search: events::short_type_name::<P>(),
+        expanded,
+        skipped,
+        found: found.is_some(),
+    });
+    found
 }
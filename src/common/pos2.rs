@@ -1,8 +1,8 @@
 #![allow(dead_code)]
 
 use super::direction::Direction;
-use super::{abs::Abs, math::gcd};
-use num_traits::{CheckedAdd, CheckedSub, Float, Num, NumCast, Signed, Zero};
+use super::{abs::Abs, math::gcd, scalar::Scalar};
+use num_traits::{Float, Num, NumCast, Signed, Zero};
 use std::fmt;
 use std::ops::{Add, AddAssign, Div, Index, Mul, Neg, Sub, SubAssign};
 
@@ -108,27 +108,45 @@ where
 
 impl<T> Pos2<T>
 where
-    T: Num + Ord + Copy,
+    T: Scalar,
 {
     pub fn normalize(self) -> Result<(Pos2<T>, T), Pos2<T>> {
         if self.x.is_zero() && self.y.is_zero() {
             Err(self)
         } else {
-            let x = if self.x >= T::zero() {
-                self.x
-            } else {
-                T::zero() - self.x
-            };
-            let y = if self.y >= T::zero() {
-                self.y
-            } else {
-                T::zero() - self.y
-            };
-            gcd(x, y)
+            gcd(Abs::abs(&self.x), Abs::abs(&self.y))
                 .map(|ggt| (Pos2::new(self.x / ggt, self.y / ggt), ggt))
                 .ok_or(self)
         }
     }
+
+    pub fn signum(self) -> Self {
+        Pos2::new(self.x.signum(), self.y.signum())
+    }
+
+    pub fn abs(self) -> T {
+        Abs::abs(&self.x) + Abs::abs(&self.y)
+    }
+
+    pub fn taxicab_between(self, other: Pos2<T>) -> T {
+        self.x.abs_beween(&other.x) + self.y.abs_beween(&other.y)
+    }
+
+    pub fn checked_add(self, other: Pos2<T>) -> Option<Self> {
+        Some(Pos2::new(
+            self.x.checked_add(&other.x)?,
+            self.y.checked_add(&other.y)?,
+        ))
+    }
+
+    pub fn check_add(self, direction: Direction) -> Option<Self> {
+        match direction {
+            Direction::East => self.x.checked_add(&T::one()).map(|x| Pos2::new(x, self.y)),
+            Direction::North => self.y.checked_sub(&T::one()).map(|y| Pos2::new(self.x, y)),
+            Direction::West => self.x.checked_sub(&T::one()).map(|x| Pos2::new(x, self.y)),
+            Direction::South => self.y.checked_add(&T::one()).map(|y| Pos2::new(self.x, y)),
+        }
+    }
 }
 
 impl<T> Pos2<T>
@@ -179,15 +197,6 @@ where
     }
 }
 
-impl<T> Pos2<T>
-where
-    T: Num + Abs,
-{
-    pub fn abs(self) -> T {
-        self.x.abs() + self.y.abs()
-    }
-}
-
 impl<T> Pos2<T>
 where
     T: Float,
@@ -303,26 +312,3 @@ where
         Self::new(-self.x, -self.y)
     }
 }
-
-impl<T> Pos2<T>
-where
-    T: Num + Abs + Copy,
-{
-    pub fn taxicab_between(self, other: Pos2<T>) -> T {
-        self.x.abs_beween(&other.x) + self.y.abs_beween(&other.y)
-    }
-}
-
-impl<T> Pos2<T>
-where
-    T: Num + Copy + CheckedAdd + CheckedSub,
-{
-    pub fn check_add(self, direction: Direction) -> Option<Self> {
-        match direction {
-            Direction::East => self.x.checked_add(&T::one()).map(|x| Pos2::new(x, self.y)),
-            Direction::North => self.y.checked_sub(&T::one()).map(|y| Pos2::new(self.x, y)),
-            Direction::West => self.x.checked_sub(&T::one()).map(|x| Pos2::new(x, self.y)),
-            Direction::South => self.y.checked_add(&T::one()).map(|y| Pos2::new(self.x, y)),
-        }
-    }
-}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
-use num_traits::{Num, PrimInt, Signed, Zero};
+use super::{abs::Abs, math::gcd, scalar::Scalar};
+use num_traits::{Num, Signed, Zero};
 use std::fmt;
 use std::ops::{Add, Div, Index, Mul, Neg, Sub};
 
@@ -32,16 +33,40 @@ impl<T> Pos3<T> {
     }
 }
 
-impl<T: Signed + PrimInt> Pos3<T> {
+impl<T> Pos3<T>
+where
+    T: Scalar,
+{
     pub fn is_unit(&self) -> bool {
         self.abs() == T::one()
     }
-}
 
-impl<T: Signed> Pos3<T> {
+    pub fn normalize(self) -> Result<(Pos3<T>, T), Pos3<T>> {
+        let xy = gcd(Abs::abs(&self.x), Abs::abs(&self.y)).unwrap_or_else(T::zero);
+        gcd(xy, Abs::abs(&self.z))
+            .map(|ggt| (self / ggt, ggt))
+            .ok_or(self)
+    }
+
     pub fn signum(&self) -> Pos3<T> {
         Pos3::new(self.x.signum(), self.y.signum(), self.z.signum())
     }
+
+    pub fn abs(self) -> T {
+        Abs::abs(&self.x) + Abs::abs(&self.y) + Abs::abs(&self.z)
+    }
+
+    pub fn taxicab_between(self, other: Pos3<T>) -> T {
+        self.x.abs_beween(&other.x) + self.y.abs_beween(&other.y) + self.z.abs_beween(&other.z)
+    }
+
+    pub fn checked_add(self, other: Pos3<T>) -> Option<Self> {
+        Some(Pos3::new(
+            self.x.checked_add(&other.x)?,
+            self.y.checked_add(&other.y)?,
+            self.z.checked_add(&other.z)?,
+        ))
+    }
 }
 
 impl<T: Copy + Default> From<&[T]> for Pos3<T> {
@@ -126,15 +151,6 @@ where
     }
 }
 
-impl<T> Pos3<T>
-where
-    T: Signed,
-{
-    pub fn abs(self) -> T {
-        self.x.abs() + self.y.abs() + self.z.abs()
-    }
-}
-
 impl<T> fmt::Display for Pos3<T>
 where
     T: fmt::Display,
@@ -0,0 +1,84 @@
+use super::abs::Abs;
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub, Num, NumAssignOps, NumCast};
+use std::{fmt::Debug, hash::Hash};
+
+/**
+ * Everything positions and areas ask of their coordinates. All primitive
+ * integers are scalars, signed or not, so a method written against this
+ * exists for `Pos2<usize>` just as for `Pos2<i64>`.
+ */
+pub trait Scalar:
+    Num
+    + NumAssignOps
+    + NumCast
+    + Ord
+    + Copy
+    + Default
+    + Hash
+    + Debug
+    + CheckedAdd
+    + CheckedSub
+    + CheckedMul
+    + Abs
+{
+    /** -1, 0 or 1, never -1 for unsigned types */
+    fn signum(self) -> Self;
+}
+
+macro_rules! signed_impl {
+    ($($t:ty)*) => ($(
+        impl Scalar for $t {
+            #[inline]
+            fn signum(self) -> $t {
+                <$t>::signum(self)
+            }
+        }
+    )*)
+}
+signed_impl!(isize i8 i16 i32 i64 i128);
+
+macro_rules! unsigned_impl {
+    ($($t:ty)*) => ($(
+        impl Scalar for $t {
+            #[inline]
+            fn signum(self) -> $t {
+                if self == 0 { 0 } else { 1 }
+            }
+        }
+    )*)
+}
+unsigned_impl!(usize u8 u16 u32 u64 u128);
+
+#[cfg(test)]
+mod test {
+    use crate::common::{area::Area, pos2::Pos2, pos3::Pos3};
+
+    #[test]
+    fn signed_and_unsigned() {
+        assert_eq!(Pos2::new(-4i64, 6).normalize(), Ok((Pos2::new(-2, 3), 2)));
+        assert_eq!(Pos2::new(4usize, 6).normalize(), Ok((Pos2::new(2, 3), 2)));
+        assert_eq!(Pos2::new(0usize, 0).normalize(), Err(Pos2::new(0, 0)));
+        assert_eq!(Pos2::new(-4i64, 0).signum(), Pos2::new(-1, 0));
+        assert_eq!(Pos2::new(4usize, 0).signum(), Pos2::new(1, 0));
+        assert_eq!(Pos2::new(1usize, 5).taxicab_between(Pos2::new(3, 2)), 5);
+        assert_eq!(Pos2::new(usize::MAX, 0).checked_add(Pos2::new(1, 0)), None);
+
+        assert_eq!(
+            Pos3::new(0i64, -6, 9).normalize(),
+            Ok((Pos3::new(0, -2, 3), 3))
+        );
+        assert_eq!(
+            Pos3::new(0u8, 0, 7).normalize(),
+            Ok((Pos3::new(0, 0, 1), 7))
+        );
+        assert_eq!(Pos3::new(0i8, 0, 0).normalize(), Err(Pos3::new(0, 0, 0)));
+        assert_eq!(Pos3::new(3u32, 0, 1).signum(), Pos3::new(1, 0, 1));
+        assert!(Pos3::new(0u8, 1, 0).is_unit());
+        assert_eq!(Pos3::new(1i64, -2, 3).abs(), 6);
+        assert_eq!(Pos3::new(1u8, 2, 3).taxicab_between(Pos3::new(3, 2, 1)), 4);
+
+        let area = Area::new(Pos2::new(3usize, 1), Pos2::new(1, 2));
+        assert_eq!(area.area(), 6);
+        assert_eq!(area.cells(true).count(), 6);
+    }
+}
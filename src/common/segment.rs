@@ -0,0 +1,289 @@
+#![allow(dead_code)]
+use super::{math::gcd, pos2::Pos2};
+
+/**
+ * A straight line between two points of the integer grid, both ends included.
+ * It does not have to be axis aligned.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Segment {
+    start: Pos2<i64>,
+    end: Pos2<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intersection {
+    Point(Pos2<i64>),
+    /** The segments are collinear and share this part */
+    Overlap(Segment),
+}
+
+impl Intersection {
+    pub fn points(&self) -> Vec<Pos2<i64>> {
+        match self {
+            Intersection::Point(point) => vec![*point],
+            Intersection::Overlap(segment) => segment.points().collect(),
+        }
+    }
+}
+
+#[inline]
+fn cross(a: Pos2<i64>, b: Pos2<i64>) -> i64 {
+    a.x() * b.y() - a.y() * b.x()
+}
+
+#[inline]
+fn dot(a: Pos2<i64>, b: Pos2<i64>) -> i64 {
+    a.x() * b.x() + a.y() * b.y()
+}
+
+impl Segment {
+    pub fn new(start: Pos2<i64>, end: Pos2<i64>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> Pos2<i64> {
+        self.start
+    }
+
+    pub fn end(&self) -> Pos2<i64> {
+        self.end
+    }
+
+    fn delta(&self) -> Pos2<i64> {
+        self.end - self.start
+    }
+
+    pub fn is_point(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn is_axis_aligned(&self) -> bool {
+        self.start.x() == self.end.x() || self.start.y() == self.end.y()
+    }
+
+    /**
+     * The number of grid steps from one end to the other. A step goes to the
+     * next grid point on the segment, so for axis aligned segments this is
+     * just the length.
+     */
+    pub fn steps(&self) -> i64 {
+        let delta = self.delta();
+        gcd(delta.x().abs(), delta.y().abs()).unwrap_or_default()
+    }
+
+    /**
+     * All grid points on the segment, from start to end.
+     */
+    pub fn points(&self) -> impl Iterator<Item = Pos2<i64>> {
+        let steps = self.steps();
+        let step = if steps == 0 {
+            Pos2::default()
+        } else {
+            self.delta() / steps
+        };
+        let start = self.start;
+        (0..=steps).map(move |idx| start + step * idx)
+    }
+
+    pub fn contains(&self, point: Pos2<i64>) -> bool {
+        cross(point - self.start, self.delta()) == 0
+            && self.start.min_components(self.end).x() <= point.x()
+            && point.x() <= self.start.max_components(self.end).x()
+            && self.start.min_components(self.end).y() <= point.y()
+            && point.y() <= self.start.max_components(self.end).y()
+    }
+
+    /**
+     * Whether the segments touch at all, even if they cross between grid points.
+     */
+    pub fn intersects(&self, other: &Segment) -> bool {
+        let side =
+            |segment: &Segment, point| cross(segment.delta(), point - segment.start).signum();
+        let (d1, d2) = (side(other, self.start), side(other, self.end));
+        let (d3, d4) = (side(self, other.start), side(self, other.end));
+        if d1 * d2 < 0 && d3 * d4 < 0 {
+            return true;
+        }
+        other.contains(self.start)
+            || other.contains(self.end)
+            || self.contains(other.start)
+            || self.contains(other.end)
+    }
+
+    /**
+     * Where the segments meet. Crossings that do not fall on a grid point
+     * are not reported, use `intersects` for those.
+     */
+    pub fn intersection(&self, other: &Segment) -> Option<Intersection> {
+        if self.is_point() {
+            return other
+                .contains(self.start)
+                .then_some(Intersection::Point(self.start));
+        }
+        if other.is_point() {
+            return self
+                .contains(other.start)
+                .then_some(Intersection::Point(other.start));
+        }
+
+        let r = self.delta();
+        let s = other.delta();
+        let offset = other.start - self.start;
+        let denominator = cross(r, s);
+
+        if denominator == 0 {
+            if cross(offset, r) != 0 {
+                return None;
+            }
+            return self.collinear_overlap(other);
+        }
+
+        let (t, u, denominator) = if denominator < 0 {
+            (-cross(offset, s), -cross(offset, r), -denominator)
+        } else {
+            (cross(offset, s), cross(offset, r), denominator)
+        };
+        if !(0..=denominator).contains(&t) || !(0..=denominator).contains(&u) {
+            return None;
+        }
+        let scaled = r * t;
+        if scaled.x() % denominator != 0 || scaled.y() % denominator != 0 {
+            return None;
+        }
+        Some(Intersection::Point(self.start + scaled / denominator))
+    }
+
+    fn collinear_overlap(&self, other: &Segment) -> Option<Intersection> {
+        let r = self.delta();
+        let length = dot(r, r);
+        let position = |point| dot(point - self.start, r);
+
+        let (near, far) = if position(other.start) <= position(other.end) {
+            (other.start, other.end)
+        } else {
+            (other.end, other.start)
+        };
+        let start = if position(near) > 0 { near } else { self.start };
+        let end = if position(far) < length {
+            far
+        } else {
+            self.end
+        };
+
+        if position(start) > position(end) {
+            None
+        } else if start == end {
+            Some(Intersection::Point(start))
+        } else {
+            Some(Intersection::Overlap(Segment::new(start, end)))
+        }
+    }
+
+    /**
+     * The number of grid steps both segments share, zero unless they overlap.
+     */
+    pub fn overlap_length(&self, other: &Segment) -> i64 {
+        match self.intersection(other) {
+            Some(Intersection::Overlap(segment)) => segment.steps(),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::HashSet;
+
+    fn segment(x1: i64, y1: i64, x2: i64, y2: i64) -> Segment {
+        Segment::new(Pos2::new(x1, y1), Pos2::new(x2, y2))
+    }
+
+    #[test]
+    fn intersections() {
+        let horizontal = segment(0, 0, 10, 0);
+        assert_eq!(
+            horizontal.intersection(&segment(3, -2, 3, 5)),
+            Some(Intersection::Point(Pos2::new(3, 0)))
+        );
+        assert_eq!(horizontal.intersection(&segment(3, 1, 3, 5)), None);
+        assert_eq!(
+            horizontal.intersection(&segment(12, 0, 4, 0)),
+            Some(Intersection::Overlap(segment(4, 0, 10, 0)))
+        );
+        assert_eq!(horizontal.overlap_length(&segment(12, 0, 4, 0)), 6);
+        assert_eq!(
+            horizontal.intersection(&segment(10, 0, 15, 0)),
+            Some(Intersection::Point(Pos2::new(10, 0)))
+        );
+        assert_eq!(horizontal.intersection(&segment(0, 1, 10, 1)), None);
+
+        let diagonal = segment(0, 0, 4, 4);
+        assert_eq!(
+            diagonal.intersection(&segment(0, 4, 4, 0)),
+            Some(Intersection::Point(Pos2::new(2, 2)))
+        );
+        assert_eq!(diagonal.steps(), 4);
+        assert!(diagonal.contains(Pos2::new(3, 3)));
+        assert!(!diagonal.contains(Pos2::new(5, 5)));
+
+        let between = segment(0, 1, 1, 0);
+        assert!(segment(0, 0, 1, 1).intersects(&between));
+        assert_eq!(segment(0, 0, 1, 1).intersection(&between), None);
+    }
+
+    fn random_segment(rng: &mut StdRng, axis_aligned: bool) -> Segment {
+        let start = Pos2::new(rng.gen_range(-6..=6), rng.gen_range(-6..=6));
+        let end = if !axis_aligned {
+            Pos2::new(rng.gen_range(-6..=6), rng.gen_range(-6..=6))
+        } else if rng.gen_bool(0.5) {
+            Pos2::new(start.x(), rng.gen_range(-6..=6))
+        } else {
+            Pos2::new(rng.gen_range(-6..=6), start.y())
+        };
+        Segment::new(start, end)
+    }
+
+    /**
+     * Compares against the slow way: walk every grid point of both segments.
+     */
+    #[test]
+    fn matches_cell_by_cell() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for round in 0..5_000 {
+            let axis_aligned = round % 2 == 0;
+            let first = random_segment(&mut rng, axis_aligned);
+            let second = random_segment(&mut rng, axis_aligned);
+
+            let cells: HashSet<_> = first.points().collect();
+            let expected = second
+                .points()
+                .filter(|point| cells.contains(point))
+                .sorted_by_key(|point| (point.x(), point.y()))
+                .collect_vec();
+            let found = first
+                .intersection(&second)
+                .map(|intersection| intersection.points())
+                .unwrap_or_default()
+                .into_iter()
+                .sorted_by_key(|point| (point.x(), point.y()))
+                .collect_vec();
+            assert_eq!(found, expected, "{first:?} {second:?}");
+
+            assert_eq!(
+                first.overlap_length(&second),
+                (expected.len() as i64 - 1).max(0),
+                "{first:?} {second:?}"
+            );
+            if !expected.is_empty() {
+                assert!(first.intersects(&second));
+            }
+            for point in second.points() {
+                assert_eq!(first.contains(point), cells.contains(&point));
+            }
+        }
+    }
+}
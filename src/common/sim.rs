@@ -0,0 +1,167 @@
+#![allow(dead_code)]
+use super::events::{self, Event};
+use std::{collections::HashMap, hash::Hash};
+
+/**
+ * Anything that advances in discrete time steps.
+ * The fingerprint must capture everything that influences later steps,
+ * otherwise cycle detection reports repeats that are none.
+ */
+pub trait Simulation {
+    type Fingerprint: Eq + Hash;
+
+    fn step(&mut self);
+
+    fn fingerprint(&self) -> Self::Fingerprint;
+
+    fn render(&self) -> String;
+}
+
+/**
+ * The first state seen twice: it appeared after `start` steps
+ * and again `period` steps later.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /**
+     * The number of steps that lead to the same state as `steps` steps
+     * but stay within the first pass through the cycle.
+     */
+    pub fn equivalent_step(&self, steps: usize) -> usize {
+        if steps < self.start {
+            steps
+        } else {
+            self.start + (steps - self.start) % self.period
+        }
+    }
+}
+
+pub fn run<S: Simulation>(sim: &mut S, steps: usize) {
+    run_with(sim, steps, |_, _| {});
+}
+
+/**
+ * Like `run`, but calls `progress` after every step with the number of steps done so far.
+ * The progress is published as an event as well.
+ */
+pub fn run_with<S, F>(sim: &mut S, steps: usize, mut progress: F)
+where
+    S: Simulation,
+    F: FnMut(usize, &S),
+{
+    for done in 1..=steps {
+        sim.step();
+        progress(done, sim);
+        events::publish_with(|| Event::Progress {
+            task: events::short_type_name::<S>(),
+            done: done as u64,
+            total: Some(steps as u64),
+        });
+    }
+}
+
+/**
+ * Steps until the predicate holds and returns the number of steps taken.
+ * The starting state is checked as well, so this might return 0.
+ */
+pub fn run_until<S, P>(sim: &mut S, mut predicate: P) -> usize
+where
+    S: Simulation,
+    P: FnMut(&S) -> bool,
+{
+    let mut steps = 0;
+    while !predicate(sim) {
+        sim.step();
+        steps += 1;
+    }
+    steps
+}
+
+/**
+ * Steps until a state repeats. The simulation is left in the repeated state.
+ * Never returns if the simulation does not run into a cycle.
+ */
+pub fn run_until_repeat<S: Simulation>(sim: &mut S) -> Cycle {
+    let mut seen = HashMap::new();
+    seen.insert(sim.fingerprint(), 0);
+    for steps in 1.. {
+        sim.step();
+        if let Some(start) = seen.insert(sim.fingerprint(), steps) {
+            return Cycle {
+                start,
+                period: steps - start,
+            };
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Counter {
+        value: usize,
+        modulus: usize,
+    }
+
+    impl Simulation for Counter {
+        type Fingerprint = usize;
+
+        fn step(&mut self) {
+            self.value = (self.value * self.value + 1) % self.modulus;
+        }
+
+        fn fingerprint(&self) -> Self::Fingerprint {
+            self.value
+        }
+
+        fn render(&self) -> String {
+            self.value.to_string()
+        }
+    }
+
+    #[test]
+    fn drivers() {
+        let mut counter = Counter {
+            value: 0,
+            modulus: 11,
+        };
+        let mut seen = vec![];
+        run_with(&mut counter, 4, |step, sim| seen.push((step, sim.render())));
+        assert_eq!(
+            seen,
+            vec![
+                (1, "1".to_owned()),
+                (2, "2".to_owned()),
+                (3, "5".to_owned()),
+                (4, "4".to_owned())
+            ]
+        );
+
+        assert_eq!(run_until(&mut counter, |sim| sim.value == 6), 1);
+        assert_eq!(run_until(&mut counter, |sim| sim.value == 6), 0);
+
+        let mut counter = Counter {
+            value: 0,
+            modulus: 11,
+        };
+        let cycle = run_until_repeat(&mut counter);
+        assert_eq!(
+            cycle,
+            Cycle {
+                start: 4,
+                period: 2
+            }
+        );
+        assert_eq!(counter.value, 4);
+        assert_eq!(cycle.equivalent_step(3), 3);
+        assert_eq!(cycle.equivalent_step(10), 4);
+        assert_eq!(cycle.equivalent_step(11), 5);
+    }
+}
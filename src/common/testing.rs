@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MassDistribution {
+    Uniform,
+    /** Most modules are light, only a few get close to the maximum */
+    LightHeavy,
+}
+
+/**
+ * Creates synthetic module lists for day 1 style inputs.
+ * The same seed always gives the same list, so results can be compared across runs.
+ */
+#[derive(Debug, Clone)]
+pub struct ModuleMassGenerator {
+    count: usize,
+    min: u64,
+    max: u64,
+    distribution: MassDistribution,
+    seed: u64,
+}
+
+impl ModuleMassGenerator {
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            min: 1_000,
+            max: 150_000,
+            distribution: MassDistribution::Uniform,
+            seed: 0,
+        }
+    }
+
+    pub fn with_range(mut self, min: u64, max: u64) -> Self {
+        self.min = min.min(max);
+        self.max = min.max(max);
+        self
+    }
+
+    pub fn with_distribution(mut self, distribution: MassDistribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn masses(&self) -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let span = self.max - self.min;
+        (0..self.count)
+            .map(|_| {
+                let offset = match self.distribution {
+                    MassDistribution::Uniform => rng.gen_range(0..=span),
+                    MassDistribution::LightHeavy => {
+                        let factor: f64 = rng.gen_range(0.0..1.0);
+                        (span as f64 * factor * factor) as u64
+                    }
+                };
+                self.min + offset
+            })
+            .collect()
+    }
+
+    pub fn input(&self) -> String {
+        self.masses().into_iter().join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reproducible_masses() {
+        let generator = ModuleMassGenerator::new(100)
+            .with_range(10, 20)
+            .with_distribution(MassDistribution::LightHeavy)
+            .with_seed(42);
+        let masses = generator.masses();
+
+        assert_eq!(masses.len(), 100);
+        assert!(masses.iter().all(|mass| (10..=20).contains(mass)));
+        assert_eq!(masses, generator.masses());
+        assert_eq!(generator.input().lines().count(), 100);
+    }
+}
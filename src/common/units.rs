@@ -0,0 +1,117 @@
+#![allow(dead_code)]
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign},
+};
+
+macro_rules! unit {
+    ($($(#[$meta:meta])* $name:ident)*) => ($(
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(usize);
+
+        impl $name {
+            pub const ZERO: Self = Self(0);
+
+            #[inline]
+            pub const fn new(value: usize) -> Self {
+                Self(value)
+            }
+
+            #[inline]
+            pub const fn get(self) -> usize {
+                self.0
+            }
+
+            #[inline]
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            #[inline]
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+        }
+
+        /** Overflowing is a bug in the caller, so it panics even in release builds */
+        impl Add for $name {
+            type Output = Self;
+
+            #[inline]
+            fn add(self, other: Self) -> Self {
+                self.checked_add(other)
+                    .unwrap_or_else(|| panic!("{} overflowed", stringify!($name)))
+            }
+        }
+
+        impl AddAssign for $name {
+            #[inline]
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    )*)
+}
+
+unit! {
+    /** A number of single moves from one cell to the next */
+    Steps
+    /** How deep down a recursive structure something is, 0 being the outermost */
+    Level
+    /** Time passed in a simulation that advances a minute per round */
+    Minutes
+}
+
+impl Steps {
+    pub const ONE: Self = Self(1);
+}
+
+impl Level {
+    #[inline]
+    pub fn deeper(self) -> Self {
+        self + Self(1)
+    }
+
+    /** The level above, `None` on the outermost one */
+    #[inline]
+    pub fn higher(self) -> Option<Self> {
+        self.checked_sub(Self(1))
+    }
+
+    #[inline]
+    pub fn is_outermost(self) -> bool {
+        self == Self::ZERO
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checked_units() {
+        assert_eq!(Steps::new(3) + Steps::ONE, Steps::new(4));
+        assert_eq!(Steps::new(3).checked_sub(Steps::new(4)), None);
+        assert_eq!(Steps::new(usize::MAX).checked_add(Steps::ONE), None);
+
+        let level = Level::ZERO.deeper();
+        assert_eq!(level.higher(), Some(Level::ZERO));
+        assert_eq!(Level::ZERO.higher(), None);
+        assert!(!level.is_outermost());
+
+        assert_eq!(Minutes::new(12).to_string(), "12");
+    }
+
+    #[test]
+    #[should_panic(expected = "Steps overflowed")]
+    fn overflow_panics() {
+        let _ = Steps::new(usize::MAX) + Steps::ONE;
+    }
+}
@@ -0,0 +1,11 @@
+#![allow(unsafe_code)]
+#![warn(clippy::undocumented_unsafe_blocks)]
+
+// The only place `unsafe` is allowed, the crate denies it everywhere else.
+// Denied rather than forbidden, as forbidding it would not let this module
+// allow it again.
+//
+// Empty on purpose: so far, every primitive that was tried here was just
+// as fast written safely, see `helper::repeated`. Anything that comes here
+// needs a safe interface, a `SAFETY` comment on every unsafe block saying
+// why it is sound, tests, and a measured reason not to do it safely.
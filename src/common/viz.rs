@@ -0,0 +1,508 @@
+#![allow(dead_code)]
+use super::{
+    direction::Direction,
+    grid::{Grid, OffsetGrid, SparseGrid},
+    pos2::Pos2,
+};
+use itertools::Itertools;
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+pub trait CharTile {
+    fn as_char(&self) -> char;
+}
+
+impl CharTile for bool {
+    #[inline]
+    fn as_char(&self) -> char {
+        if *self {
+            '█'
+        } else {
+            ' '
+        }
+    }
+}
+
+impl CharTile for char {
+    #[inline]
+    fn as_char(&self) -> char {
+        *self
+    }
+}
+
+/**
+ * Anything that can be shown as a rectangle of chars.
+ * Implementors only describe single cells, the rendering itself is shared.
+ */
+pub trait RenderGrid {
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    fn cell_char(&self, x: usize, y: usize) -> char;
+
+    fn render_lines(&self) -> Vec<String> {
+        (0..self.height())
+            .map(|y| (0..self.width()).map(|x| self.cell_char(x, y)).collect())
+            .collect()
+    }
+
+    fn render(&self) -> String {
+        self.render_lines().into_iter().join("\n")
+    }
+}
+
+impl<T: CharTile> RenderGrid for Vec<Vec<T>> {
+    fn width(&self) -> usize {
+        self.iter().map(|row| row.len()).max().unwrap_or_default()
+    }
+
+    fn height(&self) -> usize {
+        self.len()
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        self.get(y)
+            .and_then(|row| row.get(x))
+            .map(CharTile::as_char)
+            .unwrap_or(' ')
+    }
+}
+
+impl<T: CharTile> RenderGrid for Grid<T> {
+    fn width(&self) -> usize {
+        Grid::width(self)
+    }
+
+    fn height(&self) -> usize {
+        Grid::height(self)
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        self.rows()[y][x].as_char()
+    }
+}
+
+impl<T: CharTile> RenderGrid for SparseGrid<T> {
+    fn width(&self) -> usize {
+        self.area().map(|area| area.width() as usize).unwrap_or(0)
+    }
+
+    fn height(&self) -> usize {
+        self.area().map(|area| area.height() as usize).unwrap_or(0)
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        self.get_relative(x, y)
+            .map(CharTile::as_char)
+            .unwrap_or(' ')
+    }
+}
+
+impl<T: CharTile> RenderGrid for OffsetGrid<T> {
+    fn width(&self) -> usize {
+        self.area().map(|area| area.width() as usize).unwrap_or(0)
+    }
+
+    fn height(&self) -> usize {
+        self.area().map(|area| area.height() as usize).unwrap_or(0)
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        self.get_relative(x, y)
+            .map(CharTile::as_char)
+            .unwrap_or(' ')
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathGlyphs {
+    pub east: char,
+    pub north: char,
+    pub west: char,
+    pub south: char,
+    pub start: char,
+    pub end: char,
+    /** Used for steps that are no straight line, like portals */
+    pub jump: char,
+}
+
+impl Default for PathGlyphs {
+    fn default() -> Self {
+        Self {
+            east: '>',
+            north: '^',
+            west: '<',
+            south: 'v',
+            start: 'S',
+            end: 'E',
+            jump: '*',
+        }
+    }
+}
+
+impl PathGlyphs {
+    pub fn arrow(&self, direction: Direction) -> char {
+        match direction {
+            Direction::East => self.east,
+            Direction::North => self.north,
+            Direction::West => self.west,
+            Direction::South => self.south,
+        }
+    }
+}
+
+/**
+ * A grid with a path drawn on top of it. Every cell of the path shows
+ * an arrow in the direction of the next step.
+ */
+pub struct PathOverlay<'a, G> {
+    grid: &'a G,
+    marks: HashMap<Pos2<usize>, char>,
+}
+
+impl<'a, G: RenderGrid> PathOverlay<'a, G> {
+    pub fn new(grid: &'a G, path: &[Pos2<usize>], glyphs: &PathGlyphs) -> Self {
+        Self {
+            grid,
+            marks: path_marks(path, glyphs),
+        }
+    }
+}
+
+/**
+ * The chars that draw a path. Where the path crosses itself, the later
+ * step wins.
+ */
+fn path_marks(path: &[Pos2<usize>], glyphs: &PathGlyphs) -> HashMap<Pos2<usize>, char> {
+    let mut marks = HashMap::new();
+    for (from, to) in path.iter().copied().tuple_windows() {
+        let Some(direction) = straight_direction(from, to) else {
+            marks.insert(from, glyphs.jump);
+            continue;
+        };
+        let mut pos = from;
+        while pos != to {
+            marks.insert(pos, glyphs.arrow(direction));
+            let Some(next) = pos.check_add(direction) else {
+                break;
+            };
+            pos = next;
+        }
+    }
+    if let Some(first) = path.first() {
+        marks.insert(*first, glyphs.start);
+    }
+    if let Some(last) = path.last() {
+        marks.insert(*last, glyphs.end);
+    }
+    marks
+}
+
+fn straight_direction(from: Pos2<usize>, to: Pos2<usize>) -> Option<Direction> {
+    use std::cmp::Ordering::*;
+    match (to.x().cmp(&from.x()), to.y().cmp(&from.y())) {
+        (Greater, Equal) => Some(Direction::East),
+        (Less, Equal) => Some(Direction::West),
+        (Equal, Less) => Some(Direction::North),
+        (Equal, Greater) => Some(Direction::South),
+        _ => None,
+    }
+}
+
+impl<G: RenderGrid> RenderGrid for PathOverlay<'_, G> {
+    fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        self.marks
+            .get(&Pos2::new(x, y))
+            .copied()
+            .unwrap_or_else(|| self.grid.cell_char(x, y))
+    }
+}
+
+pub fn overlay_path<G: RenderGrid>(grid: &G, path: &[Pos2<usize>], glyphs: &PathGlyphs) -> String {
+    PathOverlay::new(grid, path, glyphs).render()
+}
+
+/**
+ * The layers of a `Canvas`, from bottom to top. Where several layers
+ * have a char for the same cell, the topmost one is shown.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    /** The tiles of the grid itself, like walls and floor */
+    Background,
+    /** Routes drawn over the tiles */
+    Path,
+    /** Points of interest, which stay visible even on a route */
+    Marker,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Gray,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    /** The ANSI code that selects it as foreground color */
+    fn code(self) -> u8 {
+        match self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::Gray => 90,
+            Color::BrightRed => 91,
+            Color::BrightGreen => 92,
+            Color::BrightYellow => 93,
+            Color::BrightBlue => 94,
+            Color::BrightMagenta => 95,
+            Color::BrightCyan => 96,
+            Color::BrightWhite => 97,
+        }
+    }
+}
+
+/**
+ * The color of every layer. Layers without one keep the color of the
+ * terminal, so the plain theme renders no escape codes at all.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub background: Option<Color>,
+    pub path: Option<Color>,
+    pub marker: Option<Color>,
+}
+
+impl Theme {
+    pub const PLAIN: Theme = Theme {
+        background: None,
+        path: None,
+        marker: None,
+    };
+
+    pub const DARK: Theme = Theme {
+        background: Some(Color::Gray),
+        path: Some(Color::BrightYellow),
+        marker: Some(Color::BrightCyan),
+    };
+
+    pub const LIGHT: Theme = Theme {
+        background: Some(Color::Gray),
+        path: Some(Color::Blue),
+        marker: Some(Color::Red),
+    };
+
+    pub fn color(&self, layer: Layer) -> Option<Color> {
+        match layer {
+            Layer::Background => self.background,
+            Layer::Path => self.path,
+            Layer::Marker => self.marker,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::PLAIN
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown theme: {0} (known: plain, dark, light)")]
+pub struct UnknownTheme(String);
+
+impl FromStr for Theme {
+    type Err = UnknownTheme;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Theme::PLAIN),
+            "dark" => Ok(Theme::DARK),
+            "light" => Ok(Theme::LIGHT),
+            _ => Err(UnknownTheme(s.to_owned())),
+        }
+    }
+}
+
+static DEFAULT_THEME: OnceLock<Theme> = OnceLock::new();
+
+/**
+ * Sets the theme days render with. Can only be done once, before the
+ * first render. Returns false if it was too late.
+ */
+pub fn set_default_theme(theme: Theme) -> bool {
+    DEFAULT_THEME.set(theme).is_ok()
+}
+
+pub fn default_theme() -> Theme {
+    *DEFAULT_THEME.get_or_init(Theme::default)
+}
+
+/**
+ * A grid as background with layers of chars drawn on top. Which char a
+ * cell shows only depends on the layers, never on the order things were
+ * drawn in, except within the same layer, where the later one wins.
+ */
+pub struct Canvas<'a, G> {
+    background: &'a G,
+    layers: BTreeMap<Layer, HashMap<Pos2<usize>, char>>,
+}
+
+impl<'a, G: RenderGrid> Canvas<'a, G> {
+    pub fn new(background: &'a G) -> Self {
+        Self {
+            background,
+            layers: BTreeMap::new(),
+        }
+    }
+
+    pub fn draw(&mut self, layer: Layer, pos: Pos2<usize>, glyph: char) {
+        self.layers.entry(layer).or_default().insert(pos, glyph);
+    }
+
+    /** Draws the path like a `PathOverlay` does */
+    pub fn draw_path(&mut self, layer: Layer, path: &[Pos2<usize>], glyphs: &PathGlyphs) {
+        self.layers
+            .entry(layer)
+            .or_default()
+            .extend(path_marks(path, glyphs));
+    }
+
+    /** The char a cell shows and the layer it comes from */
+    pub fn top(&self, x: usize, y: usize) -> (Layer, char) {
+        let pos = Pos2::new(x, y);
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|(layer, marks)| Some((*layer, *marks.get(&pos)?)))
+            .unwrap_or_else(|| (Layer::Background, self.background.cell_char(x, y)))
+    }
+
+    /**
+     * Renders every cell in the color its layer has in the theme. The
+     * color only changes where the layer does.
+     */
+    pub fn render_themed(&self, theme: &Theme) -> String {
+        (0..self.height())
+            .map(|y| {
+                let mut line = String::new();
+                let cells = (0..self.width()).map(|x| self.top(x, y)).collect_vec();
+                for run in cells.chunk_by(|a, b| theme.color(a.0) == theme.color(b.0)) {
+                    let text: String = run.iter().map(|(_, glyph)| glyph).collect();
+                    match theme.color(run[0].0) {
+                        Some(color) => {
+                            line.push_str(&format!("\x1b[{}m{text}\x1b[0m", color.code()))
+                        }
+                        None => line.push_str(&text),
+                    }
+                }
+                line
+            })
+            .join("\n")
+    }
+}
+
+impl<G: RenderGrid> RenderGrid for Canvas<'_, G> {
+    fn width(&self) -> usize {
+        self.background.width()
+    }
+
+    fn height(&self) -> usize {
+        self.background.height()
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        self.top(x, y).1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_all_kinds() {
+        let picture = vec![vec![true, false], vec![false, true]];
+        assert_eq!(picture.render(), "█ \n █");
+
+        let grid = Grid::parse_padded("ab\nc", '.').unwrap();
+        assert_eq!(grid.render_lines(), vec!["ab", "c."]);
+
+        let mut sparse = SparseGrid::new();
+        sparse.insert(Pos2::new(-1, 5), '#');
+        sparse.insert(Pos2::new(1, 6), '@');
+        assert_eq!(sparse.render(), "#  \n  @");
+    }
+
+    #[test]
+    fn path_overlay() {
+        let grid = Grid::parse_padded("....\n....\n....", '.').unwrap();
+        let path = [
+            Pos2::new(0, 0),
+            Pos2::new(2, 0),
+            Pos2::new(2, 2),
+            Pos2::new(0, 1),
+            Pos2::new(0, 2),
+        ];
+        assert_eq!(
+            overlay_path(&grid, &path, &PathGlyphs::default()),
+            "S>v.\nv.v.\nE.*."
+        );
+    }
+
+    #[test]
+    fn layered_canvas() {
+        let grid = Grid::parse_padded("#...\n#..#", ' ').unwrap();
+        let mut canvas = Canvas::new(&grid);
+        // Markers win over the path, no matter which was drawn first
+        canvas.draw(Layer::Marker, Pos2::new(2, 0), 'k');
+        canvas.draw_path(
+            Layer::Path,
+            &[Pos2::new(1, 0), Pos2::new(3, 0), Pos2::new(3, 1)],
+            &PathGlyphs::default(),
+        );
+        canvas.draw(Layer::Marker, Pos2::new(1, 1), 'x');
+        canvas.draw(Layer::Marker, Pos2::new(1, 1), 'y');
+        assert_eq!(canvas.render(), "#Skv\n#y.E");
+        assert_eq!(canvas.top(0, 1), (Layer::Background, '#'));
+        assert_eq!(canvas.render_themed(&Theme::PLAIN), canvas.render());
+
+        let theme = "dark".parse::<Theme>().unwrap();
+        assert_eq!(
+            canvas.render_themed(&theme),
+            "\x1b[90m#\x1b[0m\x1b[93mS\x1b[0m\x1b[96mk\x1b[0m\x1b[93mv\x1b[0m\n\
+             \x1b[90m#\x1b[0m\x1b[96my\x1b[0m\x1b[90m.\x1b[0m\x1b[93mE\x1b[0m"
+        );
+        assert!("neon".parse::<Theme>().is_err());
+    }
+}
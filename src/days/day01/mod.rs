@@ -54,6 +54,23 @@ mod day_impl {
         get_fuel(input, func)
     }
 
+    /**
+     * Same as `get_complex_fuel`, written as plain loops.
+     * Only kept to compare both styles in the benchmark.
+     */
+    #[allow(dead_code)]
+    pub fn get_complex_fuel_loop(input: &str) -> Result<u64, DayError> {
+        let mut total = 0;
+        for line in input.lines() {
+            let mut mass = line.parse::<u64>()?;
+            while mass >= 9 {
+                mass = calc(mass);
+                total += mass;
+            }
+        }
+        Ok(total)
+    }
+
     fn get_fuel<F>(input: &str, func: F) -> Result<u64, DayError>
     where
         F: FnMut(u64) -> u64,
@@ -69,7 +86,11 @@ mod day_impl {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::days::{read_string, ResultType, UnitResult};
+    use crate::{
+        common::testing::{MassDistribution, ModuleMassGenerator},
+        days::{read_string, ResultType, UnitResult},
+    };
+    use std::time::Instant;
 
     #[test]
     fn test_part1() -> UnitResult {
@@ -92,4 +113,26 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored --nocapture"]
+    fn bench_pipeline_vs_loop() -> UnitResult {
+        let input = ModuleMassGenerator::new(1_000_000)
+            .with_distribution(MassDistribution::LightHeavy)
+            .with_seed(1)
+            .input();
+
+        let start = Instant::now();
+        let pipeline = day_impl::get_complex_fuel(&input)?;
+        let pipeline_time = start.elapsed();
+
+        let start = Instant::now();
+        let manual = day_impl::get_complex_fuel_loop(&input)?;
+        let manual_time = start.elapsed();
+
+        assert_eq!(pipeline, manual);
+        println!("pipeline: {pipeline_time:?}, loop: {manual_time:?}");
+
+        Ok(())
+    }
 }
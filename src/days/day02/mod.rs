@@ -1,5 +1,6 @@
 use super::{DayTrait, DayType, RResult};
-use crate::int_code::{ComputerFactory, Pointer};
+use crate::int_code::{ComputerError, ComputerFactory, Pointer};
+use itertools::Itertools;
 
 const DAY_NUMBER: DayType = 2;
 
@@ -12,10 +13,11 @@ impl DayTrait for Day {
 
     fn part1(&self, input: &str) -> RResult {
         let factory = ComputerFactory::init(input)?;
-        let mut computer = factory.build();
-
-        computer.manipulate_memory(Pointer::new(1), 12);
-        computer.manipulate_memory(Pointer::new(2), 2);
+        let mut computer = factory
+            .builder()
+            .set(Pointer::new(1), 12)
+            .set(Pointer::new(2), 2)
+            .build();
         computer.run_till_halt()?;
         Ok(computer.get_memory_value(Pointer::new(0)).into())
     }
@@ -23,27 +25,41 @@ impl DayTrait for Day {
     fn part2(&self, input: &str) -> RResult {
         let factory = ComputerFactory::init(input)?;
         let target = 19690720;
-        for noun in 0..100 {
-            for verb in 0..100 {
-                let mut computer = factory.build();
+        let results = factory.sweep(
+            (0..100).cartesian_product(0..100),
+            |computer, (noun, verb)| {
                 computer.manipulate_memory(Pointer::new(1), noun);
                 computer.manipulate_memory(Pointer::new(2), verb);
                 computer.run_till_halt()?;
-                if computer.get_memory_value(Pointer::new(0)) == target {
-                    return Ok((noun * 100 + verb).into());
-                }
+                Ok::<_, ComputerError>(
+                    (computer.get_memory_value(Pointer::new(0)) == target)
+                        .then_some(noun * 100 + verb),
+                )
+            },
+        );
+        for result in results {
+            if let Some(answer) = result? {
+                return Ok(answer.into());
             }
         }
-        unreachable!()
+        Err(DayError::NoNounAndVerb(target))?
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+enum DayError {
+    #[error("No noun and verb produce {0}")]
+    NoNounAndVerb(i64),
+}
+
 #[cfg(test)]
 mod test {
+    use super::*;
     use crate::{
-        days::UnitResult,
-        int_code::{ComputerFactory, Pointer},
+        days::{read_string, UnitResult},
+        int_code::IntCodeComputer,
     };
+    use std::time::Instant;
 
     #[test]
     fn simple() -> UnitResult {
@@ -58,4 +74,38 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored --nocapture"]
+    fn bench_noun_verb_search() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let factory = ComputerFactory::init(&input)?;
+        let run = |mut computer: IntCodeComputer, noun, verb| {
+            computer.manipulate_memory(Pointer::new(1), noun);
+            computer.manipulate_memory(Pointer::new(2), verb);
+            computer.run_till_halt()?;
+            Ok::<_, ComputerError>(computer.get_memory_value(Pointer::new(0)))
+        };
+
+        let start = Instant::now();
+        let mut shared = vec![];
+        for (noun, verb) in (0..100).cartesian_product(0..100) {
+            shared.push(run(factory.build(), noun, verb)?);
+        }
+        let shared_time = start.elapsed();
+
+        // Every computer gets a program of its own
+        let start = Instant::now();
+        let mut copied = vec![];
+        for (noun, verb) in (0..100).cartesian_product(0..100) {
+            let computer = ComputerFactory::new(factory.program().to_vec()).build();
+            copied.push(run(computer, noun, verb)?);
+        }
+        let copied_time = start.elapsed();
+
+        assert_eq!(shared, copied);
+        println!("shared program: {shared_time:?}, copied program: {copied_time:?}");
+
+        Ok(())
+    }
 }
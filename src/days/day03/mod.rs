@@ -1,7 +1,7 @@
 use super::{DayTrait, DayType, RResult};
-use crate::common::{direction::Direction, pos2::Pos2};
+use crate::common::{collections::FastMap, direction::Direction, pos2::Pos2, segment::Segment};
 use itertools::Itertools;
-use std::{collections::HashMap, num, str::FromStr};
+use std::{num, str::FromStr};
 
 const DAY_NUMBER: DayType = 3;
 
@@ -75,11 +75,16 @@ impl FromStr for Wire {
     }
 }
 impl Wire {
-    pub fn coords(&self) -> HashMap<Pos2<i64>, usize> {
+    /**
+     * Every point the wire visits with the steps needed to get there first.
+     * This walks cell by cell and only serves as a reference for `crossings`.
+     */
+    #[allow(dead_code)]
+    pub fn coords(&self) -> FastMap<Pos2<i64>, usize> {
         self.sections
             .iter()
             .fold(
-                (HashMap::new(), Pos2::default(), 0),
+                (FastMap::default(), Pos2::default(), 0),
                 |(mut coords, mut pos, mut steps), &(direction, dist)| {
                     for _ in 1..=dist {
                         pos += direction;
@@ -92,14 +97,52 @@ impl Wire {
             .0
     }
 
-    pub fn crossings(&self, other: &Wire) -> Vec<(Pos2<i64>, usize)> {
-        let other = other.coords();
-        self.coords()
+    /**
+     * The straight parts of the wire, each with the steps taken before it starts.
+     */
+    pub fn segments(&self) -> Vec<(Segment, usize)> {
+        self.sections
             .iter()
-            .filter_map(|(coord, steps1)| other.get(coord).map(|steps2| (*coord, steps1 + steps2)))
+            .scan((Pos2::default(), 0), |(pos, steps), &(direction, dist)| {
+                let start = *pos;
+                let before = *steps;
+                *pos = start + Pos2::from(direction) * dist;
+                *steps += dist as usize;
+                Some((Segment::new(start, *pos), before))
+            })
             .collect()
     }
 
+    /**
+     * All points both wires visit, except the origin, with the combined steps
+     * both wires need to get there first.
+     */
+    pub fn crossings(&self, other: &Wire) -> Vec<(Pos2<i64>, usize)> {
+        let other = other.segments();
+        let mut crossings = FastMap::default();
+        for (segment1, steps1) in self.segments() {
+            for (segment2, steps2) in &other {
+                let Some(intersection) = segment1.intersection(segment2) else {
+                    continue;
+                };
+                for point in intersection.points() {
+                    if point == Pos2::default() {
+                        continue;
+                    }
+                    let steps = steps1
+                        + steps2
+                        + segment1.start().taxicab_between(point) as usize
+                        + segment2.start().taxicab_between(point) as usize;
+                    crossings
+                        .entry(point)
+                        .and_modify(|known: &mut usize| *known = (*known).min(steps))
+                        .or_insert(steps);
+                }
+            }
+        }
+        crossings.into_iter().collect()
+    }
+
     fn parse_two(input: &str) -> Result<(Wire, Wire), DayError> {
         let mut wires: Vec<_> = input.lines().map(|line| line.parse()).try_collect()?;
         if wires.len() < 2 {
@@ -114,6 +157,7 @@ impl Wire {
 mod test {
     use super::*;
     use crate::days::{read_string, ResultType, UnitResult};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
     #[test]
     fn test_part1() -> UnitResult {
@@ -170,4 +214,38 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn crossings_match_cell_by_cell() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut random_wire = || Wire {
+            sections: (0..rng.gen_range(1..20))
+                .map(|_| {
+                    let direction = Direction::from(rng.gen_range(0..4u8));
+                    (direction, rng.gen_range(1..8))
+                })
+                .collect(),
+        };
+
+        for _ in 0..500 {
+            let wire1 = random_wire();
+            let wire2 = random_wire();
+            let other = wire2.coords();
+            let expected = wire1
+                .coords()
+                .into_iter()
+                .filter(|(coord, _)| *coord != Pos2::default())
+                .filter_map(|(coord, steps1)| {
+                    other.get(&coord).map(|steps2| (coord, steps1 + steps2))
+                })
+                .sorted_by_key(|(p, _)| (p.x(), p.y()))
+                .collect_vec();
+            let found = wire1
+                .crossings(&wire2)
+                .into_iter()
+                .sorted_by_key(|(p, _)| (p.x(), p.y()))
+                .collect_vec();
+            assert_eq!(found, expected);
+        }
+    }
 }
@@ -11,11 +11,13 @@ impl DayTrait for Day {
     }
 
     fn part1(&self, input: &str) -> RResult {
-        Ok(day_impl::check_range(input, day_impl::check_password)?.into())
+        let counts = day_impl::check_ranges(input, day_impl::check_password)?;
+        Ok(counts.iter().sum::<usize>().into())
     }
 
     fn part2(&self, input: &str) -> RResult {
-        Ok(day_impl::check_range(input, day_impl::check_better_password)?.into())
+        let counts = day_impl::check_ranges(input, day_impl::check_better_password)?;
+        Ok(counts.iter().sum::<usize>().into())
     }
 }
 
@@ -25,6 +27,10 @@ enum DayError {
     ParseError(String),
     #[error("Not an Int")]
     ParseIntError(#[from] num::ParseIntError),
+    #[error("Not a six digit number: {0}")]
+    NotSixDigits(u64),
+    #[error("Range must be ordered: {0}-{1}")]
+    RangeNotOrdered(u64, u64),
 }
 
 mod day_impl {
@@ -32,6 +38,18 @@ mod day_impl {
 
     use super::DayError;
 
+    const MIN_PASSWORD: u64 = 100_000;
+    const MAX_PASSWORD: u64 = 999_999;
+
+    fn parse_bound(input: &str) -> Result<u64, DayError> {
+        let bound = input.trim().parse()?;
+        if (MIN_PASSWORD..=MAX_PASSWORD).contains(&bound) {
+            Ok(bound)
+        } else {
+            Err(DayError::NotSixDigits(bound))
+        }
+    }
+
     pub fn check_range<F>(input: &str, func: F) -> Result<usize, DayError>
     where
         F: Fn(u64) -> bool,
@@ -39,11 +57,28 @@ mod day_impl {
         let Some((fst, snd)) = input.split_once('-') else {
             return Err(DayError::ParseError(input.to_owned()));
         };
-        let fst = fst.parse()?;
-        let snd = snd.parse()?;
+        let fst = parse_bound(fst)?;
+        let snd = parse_bound(snd)?;
+        if fst > snd {
+            return Err(DayError::RangeNotOrdered(fst, snd));
+        }
         Ok((fst..=snd).filter(|&num| func(num)).count())
     }
 
+    /**
+     * Checks comma separated ranges and returns the count for each of them.
+     */
+    pub fn check_ranges<F>(input: &str, func: F) -> Result<Vec<usize>, DayError>
+    where
+        F: Fn(u64) -> bool,
+    {
+        input
+            .trim()
+            .split(',')
+            .map(|range| check_range(range, &func))
+            .collect()
+    }
+
     pub fn extract_digits(number: u64) -> impl Iterator<Item = u64> {
         itertools::unfold(number, |number| {
             if *number > 0 {
@@ -140,4 +175,22 @@ mod test {
         assert!(!day_impl::check_better_password(123789));
         assert!(day_impl::check_better_password(111122));
     }
+
+    #[test]
+    fn check_ranges() -> Result<(), DayError> {
+        assert_eq!(
+            day_impl::check_ranges("123443-123445, 111110-111112", day_impl::check_password)?,
+            vec![2, 2]
+        );
+        assert!(matches!(
+            day_impl::check_ranges("12344-123445", day_impl::check_password),
+            Err(DayError::NotSixDigits(12344))
+        ));
+        assert!(matches!(
+            day_impl::check_ranges("123445-123443", day_impl::check_password),
+            Err(DayError::RangeNotOrdered(123445, 123443))
+        ));
+
+        Ok(())
+    }
 }
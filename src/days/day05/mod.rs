@@ -1,5 +1,5 @@
-use super::{DayTrait, DayType, RResult};
-use crate::int_code::ComputerFactory;
+use super::{DayTrait, DayType, ExtraSolver, RResult};
+use crate::int_code::{diagnostics, ComputerFactory};
 
 const DAY_NUMBER: DayType = 5;
 
@@ -12,32 +12,42 @@ impl DayTrait for Day {
 
     fn part1(&self, input: &str) -> RResult {
         let factory = ComputerFactory::init(input)?;
-        let mut computer = factory.build();
-        computer.send_i64(1);
-        let mut result = 0;
-        for output in computer.as_iter() {
-            result = output?;
-        }
-        Ok(result.into())
+        let report = diagnostics::run_with_input(&factory, 1)?;
+        Ok(report.final_code()?.into())
     }
 
     fn part2(&self, input: &str) -> RResult {
         let factory = ComputerFactory::init(input)?;
-        let mut computer = factory.build();
-        computer.send_i64(5);
-        let mut result = 0;
-        for output in computer.as_iter() {
-            result = output?;
-        }
-        Ok(result.into())
+        let report = diagnostics::run_with_input(&factory, 5)?;
+        Ok(report.final_code()?.into())
+    }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("self-modification", self_modification)]
+    }
+}
+
+/**
+ * Which parts of its own code the diagnostic program rewrites before it
+ * runs them, for both systems together.
+ */
+fn self_modification(input: &str) -> RResult {
+    let mut computer = ComputerFactory::init(input)?.build();
+    computer.enable_modification_tracking();
+    for system in [1, 5] {
+        computer.reset();
+        computer.send_i64(system);
+        computer.run_till_halt()?;
     }
+    let report = computer.modification_report().unwrap_or_default();
+    Ok(report.to_string().into())
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
         days::UnitResult,
-        int_code::{ComputerFactory, Pointer},
+        int_code::{diagnostics, ComputerError, ComputerFactory, Pointer},
     };
 
     #[test]
@@ -74,4 +84,24 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn diagnostic_report() -> UnitResult {
+        let factory = ComputerFactory::init("3,0,104,0,104,3,4,0,99")?;
+        let report = diagnostics::run_with_input(&factory, 42)?;
+        assert_eq!(report.outputs(), [0, 3, 42]);
+        assert_eq!(report.checks(), [0, 3]);
+        assert!(!report.passed());
+        assert!(matches!(
+            report.final_code(),
+            Err(ComputerError::DiagnosticCheckFailed(1, 3))
+        ));
+
+        let factory = ComputerFactory::init("3,0,104,0,4,0,99")?;
+        let report = diagnostics::run_with_input(&factory, 42)?;
+        assert!(report.passed());
+        assert_eq!(report.final_code()?, 42);
+
+        Ok(())
+    }
 }
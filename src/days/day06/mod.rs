@@ -1,4 +1,5 @@
 use super::{DayTrait, DayType, RResult};
+use crate::common::graph::strongly_connected_components;
 use itertools::Itertools;
 
 const DAY_NUMBER: DayType = 6;
@@ -25,6 +26,8 @@ impl DayTrait for Day {
 enum DayError {
     #[error("Not a valid description: {0}")]
     ParseError(String),
+    #[error("Objects orbit each other: {0}")]
+    OrbitCycle(String),
 }
 
 struct Orbit<'a>(&'a str, &'a str);
@@ -85,6 +88,12 @@ impl<'a> TryFrom<&'a str> for System<'a> {
         if parent.iter().skip(1).any(|item| item.is_none()) {
             return Err(DayError::ParseError(value.to_owned()));
         }
+        let components = strongly_connected_components(0..objects.len(), |idx| parent[*idx]);
+        if let Some(cycle) = components.cycles().next() {
+            return Err(DayError::OrbitCycle(
+                cycle.iter().map(|idx| objects[*idx]).sorted().join(", "),
+            ));
+        }
         Ok(System { objects, parent })
     }
 }
@@ -191,4 +200,13 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn orbit_cycle() {
+        let input = "COM)A\nB)C\nC)B";
+        assert!(matches!(
+            System::try_from(input),
+            Err(DayError::OrbitCycle(cycle)) if cycle == "B, C"
+        ));
+    }
 }
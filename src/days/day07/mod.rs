@@ -1,8 +1,14 @@
-use crate::int_code::{ComputerError, ComputerFactory, IntCodeComputer};
+use crate::int_code::{
+    cluster::{Cluster, Schedule},
+    ComputerError, ComputerFactory, IntCodeComputer,
+};
 
-use super::{DayTrait, DayType, RResult};
+use super::{DayTrait, DayType, ExtraSolver, RResult};
 use itertools::Itertools;
-use std::num;
+use std::{
+    collections::{HashMap, HashSet},
+    num,
+};
 
 const DAY_NUMBER: DayType = 7;
 
@@ -15,7 +21,7 @@ impl DayTrait for Day {
 
     fn part1(&self, input: &str) -> RResult {
         let amplifier = Amplifier::create(input)?;
-        let result = amplifier.max_once()?;
+        let result = amplifier.max_once(Strategy::Permutations)?;
         Ok(result.into())
     }
 
@@ -24,6 +30,24 @@ impl DayTrait for Day {
         let result = amplifier.max_recursive()?;
         Ok(result.into())
     }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![
+            ("memoized", |input| max_once(input, Strategy::Memoized)),
+            ("bounded", |input| max_once(input, Strategy::Bounded)),
+        ]
+    }
+}
+
+/**
+ * Part 1 with another search strategy, to compare them with the scan over all
+ * permutations. `bounded` trusts three probes per amplifier, so it is only an
+ * experiment and can miss the best chain of an amplifier that is not a line.
+ */
+fn max_once(input: &str, strategy: Strategy) -> RResult {
+    let amplifier = Amplifier::create(input)?;
+    let result = amplifier.max_once(strategy)?;
+    Ok(result.into())
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -34,6 +58,31 @@ enum DayError {
     ComputerError(#[from] ComputerError),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    Permutations,
+    Memoized,
+    Bounded,
+}
+
+/**
+ * What an amplifier with a fixed phase does to its signal,
+ * as far as three probes can tell: `slope * signal + offset`.
+ */
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    slope: i64,
+    offset: i64,
+}
+
+impl Line {
+    fn apply(&self, signal: i64) -> i64 {
+        self.slope
+            .saturating_mul(signal)
+            .saturating_add(self.offset)
+    }
+}
+
 struct Amplifier {
     factory: ComputerFactory,
 }
@@ -44,12 +93,139 @@ impl Amplifier {
         Ok(Self { factory })
     }
 
-    fn max_once(&self) -> Result<i64, DayError> {
-        self.max_result(0..5, |phase| self.run(&phase))
+    fn max_once(&self, strategy: Strategy) -> Result<i64, DayError> {
+        match strategy {
+            Strategy::Permutations => self.max_result(0..5, |phase| self.run(&phase, 0)),
+            Strategy::Memoized => self.max_memoized(0..5),
+            Strategy::Bounded => self.max_bounded(0..5),
+        }
+    }
+
+    /**
+     * Searches over partial phase assignments instead of all permutations.
+     * Two partial chains that used the same phases and produced the same intermediate
+     * signal must end in the same result, so only one of them is followed further.
+     */
+    fn max_memoized(&self, phase_values: std::ops::Range<i64>) -> Result<i64, DayError> {
+        let phases = phase_values.collect_vec();
+        let all_used = (1 << phases.len()) - 1;
+        let mut outputs = HashMap::new();
+        let mut seen = HashSet::new();
+        let mut best = i64::MIN;
+
+        let mut queue = vec![(0_usize, 0)];
+        while let Some((used, signal)) = queue.pop() {
+            if used == all_used {
+                best = best.max(signal);
+                continue;
+            }
+            for (idx, phase) in phases.iter().enumerate() {
+                if used & (1 << idx) != 0 {
+                    continue;
+                }
+                let output = match outputs.get(&(*phase, signal)) {
+                    Some(output) => *output,
+                    None => {
+                        let output = self.run(&[*phase], signal)?;
+                        outputs.insert((*phase, signal), output);
+                        output
+                    }
+                };
+                let next = (used | (1 << idx), output);
+                if seen.insert(next) {
+                    queue.push(next);
+                }
+            }
+        }
+
+        Ok(best)
+    }
+
+    /**
+     * The amplifier for `phase` as a line, if it looks like a non-decreasing one
+     * on three probes. Every amplifier of the puzzle does.
+     */
+    fn probe_line(&self, phase: i64) -> Result<Option<Line>, DayError> {
+        let offset = self.run(&[phase], 0)?;
+        let slope = self.run(&[phase], 1)? - offset;
+        let line = Line { slope, offset };
+        let check = 1_000;
+        if slope < 0 || self.run(&[phase], check)? != line.apply(check) {
+            return Ok(None);
+        }
+        Ok(Some(line))
+    }
+
+    /**
+     * Branch and bound over partial phase assignments. The lines are guessed
+     * from probes, not proven from the program, so an amplifier that bends
+     * between the probes can get the best chain pruned. If all amplifiers are
+     * non-decreasing lines, no chain can do better than feeding the intermediate
+     * signal through the steepest remaining line at every step, so branches
+     * whose bound does not beat the best chain so far are dropped.
+     * Promising branches are tried first to find a good chain early.
+     * Without lines there is no bound and this is a plain depth first search.
+     */
+    fn max_bounded(&self, phase_values: std::ops::Range<i64>) -> Result<i64, DayError> {
+        let phases = phase_values.collect_vec();
+        let lines: Vec<_> = phases
+            .iter()
+            .map(|phase| self.probe_line(*phase))
+            .try_collect()?;
+        let lines: Option<Vec<_>> = lines.into_iter().collect();
+
+        let bound = |used: usize, signal: i64| -> Option<i64> {
+            let lines = lines.as_ref()?;
+            let remaining = lines
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| used & (1 << idx) == 0)
+                .map(|(_, line)| line)
+                .collect_vec();
+            Some((0..remaining.len()).fold(signal, |signal, _| {
+                remaining
+                    .iter()
+                    .map(|line| line.apply(signal))
+                    .max()
+                    .unwrap_or(signal)
+            }))
+        };
+
+        let all_used = (1 << phases.len()) - 1;
+        let mut best = i64::MIN;
+        let mut stack = vec![(0_usize, 0)];
+        while let Some((used, signal)) = stack.pop() {
+            if used == all_used {
+                best = best.max(signal);
+                continue;
+            }
+            if bound(used, signal).is_some_and(|bound| bound <= best) {
+                continue;
+            }
+
+            let mut next: Vec<_> = phases
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| used & (1 << idx) == 0)
+                .map(|(idx, phase)| Ok((used | (1 << idx), self.run(&[*phase], signal)?)))
+                .try_collect::<_, _, DayError>()?;
+            // the stack takes the largest output first
+            next.sort_unstable_by_key(|(_, output)| *output);
+            stack.extend(next);
+        }
+
+        Ok(best)
     }
 
+    /**
+     * The feedback loop keeps every amplifier running across rounds, so a
+     * partial chain has no single intermediate signal to bound or to share.
+     * This stays a scan over all permutations.
+     */
     fn max_recursive(&self) -> Result<i64, DayError> {
-        self.max_result(5..10, |phase| self.run_recursive(&phase))
+        self.max_result(5..10, |phase| {
+            self.run_recursive(&phase, &Schedule::RoundRobin)
+        })
     }
 
     fn max_result<F>(&self, phase_values: std::ops::Range<i64>, func: F) -> Result<i64, DayError>
@@ -69,36 +245,30 @@ impl Amplifier {
     ) -> impl Iterator<Item = IntCodeComputer> + 'a {
         phase_values
             .iter()
-            .zip(std::iter::repeat_with(|| self.factory.build()))
-            .map(|(phase, mut computer)| {
-                computer.send_i64(*phase);
-                computer
-            })
+            .map(|phase| self.factory.builder().input(*phase).build())
     }
 
-    pub fn run(&self, phase_values: &[i64]) -> Result<i64, DayError> {
+    pub fn run(&self, phase_values: &[i64], signal: i64) -> Result<i64, DayError> {
         Ok(self
             .initialize_computers(phase_values)
-            .try_fold(0, |value, mut computer| {
+            .try_fold(signal, |value, mut computer| {
                 computer.send_i64(value);
                 computer.expect_i64()
             })?)
     }
 
-    pub fn run_recursive(&self, phase_values: &[i64]) -> Result<i64, DayError> {
-        let mut computers = self.initialize_computers(phase_values).collect_vec();
+    pub fn run_recursive(
+        &self,
+        phase_values: &[i64],
+        schedule: &Schedule,
+    ) -> Result<i64, DayError> {
+        let mut cluster = Cluster::ring(self.initialize_computers(phase_values).collect_vec());
+        cluster.send_i64(0, 0);
+        cluster.run(schedule)?;
 
-        let mut value = 0;
-        loop {
-            for computer in computers.iter_mut() {
-                computer.send_i64(value);
-                if let Some(next_value) = computer.maybe_i64()? {
-                    value = next_value;
-                } else {
-                    return Ok(value);
-                }
-            }
-        }
+        Ok(cluster
+            .last_output(phase_values.len() - 1)
+            .ok_or(ComputerError::PrematureEndOfOutput)?)
     }
 }
 
@@ -134,17 +304,17 @@ mod test {
         let day = Day {};
         let input = read_string(day.get_day_number(), "example01.txt")?;
         let amplifier = Amplifier::create(&input)?;
-        let result = amplifier.run(&[4, 3, 2, 1, 0])?;
+        let result = amplifier.run(&[4, 3, 2, 1, 0], 0)?;
         assert_eq!(result, 43210);
 
         let input = read_string(day.get_day_number(), "example02.txt")?;
         let amplifier = Amplifier::create(&input)?;
-        let result = amplifier.run(&[0, 1, 2, 3, 4])?;
+        let result = amplifier.run(&[0, 1, 2, 3, 4], 0)?;
         assert_eq!(result, 54321);
 
         let input = read_string(day.get_day_number(), "example03.txt")?;
         let amplifier = Amplifier::create(&input)?;
-        let result = amplifier.run(&[1, 0, 4, 3, 2])?;
+        let result = amplifier.run(&[1, 0, 4, 3, 2], 0)?;
         assert_eq!(result, 65210);
 
         Ok(())
@@ -155,22 +325,40 @@ mod test {
         let day = Day {};
         let input = read_string(day.get_day_number(), "example01.txt")?;
         let amplifier = Amplifier::create(&input)?;
-        let result = amplifier.max_once()?;
+        let result = amplifier.max_once(Strategy::Permutations)?;
         assert_eq!(result, 43210);
         Ok(())
     }
 
+    #[test]
+    fn max_once_strategies() -> UnitResult {
+        let day = Day {};
+        for file in [
+            "example01.txt",
+            "example02.txt",
+            "example03.txt",
+            "input.txt",
+        ] {
+            let input = read_string(day.get_day_number(), file)?;
+            let amplifier = Amplifier::create(&input)?;
+            let expected = amplifier.max_once(Strategy::Permutations)?;
+            assert_eq!(amplifier.max_once(Strategy::Memoized)?, expected);
+            assert_eq!(amplifier.max_once(Strategy::Bounded)?, expected);
+        }
+        Ok(())
+    }
+
     #[test]
     fn run_recursive() -> UnitResult {
         let day = Day {};
         let input = read_string(day.get_day_number(), "example04.txt")?;
         let amplifier = Amplifier::create(&input)?;
-        let result = amplifier.run_recursive(&[9, 8, 7, 6, 5])?;
+        let result = amplifier.run_recursive(&[9, 8, 7, 6, 5], &Schedule::RoundRobin)?;
         assert_eq!(result, 139629729);
 
         let input = read_string(day.get_day_number(), "example05.txt")?;
         let amplifier = Amplifier::create(&input)?;
-        let result = amplifier.run_recursive(&[9, 7, 8, 5, 6])?;
+        let result = amplifier.run_recursive(&[9, 7, 8, 5, 6], &Schedule::RoundRobin)?;
         assert_eq!(result, 18216);
 
         Ok(())
@@ -186,4 +374,28 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn schedule_invariance() -> UnitResult {
+        let day = Day {};
+        let schedules = [
+            Schedule::RoundRobin,
+            Schedule::Priority(vec![4, 3, 2, 1, 0]),
+            Schedule::Priority(vec![2, 0, 4, 1, 3]),
+            Schedule::Random(7),
+            Schedule::Random(2019),
+        ];
+        for (file, phases, expected) in [
+            ("example04.txt", [9, 8, 7, 6, 5], 139629729),
+            ("example05.txt", [9, 7, 8, 5, 6], 18216),
+        ] {
+            let input = read_string(day.get_day_number(), file)?;
+            let amplifier = Amplifier::create(&input)?;
+            for schedule in &schedules {
+                assert_eq!(amplifier.run_recursive(&phases, schedule)?, expected);
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -30,6 +30,38 @@ enum DayError {
     ParseError(String),
     #[error("no complete picture Found")]
     NoCompletePictureFound,
+    #[error("Input length {0} is not a multiple of the layer size {1}")]
+    IncompleteLayer(usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LayerStats {
+    histogram: [usize; 10],
+}
+
+impl LayerStats {
+    fn new(layer: &[u8]) -> Self {
+        let mut histogram = [0; 10];
+        for digit in layer {
+            histogram[*digit as usize] += 1;
+        }
+        Self { histogram }
+    }
+
+    #[inline]
+    pub fn count(&self, digit: u8) -> usize {
+        self.histogram[digit as usize]
+    }
+
+    #[inline]
+    #[allow(dead_code)]
+    pub fn histogram(&self) -> &[usize; 10] {
+        &self.histogram
+    }
+
+    pub fn checksum(&self, first: u8, second: u8) -> usize {
+        self.count(first) * self.count(second)
+    }
 }
 
 struct Picture {
@@ -43,6 +75,10 @@ impl Picture {
         if input.chars().any(|c| !('0'..='2').contains(&c)) {
             return Err(DayError::ParseError(input.to_owned()));
         }
+        let layer_size = cols * rows;
+        if input.is_empty() || !input.len().is_multiple_of(layer_size) {
+            return Err(DayError::IncompleteLayer(input.len(), layer_size));
+        }
         let layers = input
             .chars()
             .map(|c| c.to_digit(10).unwrap() as u8)
@@ -53,21 +89,19 @@ impl Picture {
         Ok(Self { layers, cols, rows })
     }
 
-    pub fn count_numbers(&self) -> usize {
-        let (_, ones, twos) = self
-            .layers
+    pub fn layer_stats(&self) -> Vec<LayerStats> {
+        self.layers
             .iter()
-            .map(|layer| {
-                let counts = layer.iter().counts();
-                (
-                    counts.get(&0).copied().unwrap_or_default(),
-                    counts.get(&1).copied().unwrap_or_default(),
-                    counts.get(&2).copied().unwrap_or_default(),
-                )
-            })
-            .min_by_key(|(zeros, _, _)| *zeros)
-            .unwrap();
-        ones * twos
+            .map(|layer| LayerStats::new(layer))
+            .collect()
+    }
+
+    pub fn count_numbers(&self) -> usize {
+        self.layer_stats()
+            .into_iter()
+            .min_by_key(|stats| stats.count(0))
+            .map(|stats| stats.checksum(1, 2))
+            .unwrap_or_default()
     }
 
     pub fn decode(&self) -> Result<Vec<Vec<bool>>, DayError> {
@@ -98,3 +132,26 @@ impl Picture {
             .collect_vec())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layer_stats() -> Result<(), DayError> {
+        let picture = Picture::parse("122012110220", 3, 2)?;
+        let stats = picture.layer_stats();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].histogram()[0..3], [1, 2, 3]);
+        assert_eq!(stats[1].checksum(1, 2), 4);
+        assert_eq!(picture.count_numbers(), 6);
+
+        assert!(matches!(
+            Picture::parse("1220121102", 3, 2),
+            Err(DayError::IncompleteLayer(10, 6))
+        ));
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::int_code::ComputerFactory;
+use crate::int_code::{diagnostics, memory::DenseMemory, ComputerFactory};
 
 use super::{DayTrait, DayType, RResult};
 use std::num;
@@ -14,24 +14,15 @@ impl DayTrait for Day {
 
     fn part1(&self, input: &str) -> RResult {
         let factory = ComputerFactory::init(input)?;
-        let mut computer = factory.build();
-        computer.send_i64(1);
-        let mut result = 0;
-        for output in computer.as_iter() {
-            result = output?;
-        }
-        Ok(result.into())
+        let report = diagnostics::run_with_input(&factory, 1)?;
+        Ok(report.final_code()?.into())
     }
 
     fn part2(&self, input: &str) -> RResult {
         let factory = ComputerFactory::init(input)?;
-        let mut computer = factory.build();
-        computer.send_i64(2);
-        let mut result = 0;
-        for output in computer.as_iter() {
-            result = output?;
-        }
-        Ok(result.into())
+        // A private copy spares the long run the fall through to the shared program
+        let report = diagnostics::run_on::<DenseMemory>(&factory, 2)?;
+        Ok(report.final_code()?.into())
     }
 }
 
@@ -43,8 +34,16 @@ enum DayError {
 
 #[cfg(test)]
 mod test {
-    use crate::{days::UnitResult, int_code::ComputerFactory};
+    use super::*;
+    use crate::{
+        days::{read_string, UnitResult},
+        int_code::{
+            memory::{Memory, SharedMemory, SparseMemory},
+            Arithmetic, ComputerError,
+        },
+    };
     use itertools::Itertools;
+    use std::time::Instant;
 
     #[test]
     fn copy() -> UnitResult {
@@ -58,4 +57,46 @@ mod test {
         assert_eq!(result, input);
         Ok(())
     }
+
+    #[test]
+    fn boost_never_overflows() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let factory = ComputerFactory::init(&input)?;
+        for mode in [1, 2] {
+            let mut computer = factory.build();
+            computer.set_arithmetic(Arithmetic::Checked);
+            computer.send_i64(mode);
+            let checked: Vec<_> = computer.as_iter().try_collect()?;
+
+            let mut computer = factory.build();
+            computer.send_i64(mode);
+            let wrapping: Vec<_> = computer.as_iter().try_collect()?;
+            assert_eq!(checked, wrapping);
+        }
+        assert_eq!("checked".parse::<Arithmetic>()?, Arithmetic::Checked);
+        assert!("saturating".parse::<Arithmetic>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored --nocapture"]
+    fn bench_memory_backends() -> UnitResult {
+        fn boost<M: Memory>(factory: &ComputerFactory) -> Result<Vec<i64>, ComputerError> {
+            let start = Instant::now();
+            let mut computer = factory.build_with::<M>();
+            computer.send_i64(2);
+            let outputs = computer.as_iter().try_collect()?;
+            println!("{}: {:?}", std::any::type_name::<M>(), start.elapsed());
+            Ok(outputs)
+        }
+
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let factory = ComputerFactory::init(&input)?;
+        let shared = boost::<SharedMemory>(&factory)?;
+        assert_eq!(boost::<DenseMemory>(&factory)?, shared);
+        assert_eq!(boost::<SparseMemory>(&factory)?, shared);
+
+        Ok(())
+    }
 }
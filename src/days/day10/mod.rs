@@ -1,7 +1,14 @@
 use super::{DayTrait, DayType, RResult};
 use crate::common::pos2::Pos2;
 use itertools::Itertools;
-use std::{cell::RefCell, num, str::FromStr};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    num,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 const DAY_NUMBER: DayType = 10;
 
@@ -63,20 +70,45 @@ impl AsteroidField {
         Ok(Self { objects })
     }
 
+    #[allow(dead_code)]
     fn count_seen_at(&self, station: Pos2<i64>) -> usize {
-        self.objects
-            .iter()
-            .filter_map(|&pos| (pos - station).normalize().map(|(pos, _)| pos).ok())
-            .unique()
-            .count()
+        self.count_seen_reaching(station, 0).unwrap_or_default()
     }
 
+    /**
+     * Like `count_seen_at`, but gives up with `None` as soon as even seeing
+     * all the asteroids not looked at yet would stay below `at_least`.
+     */
+    fn count_seen_reaching(&self, station: Pos2<i64>, at_least: usize) -> Option<usize> {
+        let mut directions = HashSet::new();
+        for (looked_at, &pos) in self.objects.iter().enumerate() {
+            if directions.len() + (self.objects.len() - looked_at) < at_least {
+                return None;
+            }
+            if let Ok((direction, _)) = (pos - station).normalize() {
+                directions.insert(direction);
+            }
+        }
+        Some(directions.len())
+    }
+
+    /**
+     * Counts for all asteroids in parallel. Every count shares the best one
+     * so far, so asteroids that cannot reach it stop counting early. Of
+     * several equally good places the last one wins, like it always did.
+     */
     fn best_place_for_station(&self) -> Pos2<i64> {
+        let best = AtomicUsize::new(0);
         self.objects
-            .iter()
-            .map(|&pos| (pos, self.count_seen_at(pos)))
-            .max_by_key(|&(_, count)| count)
-            .map(|(pos, _)| pos)
+            .par_iter()
+            .enumerate()
+            .filter_map(|(index, &pos)| {
+                let count = self.count_seen_reaching(pos, best.load(Ordering::Relaxed))?;
+                best.fetch_max(count, Ordering::Relaxed);
+                Some((count, index, pos))
+            })
+            .max_by_key(|&(count, index, _)| (count, index))
+            .map(|(_, _, pos)| pos)
             .unwrap()
     }
 
@@ -241,6 +273,8 @@ impl Station {
 mod test {
     use super::*;
     use crate::days::{read_string, ResultType, UnitResult};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::time::Instant;
 
     #[test]
     fn test_part1() -> UnitResult {
@@ -274,6 +308,37 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored --nocapture"]
+    fn bench_dense_field() -> UnitResult {
+        let mut rng = StdRng::seed_from_u64(10);
+        let raw = (0..80)
+            .map(|_| (0..80).map(|_| rng.gen_range(0..3) != 0).collect_vec())
+            .collect_vec();
+        let field = AsteroidField::new(raw)?;
+
+        let start = Instant::now();
+        let serial = field
+            .objects
+            .iter()
+            .map(|&pos| (pos, field.count_seen_at(pos)))
+            .max_by_key(|&(_, count)| count)
+            .map(|(pos, _)| pos);
+        let serial_time = start.elapsed();
+
+        let start = Instant::now();
+        let parallel = field.best_place_for_station();
+        let parallel_time = start.elapsed();
+
+        assert_eq!(serial, Some(parallel));
+        println!(
+            "{} asteroids, serial: {serial_time:?}, parallel with bound: {parallel_time:?}",
+            field.objects.len()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn shooting_some() -> UnitResult {
         let day = Day {};
@@ -1,10 +1,18 @@
 use crate::{
-    common::{area::Area, direction::Direction, pos2::Pos2, turn::Turn},
-    int_code::{ComputerError, ComputerFactory, IntCodeComputer},
+    common::{
+        area::Area,
+        direction::Direction,
+        grid::OffsetGrid,
+        pos2::Pos2,
+        turn::Turn,
+        viz::{CharTile, RenderGrid},
+    },
+    int_code::{ComputerError, ComputerFactory, IntCodeComputer, Observer},
 };
 
-use super::{DayTrait, DayType, RResult};
-use std::collections::HashMap;
+use super::{DayTrait, DayType, ExtraSolver, RResult};
+use itertools::Itertools;
+use std::{collections::BTreeMap, fmt::Display};
 
 const DAY_NUMBER: DayType = 11;
 
@@ -24,62 +32,229 @@ impl DayTrait for Day {
     fn part2(&self, input: &str) -> RResult {
         let mut robby = Robot::new(input)?;
         robby.run(true)?;
-        Ok(robby.get_picture().into())
+        Ok(robby.hull().render_lines().into())
+    }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("hull-report", hull_report)]
     }
 }
 
+/**
+ * How the robot covered the hull, for both starting colors.
+ */
+fn hull_report(input: &str) -> RResult {
+    let mut lines = vec![];
+    for (name, starting_color) in [("black", false), ("white", true)] {
+        let mut robby = Robot::new(input)?;
+        robby.run(starting_color)?;
+        lines.push(format!("Starting on {name}:"));
+        lines.extend(robby.report().to_string().lines().map(str::to_owned));
+    }
+    Ok(lines.into())
+}
+
 #[derive(Debug, thiserror::Error)]
 enum DayError {
     #[error("Computer Error")]
     ComputerError(#[from] ComputerError),
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Panel {
+    white: bool,
+    /** The starting panel gets its color before the robot paints anything */
+    paints: usize,
+}
+
+impl CharTile for Panel {
+    fn as_char(&self) -> char {
+        self.white.as_char()
+    }
+}
+
+/**
+ * The body of the robot, it follows the orders of the brain.
+ * The brain alternately outputs the color to paint and the direction to turn.
+ */
+#[derive(Default)]
+struct Painter {
+    hull: OffsetGrid<Panel>,
+    pos: Pos2<i64>,
+    facing: Direction,
+    painted: bool,
+    moves: usize,
+}
+
+impl Observer for Painter {
+    type Error = DayError;
+
+    fn on_output(&mut self, value: i64) -> Result<(), Self::Error> {
+        if !self.painted {
+            let paints = self.hull.get(&self.pos).map_or(0, |panel| panel.paints);
+            self.hull.insert(
+                self.pos,
+                Panel {
+                    white: value != 0,
+                    paints: paints + 1,
+                },
+            );
+        } else {
+            self.facing = self.facing + if value != 0 { Turn::Right } else { Turn::Left };
+            self.pos += self.facing;
+            self.moves += 1;
+        }
+        self.painted = !self.painted;
+        Ok(())
+    }
+
+    fn on_input_request(&mut self) -> Result<Option<i64>, Self::Error> {
+        let white = self.hull.get(&self.pos).is_some_and(|panel| panel.white);
+        Ok(Some(white as i64))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HullReport {
+    area: Option<Area<i64>>,
+    touched: usize,
+    white: usize,
+    black: usize,
+    moves: usize,
+    /** How many panels were painted how often */
+    paints: BTreeMap<usize, usize>,
+}
+
+impl HullReport {
+    /** Panels the robot painted more than once */
+    pub fn repainted(&self) -> usize {
+        self.paints
+            .iter()
+            .filter(|(paints, _)| **paints > 1)
+            .map(|(_, panels)| panels)
+            .sum()
+    }
+}
+
+impl Display for HullReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.area {
+            Some(area) => writeln!(
+                f,
+                "area {area}, {}x{} = {} panels",
+                area.width(),
+                area.height(),
+                area.area()
+            )?,
+            None => writeln!(f, "nothing painted")?,
+        }
+        writeln!(
+            f,
+            "touched {} panels in {} moves: {} white, {} black",
+            self.touched, self.moves, self.white, self.black
+        )?;
+        writeln!(
+            f,
+            "repainted {} panels, painted (times: panels) {}",
+            self.repainted(),
+            self.paints
+                .iter()
+                .map(|(paints, panels)| format!("{paints}: {panels}"))
+                .join(", ")
+        )
+    }
+}
+
 struct Robot {
     brain: IntCodeComputer,
-    tiles: HashMap<Pos2<i64>, bool>,
+    painter: Painter,
 }
 
 impl Robot {
     pub fn new(code: &str) -> Result<Self, DayError> {
         Ok(Self {
             brain: ComputerFactory::init(code)?.build(),
-            tiles: HashMap::new(),
+            painter: Painter {
+                facing: Direction::North,
+                ..Default::default()
+            },
         })
     }
 
     pub fn run(&mut self, starting_color: bool) -> Result<(), DayError> {
-        let mut pos = Pos2::splat(0);
-        let mut facing = Direction::North;
-        self.tiles.insert(pos, starting_color);
-        self.brain.send_bool(starting_color);
-        while let Some(color) = self.brain.maybe_bool()? {
-            self.tiles.insert(pos, color);
-            let turn_right = self.brain.expect_bool()?;
-            facing = facing + if turn_right { Turn::Right } else { Turn::Left };
-            pos += facing;
-            let color = self.tiles.get(&pos).copied().unwrap_or(false);
-            self.brain.send_bool(color);
-        }
+        self.painter.hull.insert(
+            self.painter.pos,
+            Panel {
+                white: starting_color,
+                paints: 0,
+            },
+        );
+        self.brain.run_observed(&mut self.painter)?;
         Ok(())
     }
 
     pub fn get_touched_tiles(&self) -> usize {
-        self.tiles.len()
-    }
-
-    pub fn get_picture(&self) -> Vec<Vec<bool>> {
-        let Some(area) = Area::from_iterator(self.tiles.keys()) else {
-            return vec![vec![]];
-        };
-        self.tiles.iter().fold(
-            vec![vec![false; area.width() as usize]; area.height() as usize],
-            |mut picture, (pos, color)| {
-                if *color {
-                    picture[(pos.y() - area.bottom()) as usize][(pos.x() - area.left()) as usize] =
-                        true
-                };
-                picture
-            },
-        )
+        self.painter.hull.len()
+    }
+
+    pub fn hull(&self) -> &OffsetGrid<Panel> {
+        &self.painter.hull
+    }
+
+    pub fn report(&self) -> HullReport {
+        let hull = &self.painter.hull;
+        let white = hull.iter().filter(|(_, panel)| panel.white).count();
+        HullReport {
+            area: hull.area(),
+            touched: hull.len(),
+            white,
+            black: hull.len() - white,
+            moves: self.painter.moves,
+            paints: hull
+                .iter()
+                .map(|(_, panel)| panel.paints)
+                .counts()
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{days::UnitResult, int_code::assembler::assemble};
+
+    #[test]
+    fn report() -> UnitResult {
+        // Paints white and turns right four times, ending up on the first panel again
+        let code = assemble(
+            "
+                    IN color
+                    OUT #1
+                    OUT #1
+                    ADD count, #1, count
+                    LT count, #5, flag
+                    JT flag, #0
+                    HALT
+            color:  DATA 0
+            count:  DATA 0
+            flag:   DATA 0
+            ",
+        )?;
+        let code = code.iter().join(",");
+        let mut robby = Robot::new(&code)?;
+        robby.run(false)?;
+
+        let report = robby.report();
+        assert_eq!(report.touched, 4);
+        assert_eq!(report.white, 4);
+        assert_eq!(report.moves, 5);
+        assert_eq!(report.paints, BTreeMap::from([(1, 3), (2, 1)]));
+        assert_eq!(report.repainted(), 1);
+        assert_eq!(report.area.map(|area| area.area()), Some(4));
+        assert_eq!(robby.hull().render_lines(), vec!["██", "██"]);
+
+        Ok(())
     }
 }
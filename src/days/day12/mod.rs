@@ -1,8 +1,8 @@
 use super::{DayTrait, DayType, RResult};
 use crate::common::math::lcm;
 use crate::common::pos3::Pos3;
+use crate::common::sim::{self, Simulation};
 use itertools::Itertools;
-use std::collections::HashMap;
 use std::iter::Sum;
 use std::ops::{Add, Sub};
 use std::{num, str::FromStr};
@@ -70,7 +70,7 @@ enum DayError {
     ParseIntError(#[from] num::ParseIntError),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Moon {
     location: Pos3<i64>,
     velocity: Pos3<i64>,
@@ -168,30 +168,62 @@ impl System {
         self.moons.iter().map(|moon| moon.energy()).sum()
     }
 
-    pub fn tick(self, times: usize) -> Self {
-        let mut data = self.moons;
-        for _ in 0..times {
-            data = Moon::tick(data)
+    pub fn tick(mut self, times: usize) -> Self {
+        sim::run(&mut self, times);
+        self
+    }
+
+    /**
+     * The total energy after every tick, starting with the first one.
+     */
+    #[allow(dead_code)]
+    pub fn simulate_iter(&self) -> impl Iterator<Item = i64> {
+        let mut system = Self {
+            moons: self.moons.clone(),
+        };
+        std::iter::from_fn(move || {
+            system.step();
+            Some(system.energy())
+        })
+    }
+
+    fn axis(&self, index: usize) -> Axis {
+        Axis(
+            self.moons
+                .iter()
+                .map(|moon| (moon.location()[index], moon.velocity()[index]))
+                .collect_vec(),
+        )
+    }
+
+    /**
+     * The first tick after a start at rest at which the axis is at rest again,
+     * together with the axis at that tick. The dynamics are time reversible,
+     * so from there the moons run back to the start in as many ticks again.
+     * Without a start at rest this does not hold.
+     */
+    fn first_rest(&self, index: usize) -> Option<(usize, Axis)> {
+        let mut axis = self.axis(index);
+        if !axis.at_rest() {
+            return None;
         }
-        Self { moons: data }
+        let tick = (1..).find(|_| {
+            axis.step();
+            axis.at_rest()
+        })?;
+        Some((tick, axis))
     }
 
     fn repeat_one(&self, index: usize) -> usize {
-        let mut data = self
-            .moons
-            .iter()
-            .map(|moon| (moon.location()[index], moon.velocity()[index]))
-            .collect_vec();
-        let mut seen = HashMap::new();
-        seen.insert(data.clone(), 0);
-        for round in 1.. {
-            data = Moonish::tick(data);
-            if let Some(prev) = seen.get(&data) {
-                return round - *prev;
+        if let Some((half, axis)) = self.first_rest(index) {
+            // already back at the start, so the half period is the whole one
+            if axis.fingerprint() == self.axis(index).fingerprint() {
+                return half;
             }
-            seen.insert(data.clone(), round);
+            return 2 * half;
         }
-        unreachable!()
+
+        sim::run_until_repeat(&mut self.axis(index)).period
     }
 
     pub fn test_repeat(self) -> usize {
@@ -199,6 +231,55 @@ impl System {
     }
 }
 
+impl Simulation for System {
+    type Fingerprint = Vec<Moon>;
+
+    fn step(&mut self) {
+        self.moons = Moon::tick(std::mem::take(&mut self.moons));
+    }
+
+    fn fingerprint(&self) -> Self::Fingerprint {
+        self.moons.clone()
+    }
+
+    fn render(&self) -> String {
+        self.moons
+            .iter()
+            .map(|moon| format!("pos={}, vel={}", moon.location, moon.velocity))
+            .join("\n")
+    }
+}
+
+/**
+ * The axes are independent of each other, so each one can be simulated on its own.
+ */
+struct Axis(Vec<(i64, i64)>);
+
+impl Axis {
+    fn at_rest(&self) -> bool {
+        self.0.iter().all(|(_, velocity)| *velocity == 0)
+    }
+}
+
+impl Simulation for Axis {
+    type Fingerprint = Vec<(i64, i64)>;
+
+    fn step(&mut self) {
+        self.0 = Moonish::tick(std::mem::take(&mut self.0));
+    }
+
+    fn fingerprint(&self) -> Self::Fingerprint {
+        self.0.clone()
+    }
+
+    fn render(&self) -> String {
+        self.0
+            .iter()
+            .map(|(location, velocity)| format!("{location} {velocity}"))
+            .join("\n")
+    }
+}
+
 impl Sign for i64 {
     #[inline]
     fn sign(self) -> Self {
@@ -327,4 +408,71 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn energy_series() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "example01.txt")?;
+        let (ticks, system) = System::parse(&input)?;
+
+        let energies = system.simulate_iter().take(ticks).collect_vec();
+        assert_eq!(energies.len(), ticks);
+        assert_eq!(energies.last(), Some(&179));
+
+        Ok(())
+    }
+
+    #[test]
+    fn half_period() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "example01.txt")?;
+        let (_, system) = System::parse(&input)?;
+
+        for index in 0..3 {
+            let (half, mut axis) = system.first_rest(index).unwrap();
+            for _ in 0..half {
+                axis.step();
+            }
+            assert_eq!(axis.fingerprint(), system.axis(index).fingerprint());
+        }
+
+        let moving = system.tick(1);
+        assert!(moving.first_rest(0).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn periodic_at_rest() {
+        let moon = Moon {
+            location: Pos3::new(3, 3, 3),
+            velocity: Pos3::new(0, 0, 0),
+        };
+        let system = System {
+            moons: vec![moon; 4],
+        };
+
+        assert_eq!(system.first_rest(0).map(|(tick, _)| tick), Some(1));
+        assert_eq!(system.repeat_one(0), 1);
+        assert_eq!(system.test_repeat(), 1);
+    }
+
+    #[test]
+    fn simulation() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "example01.txt")?;
+        let (_, mut system) = System::parse(&input)?;
+
+        system.step();
+        assert_eq!(
+            system.render().lines().next(),
+            Some("pos=(2, -1, 1), vel=(3, -1, -1)")
+        );
+
+        let cycle = sim::run_until_repeat(&mut system);
+        assert_eq!(cycle.start, 0);
+        assert_eq!(cycle.period, 2772);
+
+        Ok(())
+    }
 }
@@ -1,9 +1,12 @@
 use crate::{
     common::pos2::Pos2,
-    int_code::{ComputerError, ComputerFactory, IntCodeComputer, Pointer},
+    int_code::{
+        memory::Memory, ComputerError, ComputerFactory, ExecutionMode, FromOutput, FromOutputs,
+        IntCodeComputer, Observer, PatchError, Pointer,
+    },
 };
 
-use super::{DayTrait, DayType, RResult};
+use super::{DayTrait, DayType, ExtraSolver, RResult};
 use std::collections::HashMap;
 
 const DAY_NUMBER: DayType = 13;
@@ -16,27 +19,71 @@ impl DayTrait for Day {
     }
 
     fn part1(&self, input: &str) -> RResult {
-        let brain = ComputerFactory::init(input)?.build();
-        let game = Game::run(brain)?;
+        let mut brain = ComputerFactory::init(input)?.build();
+        brain.set_execution_mode(ExecutionMode::Compiled);
+        let game = Game::run(&mut brain, Strategy::Follow)?;
         Ok(game.blocks().into())
     }
 
     fn part2(&self, input: &str) -> RResult {
-        let mut brain = ComputerFactory::init(input)?.build();
-        brain.manipulate_memory(Pointer::new(0), 2);
-        let result = Game::run(brain)?;
+        let mut brain = free_play(input)?;
+        let result = Game::run(&mut brain, Strategy::Follow)?;
         Ok(result.score()?.into())
     }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![
+            ("minimal-moves", minimal_moves),
+            ("fewest-frames", fewest_frames),
+        ]
+    }
+}
+
+/**
+ * Wins the game while moving the paddle as little as possible.
+ */
+fn minimal_moves(input: &str) -> RResult {
+    let mut brain = free_play(input)?;
+    let result = Game::run(&mut brain, Strategy::Intercept)?;
+    result.score()?;
+    Ok(result.moves().into())
+}
+
+/**
+ * Wins the game in as few frames as the search finds, and shows the
+ * joystick inputs that do it.
+ */
+fn fewest_frames(input: &str) -> RResult {
+    let mut brain = free_play(input)?;
+    let rally = search::fewest_frames(&mut brain)?;
+    let mut lines = vec![format!("{} frames", rally.frames())];
+    lines.extend(rally.joystick());
+    Ok(lines.into())
+}
+
+/**
+ * The arcade with two quarters inserted, so the game can be played.
+ */
+fn free_play(input: &str) -> Result<IntCodeComputer, DayError> {
+    let quarters = Pointer::new(0);
+    let factory = ComputerFactory::init(input)?;
+    Ok(factory
+        .builder()
+        .patch(factory.patch().expect(quarters, 1).set(quarters, 2))?
+        .execution_mode(ExecutionMode::Compiled)
+        .build())
 }
 
 #[derive(Debug, thiserror::Error)]
 enum DayError {
     #[error("Computer error: {0}")]
     ComputerError(#[from] ComputerError),
-    #[error("Unknown tile: [{0}")]
-    UnknownTile(i64),
+    #[error("Patch error: {0}")]
+    PatchError(#[from] PatchError),
     #[error("There are still {0} blocks left")]
     StillBlocksLeft(usize),
+    #[error("No way found to clear all blocks")]
+    NoWinFound,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
@@ -49,62 +96,232 @@ enum Tile {
     Ball,
 }
 
-impl TryFrom<i64> for Tile {
-    type Error = DayError;
-
-    fn try_from(value: i64) -> Result<Self, Self::Error> {
+impl FromOutput for Tile {
+    fn from_output(value: i64) -> Option<Self> {
         match value {
-            0 => Ok(Tile::Empty),
-            1 => Ok(Tile::Wall),
-            2 => Ok(Tile::Block),
-            3 => Ok(Tile::Paddle),
-            4 => Ok(Tile::Ball),
-            _ => Err(DayError::UnknownTile(value)),
+            0 => Some(Tile::Empty),
+            1 => Some(Tile::Wall),
+            2 => Some(Tile::Block),
+            3 => Some(Tile::Paddle),
+            4 => Some(Tile::Ball),
+            _ => None,
         }
     }
 }
 
+/**
+ * The arcade sends triples: a tile to draw at a position, or the score
+ * at the position off the screen.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Score(i64),
+    Draw(Pos2<i64>, Tile),
+}
+
+impl FromOutputs for Output {
+    const LEN: usize = 3;
+
+    fn decode(values: &[i64]) -> Result<Self, ComputerError> {
+        let (x, y, payload) = <(i64, i64, i64)>::decode(values)?;
+        if (x, y) == SCORE {
+            Ok(Output::Score(payload))
+        } else {
+            Ok(Output::Draw(Pos2::new(x, y), Tile::decode(payload)?))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    /** Keep the paddle below the ball all the time */
+    Follow,
+    /** Only move when the paddle would otherwise not make it to where the ball comes down */
+    Intercept,
+}
+
+/**
+ * Where the ball comes down next. With the paddle right below, the ball
+ * bounces straight back up; with the paddle one further in the direction
+ * the ball moves, it is sent back the way it came.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Descent {
+    /** Of the ball right above the paddle's row */
+    x: i64,
+    dx: i64,
+    /** Joystick inputs until then, the one for the current frame included */
+    inputs: i64,
+}
+
+/**
+ * The joystick input for the next frame, to have the paddle at `target`
+ * when `inputs_left` inputs are used up. Moves as late as possible.
+ */
+fn steer(paddle: i64, target: i64, inputs_left: i64) -> i64 {
+    if (target - paddle).abs() >= inputs_left {
+        (target - paddle).signum()
+    } else {
+        0
+    }
+}
+
 struct Game {
     blocks: usize,
     score: i64,
+    moves: usize,
 }
 
 const SCORE: (i64, i64) = (-1, 0);
 
-impl Game {
-    pub fn run(mut brain: IntCodeComputer) -> Result<Self, DayError> {
-        let mut tiles = HashMap::new();
-        let mut blocks = 0;
-        let mut score = 0;
-        let mut paddle_pos = None;
-        while let Some(v) = brain.maybe_take_exactly(3)? {
-            let [x, y, payload] = v[..] else {
-                unreachable!();
-            };
-            if (x, y) == SCORE {
-                score = payload;
-                continue;
-            }
+/**
+ * Watches the arcade draw and moves the joystick whenever it is asked to.
+ */
+struct Player {
+    strategy: Strategy,
+    pending: Vec<i64>,
+    tiles: HashMap<Pos2<i64>, Tile>,
+    ball: Option<Pos2<i64>>,
+    paddle: Option<Pos2<i64>>,
+    /** Where the paddle has to be and the joystick inputs left to get there */
+    plan: Option<(i64, i64)>,
+    blocks: usize,
+    score: i64,
+    moves: usize,
+}
 
-            let tile = Tile::try_from(payload)?;
-            match tile {
-                Tile::Block => blocks += 1,
-                Tile::Paddle => paddle_pos = Some(x),
-                Tile::Ball => match paddle_pos {
-                    Some(paddle_pos) if x > paddle_pos => brain.send_i64(1),
-                    Some(_) => brain.send_i64(-1),
-                    None => brain.send_i64(0),
-                },
-                _ => {}
+impl Player {
+    fn new(strategy: Strategy) -> Self {
+        Self {
+            strategy,
+            pending: Vec::with_capacity(Output::LEN),
+            tiles: HashMap::new(),
+            ball: None,
+            paddle: None,
+            plan: None,
+            blocks: 0,
+            score: 0,
+            moves: 0,
+        }
+    }
+
+    fn draw(&mut self, pos: Pos2<i64>, tile: Tile) {
+        match tile {
+            Tile::Block => self.blocks += 1,
+            Tile::Paddle => self.paddle = Some(pos),
+            Tile::Ball => self.ball = Some(pos),
+            _ => {}
+        }
+        let prev_tile = self.tiles.insert(pos, tile).unwrap_or_default();
+        if matches!(prev_tile, Tile::Block) {
+            self.blocks -= 1;
+        }
+    }
+
+    fn intercept<M: Memory>(
+        &mut self,
+        brain: &IntCodeComputer<M>,
+        ball: Pos2<i64>,
+        paddle: Pos2<i64>,
+    ) -> Result<i64, DayError> {
+        if self.plan.is_none() {
+            self.plan =
+                Game::predict(brain, ball, paddle)?.map(|descent| (descent.x, descent.inputs));
+        }
+        let Some((target, inputs_left)) = self.plan else {
+            return Ok(0);
+        };
+        self.plan = (inputs_left > 1).then_some((target, inputs_left - 1));
+        Ok(steer(paddle.x(), target, inputs_left))
+    }
+}
+
+impl Observer for Player {
+    type Error = DayError;
+
+    fn on_output(&mut self, value: i64) -> Result<(), Self::Error> {
+        self.pending.push(value);
+        if self.pending.len() < Output::LEN {
+            return Ok(());
+        }
+        match Output::decode(&self.pending)? {
+            Output::Score(value) => self.score = value,
+            Output::Draw(pos, tile) => self.draw(pos, tile),
+        }
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn on_input_request_from<M: Memory>(
+        &mut self,
+        brain: &IntCodeComputer<M>,
+    ) -> Result<Option<i64>, Self::Error> {
+        let joystick = match (self.strategy, self.ball, self.paddle) {
+            (_, None, _) | (_, _, None) => 0,
+            (Strategy::Follow, Some(ball), Some(paddle)) => (ball.x() - paddle.x()).signum(),
+            (Strategy::Intercept, Some(ball), Some(paddle)) => {
+                self.intercept(brain, ball, paddle)?
             }
+        };
+        if joystick != 0 {
+            self.moves += 1;
+        }
+        Ok(Some(joystick))
+    }
 
-            let prev_tile = tiles.insert(Pos2::new(x, y), tile).unwrap_or_default();
-            if matches!(prev_tile, Tile::Block) {
-                blocks -= 1;
+    fn on_halt(&mut self) -> Result<(), Self::Error> {
+        if !self.pending.is_empty() {
+            let values = std::mem::take(&mut self.pending);
+            return Err(ComputerError::IncompleteChunk(values, Output::LEN).into());
+        }
+        Ok(())
+    }
+}
+
+impl Game {
+    pub fn run(brain: &mut IntCodeComputer, strategy: Strategy) -> Result<Self, DayError> {
+        let mut player = Player::new(strategy);
+        brain.run_observed(&mut player)?;
+        Ok(Self {
+            blocks: player.blocks,
+            score: player.score,
+            moves: player.moves,
+        })
+    }
+
+    /**
+     * Lets a copy of the game run on with a resting paddle until the ball,
+     * now at `ball`, comes down right above the paddle's row.
+     */
+    fn predict<M: Memory>(
+        brain: &IntCodeComputer<M>,
+        ball: Pos2<i64>,
+        paddle: Pos2<i64>,
+    ) -> Result<Option<Descent>, DayError> {
+        let mut brain = brain.clone();
+        brain.send_i64(0);
+        let mut inputs = 1;
+        let mut last = ball;
+        while let Some(output) = brain.read::<Output>()? {
+            let Output::Draw(ball, Tile::Ball) = output else {
+                continue;
+            };
+            inputs += 1;
+            if ball.y() == paddle.y() - 1 {
+                return Ok(Some(Descent {
+                    x: ball.x(),
+                    dx: ball.x() - last.x(),
+                    inputs,
+                }));
             }
+            last = ball;
+            brain.send_i64(0);
         }
+        Ok(None)
+    }
 
-        Ok(Self { blocks, score })
+    pub fn moves(&self) -> usize {
+        self.moves
     }
 
     pub fn blocks(&self) -> usize {
@@ -119,3 +336,311 @@ impl Game {
         }
     }
 }
+
+mod search {
+    use super::{steer, DayError, Descent, Game, Output, Tile};
+    use crate::{
+        common::pos2::Pos2,
+        int_code::{snapshot::Snapshot, IntCodeComputer},
+    };
+    use itertools::Itertools;
+    use std::collections::HashSet;
+
+    /** How many unfinished games the search follows at once */
+    const BEAM: usize = 16;
+
+    /** The joystick inputs are shown this many to a line, counted in runs */
+    const RUNS_PER_LINE: usize = 12;
+
+    /**
+     * A game played up to a frame that waits for the joystick while the ball
+     * is on its way up again.
+     */
+    #[derive(Debug, Clone)]
+    struct Rally {
+        snapshot: Snapshot,
+        ball: Pos2<i64>,
+        paddle: Pos2<i64>,
+        blocks: HashSet<Pos2<i64>>,
+        inputs: Vec<i64>,
+    }
+
+    enum Outcome {
+        Rally(Rally),
+        Won(Playthrough),
+        Lost,
+    }
+
+    /** A won game, by the joystick input of every frame */
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Playthrough {
+        inputs: Vec<i64>,
+    }
+
+    impl Playthrough {
+        pub fn frames(&self) -> usize {
+            self.inputs.len()
+        }
+
+        #[cfg(test)]
+        pub fn inputs(&self) -> &[i64] {
+            &self.inputs
+        }
+
+        /**
+         * The inputs in runs like `12.` for twelve frames without moving,
+         * `<` and `>` for moving left and right.
+         */
+        pub fn joystick(&self) -> Vec<String> {
+            let runs = self
+                .inputs
+                .chunk_by(|a, b| a == b)
+                .map(|run| {
+                    let symbol = match run[0] {
+                        -1 => '<',
+                        1 => '>',
+                        _ => '.',
+                    };
+                    match run.len() {
+                        1 => symbol.to_string(),
+                        count => format!("{count}{symbol}"),
+                    }
+                })
+                .collect_vec();
+            runs.chunks(RUNS_PER_LINE)
+                .map(|line| line.join(" "))
+                .collect()
+        }
+    }
+
+    impl Rally {
+        /** Plays until the first frame the paddle can be steered in */
+        fn start(brain: &mut IntCodeComputer) -> Result<Option<Self>, DayError> {
+            let mut paddle = None;
+            let mut blocks = HashSet::new();
+            let mut inputs = vec![];
+            while let Some(output) = brain.read::<Output>()? {
+                match output {
+                    Output::Score(_) => {}
+                    Output::Draw(pos, Tile::Block) => {
+                        blocks.insert(pos);
+                    }
+                    Output::Draw(pos, Tile::Paddle) => paddle = Some(pos),
+                    Output::Draw(ball, Tile::Ball) => {
+                        if let Some(paddle) = paddle {
+                            return Ok(Some(Self {
+                                snapshot: brain.snapshot(),
+                                ball,
+                                paddle,
+                                blocks,
+                                inputs,
+                            }));
+                        }
+                        brain.send_i64(0);
+                        inputs.push(0);
+                    }
+                    Output::Draw(..) => {}
+                }
+            }
+            Ok(None)
+        }
+
+        fn frames(&self) -> usize {
+            self.inputs.len()
+        }
+
+        /**
+         * Where the paddle can catch the ball next, straight below it or
+         * one further, which sends the ball back.
+         */
+        fn catches(&self, brain: &mut IntCodeComputer) -> Result<Vec<Descent>, DayError> {
+            brain.restore(&self.snapshot);
+            let Some(descent) = Game::predict(brain, self.ball, self.paddle)? else {
+                // The last block goes before the ball comes down again
+                return Ok(vec![Descent {
+                    x: self.paddle.x(),
+                    dx: 0,
+                    inputs: i64::MAX,
+                }]);
+            };
+            let mut catches = vec![descent];
+            if descent.dx != 0 {
+                catches.push(Descent {
+                    x: descent.x + descent.dx,
+                    ..descent
+                });
+            }
+            Ok(catches)
+        }
+
+        /** Steers the paddle to catch the ball, and plays until it can be steered again */
+        fn play(&self, brain: &mut IntCodeComputer, catch: Descent) -> Result<Outcome, DayError> {
+            brain.restore(&self.snapshot);
+            let mut rally = self.clone();
+            let mut plan = Some(catch.inputs);
+            while let Some(inputs_left) = plan {
+                plan = (inputs_left > 1).then_some(inputs_left - 1);
+                let joystick = steer(rally.paddle.x(), catch.x, inputs_left);
+                brain.send_i64(joystick);
+                rally.inputs.push(joystick);
+                loop {
+                    let Some(output) = brain.read::<Output>()? else {
+                        return Ok(if rally.blocks.is_empty() {
+                            Outcome::Won(Playthrough {
+                                inputs: rally.inputs,
+                            })
+                        } else {
+                            Outcome::Lost
+                        });
+                    };
+                    let Output::Draw(pos, tile) = output else {
+                        continue;
+                    };
+                    if tile != Tile::Block {
+                        rally.blocks.remove(&pos);
+                    }
+                    match tile {
+                        Tile::Block => {
+                            rally.blocks.insert(pos);
+                        }
+                        Tile::Paddle => rally.paddle = pos,
+                        Tile::Ball => {
+                            rally.ball = pos;
+                            break;
+                        }
+                        Tile::Empty | Tile::Wall => {}
+                    }
+                }
+            }
+            rally.snapshot = brain.snapshot();
+            Ok(Outcome::Rally(rally))
+        }
+    }
+
+    /**
+     * Every time the ball comes down, the paddle can catch it in one of two
+     * places, and each sends it on a different way. A beam search follows
+     * the games with the fewest blocks left, snapshots of the arcade let it
+     * go back to any of them. The game that always catches the ball straight
+     * below bounds the search, so it finds at least that one.
+     */
+    pub fn fewest_frames(brain: &mut IntCodeComputer) -> Result<Playthrough, DayError> {
+        let Some(first) = Rally::start(brain)? else {
+            return Err(DayError::NoWinFound);
+        };
+
+        let mut best = None;
+        let mut rally = first.clone();
+        while best.is_none() {
+            let below = rally.catches(brain)?[0];
+            match rally.play(brain, below)? {
+                Outcome::Rally(next) => rally = next,
+                Outcome::Won(playthrough) => best = Some(playthrough),
+                Outcome::Lost => return Err(DayError::NoWinFound),
+            }
+        }
+        let Some(mut best) = best else {
+            return Err(DayError::NoWinFound);
+        };
+
+        let mut beam = vec![first];
+        while !beam.is_empty() {
+            let mut next = vec![];
+            for rally in &beam {
+                for catch in rally.catches(brain)? {
+                    match rally.play(brain, catch)? {
+                        Outcome::Rally(rally) if rally.frames() < best.frames() => next.push(rally),
+                        Outcome::Won(playthrough) if playthrough.frames() < best.frames() => {
+                            best = playthrough
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            next.sort_by_key(|rally| (rally.blocks.len(), rally.frames()));
+            next.truncate(BEAM);
+            beam = next;
+        }
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::{read_string, UnitResult},
+        int_code::VmEvent,
+    };
+
+    #[test]
+    fn intercept_wins_with_fewer_moves() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let mut brain = free_play(&input)?;
+        let follow = Game::run(&mut brain, Strategy::Follow)?;
+
+        let mut brain = free_play(&input)?;
+        let intercept = Game::run(&mut brain, Strategy::Intercept)?;
+
+        assert_eq!(intercept.score()?, follow.score()?);
+        assert!(intercept.moves() < follow.moves());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fewest_frames_replays() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let mut brain = free_play(&input)?;
+        let playthrough = search::fewest_frames(&mut brain)?;
+
+        let mut brain = free_play(&input)?;
+        for joystick in playthrough.inputs() {
+            brain.send_i64(*joystick);
+        }
+        // Follow only adds inputs behind the ones of the playthrough
+        let game = Game::run(&mut brain, Strategy::Follow)?;
+        assert_eq!(game.blocks(), 0);
+        // Always catching the ball straight below takes 4509 frames
+        assert!(playthrough.frames() < 4509);
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "prints a profile, run with --release -- --ignored --nocapture"]
+    fn profile_game() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let mut brain = free_play(&input)?;
+        brain.enable_profile();
+        Game::run(&mut brain, Strategy::Follow)?;
+        if let Some(profile) = brain.profile() {
+            println!("{profile}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "prints what one frame changes, run with -- --ignored --nocapture"]
+    fn frame_diff() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let mut brain = free_play(&input)?;
+        let wait_for_joystick = |brain: &mut IntCodeComputer| loop {
+            match brain.step()? {
+                VmEvent::NeedsInput => return Ok::<_, ComputerError>(()),
+                VmEvent::Halted => return Err(ComputerError::WaitingForInput),
+                _ => {}
+            }
+        };
+        wait_for_joystick(&mut brain)?;
+        let before = brain.snapshot();
+        brain.send_i64(0);
+        wait_for_joystick(&mut brain)?;
+        let diff = before.diff(&brain.snapshot());
+        println!("{diff}");
+        assert!(!diff.is_empty());
+
+        Ok(())
+    }
+}
@@ -1,8 +1,13 @@
-use super::{DayTrait, DayType, RResult};
+mod planner;
+
+use super::{DayTrait, DayType, ExtraSolver, RResult};
+use crate::common::graph::condense;
 use itertools::Itertools;
+use planner::Planner;
 use std::{
     cell::Cell,
     collections::{hash_map::Entry, HashMap},
+    fmt::Display,
     num,
 };
 
@@ -30,6 +35,21 @@ impl DayTrait for Day {
         let amount = recipe.fuel_from_ore(FREE_ORE)?;
         Ok(amount.into())
     }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("plan", plan)]
+    }
+}
+
+/**
+ * The cheapest way to make one fuel, also for variants where some chemicals
+ * can be made by several reactions. Lists how often each reaction runs.
+ */
+fn plan(input: &str) -> RResult {
+    let reactions = input.lines().map(|line| line.try_into()).try_collect()?;
+    let planner = Planner::new(reactions)?;
+    let plan = planner.ore_per_fuel(1)?;
+    Ok(planner.describe(&plan).into())
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -44,6 +64,10 @@ enum DayError {
     NoFuelInRecipe,
     #[error("Unknown Ingredient: {0}")]
     UnknownIngredient(String),
+    #[error("Reactions depend on each other: {0}")]
+    CyclicReactions(String),
+    #[error("Several reactions produce {0}, only the planner can choose between them")]
+    SeveralReactions(String),
 }
 
 #[derive(Debug)]
@@ -81,6 +105,17 @@ impl<'a> TryFrom<&'a str> for Reaction<'a> {
     }
 }
 
+impl Display for Reaction<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ingredients = self
+            .ingredients
+            .iter()
+            .map(|(name, amount)| format!("{amount} {name}"))
+            .join(", ");
+        write!(f, "{ingredients} => {} {}", self.produced_amount, self.name)
+    }
+}
+
 impl Reaction<'_> {
     pub fn level(&self) -> Option<usize> {
         self.level.get()
@@ -189,6 +224,10 @@ impl<'a> Recipe<'a> {
         if !names.contains(&FUEL) {
             return Err(DayError::NoFuelInRecipe);
         };
+        if let Some(name) = names.iter().duplicates().next() {
+            return Err(DayError::SeveralReactions(name.to_string()));
+        }
+        check_cycles(&names, &reactions)?;
         let _ = Self::get_level_of(&reactions, FUEL);
 
         let reactions = std::iter::once(Ok(InternalReactions::ore(ORE, 0)))
@@ -203,6 +242,24 @@ impl<'a> Recipe<'a> {
     }
 }
 
+/**
+ * Reactions that need what they produce, even in a roundabout way, can
+ * never be resolved to ore.
+ */
+fn check_cycles(names: &[&str], reactions: &[Reaction]) -> Result<(), DayError> {
+    let dependencies = condense(names.iter().copied(), |name| {
+        reactions
+            .iter()
+            .filter(|reaction| reaction.name == *name)
+            .flat_map(|reaction| reaction.ingredients.iter().map(|(name, _)| *name))
+            .collect_vec()
+    });
+    if let Some(cycle) = dependencies.components().cycles().next() {
+        return Err(DayError::CyclicReactions(cycle.iter().sorted().join(", ")));
+    }
+    Ok(())
+}
+
 impl Recipe<'_> {
     fn get(&self, ingredient: &str) -> Option<&InternalReactions> {
         self.reactions.iter().find(|r| r.name == ingredient)
@@ -316,6 +373,15 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn cyclic_reactions() {
+        let input = "1 ORE, 1 B => 1 A\n1 A => 1 B\n1 A => 1 FUEL";
+        assert!(matches!(
+            Recipe::try_from(input),
+            Err(DayError::CyclicReactions(cycle)) if cycle == "A, B"
+        ));
+    }
+
     #[test]
     fn example1() -> UnitResult {
         let day = Day {};
@@ -0,0 +1,294 @@
+use super::{check_cycles, DayError, Reaction, FUEL, ORE};
+use itertools::Itertools;
+
+/**
+ * Branches whose bound is within this of the best plan minus one ore are
+ * pruned, which keeps rounding in the unit costs from cutting off a better plan.
+ */
+const EPSILON: f64 = 1e-6;
+
+/**
+ * How often each reaction runs and how much ore that takes.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub ore: usize,
+    /** Batches per reaction, in the order the planner was given them */
+    pub batches: Vec<usize>,
+}
+
+/**
+ * Minimizes the ore for some fuel as an integer linear program: the batch
+ * count of every reaction is a variable, every chemical must be produced at
+ * least as much as is consumed, and the ore consumed is the objective.
+ *
+ * Unlike `Recipe`, a chemical may have several producing reactions. The
+ * chemicals are resolved from the top down like in the greedy solver, but
+ * every way to split the demand among the producers is a branch. A branch is
+ * dropped once the ore so far plus the demand still open, priced at the
+ * cheapest fractional cost per unit, cannot beat the best plan found. With
+ * one reaction per chemical there is nothing to choose and this is the
+ * greedy solver. Exact, but meant for small amounts of fuel.
+ */
+pub struct Planner<'a> {
+    reactions: Vec<Reaction<'a>>,
+    /** ORE first, then every chemical some reaction produces */
+    chemicals: Vec<&'a str>,
+    /** The reactions producing each chemical, cheapest first */
+    producers: Vec<Vec<usize>>,
+    /** The ingredients of every reaction as chemical indices */
+    ingredients: Vec<Vec<(usize, usize)>>,
+    /** Every chemical but ORE, each before all of its ingredients */
+    order: Vec<usize>,
+    /** The lowest ore per unit, ignoring that reactions run in whole batches */
+    unit_cost: Vec<f64>,
+}
+
+impl<'a> Planner<'a> {
+    pub fn new(reactions: Vec<Reaction<'a>>) -> Result<Self, DayError> {
+        let chemicals = std::iter::once(ORE)
+            .chain(reactions.iter().map(|reaction| reaction.name))
+            .unique()
+            .collect_vec();
+        if !chemicals.contains(&FUEL) {
+            return Err(DayError::NoFuelInRecipe);
+        }
+        check_cycles(&chemicals, &reactions)?;
+
+        let index_of = |name: &str| {
+            chemicals
+                .iter()
+                .position(|chemical| *chemical == name)
+                .ok_or_else(|| DayError::UnknownIngredient(name.to_owned()))
+        };
+        let ingredients: Vec<Vec<_>> = reactions
+            .iter()
+            .map(|reaction| {
+                reaction
+                    .ingredients
+                    .iter()
+                    .map(|(name, amount)| Ok::<_, DayError>((index_of(name)?, *amount)))
+                    .try_collect()
+            })
+            .try_collect()?;
+        let mut producers = vec![vec![]; chemicals.len()];
+        for (index, reaction) in reactions.iter().enumerate() {
+            producers[index_of(reaction.name)?].push(index);
+        }
+
+        let mut levels = vec![None; chemicals.len()];
+        levels[0] = Some(0);
+        for chemical in 1..chemicals.len() {
+            Self::level_of(chemical, &producers, &ingredients, &mut levels);
+        }
+        let order = (1..chemicals.len())
+            .sorted_by_key(|chemical| std::cmp::Reverse(levels[*chemical]))
+            .collect_vec();
+
+        let mut unit_cost = vec![f64::INFINITY; chemicals.len()];
+        unit_cost[0] = 1.0;
+        for chemical in order.iter().rev().copied() {
+            let cost = |index: &usize| {
+                let ore: f64 = ingredients[*index]
+                    .iter()
+                    .map(|(ingredient, amount)| *amount as f64 * unit_cost[*ingredient])
+                    .sum();
+                ore / reactions[*index].produced_amount as f64
+            };
+            producers[chemical].sort_by(|a, b| cost(a).total_cmp(&cost(b)));
+            unit_cost[chemical] = producers[chemical].first().map_or(f64::INFINITY, cost);
+        }
+
+        Ok(Self {
+            reactions,
+            chemicals,
+            producers,
+            ingredients,
+            order,
+            unit_cost,
+        })
+    }
+
+    fn level_of(
+        chemical: usize,
+        producers: &[Vec<usize>],
+        ingredients: &[Vec<(usize, usize)>],
+        levels: &mut [Option<usize>],
+    ) -> usize {
+        if let Some(level) = levels[chemical] {
+            return level;
+        }
+        let level = 1 + producers[chemical]
+            .iter()
+            .flat_map(|reaction| &ingredients[*reaction])
+            .map(|(ingredient, _)| Self::level_of(*ingredient, producers, ingredients, levels))
+            .max()
+            .unwrap_or_default();
+        levels[chemical] = Some(level);
+        level
+    }
+
+    pub fn ore_per_fuel(&self, amount: usize) -> Result<Plan, DayError> {
+        let fuel = self
+            .chemicals
+            .iter()
+            .position(|chemical| *chemical == FUEL)
+            .ok_or(DayError::NoFuelInRecipe)?;
+        let mut need = vec![0; self.chemicals.len()];
+        need[fuel] = amount;
+        let mut batches = vec![0; self.reactions.len()];
+        let mut best = None;
+        self.search(0, &mut need, &mut batches, &mut best);
+        best.ok_or(DayError::CouldNotResolveOre)
+    }
+
+    fn search(
+        &self,
+        step: usize,
+        need: &mut [usize],
+        batches: &mut [usize],
+        best: &mut Option<Plan>,
+    ) {
+        if let Some(best) = best {
+            let bound = need[0] as f64
+                + self.order[step..]
+                    .iter()
+                    .map(|chemical| need[*chemical] as f64 * self.unit_cost[*chemical])
+                    .sum::<f64>();
+            if bound > best.ore as f64 - 1.0 + EPSILON {
+                return;
+            }
+        }
+        let Some(chemical) = self.order.get(step).copied() else {
+            *best = Some(Plan {
+                ore: need[0],
+                batches: batches.to_vec(),
+            });
+            return;
+        };
+        if need[chemical] == 0 {
+            self.search(step + 1, need, batches, best);
+        } else if !self.producers[chemical].is_empty() {
+            self.split(step, chemical, 0, need[chemical], need, batches, best);
+        }
+    }
+
+    /**
+     * Tries every way the producers from `producer` on can make `remaining`
+     * units, most batches of the cheapest first. The last one makes up the rest.
+     */
+    #[allow(clippy::too_many_arguments)]
+    fn split(
+        &self,
+        step: usize,
+        chemical: usize,
+        producer: usize,
+        remaining: usize,
+        need: &mut [usize],
+        batches: &mut [usize],
+        best: &mut Option<Plan>,
+    ) {
+        let producers = &self.producers[chemical];
+        let reaction = producers[producer];
+        let most = remaining.div_ceil(self.reactions[reaction].produced_amount);
+        let least = if producer + 1 == producers.len() {
+            most
+        } else {
+            0
+        };
+        for count in (least..=most).rev() {
+            self.run(reaction, count, need, batches);
+            if producer + 1 == producers.len() {
+                self.search(step + 1, need, batches, best);
+            } else {
+                let made = count * self.reactions[reaction].produced_amount;
+                let remaining = remaining.saturating_sub(made);
+                self.split(step, chemical, producer + 1, remaining, need, batches, best);
+            }
+            self.undo(reaction, count, need, batches);
+        }
+    }
+
+    fn run(&self, reaction: usize, count: usize, need: &mut [usize], batches: &mut [usize]) {
+        batches[reaction] = count;
+        for (ingredient, amount) in &self.ingredients[reaction] {
+            need[*ingredient] += amount * count;
+        }
+    }
+
+    fn undo(&self, reaction: usize, count: usize, need: &mut [usize], batches: &mut [usize]) {
+        batches[reaction] = 0;
+        for (ingredient, amount) in &self.ingredients[reaction] {
+            need[*ingredient] -= amount * count;
+        }
+    }
+
+    /** One line per reaction that runs, then the ore it all takes */
+    pub fn describe(&self, plan: &Plan) -> Vec<String> {
+        self.reactions
+            .iter()
+            .zip(&plan.batches)
+            .filter(|(_, batches)| **batches > 0)
+            .map(|(reaction, batches)| format!("{batches:>6} x {reaction}"))
+            .chain(std::iter::once(format!("{} ORE", plan.ore)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::{day14::Recipe, read_string, UnitResult};
+
+    fn planner(input: &str) -> Result<Planner<'_>, DayError> {
+        Planner::new(input.lines().map(|line| line.try_into()).try_collect()?)
+    }
+
+    #[test]
+    fn same_as_greedy() -> UnitResult {
+        for example in 1..=5 {
+            let input = read_string(14, &format!("example{example:02}.txt"))?;
+            let recipe: Recipe = input.as_str().try_into()?;
+            let planner = planner(&input)?;
+            for fuel in [1, 2, 7] {
+                assert_eq!(planner.ore_per_fuel(fuel)?.ore, recipe.ore_per_fuel(fuel)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn several_producers() -> UnitResult {
+        // The big batch is cheaper per unit, but topping it up with a small
+        // one beats running it twice
+        let input = "10 ORE => 10 A\n2 ORE => 1 A\n11 A => 1 FUEL";
+        assert!(matches!(
+            Recipe::try_from(input),
+            Err(DayError::SeveralReactions(name)) if name == "A"
+        ));
+
+        let planner = planner(input)?;
+        let plan = planner.ore_per_fuel(1)?;
+        assert_eq!(
+            plan,
+            Plan {
+                ore: 12,
+                batches: vec![1, 1, 1]
+            }
+        );
+        assert_eq!(planner.ore_per_fuel(2)?.ore, 24);
+        assert_eq!(planner.ore_per_fuel(10)?.ore, 110);
+        assert_eq!(
+            planner.describe(&plan),
+            vec![
+                "     1 x 10 ORE => 10 A",
+                "     1 x 2 ORE => 1 A",
+                "     1 x 11 A => 1 FUEL",
+                "12 ORE",
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use super::{DayTrait, DayType, RResult};
+use super::{read_string, solve_shared, DayTrait, DayType, ExtraSolver, RResult};
 use crate::int_code::{ComputerError, ComputerFactory};
 
 const DAY_NUMBER: DayType = 15;
@@ -23,6 +23,45 @@ impl DayTrait for Day {
 
         Ok(maze.oxygenize()?.into())
     }
+
+    /**
+     * Exploring the maze is most of the work, both parts use the same one.
+     */
+    fn solve_both(&self, input: &str) -> Option<(RResult, RResult)> {
+        Some(solve_shared(
+            || Ok(maze::Maze::new(&mut ComputerFactory::init(input)?.build())?),
+            |maze| Ok(maze.steps()?.into()),
+            |maze| Ok(maze.oxygenize()?.into()),
+        ))
+    }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("transcript", transcript), ("replay", replay)]
+    }
+}
+
+/**
+ * The moves of the exploration, to be kept as a fixture for `Maze::from_transcript`.
+ */
+fn transcript(input: &str) -> RResult {
+    let mut brain = ComputerFactory::init(input)?.build();
+    let maze = maze::Maze::new(&mut brain)?;
+
+    Ok(maze.transcript().to_string().into())
+}
+
+/**
+ * Both answers from `transcript.txt` in the day's data, which the extra
+ * `transcript` writes, without running the program again.
+ */
+fn replay(_input: &str) -> RResult {
+    let transcript: maze::Transcript = read_string(DAY_NUMBER, "transcript.txt")?.parse()?;
+    let maze = maze::Maze::from_transcript(&transcript)?;
+    Ok(vec![
+        format!("steps to the oxygen: {}", maze.steps()?),
+        format!("minutes to fill: {}", maze.oxygenize()?),
+    ]
+    .into())
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -37,15 +76,28 @@ enum DayError {
     NoOxygenFound,
     #[error("More than one Oxygen found")]
     MoreThanOneOxygenFond,
+    #[error("Unknown movement command: {0}")]
+    UnknownCommand(i64),
+    #[error("Not a transcript line: {0}")]
+    NotATranscriptLine(String),
+    #[error("Transcript contradicts itself at line {0}")]
+    InconsistentTranscript(usize),
 }
 
 mod maze {
     use super::DayError;
+    use crate::common::units::{Minutes, Steps};
     use crate::{
-        common::{area::Area, direction::Direction, pos2::Pos2},
+        common::{
+            direction::Direction,
+            grid::OffsetGrid,
+            grid_search::{distance_map, CostMap},
+            pos2::Pos2,
+            viz::{CharTile, RenderGrid},
+        },
         int_code::IntCodeComputer,
     };
-    use std::collections::{hash_map::Entry, HashMap};
+    use std::{fmt::Display, str::FromStr};
 
     #[derive(Debug, Copy, Clone, Eq, PartialEq)]
     enum Tile {
@@ -61,6 +113,16 @@ mod maze {
         }
     }
 
+    impl CharTile for Tile {
+        fn as_char(&self) -> char {
+            match self {
+                Tile::Empty => '.',
+                Tile::Wall => '#',
+                Tile::Oxygen => 'X',
+            }
+        }
+    }
+
     impl TryFrom<i64> for Tile {
         type Error = DayError;
 
@@ -74,24 +136,116 @@ mod maze {
         }
     }
 
-    type Coordinate = Pos2<i32>;
+    type Coordinate = Pos2<i64>;
+
+    /**
+     * A movement command sent to the droid and the status it answered with,
+     * both as the numbers the droid understands.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) struct Move {
+        pub command: i64,
+        pub status: i64,
+    }
+
+    /**
+     * Every move of an exploration, one `command status` pair per line.
+     */
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub(super) struct Transcript {
+        moves: Vec<Move>,
+    }
+
+    impl Display for Transcript {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            for mv in &self.moves {
+                writeln!(f, "{} {}", mv.command, mv.status)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl FromStr for Transcript {
+        type Err = DayError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let moves = s
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    line.split_once(' ')
+                        .and_then(|(command, status)| {
+                            Some(Move {
+                                command: command.trim().parse().ok()?,
+                                status: status.trim().parse().ok()?,
+                            })
+                        })
+                        .ok_or_else(|| DayError::NotATranscriptLine(line.to_owned()))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Self { moves })
+        }
+    }
 
     #[derive(Debug)]
     pub struct Maze {
-        tiles: HashMap<Coordinate, Tile>,
+        tiles: OffsetGrid<Tile>,
         oxygen: Option<Coordinate>,
+        transcript: Transcript,
     }
 
     impl Maze {
         pub fn new(brain: &mut IntCodeComputer) -> Result<Self, DayError> {
             let mut maze = Self {
-                tiles: HashMap::new(),
+                tiles: OffsetGrid::new(),
                 oxygen: None,
+                transcript: Transcript::default(),
             };
-            maze.explore(brain)?;
+            let mut transcript = Transcript::default();
+            maze.explore(brain, &mut transcript)?;
+            maze.transcript = transcript;
             Ok(maze)
         }
 
+        /**
+         * Rebuilds the maze from the moves of an earlier exploration,
+         * without any droid to ask.
+         */
+        pub fn from_transcript(transcript: &Transcript) -> Result<Self, DayError> {
+            let mut maze = Self {
+                tiles: OffsetGrid::new(),
+                oxygen: None,
+                transcript: transcript.clone(),
+            };
+            let mut pos = Pos2::default();
+            maze.tiles.insert(pos, Tile::Empty);
+            for (line, mv) in transcript.moves.iter().enumerate() {
+                let next_pos = pos + Self::command_to_direction(mv.command)?;
+                let tile: Tile = mv.status.try_into()?;
+                if maze
+                    .tiles
+                    .insert(next_pos, tile)
+                    .is_some_and(|old| old != tile)
+                {
+                    return Err(DayError::InconsistentTranscript(line + 1));
+                }
+                if tile == Tile::Oxygen {
+                    if maze.oxygen.is_some_and(|oxygen| oxygen != next_pos) {
+                        return Err(DayError::MoreThanOneOxygenFond);
+                    }
+                    maze.oxygen = Some(next_pos);
+                }
+                if tile.can_walk() {
+                    pos = next_pos;
+                }
+            }
+            Ok(maze)
+        }
+
+        pub fn transcript(&self) -> &Transcript {
+            &self.transcript
+        }
+
         fn direction_to_command(dir: Direction) -> i64 {
             match dir {
                 Direction::East => 4,
@@ -101,41 +255,47 @@ mod maze {
             }
         }
 
+        fn command_to_direction(command: i64) -> Result<Direction, DayError> {
+            match command {
+                4 => Ok(Direction::East),
+                1 => Ok(Direction::North),
+                3 => Ok(Direction::West),
+                2 => Ok(Direction::South),
+                _ => Err(DayError::UnknownCommand(command)),
+            }
+        }
+
+        fn send_move(
+            brain: &mut IntCodeComputer,
+            transcript: &mut Transcript,
+            direction: Direction,
+        ) -> Result<Tile, DayError> {
+            let command = Self::direction_to_command(direction);
+            brain.send_i64(command);
+            let status = brain.expect_i64()?;
+            transcript.moves.push(Move { command, status });
+            status.try_into()
+        }
+
         #[allow(dead_code)]
         fn print_maze(&self) {
-            let Some(area) = Area::from_iterator(self.tiles.keys()) else {
-                println!("fizzle");
-                return;
-            };
-            for y in area.bottom()..=area.top() {
-                for x in area.left()..=area.right() {
-                    if x == 0 && y == 0 {
-                        print!("X");
-                        continue;
-                    }
-                    let tile = self.tiles.get(&Pos2::new(x, y));
-                    match tile {
-                        Some(Tile::Empty) => print!("."),
-                        Some(Tile::Wall) => print!("#"),
-                        Some(Tile::Oxygen) => print!("X"),
-                        None => print!(" "),
-                    }
-                }
-                println!();
-            }
+            println!("{}", self.tiles.render());
         }
 
-        fn explore(&mut self, brain: &mut IntCodeComputer) -> Result<(), DayError> {
+        fn explore(
+            &mut self,
+            brain: &mut IntCodeComputer,
+            transcript: &mut Transcript,
+        ) -> Result<(), DayError> {
             let mut path = vec![Direction::East];
             let mut pos = Pos2::default();
             self.tiles.insert(pos, Tile::Empty);
             while let Some(facing) = path.pop() {
                 let next_pos = pos + facing;
                 let mut do_walk = false;
-                if let Entry::Vacant(location) = self.tiles.entry(next_pos) {
-                    brain.send_i64(Self::direction_to_command(facing));
-                    let tile: Tile = brain.expect_i64()?.try_into()?;
-                    location.insert(tile);
+                if !self.tiles.contains(&next_pos) {
+                    let tile = Self::send_move(brain, transcript, facing)?;
+                    self.tiles.insert(next_pos, tile);
                     if tile == Tile::Oxygen {
                         if self.oxygen.is_some() {
                             return Err(DayError::MoreThanOneOxygenFond);
@@ -155,8 +315,7 @@ mod maze {
                             while let Some(prev) = path.pop() {
                                 let back = prev.turn_back();
                                 pos += back;
-                                brain.send_i64(Self::direction_to_command(back));
-                                let tile: Tile = brain.expect_i64()?.try_into()?;
+                                let tile = Self::send_move(brain, transcript, back)?;
                                 if !tile.can_walk() {
                                     return Err(DayError::IllegalBackstep);
                                 }
@@ -175,64 +334,65 @@ mod maze {
             Ok(())
         }
 
-        pub fn steps(&self) -> Result<usize, DayError> {
-            if let Some(oxygen) = self.oxygen {
-                let times = self.march_tiles(Pos2::default())?;
-                Ok(times.get(&oxygen).copied().unwrap())
-            } else {
-                Err(DayError::NoOxygenFound)
-            }
+        pub fn steps(&self) -> Result<Steps, DayError> {
+            let oxygen = self.oxygen.ok_or(DayError::NoOxygenFound)?;
+            let distances = self.distances_from(Pos2::default());
+            distances
+                .cost(oxygen)
+                .map(Steps::new)
+                .ok_or(DayError::NoOxygenFound)
         }
 
-        pub fn oxygenize(&self) -> Result<usize, DayError> {
-            if let Some(oxygen) = self.oxygen {
-                let times = self.march_tiles(oxygen)?;
-                Ok(times.values().max().copied().unwrap())
-            } else {
-                Err(DayError::NoOxygenFound)
-            }
+        /**
+         * The oxygen spreads one tile a minute, so the minutes until all is
+         * filled are the steps to the farthest tile.
+         */
+        pub fn oxygenize(&self) -> Result<Minutes, DayError> {
+            let oxygen = self.oxygen.ok_or(DayError::NoOxygenFound)?;
+            let farthest = self.distances_from(oxygen).farthest();
+            Ok(Minutes::new(farthest.map_or(0, |(_, minutes)| minutes)))
         }
 
-        fn march_tiles(&self, start: Coordinate) -> Result<HashMap<Coordinate, usize>, DayError> {
-            let mut times = HashMap::new();
-            times.insert(start, 0);
-            let mut path = vec![Direction::East];
-            let mut pos = start;
-            while let Some(facing) = path.pop() {
-                let next_pos = pos + facing;
+        fn distances_from(&self, start: Coordinate) -> CostMap<i64> {
+            distance_map(start, |pos| {
+                self.tiles.get(&pos).is_some_and(|tile| tile.can_walk())
+            })
+        }
+    }
+}
 
-                let tile = self.tiles.get(&next_pos).copied().unwrap_or(Tile::Wall);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::units::{Minutes, Steps};
+    use crate::days::{read_string, UnitResult};
+    use maze::{Maze, Transcript};
 
-                let move_along = tile.can_walk()
-                    && times
-                        .get(&next_pos)
-                        .map(|&steps| steps > path.len())
-                        .unwrap_or(true);
+    #[test]
+    fn from_transcript() -> UnitResult {
+        let transcript: Transcript = read_string(DAY_NUMBER, "transcript01.txt")?.parse()?;
+        let maze = Maze::from_transcript(&transcript)?;
+        assert_eq!(maze.steps()?, Steps::new(4));
+        assert_eq!(maze.oxygenize()?, Minutes::new(4));
 
-                if move_along {
-                    times.insert(next_pos, path.len() + 1);
-                    pos = next_pos;
-                    path.push(facing);
-                    path.push(Direction::East);
-                } else {
-                    match facing.turn_left() {
-                        Direction::East => {
-                            while let Some(prev) = path.pop() {
-                                let back = prev.turn_back();
-                                pos += back;
+        let broken: Transcript = "4 1\n3 1\n4 0\n".parse()?;
+        assert!(matches!(
+            Maze::from_transcript(&broken),
+            Err(DayError::InconsistentTranscript(3))
+        ));
 
-                                let next = prev.turn_left();
-                                if next != Direction::East {
-                                    path.push(next);
-                                    break;
-                                }
-                            }
-                        }
-                        next_facing => path.push(next_facing),
-                    }
-                }
-            }
-            Ok(times)
-        }
+        Ok(())
+    }
+
+    #[test]
+    fn transcript_round_trip() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let maze = Maze::new(&mut ComputerFactory::init(&input)?.build())?;
+        let transcript: Transcript = maze.transcript().to_string().parse()?;
+        let rebuilt = Maze::from_transcript(&transcript)?;
+        assert_eq!(rebuilt.steps()?, maze.steps()?);
+        assert_eq!(rebuilt.oxygenize()?, maze.oxygenize()?);
+
+        Ok(())
     }
 }
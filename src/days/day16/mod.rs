@@ -1,15 +1,63 @@
-use super::{DayTrait, DayType, RResult};
+use super::{DayTrait, DayType, ExtraSolver, RResult};
+use crate::common::helper::repeated;
 use itertools::Itertools;
-use std::str::FromStr;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
 
 const DAY_NUMBER: DayType = 16;
 
 pub struct Day;
 
+/** Digits are stored as bytes, the sums are done on the wider `Number` */
+type Digit = u8;
 type Number = i32;
 
+/**
+ * What the digits are kept in while the rounds run. Summing and wrapping
+ * bytes is a lot slower than doing the same on `Number`, which in turn
+ * takes four times the memory of the expanded signal.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cells {
+    #[default]
+    Wide,
+    /** For signals too long to expand into `Number`s */
+    Bytes,
+}
+
+trait Cell: Copy {
+    fn from_number(number: Number) -> Self;
+    fn number(self) -> Number;
+}
+
+impl Cell for Number {
+    fn from_number(number: Number) -> Self {
+        number
+    }
+
+    fn number(self) -> Number {
+        self
+    }
+}
+
+impl Cell for Digit {
+    fn from_number(number: Number) -> Self {
+        number as Digit
+    }
+
+    fn number(self) -> Number {
+        self as Number
+    }
+}
+
 const BASE: [Number; 4] = [0, 1, 0, -1];
 const PHASES: usize = 100;
+const OFFSET_DIGITS: usize = 7;
+const MESSAGE_LEN: usize = 8;
 
 impl DayTrait for Day {
     fn get_day_number(&self) -> DayType {
@@ -19,56 +67,127 @@ impl DayTrait for Day {
     fn part1(&self, input: &str) -> RResult {
         let fft: Fft = input.parse()?;
         let fft = fft.rounds(PHASES);
-        Ok(fft.as_usize(8).into())
+        Ok(fft.window(0, MESSAGE_LEN)?.into())
     }
 
     fn part2(&self, input: &str) -> RResult {
         let fft: Fft = input.parse()?;
-        let skip = fft.as_usize(7);
-        let fft = fft.complex_rounds(PHASES, 10_000, skip);
-        Ok(fft.as_usize(8).into())
+        let offset = fft.message_offset(OFFSET_DIGITS);
+        let fft = fft.complex_rounds(PHASES, 10_000, offset);
+        Ok(fft.window(offset, MESSAGE_LEN)?.into())
+    }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("part2-bytes", part2_bytes)]
     }
 }
 
+/**
+ * Part 2 with the rounds run on bytes, slower but with a quarter of the
+ * memory for the expanded signal.
+ */
+fn part2_bytes(input: &str) -> RResult {
+    let fft: Fft = input.parse()?;
+    let offset = fft.message_offset(OFFSET_DIGITS);
+    let fft = fft.complex_rounds_in(Cells::Bytes, PHASES, 10_000, offset);
+    Ok(fft.window(offset, MESSAGE_LEN)?.into())
+}
+
 #[derive(Debug, thiserror::Error)]
 enum DayError {
     #[error("Not a digit: {0}")]
     NotAtDigit(char),
+    #[error("Window {0}..{1} is outside of the processed digits")]
+    WindowOutOfRange(usize, usize),
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
 }
 
+/**
+ * The digits of a signal. After processing with a skip, only the digits from
+ * that offset on are kept, so `skip` remembers where `data` starts.
+ */
 #[derive(Debug, PartialEq, Eq)]
-struct Fft(Vec<Number>);
+struct Fft {
+    data: Vec<Digit>,
+    skip: usize,
+}
 
 impl FromStr for Fft {
     type Err = DayError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Fft(s
-            .chars()
-            .map(|c| {
-                c.to_digit(10)
-                    .map(|d| d as Number)
-                    .ok_or(DayError::NotAtDigit(c))
-            })
-            .try_collect()?))
+        Ok(Fft {
+            data: s
+                .chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|d| d as Digit)
+                        .ok_or(DayError::NotAtDigit(c))
+                })
+                .try_collect()?,
+            skip: 0,
+        })
     }
 }
 
 impl Fft {
+    /**
+     * Reads the digits one by one, so the text of the signal is never held
+     * as a whole. Whitespace is ignored, signals may span several lines.
+     */
+    #[allow(dead_code)]
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, DayError> {
+        let mut data = vec![];
+        for byte in reader.bytes() {
+            match byte? {
+                digit @ b'0'..=b'9' => data.push(digit - b'0'),
+                space if space.is_ascii_whitespace() => {}
+                other => return Err(DayError::NotAtDigit(other as char)),
+            }
+        }
+        Ok(Self { data, skip: 0 })
+    }
+
+    #[allow(dead_code)]
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DayError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
     pub fn rounds(self, times: usize) -> Self {
         self.complex_rounds(times, 1, 0)
     }
 
-    fn complex_rounds(self, times: usize, self_repeat: usize, skip: usize) -> Self {
-        let len = self.0.len() * self_repeat;
-        let mut data = self
-            .0
-            .iter()
-            .copied()
-            .cycle()
-            .take(len)
-            .skip(skip)
-            .collect_vec();
+    pub fn complex_rounds(self, times: usize, self_repeat: usize, skip: usize) -> Self {
+        self.complex_rounds_in(Cells::Wide, times, self_repeat, skip)
+    }
+
+    pub fn complex_rounds_in(
+        self,
+        cells: Cells,
+        times: usize,
+        self_repeat: usize,
+        skip: usize,
+    ) -> Self {
+        let data = match cells {
+            Cells::Wide => {
+                let pattern = self.data.iter().map(|digit| *digit as Number).collect_vec();
+                Self::run_rounds(&pattern, times, self_repeat, skip)
+            }
+            Cells::Bytes => Self::run_rounds(&self.data, times, self_repeat, skip),
+        };
+        Self { data, skip }
+    }
+
+    fn run_rounds<C: Cell>(
+        pattern: &[C],
+        times: usize,
+        self_repeat: usize,
+        skip: usize,
+    ) -> Vec<Digit> {
+        let len = pattern.len() * self_repeat;
+        // Only the digits from `skip` on are ever expanded
+        let mut data = repeated(pattern, skip, len.saturating_sub(skip));
 
         let real_quick_start = len.div_ceil(2);
         let quick_start_index = if real_quick_start > skip {
@@ -91,26 +210,55 @@ impl Fft {
                 while start < end_index {
                     let idx = ((start + skip + 1) / phase) % BASE.len();
                     if BASE[idx] != 0 {
-                        digit_sum += BASE[idx] * data[start..end].iter().sum::<Number>();
+                        digit_sum += BASE[idx]
+                            * data[start..end]
+                                .iter()
+                                .map(|cell| cell.number())
+                                .sum::<Number>();
                     }
                     start = end;
                     end = (end + phase).min(end_index);
                 }
 
-                data[index] = digit_sum.abs() % 10;
+                data[index] = C::from_number(digit_sum.abs() % 10);
             }
             for index in (quick_start_index..end_index - 1).rev() {
-                data[index] = (data[index] + data[index + 1]) % 10;
+                data[index] =
+                    C::from_number((data[index].number() + data[index + 1].number()) % 10);
             }
         }
-        Self(data)
+        data.into_iter()
+            .map(|cell| cell.number() as Digit)
+            .collect()
     }
 
     pub fn as_usize(&self, digits: usize) -> usize {
-        self.0
-            .iter()
-            .take(digits)
-            .fold(0, |sum, digit| sum * 10 + *digit as usize)
+        Self::digits_to_usize(self.data.iter().take(digits))
+    }
+
+    /**
+     * The offset of the message, as given by the first digits of the signal.
+     */
+    pub fn message_offset(&self, digits: usize) -> usize {
+        self.as_usize(digits)
+    }
+
+    /**
+     * Reads `len` digits starting at `offset`, counted from the start of the
+     * whole signal, even if processing skipped the beginning.
+     */
+    pub fn window(&self, offset: usize, len: usize) -> Result<usize, DayError> {
+        let start = offset
+            .checked_sub(self.skip)
+            .ok_or(DayError::WindowOutOfRange(offset, offset + len))?;
+        let Some(window) = self.data.get(start..start + len) else {
+            return Err(DayError::WindowOutOfRange(offset, offset + len));
+        };
+        Ok(Self::digits_to_usize(window.iter()))
+    }
+
+    fn digits_to_usize<'a>(digits: impl Iterator<Item = &'a Digit>) -> usize {
+        digits.fold(0, |sum, digit| sum * 10 + *digit as usize)
     }
 }
 
@@ -168,6 +316,10 @@ mod test {
         let fft_2 = fft.complex_rounds(2, 1, 1);
         assert_eq!(fft_2.as_usize(8), 4040438);
 
+        let fft: Fft = input.parse()?;
+        let bytes = fft.complex_rounds_in(Cells::Bytes, 2, 1, 1);
+        assert_eq!(bytes, fft_2);
+
         Ok(())
     }
 
@@ -182,4 +334,55 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn streamed() -> UnitResult {
+        let parsed: Fft = "12345678".parse()?;
+        let streamed = Fft::from_reader("1234\n5678\n".as_bytes())?;
+        assert_eq!(streamed, parsed);
+        assert!(matches!(
+            Fft::from_reader("12a4".as_bytes()),
+            Err(DayError::NotAtDigit('a'))
+        ));
+
+        // A signal far longer than the puzzle's, repeated 20 times instead
+        // of 10,000, processed from the middle on
+        let signal = "0123456789".repeat(100_000);
+        let fft = Fft::from_reader(io::Cursor::new(signal))?;
+        let offset = 19_000_003;
+        let fft = fft.complex_rounds(2, 20, offset);
+        assert_eq!(fft.data.len(), 999_997);
+        assert_eq!(fft.window(offset, 8)?, 14005640);
+
+        let signal = "0123456789".repeat(100_000);
+        let fft = Fft::from_reader(io::Cursor::new(signal))?;
+        let fft = fft.complex_rounds_in(Cells::Bytes, 2, 20, offset);
+        assert_eq!(fft.window(offset, 8)?, 14005640);
+
+        let path = std::env::temp_dir().join(format!("day16-{}.txt", std::process::id()));
+        std::fs::write(&path, "98765432\n")?;
+        let from_file = Fft::from_file(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(from_file?.rounds(1), "98765432".parse::<Fft>()?.rounds(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn windows() -> UnitResult {
+        let fft: Fft = "12345678".parse()?;
+        assert_eq!(fft.message_offset(3), 123);
+
+        let fft = fft.rounds(1);
+        assert_eq!(fft.window(0, 8)?, 48226158);
+        assert_eq!(fft.window(2, 3)?, 226);
+        assert!(fft.window(6, 3).is_err());
+
+        let fft: Fft = "12345678".parse()?;
+        let fft = fft.complex_rounds(1, 1, 3);
+        assert_eq!(fft.window(3, 5)?, 26158);
+        assert!(fft.window(2, 2).is_err());
+
+        Ok(())
+    }
 }
@@ -1,7 +1,16 @@
-use super::{DayTrait, DayType, RResult};
+use super::{solve_shared, DayTrait, DayType, ExtraSolver, RResult};
 use crate::{
-    common::{direction::Direction, pos2::Pos2, turn::Turn},
-    int_code::{ComputerError, ComputerFactory, IntCodeComputer, Pointer},
+    common::{
+        direction::Direction,
+        pos2::Pos2,
+        turn::Turn,
+        viz::{CharTile, RenderGrid},
+    },
+    int_code::{
+        ascii::AsciiComputer,
+        ascii_frames::{Frame, FrameDelimiter},
+        ComputerError, ComputerFactory, PatchError, Pointer,
+    },
 };
 use itertools::Itertools;
 use std::{fmt::Display, num, ops::Add, str::FromStr};
@@ -9,13 +18,6 @@ use std::{fmt::Display, num, ops::Add, str::FromStr};
 const DAY_NUMBER: DayType = 17;
 const MAX_LEN: usize = 20;
 const MAX_DEPTH: usize = 3;
-const SHOW_OUTPUT: bool = false;
-
-fn maybe_print(output: &str) {
-    if SHOW_OUTPUT {
-        println!("{}", output);
-    }
-}
 
 pub struct Day;
 
@@ -25,27 +27,95 @@ impl DayTrait for Day {
     }
 
     fn part1(&self, input: &str) -> RResult {
-        let mut reader = AsciiBrain::new(input)?;
-        let picture: RobotPicture = reader.get_image()?.parse()?;
+        let factory = ComputerFactory::init(input)?;
+        let picture = take_picture(&factory)?;
         Ok(picture.crossing_sum().into())
     }
 
     fn part2(&self, input: &str) -> RResult {
-        let mut ascii_brain = AsciiBrain::new(input)?;
-        let picture: RobotPicture = ascii_brain.get_image()?.parse()?;
-        let path = picture.determine_path()?;
-        let parts = path.break_up_path()?;
-        let result = ascii_brain.feed_input(parts)?;
-        Ok(result.into())
+        let factory = ComputerFactory::init(input)?;
+        let picture = take_picture(&factory)?;
+        Ok(collect_dust(&factory, &picture)?.into())
+    }
+
+    /**
+     * Part 2 starts from the same picture of the scaffold.
+     */
+    fn solve_both(&self, input: &str) -> Option<(RResult, RResult)> {
+        Some(solve_shared(
+            || {
+                let factory = ComputerFactory::init(input)?;
+                let picture = take_picture(&factory)?;
+                Ok((factory, picture))
+            },
+            |(_, picture)| Ok(picture.crossing_sum().into()),
+            |(factory, picture)| Ok(collect_dust(factory, picture)?.into()),
+        ))
+    }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("movement-program", movement_program)]
     }
 }
 
+/**
+ * The scaffold as the cameras show it, up to the first blank line.
+ */
+fn take_picture(factory: &ComputerFactory) -> Result<RobotPicture, DayError> {
+    let mut cameras = AsciiComputer::new(factory.build());
+    let frame = cameras.read_frame(FrameDelimiter::BlankLine)?;
+    frame.as_ref().map(Frame::text).unwrap_or_default().parse()
+}
+
+/**
+ * Walks the robot along the whole scaffold, returns the dust it collected.
+ */
+fn collect_dust(factory: &ComputerFactory, picture: &RobotPicture) -> Result<i64, DayError> {
+    let path = picture.determine_path()?;
+    let program = path.break_up_path()?;
+    program.validate(&path)?;
+
+    // Waking the robot up makes it ask for its movement program
+    let wake_up = Pointer::new(0);
+    let mut robot = AsciiComputer::new(
+        factory
+            .builder()
+            .patch(factory.patch().expect(wake_up, 1).set(wake_up, 2))?
+            .build(),
+    );
+    for line in program.routines() {
+        robot.answer_prompt(&line)?;
+    }
+    // Declines the video feed. What the robot still prints goes to the I/O
+    // log on the way to the final number
+    robot.answer_prompt("n")?;
+    Ok(robot.expect_final_number()?)
+}
+
+/**
+ * Shows the main routine and the movement functions the robot is fed in part 2.
+ */
+fn movement_program(input: &str) -> RResult {
+    let picture = take_picture(&ComputerFactory::init(input)?)?;
+    let path = picture.determine_path()?;
+    let program = path.break_up_path()?;
+    program.validate(&path)?;
+    Ok(program
+        .to_string()
+        .lines()
+        .map(str::to_owned)
+        .collect_vec()
+        .into())
+}
+
 #[derive(Debug, thiserror::Error)]
 enum DayError {
     #[error("Not an Int")]
     ParseIntError(#[from] num::ParseIntError),
     #[error("Computer error: {0}")]
     ComputerError(#[from] ComputerError),
+    #[error("Patch error: {0}")]
+    PatchError(#[from] PatchError),
     #[error("Illegal tile: {0}")]
     IllegalTile(char),
     #[error("Empty Pictures are not allowed")]
@@ -62,6 +132,14 @@ enum DayError {
     EmptyPathNotAllowed,
     #[error("No Path Found")]
     NoPathFound,
+    #[error("Routine {0} is longer than {MAX_LEN} characters: {1}")]
+    RoutineTooLong(String, String),
+    #[error("At most {MAX_DEPTH} movement functions are allowed, got {0}")]
+    TooManyFunctions(usize),
+    #[error("Main routine calls unknown function {0}")]
+    UnknownFunction(usize),
+    #[error("Movement program does not retrace the path")]
+    ProgramDoesNotMatchPath,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -90,21 +168,23 @@ impl TryFrom<char> for Tile {
     }
 }
 
+impl CharTile for Tile {
+    fn as_char(&self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Scaffold => '#',
+            Tile::Robot(Direction::East) => '>',
+            Tile::Robot(Direction::North) => '^',
+            Tile::Robot(Direction::West) => '<',
+            Tile::Robot(Direction::South) => 'v',
+            Tile::Tumbling => 'X',
+        }
+    }
+}
+
 impl Display for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Tile::Empty => ' ',
-                Tile::Scaffold => '#',
-                Tile::Robot(Direction::East) => '>',
-                Tile::Robot(Direction::North) => '^',
-                Tile::Robot(Direction::West) => '<',
-                Tile::Robot(Direction::South) => 'v',
-                Tile::Tumbling => 'X',
-            }
-        )
+        write!(f, "{}", self.as_char())
     }
 }
 
@@ -116,13 +196,7 @@ struct RobotPicture {
 
 impl Display for RobotPicture {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.pixels.iter() {
-            for tile in row.iter() {
-                write!(f, "{}", tile)?;
-            }
-            writeln!(f)?;
-        }
-        write!(f, "")
+        writeln!(f, "{}", self.pixels.render())
     }
 }
 
@@ -387,7 +461,7 @@ impl<'a> PathFinder<'a> {
         self.add_repeats(sub)
     }
 
-    fn get_order(&self) -> String {
+    fn get_order(&self) -> Vec<usize> {
         self.sub
             .iter()
             .enumerate()
@@ -402,18 +476,90 @@ impl<'a> PathFinder<'a> {
             )
             .into_iter()
             .flatten()
-            .map(|c| (c as u8 + b'A') as char)
+            .collect_vec()
+    }
+
+    fn get_program(&self) -> MovementProgram {
+        MovementProgram {
+            order: self.get_order(),
+            functions: self.sub.iter().map(|(sub, _)| sub.clone()).collect_vec(),
+        }
+    }
+}
+
+/**
+ * The main routine as indices into the movement functions, the way the robot is programmed.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MovementProgram {
+    order: Vec<usize>,
+    functions: Vec<Path>,
+}
+
+impl MovementProgram {
+    fn function_name(idx: usize) -> char {
+        (idx as u8 + b'A') as char
+    }
+
+    pub fn main_routine(&self) -> String {
+        self.order
+            .iter()
+            .map(|idx| Self::function_name(*idx))
             .join(",")
     }
 
-    fn get_strings(&self) -> Vec<String> {
-        if !self.is_finished() {
-            vec![]
-        } else {
-            std::iter::once(self.get_order())
-                .chain(self.sub.iter().map(|(sub, _)| format!("{}", sub)))
-                .collect_vec()
+    /**
+     * The lines to send to the robot: the main routine followed by every function.
+     */
+    pub fn routines(&self) -> Vec<String> {
+        std::iter::once(self.main_routine())
+            .chain(self.functions.iter().map(|function| function.to_string()))
+            .collect_vec()
+    }
+
+    /**
+     * Checks the limits of the robot's memory and that following the
+     * program walks exactly the given path.
+     */
+    pub fn validate(&self, path: &Path) -> Result<(), DayError> {
+        if self.functions.len() > MAX_DEPTH {
+            return Err(DayError::TooManyFunctions(self.functions.len()));
+        }
+        let main = self.main_routine();
+        if main.len() > MAX_LEN {
+            return Err(DayError::RoutineTooLong("Main".to_owned(), main));
         }
+        for (idx, function) in self.functions.iter().enumerate() {
+            if function.string_len() > MAX_LEN {
+                return Err(DayError::RoutineTooLong(
+                    Self::function_name(idx).to_string(),
+                    function.to_string(),
+                ));
+            }
+        }
+
+        let mut walked = vec![];
+        for idx in &self.order {
+            let function = self
+                .functions
+                .get(*idx)
+                .ok_or(DayError::UnknownFunction(*idx))?;
+            walked.extend_from_slice(&function.path);
+        }
+        if walked != path.path {
+            return Err(DayError::ProgramDoesNotMatchPath);
+        }
+        Ok(())
+    }
+}
+
+impl Display for MovementProgram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Main: {}", self.main_routine())?;
+        for (idx, function) in self.functions.iter().enumerate() {
+            writeln!(f, "{}: {}", Self::function_name(idx), function)?;
+        }
+        Ok(())
     }
 }
 
@@ -500,12 +646,12 @@ impl Path {
             .collect_vec()
     }
 
-    pub fn break_up_path(&self) -> Result<Vec<String>, DayError> {
+    pub fn break_up_path(&self) -> Result<MovementProgram, DayError> {
         let pf = PathFinder::new(self);
         let mut queue = vec![pf];
         while let Some(current) = queue.pop() {
             if current.is_finished() {
-                return Ok(current.get_strings());
+                return Ok(current.get_program());
             }
             queue.append(&mut current.next_sub())
         }
@@ -524,49 +670,6 @@ impl Display for Path {
     }
 }
 
-struct AsciiBrain {
-    brain: IntCodeComputer,
-}
-
-impl AsciiBrain {
-    pub fn new(code: &str) -> Result<Self, DayError> {
-        let brain = ComputerFactory::init(code)?.build();
-        Ok(Self { brain })
-    }
-
-    pub fn get_image(&mut self) -> Result<String, DayError> {
-        Ok(std::iter::from_fn(|| self.brain.maybe_string().transpose())
-            .collect::<Result<Vec<_>, _>>()?
-            .iter()
-            .join("\n"))
-    }
-
-    fn receive_and_send(&mut self, to_send: &str) -> Result<(), DayError> {
-        maybe_print(&self.brain.expect_string_()?);
-        maybe_print(to_send);
-        self.brain.send_string(to_send);
-        Ok(())
-    }
-
-    fn animate(&mut self) -> Result<(), DayError> {
-        self.receive_and_send("n")?;
-        maybe_print(&self.get_image()?);
-
-        Ok(())
-    }
-
-    pub fn feed_input(&mut self, input: Vec<String>) -> Result<i64, DayError> {
-        self.brain.manipulate_memory(Pointer::new(0), 2);
-
-        for line in input {
-            self.receive_and_send(&line)?;
-        }
-        self.animate()?;
-
-        Ok(self.brain.expect_i64()?)
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -584,4 +687,29 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn movement_program() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "example02.txt")?;
+        let picture: RobotPicture = input.parse()?;
+        let path = picture.determine_path()?;
+        let program = path.break_up_path()?;
+        program.validate(&path)?;
+
+        let mut broken = program.clone();
+        broken.order.reverse();
+        assert!(matches!(
+            broken.validate(&path),
+            Err(DayError::ProgramDoesNotMatchPath)
+        ));
+
+        let mut broken = program.clone();
+        broken.order.push(MAX_DEPTH);
+        assert!(matches!(
+            broken.validate(&path),
+            Err(DayError::UnknownFunction(MAX_DEPTH))
+        ));
+
+        Ok(())
+    }
 }
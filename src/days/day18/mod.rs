@@ -1,14 +1,18 @@
 use crate::common::{
     direction::Direction,
+    graph::{strongly_connected_components, CondensedMatrix, Distance},
+    grid_search::distance_map,
     path_finder::{find_best_path, FingerprintItem, FingerprintSkipper, PathFinder},
     pos2::Pos2,
+    viz::{default_theme, Canvas, CharTile, Layer, PathGlyphs},
 };
 
-use super::{DayTrait, DayType, RResult};
+use super::{DayTrait, DayType, ExtraSolver, RResult};
 use itertools::Itertools;
 use std::{
     cell::Cell,
-    collections::{BinaryHeap, VecDeque},
+    collections::{BinaryHeap, HashMap, VecDeque},
+    fmt::Display,
     str::FromStr,
 };
 
@@ -30,9 +34,38 @@ impl DayTrait for Day {
     fn part2(&self, input: &str) -> RResult {
         let map: Map = input.parse()?;
         let map = map.expand()?;
-        let path = map.find_shortest_path()?;
+        let path = map.find_shortest_path_with(SearchMode::Decomposed)?;
         Ok(path.into())
     }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("route", route), ("dependencies", dependencies)]
+    }
+}
+
+/**
+ * The map with the shortest path of the first part drawn on it. Keys,
+ * doors and the entrance stay visible on top of the route.
+ */
+fn route(input: &str) -> RResult {
+    let map: Map = input.parse()?;
+    let route = map.shortest_route()?;
+    let mut canvas = Canvas::new(&map.tiles);
+    canvas.draw_path(Layer::Path, &route, &PathGlyphs::default());
+    for (tile, pos) in map.gather_poi() {
+        canvas.draw(Layer::Marker, pos, tile.as_char());
+    }
+    Ok(canvas.render_themed(&default_theme()).into())
+}
+
+/**
+ * Which keys are needed before which as a DOT graph, keys that all need
+ * each other drawn as a cluster.
+ */
+fn dependencies(input: &str) -> RResult {
+    let map: Map = input.parse()?;
+    let dependencies = KeyDependencies::new(&Distances::new(&map));
+    Ok(dependencies.to_dot().into())
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -49,6 +82,15 @@ enum DayError {
     NoPathFound,
     #[error("Can't expand this Map")]
     CantExpandMap,
+    #[error("Can't walk from {0:?} to {1:?}")]
+    NoWalkFound(Pos2<usize>, Pos2<usize>),
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchMode {
+    Exact,
+    Decomposed,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -86,8 +128,9 @@ impl Tile {
             Tile::Entrance(_) | Tile::Key(_) | Tile::Door(_) => true,
         }
     }
+}
 
-    #[allow(dead_code)]
+impl CharTile for Tile {
     fn as_char(&self) -> char {
         match self {
             Tile::Wall => '#',
@@ -131,22 +174,97 @@ impl Connection {
     }
 }
 
+impl Distance for Connection {
+    fn distance(&self) -> Option<usize> {
+        self.value()
+    }
+}
+
+/**
+ * The keys collected so far, one bit per key.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Keyring(u32);
+
+impl Keyring {
+    #[inline]
+    fn of(key: char) -> Self {
+        Self(1 << (key as u32 - 'a' as u32))
+    }
+
+    #[inline]
+    pub fn contains(&self, key: char) -> bool {
+        self.0 & Self::of(key).0 != 0
+    }
+
+    #[inline]
+    pub fn covers(&self, other: Keyring) -> bool {
+        other.0 & !self.0 == 0
+    }
+
+    #[inline]
+    pub fn with(&self, key: char) -> Self {
+        Self(self.0 | Self::of(key).0)
+    }
+
+    #[inline]
+    pub fn intersection(&self, other: Keyring) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = char> + '_ {
+        ('a'..='z').filter(|key| self.contains(*key))
+    }
+}
+
+impl From<&str> for Keyring {
+    fn from(keys: &str) -> Self {
+        keys.chars()
+            .fold(Keyring::default(), |ring, key| ring.with(key))
+    }
+}
+
+impl Display for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in self.keys() {
+            write!(f, "{key}")?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * The way from a point of interest to a key, open once the keyring
+ * holds the keys for all doors on it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Route {
+    target: Tile,
+    key: char,
+    steps: usize,
+    doors: Keyring,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct Distances {
     poi: Vec<Tile>,
-    dist: Vec<Vec<Connection>>,
+    dist: CondensedMatrix<Connection>,
+    /** Per point of interest all routes to keys, in the order of `poi` */
+    routes: Vec<Vec<Route>>,
 }
 
 impl Distances {
     pub fn new(map: &Map) -> Self {
         let positions = map.gather_poi();
-        let dist = (1..positions.len())
-            .map(|l| vec![Connection::Unknown; l])
-            .collect_vec();
+        let dist = CondensedMatrix::new(positions.len(), Connection::Unknown);
 
         let poi = positions.iter().map(|(tile, _)| *tile).sorted().collect();
 
-        let mut me = Self { poi, dist };
+        let mut me = Self {
+            poi,
+            dist,
+            routes: vec![],
+        };
 
         for (from, pos) in positions {
             let distances = map.get_distances_for(pos);
@@ -162,11 +280,31 @@ impl Distances {
             .position(|t| matches!(t, Tile::Door(_)))
             .unwrap();
         me.poi = me.poi[0..first_door].to_vec();
-        me.dist = me.dist[0..first_door - 1].to_vec();
+        me.dist.truncate(first_door);
+        me.routes = (0..me.poi.len()).map(|from| me.routes_from(from)).collect();
 
         me
     }
 
+    fn routes_from(&self, from: usize) -> Vec<Route> {
+        self.poi
+            .iter()
+            .enumerate()
+            .filter_map(|(to, tile)| {
+                let Tile::Key(key) = tile else {
+                    return None;
+                };
+                let connection = self.get_by_idx(from, to);
+                Some(Route {
+                    target: *tile,
+                    key: *key,
+                    steps: connection.value()?,
+                    doors: Keyring::from(connection.get_doors().as_str()),
+                })
+            })
+            .collect()
+    }
+
     #[inline]
     fn set(&mut self, from: Tile, to: Tile, distance: Connection) {
         if let (Some(from), Some(to)) = (self.tile_index(from), self.tile_index(to)) {
@@ -176,8 +314,7 @@ impl Distances {
 
     #[inline]
     fn set_by_idx(&mut self, from: usize, to: usize, distance: Connection) {
-        assert!(from != to);
-        self.dist[from.max(to) - 1][from.min(to)] = distance;
+        self.dist.set(from, to, distance);
     }
 
     #[inline]
@@ -190,11 +327,10 @@ impl Distances {
 
     #[inline]
     pub fn get_by_idx(&self, from: usize, to: usize) -> Connection {
-        if from == to {
-            Connection::Unknown
-        } else {
-            self.dist[from.max(to) - 1][from.min(to)].clone()
-        }
+        self.dist
+            .get(from, to)
+            .cloned()
+            .unwrap_or(Connection::Unknown)
     }
 
     fn tile_index(&self, tile: Tile) -> Option<usize> {
@@ -238,41 +374,49 @@ impl Distances {
         }
     }
 
-    pub fn reachable_connections(&self, tile: Tile, keyring: &str) -> Option<Vec<Tile>> {
-        let Some(idx) = self.tile_index(tile) else {
-            return None;
-        };
-        Some(
-            self.poi
-                .iter()
-                .enumerate()
-                .filter(|(pos, _)| pos != &idx)
-                .filter(|(_, tile)| match tile {
-                    Tile::Key(key_name) => !keyring.contains(*key_name),
-                    _ => false,
-                })
-                .map(|(pos, tile)| {
-                    let connection = if pos < idx {
-                        &self.dist[idx - 1][pos]
-                    } else {
-                        &self.dist[pos - 1][idx]
-                    };
-                    (tile, connection)
-                })
-                .filter_map(|(tile, connection)| match connection {
-                    Connection::Unknown => None,
-                    Connection::Direct(_) => Some(*tile),
-                    Connection::Indirect(_, doors) => {
-                        if doors.chars().all(|door_name| keyring.contains(door_name)) {
-                            Some(*tile)
-                        } else {
-                            None
-                        }
-                    }
-                })
-                .sorted()
-                .collect(),
-        )
+    #[allow(dead_code)]
+    pub fn reachable_connections(&self, tile: Tile, keyring: Keyring) -> Option<Vec<Tile>> {
+        Some(self.reachable_from(self.tile_index(tile)?, keyring))
+    }
+
+    /**
+     * The keys not on the keyring yet that can be reached from the point of interest.
+     */
+    fn reachable_from(&self, from: usize, keyring: Keyring) -> Vec<Tile> {
+        self.routes[from]
+            .iter()
+            .filter(|route| !keyring.contains(route.key) && keyring.covers(route.doors))
+            .map(|route| route.target)
+            .collect()
+    }
+
+    /**
+     * Updates what was reachable from the point of interest after `key` was
+     * added to the keyring. Only the key itself is gone, and only routes
+     * through its door can have opened, so there is no need to look at
+     * all routes again.
+     */
+    fn open_door(&self, from: usize, reachable: &[Tile], keyring: Keyring, key: char) -> Vec<Tile> {
+        let opened = self.routes[from]
+            .iter()
+            .filter(|route| {
+                route.doors.contains(key)
+                    && !keyring.contains(route.key)
+                    && keyring.covers(route.doors)
+            })
+            .map(|route| route.target);
+        reachable
+            .iter()
+            .copied()
+            .filter(|tile| *tile != Tile::Key(key))
+            .merge(opened)
+            .collect()
+    }
+
+    fn route(&self, from: usize, target: Tile) -> Option<&Route> {
+        self.routes[from]
+            .iter()
+            .find(|route| route.target == target)
     }
 
     fn count_keys(&self) -> usize {
@@ -281,23 +425,175 @@ impl Distances {
             .filter(|tile| matches!(tile, Tile::Key(_)))
             .count()
     }
+
+    fn keys_connected_to(&self, tile: Tile) -> Vec<Tile> {
+        self.poi
+            .iter()
+            .filter(|other| matches!(other, Tile::Key(_)))
+            .filter(|other| self.get(tile, **other).is_set())
+            .copied()
+            .collect()
+    }
+
+    /**
+     * Checks whether the key orders found for the single quadrants can be
+     * interleaved, so that no robot has to pass a door before the key for it
+     * was collected somewhere else.
+     */
+    fn can_interleave(&self, orders: &[(Tile, Vec<Tile>)]) -> bool {
+        let mut keyring = Keyring::default();
+        let mut next = vec![0; orders.len()];
+        loop {
+            let mut made_progress = false;
+            for ((entrance, order), next) in orders.iter().zip(next.iter_mut()) {
+                while let Some(target) = order.get(*next) {
+                    let from = if *next == 0 {
+                        *entrance
+                    } else {
+                        order[*next - 1]
+                    };
+                    let doors = self.get(from, *target).get_doors();
+                    if !keyring.covers(Keyring::from(doors.as_str())) {
+                        break;
+                    }
+                    if let Tile::Key(key_name) = target {
+                        keyring = keyring.with(*key_name);
+                    }
+                    *next += 1;
+                    made_progress = true;
+                }
+            }
+            if orders
+                .iter()
+                .zip(next.iter())
+                .all(|((_, order), next)| *next == order.len())
+            {
+                return true;
+            }
+            if !made_progress {
+                return false;
+            }
+        }
+    }
+}
+
+/**
+ * Which keys have to be collected before a key can be reached at all, as
+ * the doors on the way to it tell. Keys that need each other can never all
+ * be collected. Long chains keep the search narrow, while many keys that
+ * need nothing let it branch out.
+ */
+#[derive(Debug)]
+struct KeyDependencies {
+    /** Every key with the keys it needs, in the order of the keys */
+    needs: Vec<(char, Keyring)>,
+}
+
+impl KeyDependencies {
+    pub fn new(distances: &Distances) -> Self {
+        let entrances = distances
+            .poi
+            .iter()
+            .positions(|tile| matches!(tile, Tile::Entrance(_)))
+            .collect_vec();
+        let needs = distances
+            .poi
+            .iter()
+            .filter_map(|tile| match tile {
+                Tile::Key(key) => Some(*key),
+                _ => None,
+            })
+            .map(|key| {
+                // If more than one robot can get there, only the doors all of them pass count
+                let needed = entrances
+                    .iter()
+                    .filter_map(|from| distances.route(*from, Tile::Key(key)))
+                    .map(|route| route.doors)
+                    .reduce(|all, doors| all.intersection(doors))
+                    .unwrap_or_default();
+                (key, needed)
+            })
+            .collect();
+        Self { needs }
+    }
+
+    #[allow(dead_code)]
+    pub fn needed_for(&self, key: char) -> Keyring {
+        self.needs
+            .iter()
+            .find(|(other, _)| *other == key)
+            .map(|(_, needed)| *needed)
+            .unwrap_or_default()
+    }
+
+    /** From the key that is needed to the key that needs it */
+    pub fn edges(&self) -> impl Iterator<Item = (char, char)> + '_ {
+        self.needs
+            .iter()
+            .flat_map(|(key, needed)| needed.keys().map(move |first| (first, *key)))
+    }
+
+    /**
+     * The groups of keys that need each other, each one sorted. A key
+     * behind its own door is a group of its own.
+     */
+    pub fn clusters(&self) -> Vec<Vec<char>> {
+        let keys = self.needs.iter().map(|(key, _)| *key);
+        let components = strongly_connected_components(keys, |key| {
+            let key = *key;
+            self.needs
+                .iter()
+                .filter(move |(_, needed)| needed.contains(key))
+                .map(|(other, _)| *other)
+        });
+        components
+            .cycles()
+            .map(|members| members.iter().copied().sorted().collect())
+            .sorted()
+            .collect()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let clusters = self.clusters();
+        let mut lines = vec!["digraph keys {".to_owned()];
+        for (index, cluster) in clusters.iter().enumerate() {
+            lines.push(format!("    subgraph cluster_{index} {{"));
+            lines.push(format!("        {};", cluster.iter().join("; ")));
+            lines.push("    }".to_owned());
+        }
+        let loose = self
+            .needs
+            .iter()
+            .map(|(key, _)| *key)
+            .filter(|key| !clusters.iter().any(|cluster| cluster.contains(key)));
+        lines.extend(loose.map(|key| format!("    {key};")));
+        lines.extend(
+            self.edges()
+                .map(|(first, then)| format!("    {first} -> {then};")),
+        );
+        lines.push("}".to_owned());
+        lines.join("\n")
+    }
 }
 
 #[derive(Debug, Clone)]
 struct Player {
     position: Tile,
+    /** The index of the position in the points of interest */
+    index: usize,
     reachable: Vec<Tile>,
 }
 
 impl Player {
-    pub fn init(entrance: Tile, distances: &Distances) -> Result<Self, DayError> {
-        let Some(reachable) = distances.reachable_connections(entrance, "") else {
+    pub fn init(entrance: Tile, distances: &Distances, keyring: Keyring) -> Result<Self, DayError> {
+        let Some(index) = distances.tile_index(entrance) else {
             return Err(DayError::MapHasNoSingleEntrance);
         };
 
         Ok(Player {
             position: entrance,
-            reachable,
+            index,
+            reachable: distances.reachable_from(index, keyring),
         })
     }
 }
@@ -306,17 +602,18 @@ impl Player {
 struct MapState<'a> {
     distances: &'a Distances,
     player: Vec<Player>,
-    keyring: String,
+    keyring: Keyring,
     missing_keys: usize,
     steps: usize,
+    collected: Vec<Tile>,
 }
 
 impl FingerprintItem for MapState<'_> {
-    type Fingerprint = (Vec<Tile>, String);
+    type Fingerprint = (Vec<Tile>, Keyring);
     fn get_fingerprint(&self) -> Self::Fingerprint {
         (
             self.player.iter().map(|p| p.position).collect(),
-            self.keyring.clone(),
+            self.keyring,
         )
     }
 }
@@ -351,10 +648,10 @@ impl Ord for MapState<'_> {
 
 impl<'a> MapState<'a> {
     pub fn new_single(distances: &'a Distances) -> Result<Self, DayError> {
-        let keyring = String::new();
+        let keyring = Keyring::default();
         let missing_keys = distances.count_keys();
 
-        let player = vec![Player::init(Tile::Entrance(0), distances)?];
+        let player = vec![Player::init(Tile::Entrance(0), distances, keyring)?];
 
         Ok(Self {
             distances,
@@ -362,15 +659,16 @@ impl<'a> MapState<'a> {
             missing_keys,
             keyring,
             steps: 0,
+            collected: vec![],
         })
     }
 
     pub fn new_multi(distances: &'a Distances) -> Result<Self, DayError> {
-        let keyring = String::new();
+        let keyring = Keyring::default();
         let missing_keys = distances.count_keys();
 
         let player = (1..=4)
-            .map(|num| Player::init(Tile::Entrance(num), distances))
+            .map(|num| Player::init(Tile::Entrance(num), distances, keyring))
             .try_collect()?;
 
         Ok(Self {
@@ -379,40 +677,59 @@ impl<'a> MapState<'a> {
             missing_keys,
             keyring,
             steps: 0,
+            collected: vec![],
         })
     }
 
-    pub fn is_finished(&self) -> bool {
-        self.missing_keys == 0
+    /**
+     * A single robot that only has to collect the keys of its own quadrant.
+     * All other keys are assumed to be on the keyring already, as they
+     * will be collected by the other robots eventually.
+     */
+    pub fn new_quadrant(distances: &'a Distances, entrance: Tile) -> Result<Self, DayError> {
+        let own_keys = distances.keys_connected_to(entrance);
+        let keyring = distances
+            .poi
+            .iter()
+            .filter(|tile| !own_keys.contains(tile))
+            .fold(Keyring::default(), |keyring, tile| match tile {
+                Tile::Key(key_name) => keyring.with(*key_name),
+                _ => keyring,
+            });
+
+        let player = vec![Player::init(entrance, distances, keyring)?];
+
+        Ok(Self {
+            distances,
+            player,
+            missing_keys: own_keys.len(),
+            keyring,
+            steps: 0,
+            collected: vec![],
+        })
     }
 
-    pub fn add_key(&self, key: Tile) -> Option<String> {
-        if let Tile::Key(key_name) = key {
-            if !self.keyring.contains(key_name) {
-                let mut keyring = self.keyring.clone();
-                keyring.push(key_name);
-                keyring = keyring.chars().sorted().collect();
-                return Some(keyring);
-            }
-        }
-        None
+    pub fn is_finished(&self) -> bool {
+        self.missing_keys == 0
     }
 
     pub fn move_to(&self, target: Tile) -> Option<Self> {
-        let Some((idx, current)) = self
+        let (idx, current) = self
             .player
             .iter()
             .enumerate()
-            .find(|(_, p)| p.reachable.contains(&target))
-        else {
-            return None;
-        };
+            .find(|(_, p)| p.reachable.contains(&target))?;
 
-        let Some(keyring) = self.add_key(target) else {
+        let Tile::Key(key) = target else {
             return None;
         };
+        if self.keyring.contains(key) {
+            return None;
+        }
+        let keyring = self.keyring.with(key);
 
-        let steps = self.steps + self.distances.get(current.position, target).value()?;
+        let steps = self.steps + self.distances.route(current.index, target)?.steps;
+        let index = self.distances.tile_index(target)?;
 
         let player = self
             .player
@@ -420,33 +737,35 @@ impl<'a> MapState<'a> {
             .enumerate()
             .map(|(pos, player)| {
                 if pos != idx {
-                    let reachable = self
-                        .distances
-                        .reachable_connections(player.position, &keyring)
-                        .unwrap();
                     Player {
-                        position: player.position,
-                        reachable,
+                        reachable: self.distances.open_door(
+                            player.index,
+                            &player.reachable,
+                            keyring,
+                            key,
+                        ),
+                        ..*player
                     }
                 } else {
-                    let reachable = self
-                        .distances
-                        .reachable_connections(target, &keyring)
-                        .unwrap();
                     Player {
                         position: target,
-                        reachable,
+                        index,
+                        reachable: self.distances.reachable_from(index, keyring),
                     }
                 }
             })
             .collect();
 
+        let mut collected = self.collected.clone();
+        collected.push(target);
+
         Some(MapState {
             distances: self.distances,
             player,
             keyring,
             missing_keys: self.missing_keys - 1,
             steps,
+            collected,
         })
     }
 
@@ -591,7 +910,64 @@ impl Map {
     }
 
     pub fn find_shortest_path(&self) -> Result<usize, DayError> {
+        self.find_shortest_path_with(SearchMode::Exact)
+    }
+
+    /**
+     * Every cell the single robot steps on along the shortest path, from
+     * the entrance to the last key.
+     */
+    pub fn shortest_route(&self) -> Result<Vec<Pos2<usize>>, DayError> {
         let distances = Distances::new(self);
+        let solver = MapSolver::new(MapState::new_single(&distances)?);
+        let best = find_best_path(solver).ok_or(DayError::NoPathFound)?;
+
+        let mut pos = self.find_single_entrance()?;
+        let mut route = vec![pos];
+        let mut keyring = Keyring::default();
+        let positions: HashMap<_, _> = self.gather_poi().into_iter().collect();
+        for key in best.collected {
+            let Some(&target) = positions.get(&key) else {
+                return Err(DayError::NoPathFound);
+            };
+            let walk = self
+                .walk(pos, target, keyring)
+                .ok_or(DayError::NoWalkFound(pos, target))?;
+            route.extend(walk.into_iter().skip(1));
+            if let Tile::Key(key) = key {
+                keyring = keyring.with(key);
+            }
+            pos = target;
+        }
+        Ok(route)
+    }
+
+    /**
+     * The cells of a shortest walk between two cells, both included. It
+     * only passes doors the keyring opens.
+     */
+    fn walk(
+        &self,
+        from: Pos2<usize>,
+        to: Pos2<usize>,
+        keyring: Keyring,
+    ) -> Option<Vec<Pos2<usize>>> {
+        let passable = |pos| match self.get_tile(pos) {
+            Tile::Wall => false,
+            Tile::Door(door) => keyring.contains(door),
+            Tile::Floor | Tile::Entrance(_) | Tile::Key(_) => true,
+        };
+        distance_map(from, passable).path_to(to)
+    }
+
+    pub fn find_shortest_path_with(&self, mode: SearchMode) -> Result<usize, DayError> {
+        let distances = Distances::new(self);
+        if mode == SearchMode::Decomposed && self.is_expanded {
+            if let Some(steps) = Self::find_decomposed_path(&distances)? {
+                return Ok(steps);
+            }
+        }
+
         let state = if self.is_expanded {
             MapState::new_multi(&distances)?
         } else {
@@ -602,6 +978,27 @@ impl Map {
             .map(|result| result.steps)
             .ok_or(DayError::NoPathFound)
     }
+
+    /**
+     * Solves every quadrant on its own and sums up the steps. This is a lower
+     * bound for the exact answer, and it is the answer if the single routes
+     * can be interleaved. Otherwise we return None and the exact search must be used.
+     */
+    fn find_decomposed_path(distances: &Distances) -> Result<Option<usize>, DayError> {
+        let mut steps = 0;
+        let mut orders = vec![];
+        for num in 1..=4 {
+            let entrance = Tile::Entrance(num);
+            let solver = MapSolver::new(MapState::new_quadrant(distances, entrance)?);
+            let Some(result) = find_best_path(solver) else {
+                return Ok(None);
+            };
+            steps += result.steps;
+            orders.push((entrance, result.collected));
+        }
+
+        Ok(distances.can_interleave(&orders).then_some(steps))
+    }
 }
 
 struct MapSolver<'a> {
@@ -643,7 +1040,10 @@ impl<'a> PathFinder for MapSolver<'a> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::days::{read_string, ResultType, UnitResult};
+    use crate::{
+        common::graph,
+        days::{read_string, ResultType, UnitResult},
+    };
 
     #[test]
     fn test_part1() -> UnitResult {
@@ -667,6 +1067,27 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn route_on_map() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "example01.txt")?;
+        let map: Map = input.parse()?;
+        let cells = map.shortest_route()?;
+        assert_eq!(cells.len() - 1, map.find_shortest_path()?);
+
+        let input = read_string(day.get_day_number(), "example03.txt")?;
+        let map: Map = input.parse()?;
+        assert_eq!(map.shortest_route()?.len() - 1, 81);
+
+        let result = route(&read_string(day.get_day_number(), "example01.txt")?)?;
+        assert_eq!(
+            result,
+            ResultType::String("#########\n#b<A<@<a#\n#########".to_owned())
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn parse() -> UnitResult {
         let day = Day {};
@@ -694,21 +1115,26 @@ mod test {
 
         let distances = Distances::new(&map);
         assert_eq!(
-            distances,
-            Distances {
-                poi: vec![Tile::Entrance(0), Tile::Key('a'), Tile::Key('b'),],
-                dist: vec![
-                    vec![Connection::Direct(2)],
-                    vec![
-                        Connection::Indirect(4, String::from("a")),
-                        Connection::Indirect(6, String::from("a"))
-                    ],
-                ]
-            }
+            distances.poi,
+            vec![Tile::Entrance(0), Tile::Key('a'), Tile::Key('b'),]
+        );
+        assert_eq!(
+            distances.dist.rows(),
+            [
+                vec![Connection::Direct(2)],
+                vec![
+                    Connection::Indirect(4, String::from("a")),
+                    Connection::Indirect(6, String::from("a"))
+                ],
+            ]
         );
+        // Ignoring the doors, the greedy tour over the keys is the real path here
+        let tour = graph::nearest_neighbor(&distances.dist, 0, graph::Route::Open).unwrap();
+        assert_eq!(tour.order, [0, 1, 2]);
+        assert_eq!(tour.length, 8);
         assert_eq!(
             distances
-                .reachable_connections(Tile::Entrance(0), "")
+                .reachable_connections(Tile::Entrance(0), Keyring::default())
                 .unwrap(),
             [Tile::Key('a')]
         );
@@ -718,13 +1144,35 @@ mod test {
         let player = player.move_to(Tile::Key('a')).unwrap();
         assert_eq!(player.steps, 2);
         assert_eq!(player.reachable().copied().collect_vec(), [Tile::Key('b')]);
-        assert_eq!(player.keyring, String::from("a"));
+        assert_eq!(player.keyring.to_string(), "a");
 
         let player = player.move_to(Tile::Key('b')).unwrap();
         assert!(player.is_finished());
         assert_eq!(player.steps, 8);
         assert_eq!(player.reachable().copied().collect_vec(), []);
-        assert_eq!(player.keyring, String::from("ab"));
+        assert_eq!(player.keyring.to_string(), "ab");
+
+        Ok(())
+    }
+
+    #[test]
+    fn key_dependencies() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "example02.txt")?;
+        let dependencies = KeyDependencies::new(&Distances::new(&input.parse()?));
+        assert_eq!(dependencies.needed_for('i').to_string(), "g");
+        assert_eq!(dependencies.needed_for('a'), Keyring::default());
+        assert_eq!(dependencies.edges().count(), 8);
+        assert!(dependencies.clusters().is_empty());
+
+        // Each key lies behind the door of the other one
+        let map: Map = "#########\n#b.A@B.a#\n#########".parse()?;
+        let dependencies = KeyDependencies::new(&Distances::new(&map));
+        assert_eq!(dependencies.clusters(), vec![vec!['a', 'b']]);
+        assert_eq!(
+            dependencies.to_dot(),
+            "digraph keys {\n    subgraph cluster_0 {\n        a; b;\n    }\n    b -> a;\n    a -> b;\n}"
+        );
 
         Ok(())
     }
@@ -765,6 +1213,24 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn decomposed_search() -> UnitResult {
+        let day = Day {};
+        for (file, expected) in [("example04.txt", 8), ("example05.txt", 72)] {
+            let input = read_string(day.get_day_number(), file)?;
+            let map: Map = input.parse()?;
+            let map = map.expand()?;
+
+            assert_eq!(map.find_shortest_path_with(SearchMode::Exact)?, expected);
+            assert_eq!(
+                map.find_shortest_path_with(SearchMode::Decomposed)?,
+                expected
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn distances_and_move_expended() -> UnitResult {
         let day = Day {};
@@ -774,75 +1240,76 @@ mod test {
 
         let distances = Distances::new(&map);
         assert_eq!(
-            distances,
-            Distances {
-                poi: vec![
-                    Tile::Entrance(1),
-                    Tile::Entrance(2),
-                    Tile::Entrance(3),
-                    Tile::Entrance(4),
-                    Tile::Key('a'),
-                    Tile::Key('b'),
-                    Tile::Key('c'),
-                    Tile::Key('d'),
+            distances.poi,
+            vec![
+                Tile::Entrance(1),
+                Tile::Entrance(2),
+                Tile::Entrance(3),
+                Tile::Entrance(4),
+                Tile::Key('a'),
+                Tile::Key('b'),
+                Tile::Key('c'),
+                Tile::Key('d'),
+            ]
+        );
+        assert_eq!(
+            distances.dist.rows(),
+            [
+                vec![Connection::Unknown],
+                vec![Connection::Unknown, Connection::Unknown],
+                vec![
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Unknown
+                ],
+                vec![
+                    Connection::Direct(2),
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Unknown
+                ],
+                vec![
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Indirect(2, String::from("a")),
+                    Connection::Unknown,
                 ],
-                dist: vec![
-                    vec![Connection::Unknown],
-                    vec![Connection::Unknown, Connection::Unknown],
-                    vec![
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Unknown
-                    ],
-                    vec![
-                        Connection::Direct(2),
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Unknown
-                    ],
-                    vec![
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Indirect(2, String::from("a")),
-                        Connection::Unknown,
-                    ],
-                    vec![
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Indirect(2, String::from("b")),
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Unknown,
-                    ],
-                    vec![
-                        Connection::Unknown,
-                        Connection::Indirect(2, String::from("c")),
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Unknown,
-                        Connection::Unknown,
-                    ]
+                vec![
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Indirect(2, String::from("b")),
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Unknown,
+                ],
+                vec![
+                    Connection::Unknown,
+                    Connection::Indirect(2, String::from("c")),
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Unknown,
+                    Connection::Unknown,
                 ]
-            }
+            ]
         );
         assert_eq!(
             distances
-                .reachable_connections(Tile::Entrance(1), "")
+                .reachable_connections(Tile::Entrance(1), Keyring::default())
                 .unwrap(),
             [Tile::Key('a')]
         );
         assert_eq!(
             distances
-                .reachable_connections(Tile::Entrance(2), "")
+                .reachable_connections(Tile::Entrance(2), Keyring::default())
                 .unwrap(),
             []
         );
 
         assert_eq!(
             distances
-                .reachable_connections(Tile::Entrance(2), "c")
+                .reachable_connections(Tile::Entrance(2), Keyring::from("c"))
                 .unwrap(),
             [Tile::Key('d')]
         );
@@ -852,23 +1319,23 @@ mod test {
         let state = state.move_to(Tile::Key('a')).unwrap();
         assert_eq!(state.steps, 2);
         assert_eq!(state.reachable().copied().collect_vec(), [Tile::Key('b')]);
-        assert_eq!(state.keyring, String::from("a"));
+        assert_eq!(state.keyring.to_string(), "a");
 
         let state = state.move_to(Tile::Key('b')).unwrap();
         assert_eq!(state.steps, 4);
         assert_eq!(state.reachable().copied().collect_vec(), [Tile::Key('c')]);
-        assert_eq!(state.keyring, String::from("ab"));
+        assert_eq!(state.keyring.to_string(), "ab");
 
         let state = state.move_to(Tile::Key('c')).unwrap();
         assert_eq!(state.steps, 6);
         assert_eq!(state.reachable().copied().collect_vec(), [Tile::Key('d')]);
-        assert_eq!(state.keyring, String::from("abc"));
+        assert_eq!(state.keyring.to_string(), "abc");
 
         let state = state.move_to(Tile::Key('d')).unwrap();
         assert!(state.is_finished());
         assert_eq!(state.steps, 8);
         assert_eq!(state.reachable().copied().collect_vec(), []);
-        assert_eq!(state.keyring, String::from("abcd"));
+        assert_eq!(state.keyring.to_string(), "abcd");
 
         Ok(())
     }
@@ -1,8 +1,9 @@
-use super::{DayTrait, DayType, RResult};
+use super::{DayTrait, DayType, ExtraSolver, RResult};
 use crate::{
     common::pos2::Pos2,
-    int_code::{ComputerError, ComputerFactory, IntCodeComputer},
+    int_code::{ComputerError, ComputerFactory, ExecutionMode, IntCodeComputer},
 };
+use std::ops::Range;
 
 const DAY_NUMBER: DayType = 19;
 
@@ -10,6 +11,12 @@ pub struct Day;
 
 const SHIP_SIZE: usize = 100;
 
+/** Rows before the beam is first found are only searched up to this many times their index */
+const MAX_SLOPE: usize = 10;
+
+/** The beam model gives up on rows further away than this */
+const MAX_ROWS: usize = 10_000;
+
 impl DayTrait for Day {
     fn get_day_number(&self) -> DayType {
         DAY_NUMBER
@@ -26,12 +33,37 @@ impl DayTrait for Day {
         let (x, y) = tractor.find_closest(SHIP_SIZE)?;
         Ok((x * 10_000 + y).into())
     }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("beam-model", beam_model)]
+    }
+}
+
+/**
+ * Part 2 once more, through the beam model, and what else the model knows
+ * about the area part 1 scans.
+ */
+fn beam_model(input: &str) -> RResult {
+    let mut model = BeamModel::new(TractorBrain::new(input)?);
+    let (x, y) = model.closest_square(SHIP_SIZE)?;
+    Ok(vec![
+        format!("closest square: {}", x * 10_000 + y),
+        format!(
+            "first row {SHIP_SIZE} wide: {}",
+            model.first_row_with_width(SHIP_SIZE)?
+        ),
+        format!("pulled in 50 rows: {}", model.area(0..50)?),
+        format!("largest square in 50 rows: {}", model.largest_square(50)?),
+    ]
+    .into())
 }
 
 #[derive(Debug, thiserror::Error)]
 enum DayError {
     #[error("Computer error: {0}")]
     ComputerError(#[from] ComputerError),
+    #[error("The beam is not {0} wide within {MAX_ROWS} rows")]
+    BeamTooNarrow(usize),
 }
 
 struct TractorBrain {
@@ -40,7 +72,10 @@ struct TractorBrain {
 
 impl TractorBrain {
     pub fn new(code: &str) -> Result<Self, DayError> {
-        let brain = ComputerFactory::init(code)?.build();
+        let brain = ComputerFactory::init(code)?
+            .builder()
+            .execution_mode(ExecutionMode::Compiled)
+            .build();
         Ok(Self { brain })
     }
 
@@ -143,3 +178,257 @@ impl TractorBrain {
         Ok((x, y))
     }
 }
+
+/**
+ * Describes every row of the beam by the interval of pulled points.
+ * Both edges of the beam only ever move to the right, so each row is found
+ * starting from the one before with just a few calls to the drone program.
+ */
+struct BeamModel {
+    tractor: TractorBrain,
+    rows: Vec<Option<(usize, usize)>>,
+    last: Option<(usize, usize)>,
+}
+
+impl BeamModel {
+    pub fn new(tractor: TractorBrain) -> Self {
+        Self {
+            tractor,
+            rows: vec![],
+            last: None,
+        }
+    }
+
+    /**
+     * The first and last pulled x in that row, both inclusive.
+     */
+    pub fn row(&mut self, y: usize) -> Result<Option<(usize, usize)>, DayError> {
+        while self.rows.len() <= y {
+            let row = self.scan_row(self.rows.len())?;
+            if row.is_some() {
+                self.last = row;
+            }
+            self.rows.push(row);
+        }
+        Ok(self.rows[y])
+    }
+
+    fn scan_row(&mut self, y: usize) -> Result<Option<(usize, usize)>, DayError> {
+        let (last_start, last_end) = self.last.unwrap_or((0, 0));
+        let limit = last_start.max(y * MAX_SLOPE);
+        let mut start = last_start;
+        while !self.tractor.read_point(start, y)? {
+            if start >= limit {
+                return Ok(None);
+            }
+            start += 1;
+        }
+        let mut end = last_end.max(start);
+        while self.tractor.read_point(end + 1, y)? {
+            end += 1;
+        }
+        Ok(Some((start, end)))
+    }
+
+    pub fn width(&mut self, y: usize) -> Result<usize, DayError> {
+        Ok(self
+            .row(y)?
+            .map(|(start, end)| end - start + 1)
+            .unwrap_or(0))
+    }
+
+    /**
+     * Fails if no row up to `MAX_ROWS` is that wide, like for a beam that
+     * gets narrower.
+     */
+    pub fn first_row_with_width(&mut self, width: usize) -> Result<usize, DayError> {
+        for y in 0..MAX_ROWS {
+            if self.width(y)? >= width {
+                return Ok(y);
+            }
+        }
+        Err(DayError::BeamTooNarrow(width))
+    }
+
+    /**
+     * The number of pulled points in the given rows.
+     */
+    pub fn area(&mut self, rows: Range<usize>) -> Result<usize, DayError> {
+        let mut area = 0;
+        for y in rows {
+            area += self.width(y)?;
+        }
+        Ok(area)
+    }
+
+    /**
+     * Checks whether a square with its bottom left corner at the start of row
+     * `bottom` lies within the beam, returning its top left corner.
+     */
+    fn square_at(
+        &mut self,
+        bottom: usize,
+        size: usize,
+    ) -> Result<Option<(usize, usize)>, DayError> {
+        if size == 0 || bottom + 1 < size {
+            return Ok(None);
+        }
+        let top = bottom + 1 - size;
+        match (self.row(bottom)?, self.row(top)?) {
+            (Some((start, _)), Some((_, end))) if start + size - 1 <= end => Ok(Some((start, top))),
+            _ => Ok(None),
+        }
+    }
+
+    /**
+     * The side length of the largest square that fits into the first `rows` rows.
+     */
+    pub fn largest_square(&mut self, rows: usize) -> Result<usize, DayError> {
+        let mut best = 0;
+        for bottom in 0..rows {
+            while self.square_at(bottom, best + 1)?.is_some() {
+                best += 1;
+            }
+        }
+        Ok(best)
+    }
+
+    /**
+     * The top left corner of the square of that size closest to the emitter.
+     */
+    pub fn closest_square(&mut self, size: usize) -> Result<(usize, usize), DayError> {
+        for bottom in self.first_row_with_width(size)?..MAX_ROWS {
+            if let Some(corner) = self.square_at(bottom, size)? {
+                return Ok(corner);
+            }
+        }
+        Err(DayError::BeamTooNarrow(size))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::{read_string, UnitResult};
+    use itertools::Itertools;
+    use std::time::Instant;
+
+    #[test]
+    fn beam_model() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "input.txt")?;
+        let mut model = BeamModel::new(TractorBrain::new(&input)?);
+        let mut tractor = TractorBrain::new(&input)?;
+
+        let pulled = tractor.count_pulled(50)?;
+        let mut clipped = 0;
+        for y in 0..50 {
+            if let Some((start, end)) = model.row(y)? {
+                clipped += end.min(49) + 1 - start.min(50);
+            }
+        }
+        assert_eq!(clipped, pulled);
+
+        let sampled = ComputerFactory::init(&input)?.sweep(
+            (0..50).cartesian_product(0..50),
+            |brain, (x, y)| {
+                brain.send_i64(x);
+                brain.send_i64(y);
+                brain.expect_bool()
+            },
+        );
+        let sampled: Vec<bool> = sampled.into_iter().try_collect()?;
+        assert_eq!(sampled.into_iter().filter(|pulled| *pulled).count(), pulled);
+        assert!(model.area(0..50)? >= pulled);
+
+        let wide = model.first_row_with_width(10)?;
+        assert!(model.width(wide)? >= 10);
+        assert!(model.width(wide - 1)? < 10);
+
+        assert_eq!(
+            model.closest_square(SHIP_SIZE)?,
+            tractor.find_closest(SHIP_SIZE)?
+        );
+        let (_, top) = model.closest_square(20)?;
+        assert_eq!(model.largest_square(top + 20)?, 20);
+        assert_eq!(model.largest_square(top + 19)?, 19);
+
+        Ok(())
+    }
+
+    #[test]
+    fn narrow_beam() -> UnitResult {
+        // pulls exactly the points with x == y
+        let mut model = BeamModel::new(TractorBrain::new("3,11,3,12,8,11,12,13,4,13,99,0,0,0")?);
+        assert_eq!(model.row(5)?, Some((5, 5)));
+        assert!(matches!(
+            model.first_row_with_width(2),
+            Err(DayError::BeamTooNarrow(2))
+        ));
+        assert!(matches!(
+            model.closest_square(2),
+            Err(DayError::BeamTooNarrow(2))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored --nocapture"]
+    fn bench_probing() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let factory = ComputerFactory::init(&input)?;
+        let points = (0..100).cartesian_product(0..100).collect_vec();
+        let probe = |brain: &mut IntCodeComputer, (x, y)| {
+            brain.send_i64(x);
+            brain.send_i64(y);
+            brain.expect_bool()
+        };
+
+        let start = Instant::now();
+        let mut tractor = TractorBrain::new(&input)?;
+        let mut reset = vec![];
+        for (x, y) in &points {
+            reset.push(tractor.read_point(*x as usize, *y as usize)?);
+        }
+        let reset_time = start.elapsed();
+
+        let start = Instant::now();
+        let mut built = vec![];
+        for point in &points {
+            built.push(probe(&mut factory.build(), *point)?);
+        }
+        let built_time = start.elapsed();
+
+        // Every computer gets a program of its own
+        let start = Instant::now();
+        let mut copied = vec![];
+        for point in &points {
+            let mut brain = ComputerFactory::new(factory.program().to_vec()).build();
+            copied.push(probe(&mut brain, *point)?);
+        }
+        let copied_time = start.elapsed();
+
+        assert_eq!(reset, built);
+        assert_eq!(built, copied);
+        println!(
+            "reset: {reset_time:?}, shared program: {built_time:?}, copied program: {copied_time:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "prints a profile, run with --release -- --ignored --nocapture"]
+    fn profile_beam() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let mut tractor = TractorBrain::new(&input)?;
+        tractor.brain.enable_profile();
+        tractor.find_closest(SHIP_SIZE)?;
+        if let Some(profile) = tractor.brain.profile() {
+            println!("{profile}");
+        }
+
+        Ok(())
+    }
+}
@@ -3,12 +3,15 @@
 use super::{DayTrait, DayType, RResult};
 use crate::common::{
     direction::Direction,
+    graph::CondensedMatrix,
+    grid::pad_rows,
     path_finder::{find_best_path, FingerprintItem, FingerprintSkipper, PathFinder},
     pos2::Pos2,
+    units::{Level, Steps},
 };
 use itertools::Itertools;
 use std::{
-    collections::{BinaryHeap, VecDeque},
+    collections::{BinaryHeap, HashMap, VecDeque},
     num,
     str::FromStr,
 };
@@ -55,9 +58,14 @@ enum DayError {
     MazeHasNoEntrance,
     #[error("No path found")]
     NoPathFound,
+    #[error("Unknown portal: {0}")]
+    UnknownPortal(String),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/** The two letters of a portal, `('A', 'A')` for the entrance */
+type PortalName = (char, char);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 enum RawTile {
     Inpenetrable,
     Floor,
@@ -134,8 +142,7 @@ impl RawMap {
             .iter()
             .enumerate()
             .map(|(y, row)| {
-                let row_len = row.len();
-                row[2..row_len.min(self.width - 2)]
+                row[2..self.width - 2]
                     .iter()
                     .enumerate()
                     .map(|(x, tile)| match tile {
@@ -154,7 +161,7 @@ impl FromStr for RawMap {
     type Err = DayError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let map: Vec<Vec<_>> = s
+        let mut map: Vec<Vec<_>> = s
             .lines()
             .map(|line| line.chars().map(RawTile::try_from).try_collect())
             .try_collect()?;
@@ -162,7 +169,9 @@ impl FromStr for RawMap {
         if height == 0 {
             return Err(DayError::ParseError(s.to_owned()));
         }
-        let width = map.iter().map(|row| row.len()).max().unwrap();
+        // editors like to strip the trailing blanks behind the right portals
+        pad_rows(&mut map, RawTile::Inpenetrable);
+        let width = map[0].len();
         Ok(Self { map, width, height })
     }
 }
@@ -188,6 +197,15 @@ impl Tile {
         }
     }
 
+    fn name(&self) -> Option<PortalName> {
+        match self {
+            Tile::Inpenetrable | Tile::Floor => None,
+            Tile::Entrance => Some(('A', 'A')),
+            Tile::Exit => Some(('Z', 'Z')),
+            Tile::InnerDoor(d1, d2) | Tile::OuterDoor(d1, d2) => Some((*d1, *d2)),
+        }
+    }
+
     fn is_door(&self) -> bool {
         match self {
             Tile::Inpenetrable | Tile::Floor => false,
@@ -219,6 +237,7 @@ impl Tile {
 
 struct TileMap {
     tiles: Vec<Vec<Tile>>,
+    portals: HashMap<Tile, Pos2<usize>>,
 }
 
 impl TileMap {
@@ -248,7 +267,49 @@ impl TileMap {
             return Err(DayError::NotAllDoorsHavePartners);
         }
 
-        Ok(Self { tiles })
+        let portals = tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, tile)| tile.is_door())
+                    .map(move |(x, tile)| (*tile, Pos2::new(x, y)))
+            })
+            .collect();
+
+        Ok(Self { tiles, portals })
+    }
+
+    fn parse_name(name: &str) -> Result<PortalName, DayError> {
+        match name.chars().collect_vec()[..] {
+            [d1, d2] if d1.is_ascii_uppercase() && d2.is_ascii_uppercase() => Ok((d1, d2)),
+            _ => Err(DayError::UnknownPortal(name.to_owned())),
+        }
+    }
+
+    /**
+     * The tiles in front of the portal, the one on the outer side first.
+     * The entrance `AA` and the exit `ZZ` only have one, which is returned twice.
+     */
+    pub fn portal(&self, name: &str) -> Option<(Pos2<usize>, Pos2<usize>)> {
+        let sides = self.sides(name).ok()?;
+        let outer = *self.portals.get(sides.first()?)?;
+        let inner = *self.portals.get(sides.last()?)?;
+        Some((outer, inner))
+    }
+
+    fn sides(&self, name: &str) -> Result<Vec<Tile>, DayError> {
+        let sides = match Self::parse_name(name)? {
+            ('A', 'A') => vec![Tile::Entrance],
+            ('Z', 'Z') => vec![Tile::Exit],
+            (d1, d2) => vec![Tile::OuterDoor(d1, d2), Tile::InnerDoor(d1, d2)],
+        };
+        if sides.iter().all(|side| self.portals.contains_key(side)) {
+            Ok(sides)
+        } else {
+            Err(DayError::UnknownPortal(name.to_owned()))
+        }
     }
 
     fn get(&self, pos: &Pos2<usize>) -> Option<&Tile> {
@@ -289,45 +350,77 @@ impl TileMap {
         distances
     }
 
-    pub fn find_shortest_path(&self) -> Result<usize, DayError> {
-        let solver = MapSolver::new(self);
-        find_best_path(solver)
-            .map(|result| result.steps - 1)
-            .ok_or(DayError::NoPathFound)
+    pub fn find_shortest_path(&self) -> Result<Steps, DayError> {
+        self.shortest_path("AA", "ZZ")
     }
 
-    pub fn find_shortest_recursive_path(&self) -> Result<usize, DayError> {
-        let solver = RecursiveMapSolver::new(self);
-        find_best_path(solver)
+    pub fn find_shortest_recursive_path(&self) -> Result<Steps, DayError> {
+        self.shortest_recursive_path("AA", "ZZ")
+    }
+
+    /**
+     * The fewest steps from either side of one portal to the other one,
+     * where portals lead to the same maze.
+     */
+    pub fn shortest_path(&self, from: &str, to: &str) -> Result<Steps, DayError> {
+        let goal = Self::parse_name(to)?;
+        self.sides(to)?;
+        let distances = Distances::new(self);
+        self.sides(from)?
+            .into_iter()
+            .filter_map(|start| {
+                find_best_path(MapSolver {
+                    distances: &distances,
+                    start,
+                    goal,
+                })
+            })
             .map(|result| result.steps)
+            .min()
             .ok_or(DayError::NoPathFound)
     }
-}
 
-struct MapSolver {
-    distances: Distances,
+    /**
+     * Like `shortest_path`, but inner portals lead one level deeper and
+     * the path has to start and end on the outermost level.
+     */
+    pub fn shortest_recursive_path(&self, from: &str, to: &str) -> Result<Steps, DayError> {
+        let goal = Self::parse_name(to)?;
+        self.sides(to)?;
+        let table = LevelTable::new(&Distances::new(self), goal);
+        self.sides(from)?
+            .into_iter()
+            .filter_map(|start| {
+                find_best_path(RecursiveMapSolver {
+                    table: &table,
+                    start,
+                    goal,
+                })
+            })
+            .map(|result| result.steps)
+            .min()
+            .ok_or(DayError::NoPathFound)
+    }
 }
 
-impl MapSolver {
-    pub fn new(map: &TileMap) -> Self {
-        Self {
-            distances: Distances::new(map),
-        }
-    }
+struct MapSolver<'a> {
+    distances: &'a Distances,
+    start: Tile,
+    goal: PortalName,
 }
 
-impl PathFinder for MapSolver {
+impl PathFinder for MapSolver<'_> {
     type Item = MapState;
     type Queue = BinaryHeap<MapState>;
     type Skipper = FingerprintSkipper<MapState>;
 
     fn get_start_item(&self) -> Self::Item {
-        MapState::default()
+        MapState::start(self.start)
     }
 
     #[inline]
     fn is_finished(&self, item: &Self::Item) -> bool {
-        item.position == Tile::Exit
+        item.position.name() == Some(self.goal)
     }
 
     fn get_next_states<'a>(
@@ -339,68 +432,59 @@ impl PathFinder for MapSolver {
             .unwrap()
             .into_iter()
             .filter_map(move |tile| {
-                self.distances
-                    .get(item.position, tile)
-                    .map(|steps| MapState {
-                        steps: item.steps + steps + 1,
-                        level: item.level,
-                        position: tile.wrap(),
-                    })
+                let steps = self.distances.get(item.position, tile)?;
+                item.walk_flat_to(tile, Steps::new(steps), self.goal)
             })
     }
 }
 
-struct RecursiveMapSolver {
-    distances: Distances,
+struct RecursiveMapSolver<'a> {
+    table: &'a LevelTable,
+    start: Tile,
+    goal: PortalName,
 }
 
-impl RecursiveMapSolver {
-    pub fn new(map: &TileMap) -> Self {
-        Self {
-            distances: Distances::new(map),
-        }
-    }
-}
-
-impl PathFinder for RecursiveMapSolver {
+impl PathFinder for RecursiveMapSolver<'_> {
     type Item = MapState;
     type Queue = BinaryHeap<MapState>;
     type Skipper = FingerprintSkipper<Self::Item>;
 
     fn get_start_item(&self) -> Self::Item {
-        MapState::default()
+        MapState::start(self.start)
     }
 
     #[inline]
     fn is_finished(&self, item: &Self::Item) -> bool {
-        item.position == Tile::Exit
+        item.position.name() == Some(self.goal) && item.level.is_outermost()
     }
 
     fn get_next_states<'a>(
         &'a self,
         item: &'a Self::Item,
     ) -> impl Iterator<Item = Self::Item> + 'a {
-        self.distances
-            .reachable_connections(item.position)
-            .unwrap()
-            .into_iter()
-            .filter_map(move |target| {
-                self.distances
-                    .get(item.position, target)
-                    .and_then(|steps| item.walk_to(target, steps))
+        self.table
+            .moves(item.position, item.level)
+            .iter()
+            .filter_map(move |&(target, steps)| item.walk_to(target, steps, self.goal))
+            .map(|mut next| {
+                // saturating keeps the estimate a lower bound on very deep levels
+                let ascent = next.level.get().saturating_mul(self.table.min_ascent.get());
+                next.estimate = Steps::new(ascent);
+                next
             })
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 struct MapState {
-    steps: usize,
-    level: usize,
+    steps: Steps,
+    level: Level,
     position: Tile,
+    estimate: Steps,
 }
 
 impl FingerprintItem for MapState {
-    type Fingerprint = (Tile, usize);
+    type Fingerprint = (Tile, Level);
 
     fn get_fingerprint(&self) -> Self::Fingerprint {
         (self.position, self.level)
@@ -408,36 +492,65 @@ impl FingerprintItem for MapState {
 }
 
 impl MapState {
-    pub fn walk_to(&self, target: Tile, steps: usize) -> Option<Self> {
+    fn start(position: Tile) -> Self {
+        Self {
+            position,
+            ..Self::default()
+        }
+    }
+
+    fn arrive_at(&self, target: Tile, steps: Steps) -> Self {
+        Self {
+            steps: self.steps + steps,
+            level: self.level,
+            position: target,
+            estimate: Steps::ZERO,
+        }
+    }
+
+    /**
+     * Walks to a door and through it, where doors do not change the level.
+     * At the goal the walk ends in front of it. Entrance and exit lead
+     * nowhere, so walking to them is pointless unless they are the goal.
+     */
+    pub fn walk_flat_to(&self, target: Tile, steps: Steps, goal: PortalName) -> Option<Self> {
+        if target.name() == Some(goal) {
+            return Some(self.arrive_at(target, steps));
+        }
+        match target {
+            Tile::InnerDoor(_, _) | Tile::OuterDoor(_, _) => Some(Self {
+                steps: self.steps + steps + Steps::ONE,
+                level: self.level,
+                position: target.wrap(),
+                estimate: Steps::ZERO,
+            }),
+            Tile::Inpenetrable | Tile::Floor | Tile::Entrance | Tile::Exit => None,
+        }
+    }
+
+    /**
+     * Walks to a door and through it, one level deeper for inner doors and
+     * one higher for outer ones. The goal can only be reached on the
+     * outermost level, there the walk ends in front of it.
+     */
+    pub fn walk_to(&self, target: Tile, steps: Steps, goal: PortalName) -> Option<Self> {
+        if target.name() == Some(goal) && self.level.is_outermost() {
+            return Some(self.arrive_at(target, steps));
+        }
         match target {
             Tile::InnerDoor(_, _) => Some(Self {
-                steps: self.steps + steps + 1,
-                level: self.level + 1,
+                steps: self.steps + steps + Steps::ONE,
+                level: self.level.deeper(),
                 position: target.wrap(),
+                estimate: Steps::ZERO,
             }),
-            Tile::OuterDoor(_, _) => {
-                if self.level == 0 {
-                    None
-                } else {
-                    Some(Self {
-                        steps: self.steps + steps + 1,
-                        level: self.level - 1,
-                        position: target.wrap(),
-                    })
-                }
-            }
-            Tile::Exit => {
-                if self.level == 0 {
-                    Some(Self {
-                        steps: self.steps + steps,
-                        level: 0,
-                        position: Tile::Exit,
-                    })
-                } else {
-                    None
-                }
-            }
-            Tile::Inpenetrable | Tile::Floor | Tile::Entrance => None,
+            Tile::OuterDoor(_, _) => self.level.higher().map(|level| Self {
+                steps: self.steps + steps + Steps::ONE,
+                level,
+                position: target.wrap(),
+                estimate: Steps::ZERO,
+            }),
+            Tile::Inpenetrable | Tile::Floor | Tile::Entrance | Tile::Exit => None,
         }
     }
 }
@@ -450,7 +563,7 @@ impl PartialOrd for MapState {
 
 impl Ord for MapState {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match other.steps.cmp(&self.steps) {
+        match (other.steps + other.estimate).cmp(&(self.steps + self.estimate)) {
             std::cmp::Ordering::Equal => {}
             cmp => return cmp,
         }
@@ -461,16 +574,84 @@ impl Ord for MapState {
 impl Default for MapState {
     fn default() -> Self {
         Self {
-            steps: 0,
-            level: 0,
+            steps: Steps::ZERO,
+            level: Level::ZERO,
             position: Tile::Entrance,
+            estimate: Steps::ZERO,
+        }
+    }
+}
+
+/**
+ * The moves of the recursive maze only depend on whether we are on the
+ * outermost level (outer doors are closed, the exit is open) or on any level
+ * below it. So both tables get computed once and are shared by all levels.
+ */
+struct LevelTable {
+    outermost: HashMap<Tile, Vec<(Tile, Steps)>>,
+    inner: HashMap<Tile, Vec<(Tile, Steps)>>,
+    min_ascent: Steps,
+}
+
+impl LevelTable {
+    pub fn new(distances: &Distances, goal: PortalName) -> Self {
+        let build = |level: Level| {
+            distances
+                .doors
+                .iter()
+                .map(|&from| {
+                    let moves = distances
+                        .reachable_connections(from)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|target| {
+                            let steps = Steps::new(distances.get(from, target)?);
+                            let probe = MapState {
+                                steps: Steps::ZERO,
+                                level,
+                                position: from,
+                                estimate: Steps::ZERO,
+                            };
+                            probe.walk_to(target, steps, goal).map(|_| (target, steps))
+                        })
+                        .collect();
+                    (from, moves)
+                })
+                .collect::<HashMap<_, Vec<_>>>()
+        };
+        let outermost = build(Level::ZERO);
+        let inner = build(Level::ZERO.deeper());
+
+        // Every level we are below the outermost one costs at least one walk
+        // to an outer door and the step through it.
+        let min_ascent = inner
+            .values()
+            .flatten()
+            .filter(|(target, _)| matches!(target, Tile::OuterDoor(_, _)))
+            .map(|(_, steps)| *steps + Steps::ONE)
+            .min()
+            .unwrap_or(Steps::ZERO);
+
+        Self {
+            outermost,
+            inner,
+            min_ascent,
         }
     }
+
+    pub fn moves(&self, position: Tile, level: Level) -> &[(Tile, Steps)] {
+        let table = if level.is_outermost() {
+            &self.outermost
+        } else {
+            &self.inner
+        };
+        table.get(&position).map(Vec::as_slice).unwrap_or_default()
+    }
 }
 
 struct Distances {
     doors: Vec<Tile>,
-    dist: Vec<Vec<Option<usize>>>,
+    dist: CondensedMatrix<Option<usize>>,
 }
 
 impl Distances {
@@ -490,7 +671,7 @@ impl Distances {
             })
             .collect_vec();
 
-        let dist = (1..positions.len()).map(|l| vec![None; l]).collect_vec();
+        let dist = CondensedMatrix::new(positions.len(), None);
 
         let doors = positions.iter().map(|(tile, _)| *tile).sorted().collect();
 
@@ -515,8 +696,7 @@ impl Distances {
 
     #[inline]
     fn set_by_idx(&mut self, from: usize, to: usize, distance: usize) {
-        assert!(from != to);
-        self.dist[from.max(to) - 1][from.min(to)] = Some(distance);
+        self.dist.set(from, to, Some(distance));
     }
 
     #[inline]
@@ -526,11 +706,7 @@ impl Distances {
 
     #[inline]
     pub fn get_by_idx(&self, from: usize, to: usize) -> Option<usize> {
-        if from == to {
-            None
-        } else {
-            self.dist[from.max(to) - 1][from.min(to)]
-        }
+        self.dist.get(from, to).copied().flatten()
     }
 
     fn tile_index(&self, tile: Tile) -> Option<usize> {
@@ -546,15 +722,7 @@ impl Distances {
                 .iter()
                 .enumerate()
                 .filter(|(pos, _)| pos != &idx)
-                .filter(|(_, tile)| !matches!(tile, Tile::Entrance))
-                .filter_map(|(pos, tile)| {
-                    let connection = if pos < idx {
-                        self.dist[idx - 1][pos]
-                    } else {
-                        self.dist[pos - 1][idx]
-                    };
-                    connection.map(|_| *tile)
-                })
+                .filter_map(|(pos, tile)| self.get_by_idx(idx, pos).map(|_| *tile))
                 .collect(),
         )
     }
@@ -598,4 +766,62 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn portal_queries() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "example01.txt")?;
+        let raw_map: RawMap = input.parse()?;
+        let tile_map = raw_map.to_tile_map()?;
+
+        assert_eq!(
+            tile_map.portal("BC"),
+            Some((Pos2::new(0, 6), Pos2::new(7, 4)))
+        );
+        assert_eq!(
+            tile_map.portal("AA"),
+            Some((Pos2::new(7, 0), Pos2::new(7, 0)))
+        );
+        assert_eq!(tile_map.portal("XY"), None);
+        assert_eq!(tile_map.portal("bc"), None);
+
+        assert_eq!(tile_map.shortest_path("AA", "ZZ")?, Steps::new(23));
+        assert_eq!(tile_map.shortest_path("AA", "BC")?, Steps::new(4));
+        assert_eq!(tile_map.shortest_path("BC", "AA")?, Steps::new(4));
+        assert_eq!(tile_map.shortest_path("AA", "DE")?, Steps::new(11));
+        assert_eq!(
+            tile_map.shortest_recursive_path("AA", "ZZ")?,
+            Steps::new(26)
+        );
+        assert_eq!(tile_map.shortest_recursive_path("AA", "BC")?, Steps::new(4));
+        assert!(matches!(
+            tile_map.shortest_path("AA", "XY"),
+            Err(DayError::UnknownPortal(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn level_table() -> UnitResult {
+        let day = Day {};
+        let input = read_string(day.get_day_number(), "example03.txt")?;
+        let raw_map: RawMap = input.parse()?;
+        let tile_map = raw_map.to_tile_map()?;
+        let table = LevelTable::new(&Distances::new(&tile_map), ('Z', 'Z'));
+
+        assert!(table
+            .outermost
+            .values()
+            .flatten()
+            .all(|(target, _)| !matches!(target, Tile::OuterDoor(_, _))));
+        assert!(table
+            .inner
+            .values()
+            .flatten()
+            .all(|(target, _)| target != &Tile::Exit));
+        assert!(table.min_ascent > Steps::ZERO);
+
+        Ok(())
+    }
 }
@@ -1,10 +1,40 @@
-use super::{DayTrait, DayType, RResult};
-use crate::int_code::{ComputerError, ComputerFactory, IntCodeComputer};
+use super::{
+    read_string_of, write_string_of, DataFileError, DayTrait, DayType, ExtraSolver, RResult,
+};
+use crate::int_code::{
+    ascii::AsciiComputer,
+    ascii_frames::{Frame, FrameDelimiter},
+    coverage::Coverage,
+    decompiler, ComputerError, ComputerFactory, IntCodeComputer,
+};
 use itertools::{Either, Itertools};
-use std::fmt::Display;
+use std::{collections::BTreeSet, fmt::Display, io, str::FromStr};
 
 const DAY_NUMBER: DayType = 21;
 
+const WALK_SCRIPT: [(Instruction, Read, Write); 4] = [
+    (Instruction::Not, Read::Distance('A'), Write::Jump),
+    (Instruction::Not, Read::Distance('C'), Write::Temp),
+    (Instruction::Or, Read::Temp, Write::Jump),
+    (Instruction::And, Read::Distance('D'), Write::Jump),
+];
+
+const RUN_SCRIPT: [(Instruction, Read, Write); 7] = [
+    (Instruction::Not, Read::Distance('B'), Write::Temp),
+    (Instruction::Not, Read::Distance('C'), Write::Jump),
+    (Instruction::Or, Read::Temp, Write::Jump),
+    (Instruction::And, Read::Distance('D'), Write::Jump),
+    (Instruction::And, Read::Distance('H'), Write::Jump),
+    (Instruction::Not, Read::Distance('A'), Write::Temp),
+    (Instruction::Or, Read::Temp, Write::Jump),
+];
+
+const WINDOW_SIZE: usize = 9;
+const JUMP_LENGTH: usize = 4;
+const CORPUS_FILE: &str = "corpus.txt";
+/** The droid asks for its springscript with this */
+const PROMPT: &str = "Input instructions:";
+
 pub struct Day;
 
 impl DayTrait for Day {
@@ -13,35 +43,48 @@ impl DayTrait for Day {
     }
 
     fn part1(&self, input: &str) -> RResult {
-        let mut droid = SpringDroid::create(input, 'D', "WALK")?;
-
-        let instructions = [
-            (Instruction::Not, Read::Distance('A'), Write::Jump),
-            (Instruction::Not, Read::Distance('C'), Write::Temp),
-            (Instruction::Or, Read::Temp, Write::Jump),
-            (Instruction::And, Read::Distance('D'), Write::Jump),
-        ];
-
-        let result = droid.run_instructions(&instructions, false)?;
-        Ok(result.into())
+        let mut droid = boot(ComputerFactory::init(input)?.build())?;
+        Ok(run_script(&mut droid, &WALK_SCRIPT, 'D', "WALK")?.into())
     }
 
     fn part2(&self, input: &str) -> RResult {
-        let mut droid = SpringDroid::create(input, 'I', "RUN")?;
+        let mut droid = boot(ComputerFactory::init(input)?.build())?;
+        Ok(run_script(&mut droid, &RUN_SCRIPT, 'I', "RUN")?.into())
+    }
 
-        let instructions = [
-            (Instruction::Not, Read::Distance('B'), Write::Temp),
-            (Instruction::Not, Read::Distance('C'), Write::Jump),
-            (Instruction::Or, Read::Temp, Write::Jump),
-            (Instruction::And, Read::Distance('D'), Write::Jump),
-            (Instruction::And, Read::Distance('H'), Write::Jump),
-            (Instruction::Not, Read::Distance('A'), Write::Temp),
-            (Instruction::Or, Read::Temp, Write::Jump),
-        ];
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![("decompile", decompile), ("coverage", coverage)]
+    }
+}
 
-        let result = droid.run_instructions(&instructions, false)?;
-        Ok(result.into())
+/**
+ * What the springdroid program does, as pseudocode.
+ */
+fn decompile(input: &str) -> RResult {
+    let factory = ComputerFactory::init(input)?;
+    Ok(decompiler::decompile(factory.program()).into())
+}
+
+/**
+ * How much of the springdroid program the walking and the running script
+ * exercised together.
+ */
+fn coverage(input: &str) -> RResult {
+    let factory = ComputerFactory::init(input)?;
+    let mut coverage = Coverage::default();
+    for (script, allowed_distance, start_verb) in [
+        (&WALK_SCRIPT[..], 'D', "WALK"),
+        (&RUN_SCRIPT[..], 'I', "RUN"),
+    ] {
+        let mut computer = factory.build();
+        computer.enable_coverage();
+        let mut droid = boot(computer)?;
+        run_script(&mut droid, script, allowed_distance, start_verb)?;
+        if let Some(run) = droid.computer().coverage() {
+            coverage.merge(run);
+        }
     }
+    Ok(coverage.report(factory.program().len()).to_string().into())
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -52,76 +95,307 @@ enum DayError {
     IncorrectResult,
     #[error("Incorrect Distance: {0}")]
     IncorrectDistance(char),
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error(transparent)]
+    DataFileError(#[from] DataFileError),
+    #[error("Incorrect hull window: {0}")]
+    IncorrectWindow(String),
 }
 
-struct SpringDroid<'a> {
-    brain: IntCodeComputer,
+/**
+ * The droid, booted up and asking for its springscript.
+ */
+fn boot(computer: IntCodeComputer) -> Result<AsciiComputer, DayError> {
+    let mut droid = AsciiComputer::new(computer);
+    droid.expect_prompt(PROMPT)?;
+    Ok(droid)
+}
+
+/**
+ * Sends the script and the verb that starts it. Returns the hull damage
+ * the droid reports, or the frames showing it falling into space.
+ */
+fn try_script(
+    droid: &mut AsciiComputer,
+    script: &[(Instruction, Read, Write)],
     allowed_distance: char,
-    start_verb: &'a str,
+    start_verb: &str,
+) -> Result<Either<i64, Vec<Frame>>, DayError> {
+    for (instruction, read, write) in script {
+        if let Read::Distance(c) = read
+            && (!c.is_ascii_uppercase() || *c > allowed_distance)
+        {
+            return Err(DayError::IncorrectDistance(*c));
+        }
+        droid.send_line(&format!("{instruction} {read} {write}"));
+    }
+    droid.send_line(start_verb);
+
+    let frames: Vec<_> = droid.frames(FrameDelimiter::BlankLine).try_collect()?;
+    match droid.read_value()? {
+        Some(value) => Ok(Either::Left(value)),
+        None => Ok(Either::Right(frames)),
+    }
 }
 
-impl<'a> SpringDroid<'a> {
-    fn create(code: &str, allowed_distance: char, start_verb: &'a str) -> Result<Self, DayError> {
-        let brain = ComputerFactory::init(code)?.build();
-        Ok(Self {
-            brain,
-            allowed_distance,
-            start_verb,
-        })
+/**
+ * Like `try_script`, but falling is an error. The frames of the fall are
+ * kept as an artifact.
+ */
+fn run_script(
+    droid: &mut AsciiComputer,
+    script: &[(Instruction, Read, Write)],
+    allowed_distance: char,
+    start_verb: &str,
+) -> Result<i64, DayError> {
+    match try_script(droid, script, allowed_distance, start_verb)? {
+        Either::Left(value) => Ok(value),
+        Either::Right(frames) => {
+            let text = frames.iter().map(Frame::text).join("\n\n");
+            crate::artifacts::record("robot_output", text);
+            Err(DayError::IncorrectResult)
+        }
     }
+}
 
-    fn send_instructions(
-        &mut self,
-        instruction: Instruction,
-        read: Read,
-        write: Write,
-    ) -> Result<(), DayError> {
-        if let Read::Distance(c) = read {
-            if !c.is_ascii_uppercase() || c > self.allowed_distance {
-                return Err(DayError::IncorrectDistance(c));
+/**
+ * The jump decision of a springscript for what the sensors currently see.
+ */
+fn decide(instructions: &[(Instruction, Read, Write)], sensor: impl Fn(char) -> bool) -> bool {
+    let mut temp = false;
+    let mut jump = false;
+    for (instruction, read, write) in instructions {
+        let value = match read {
+            Read::Distance(distance) => sensor(*distance),
+            Read::Temp => temp,
+        };
+        let target = match write {
+            Write::Temp => &mut temp,
+            Write::Jump => &mut jump,
+        };
+        *target = match instruction {
+            Instruction::And => value && *target,
+            Instruction::Or => value || *target,
+            Instruction::Not => !value,
+        };
+    }
+    jump
+}
+
+/**
+ * The hull cells in front of the droid, `true` being ground.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct HullWindow([bool; WINDOW_SIZE]);
+
+impl HullWindow {
+    /**
+     * Finds the hull the droid saw at its last wrong decision, i.e. the last
+     * time it stood on ground in front of a window it still could have crossed.
+     * Cells beyond the printed hull count as ground.
+     */
+    fn from_failure(frames: &[Frame]) -> Option<Self> {
+        let mut window = None;
+        for frame in frames {
+            let [_, _, droid, hull] = frame.lines() else {
+                continue;
+            };
+            let Some(x) = droid.find('@') else {
+                continue;
+            };
+            let hull = hull.chars().map(|cell| cell == '#').collect_vec();
+            if hull.get(x) == Some(&true) {
+                let seen = Self(std::array::from_fn(|idx| {
+                    hull.get(x + 1 + idx).copied().unwrap_or(true)
+                }));
+                if seen.is_crossable() {
+                    window = Some(seen);
+                }
             }
         }
+        window
+    }
 
-        self.brain
-            .send_string(&format!("{instruction} {read} {write}"));
-        Ok(())
+    fn is_ground(&self, pos: usize) -> bool {
+        pos == 0 || pos > WINDOW_SIZE || self.0[pos - 1]
     }
 
-    fn start_program(&mut self) -> Result<Either<i64, Vec<String>>, DayError> {
-        self.brain.send_string(self.start_verb);
+    /**
+     * Whether any sequence of walking and jumping gets the droid across.
+     */
+    fn is_crossable(&self) -> bool {
+        let mut crossable = [false; WINDOW_SIZE + JUMP_LENGTH + 1];
+        for pos in (0..crossable.len()).rev() {
+            crossable[pos] = self.is_ground(pos)
+                && (pos > WINDOW_SIZE
+                    || crossable[pos + 1]
+                    || crossable.get(pos + JUMP_LENGTH).copied().unwrap_or(true));
+        }
+        crossable[0]
+    }
 
-        let mut messages = vec![];
-        while let Some(line) = self.brain.maybe_string_or_i64()? {
-            match line {
-                Either::Left(value) => return Ok(Either::Left(value)),
-                Either::Right(line) => messages.push(line),
+    /**
+     * Lets the droid run through this window, starting on the ground just before it.
+     * Everything after the window is taken as ground.
+     */
+    fn survives(&self, instructions: &[(Instruction, Read, Write)]) -> bool {
+        let mut pos = 0;
+        while pos <= WINDOW_SIZE {
+            if !self.is_ground(pos) {
+                return false;
             }
+            let jump = decide(instructions, |distance| {
+                self.is_ground(pos + 1 + (distance as u8 - b'A') as usize)
+            });
+            pos += if jump { JUMP_LENGTH } else { 1 };
         }
+        true
+    }
+}
 
-        Ok(Either::Right(messages))
+impl Display for HullWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for cell in self.0 {
+            write!(f, "{}", if cell { '#' } else { '.' })?;
+        }
+        Ok(())
     }
+}
 
-    fn run_instructions(
-        &mut self,
-        instructions: &[(Instruction, Read, Write)],
-        print_error: bool,
-    ) -> Result<i64, DayError> {
-        for (instruction, read, write) in instructions {
-            self.send_instructions(*instruction, *read, *write)?;
+impl FromStr for HullWindow {
+    type Err = DayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cells: Vec<_> = s
+            .chars()
+            .map(|cell| match cell {
+                '#' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(DayError::IncorrectWindow(s.to_owned())),
+            })
+            .try_collect()?;
+        let cells = cells
+            .try_into()
+            .map_err(|_| DayError::IncorrectWindow(s.to_owned()))?;
+        Ok(Self(cells))
+    }
+}
+
+/**
+ * All distinct hull windows some springscript failed on, one per line in its file.
+ */
+#[derive(Debug, Default)]
+struct HullCorpus(BTreeSet<HullWindow>);
+
+#[allow(dead_code)]
+impl HullCorpus {
+    pub fn insert(&mut self, window: HullWindow) -> bool {
+        self.0.insert(window)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HullWindow> {
+        self.0.iter()
+    }
+
+    /**
+     * The windows the droid does not get across with the given springscript.
+     */
+    pub fn failing_for(&self, instructions: &[(Instruction, Read, Write)]) -> Vec<HullWindow> {
+        self.iter()
+            .filter(|window| !window.survives(instructions))
+            .copied()
+            .collect()
+    }
+
+    pub fn load() -> Result<Self, DayError> {
+        match read_string_of(Day.get_year(), DAY_NUMBER, CORPUS_FILE) {
+            Ok(text) => text.parse(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
         }
+    }
 
-        match self.start_program()? {
-            Either::Left(value) => Ok(value),
-            Either::Right(messages) => {
-                if print_error {
-                    println!("{}", messages.into_iter().join("\n"));
-                }
-                Err(DayError::IncorrectResult)
+    pub fn save(&self) -> Result<(), DayError> {
+        Ok(write_string_of(
+            Day.get_year(),
+            DAY_NUMBER,
+            CORPUS_FILE,
+            &self.to_string(),
+        )?)
+    }
+
+    /**
+     * Runs every candidate springscript on the real droid and keeps the windows
+     * it failed on. Returns the number of windows that were new.
+     */
+    pub fn collect(
+        &mut self,
+        code: &str,
+        allowed_distance: char,
+        start_verb: &str,
+        candidates: &[Vec<(Instruction, Read, Write)>],
+    ) -> Result<usize, DayError> {
+        let factory = ComputerFactory::init(code)?;
+        let before = self.len();
+        for candidate in candidates {
+            let mut droid = boot(factory.build())?;
+            if let Either::Right(frames) =
+                try_script(&mut droid, candidate, allowed_distance, start_verb)?
+                && let Some(window) = HullWindow::from_failure(&frames)
+            {
+                self.insert(window);
             }
         }
+        Ok(self.len() - before)
+    }
+}
+
+impl Display for HullCorpus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for window in self.iter() {
+            writeln!(f, "{window}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HullCorpus {
+    type Err = DayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let windows = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::parse)
+            .try_collect()?;
+        Ok(Self(windows))
     }
 }
 
+/**
+ * Simple springscripts that each fail on different parts of the hull.
+ */
+#[allow(dead_code)]
+fn candidate_scripts(allowed_distance: char) -> Vec<Vec<(Instruction, Read, Write)>> {
+    ('A'..=allowed_distance)
+        .flat_map(|distance| {
+            [
+                vec![(Instruction::Not, Read::Distance(distance), Write::Jump)],
+                vec![
+                    (Instruction::Not, Read::Distance(distance), Write::Jump),
+                    (Instruction::And, Read::Distance('D'), Write::Jump),
+                ],
+            ]
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Read {
     Distance(char),
@@ -180,3 +454,50 @@ impl Display for Instruction {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::{read_string, UnitResult};
+
+    #[test]
+    fn simulate_window() -> UnitResult {
+        let window: HullWindow = "#..#.####".parse()?;
+        assert!(window.is_crossable());
+        assert!(window.survives(&WALK_SCRIPT));
+        assert!(!window.survives(&candidate_scripts('D')[0]));
+        assert!(!"..#.#####".parse::<HullWindow>()?.is_crossable());
+        assert!("#.#".parse::<HullWindow>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn hull_corpus() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let mut corpus = HullCorpus::default();
+        let found = corpus.collect(&input, 'D', "WALK", &candidate_scripts('D'))?;
+
+        assert!(found > 0);
+        assert_eq!(corpus.len(), found);
+        assert!(corpus.iter().all(HullWindow::is_crossable));
+        assert!(!corpus.failing_for(&candidate_scripts('D')[0]).is_empty());
+
+        let reread: HullCorpus = corpus.to_string().parse()?;
+        assert_eq!(reread.0, corpus.0);
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "collects the corpus file, run explicitly"]
+    fn collect_hull_corpus() -> UnitResult {
+        let input = read_string(DAY_NUMBER, "input.txt")?;
+        let mut corpus = HullCorpus::load()?;
+        corpus.collect(&input, 'D', "WALK", &candidate_scripts('D'))?;
+        corpus.collect(&input, 'I', "RUN", &candidate_scripts('I'))?;
+        corpus.save()?;
+
+        Ok(())
+    }
+}
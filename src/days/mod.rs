@@ -21,48 +21,121 @@ mod day20;
 mod day21;
 mod template;
 
-pub use template::{read_string, DayTrait, DayType, PartType, RResult, ResultType, UnitResult};
+pub use template::{
+    data_files_of, read_string, read_string_of, set_data_dir, solve_shared, write_string_of,
+    DataFileError, DayTrait, DayType, ExtraSolver, FormatPolicy, PartType, RResult, Redactor,
+    ResultType, UnitResult, YearType, DEFAULT_YEAR,
+};
 
 pub mod day_provider {
     use super::*;
+    use itertools::Itertools;
     use thiserror::Error;
 
-    const MAX_DAY: DayType = 21;
+    /**
+     * The solutions of one event. Another year gets its own module with
+     * a `get_day` like `days_2019`, and an entry in `YEARS`, everything
+     * else in the crate works for it as is.
+     */
+    struct Year {
+        year: YearType,
+        max_day: DayType,
+        get_day: fn(DayType) -> Option<Box<dyn DayTrait>>,
+        /** Where the modules of the days are, for the index */
+        modules: &'static str,
+    }
+
+    const YEARS: [Year; 1] = [Year {
+        year: 2019,
+        max_day: 21,
+        get_day: days_2019,
+        modules: "src/days",
+    }];
 
-    pub fn get_day(day_num: DayType) -> core::result::Result<Box<dyn DayTrait>, ProviderError> {
+    fn days_2019(day_num: DayType) -> Option<Box<dyn DayTrait>> {
         match day_num {
-            1 => Ok(Box::new(day01::Day)),
-            2 => Ok(Box::new(day02::Day)),
-            3 => Ok(Box::new(day03::Day)),
-            4 => Ok(Box::new(day04::Day)),
-            5 => Ok(Box::new(day05::Day)),
-            6 => Ok(Box::new(day06::Day)),
-            7 => Ok(Box::new(day07::Day)),
-            8 => Ok(Box::new(day08::Day)),
-            9 => Ok(Box::new(day09::Day)),
-            10 => Ok(Box::new(day10::Day)),
-            11 => Ok(Box::new(day11::Day)),
-            12 => Ok(Box::new(day12::Day)),
-            13 => Ok(Box::new(day13::Day)),
-            14 => Ok(Box::new(day14::Day)),
-            15 => Ok(Box::new(day15::Day)),
-            16 => Ok(Box::new(day16::Day)),
-            17 => Ok(Box::new(day17::Day)),
-            18 => Ok(Box::new(day18::Day)),
-            19 => Ok(Box::new(day19::Day)),
-            20 => Ok(Box::new(day20::Day)),
-            21 => Ok(Box::new(day21::Day)),
-            _ => Err(ProviderError::InvalidNumber(day_num)),
+            1 => Some(Box::new(day01::Day)),
+            2 => Some(Box::new(day02::Day)),
+            3 => Some(Box::new(day03::Day)),
+            4 => Some(Box::new(day04::Day)),
+            5 => Some(Box::new(day05::Day)),
+            6 => Some(Box::new(day06::Day)),
+            7 => Some(Box::new(day07::Day)),
+            8 => Some(Box::new(day08::Day)),
+            9 => Some(Box::new(day09::Day)),
+            10 => Some(Box::new(day10::Day)),
+            11 => Some(Box::new(day11::Day)),
+            12 => Some(Box::new(day12::Day)),
+            13 => Some(Box::new(day13::Day)),
+            14 => Some(Box::new(day14::Day)),
+            15 => Some(Box::new(day15::Day)),
+            16 => Some(Box::new(day16::Day)),
+            17 => Some(Box::new(day17::Day)),
+            18 => Some(Box::new(day18::Day)),
+            19 => Some(Box::new(day19::Day)),
+            20 => Some(Box::new(day20::Day)),
+            21 => Some(Box::new(day21::Day)),
+            _ => None,
         }
     }
 
-    pub fn get_all_days() -> impl Iterator<Item = Box<dyn DayTrait>> {
-        (1..=MAX_DAY).map(|day_num| get_day(day_num).expect("Must never happen"))
+    fn find_year(year: YearType) -> Result<&'static Year, ProviderError> {
+        YEARS
+            .iter()
+            .find(|known| known.year == year)
+            .ok_or(ProviderError::UnknownYear(year))
+    }
+
+    pub fn get_day(
+        year: YearType,
+        day_num: DayType,
+    ) -> core::result::Result<Box<dyn DayTrait>, ProviderError> {
+        (find_year(year)?.get_day)(day_num).ok_or(ProviderError::InvalidNumber(day_num))
+    }
+
+    pub fn get_all_days(
+        year: YearType,
+    ) -> core::result::Result<impl Iterator<Item = Box<dyn DayTrait>>, ProviderError> {
+        let year = find_year(year)?;
+        Ok((1..=year.max_day).map(|day_num| (year.get_day)(day_num).expect("Must never happen")))
+    }
+
+    /** The source file of a day, relative to the crate */
+    pub fn module_path(day: &dyn DayTrait) -> String {
+        let year = find_year(day.get_year()).expect("Must never happen");
+        format!("{}/day{:02}/mod.rs", year.modules, day.get_day_number())
     }
 
     #[derive(Debug, Error)]
     pub enum ProviderError {
         #[error("Not a valid day number: {0}")]
         InvalidNumber(DayType),
+
+        #[error("No solutions for the year {0} (known: {})", YEARS.iter().map(|year| year.year).join(", "))]
+        UnknownYear(YearType),
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn years() {
+            assert_eq!(get_all_days(DEFAULT_YEAR).unwrap().count(), 21);
+            let day = get_day(DEFAULT_YEAR, 5).unwrap();
+            assert_eq!((day.get_year(), day.get_day_number()), (2019, 5));
+            assert_eq!(module_path(day.as_ref()), "src/days/day05/mod.rs");
+            assert!(matches!(
+                get_day(DEFAULT_YEAR, 22),
+                Err(ProviderError::InvalidNumber(22))
+            ));
+            let Err(err) = get_day(2015, 1) else {
+                panic!("there are no days of 2015");
+            };
+            assert_eq!(
+                err.to_string(),
+                "No solutions for the year 2015 (known: 2019)"
+            );
+        }
     }
 }
@@ -1,5 +1,14 @@
 #![allow(dead_code)]
-use std::{fs, io};
+use crate::common::{
+    units::{Level, Minutes, Steps},
+    viz::RenderGrid,
+};
+use std::{
+    fmt::{Debug, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 use itertools::Itertools;
 
@@ -13,6 +22,72 @@ pub enum ResultType {
     Lines(Vec<String>),
 }
 
+/**
+ * Hides an answer so the output can be shared, the runner decides how.
+ * It gets the plainly formatted answer, whatever the other options are.
+ */
+pub trait Redactor: Debug + Sync {
+    fn redact(&self, answer: &str) -> String;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatPolicy {
+    pub thousands_separator: bool,
+    pub uppercase_text: bool,
+    /** Replaces every answer so the output can be shared */
+    pub redactor: Option<&'static dyn Redactor>,
+}
+
+impl FormatPolicy {
+    fn integer(&self, value: i64) -> String {
+        let digits = value.unsigned_abs().to_string();
+        let digits = if self.thousands_separator {
+            digits
+                .as_bytes()
+                .rchunks(3)
+                .rev()
+                .map(|chunk| std::str::from_utf8(chunk).expect("digits are ascii"))
+                .join(",")
+        } else {
+            digits
+        };
+        if value < 0 {
+            format!("-{digits}")
+        } else {
+            digits
+        }
+    }
+
+    fn text(&self, value: &str) -> String {
+        if self.uppercase_text {
+            value.to_uppercase()
+        } else {
+            value.to_owned()
+        }
+    }
+}
+
+impl ResultType {
+    /**
+     * Formats the result as lines of output following the given policy.
+     * All days go through here, so the runner alone decides how answers look.
+     */
+    pub fn format(&self, policy: FormatPolicy) -> Vec<String> {
+        if let Some(redactor) = policy.redactor
+            && *self != ResultType::Nothing
+        {
+            let answer = self.format(FormatPolicy::default()).join("\n");
+            return vec![redactor.redact(&answer)];
+        }
+        match self {
+            ResultType::Nothing => vec![],
+            ResultType::Integer(value) => vec![policy.integer(*value)],
+            ResultType::String(value) => vec![policy.text(value)],
+            ResultType::Lines(lines) => lines.clone(),
+        }
+    }
+}
+
 pub type RResult = anyhow::Result<ResultType>;
 pub type UnitResult = anyhow::Result<()>;
 
@@ -40,50 +115,53 @@ impl From<Vec<String>> for ResultType {
 impl From<Vec<Vec<bool>>> for ResultType {
     #[inline]
     fn from(lines: Vec<Vec<bool>>) -> Self {
-        let lines = lines
-            .into_iter()
-            .map(|row| row.into_iter().map(|p| if p { '█' } else { ' ' }).join(""))
-            .collect_vec();
-        ResultType::Lines(lines)
-    }
-}
-
-impl From<i32> for ResultType {
-    #[inline]
-    fn from(value: i32) -> Self {
-        ResultType::Integer(value as i64)
-    }
-}
-
-impl From<u32> for ResultType {
-    #[inline]
-    fn from(value: u32) -> Self {
-        ResultType::Integer(value as i64)
+        ResultType::Lines(lines.render_lines())
     }
 }
 
-impl From<u64> for ResultType {
-    #[inline]
-    fn from(value: u64) -> Self {
-        assert!(value < i64::MAX as u64);
-        ResultType::Integer(value as i64)
-    }
+/**
+ * Every integer answer becomes an `Integer`, whatever type the day counted in.
+ * How it looks is up to `FormatPolicy` alone.
+ */
+macro_rules! integer_result {
+    ($($t:ty)*) => ($(
+        impl From<$t> for ResultType {
+            #[inline]
+            fn from(value: $t) -> Self {
+                ResultType::Integer(value.into())
+            }
+        }
+    )*)
 }
+integer_result!(i8 i16 i32 i64 u8 u16 u32);
 
-impl From<i64> for ResultType {
-    #[inline]
-    fn from(value: i64) -> Self {
-        ResultType::Integer(value)
-    }
+/**
+ * Like `integer_result`, for types wider than an `i64` can hold.
+ * No answer ever gets that large, so one that does is a bug in the day.
+ */
+macro_rules! wide_integer_result {
+    ($($t:ty)*) => ($(
+        impl From<$t> for ResultType {
+            #[inline]
+            fn from(value: $t) -> Self {
+                ResultType::Integer(i64::try_from(value).expect("answers fit into an i64"))
+            }
+        }
+    )*)
 }
+wide_integer_result!(u64 usize);
 
-impl From<usize> for ResultType {
-    #[inline]
-    fn from(value: usize) -> Self {
-        assert!(value < i64::MAX as usize);
-        ResultType::Integer(value as i64)
-    }
+macro_rules! unit_result {
+    ($($t:ty)*) => ($(
+        impl From<$t> for ResultType {
+            #[inline]
+            fn from(value: $t) -> Self {
+                value.get().into()
+            }
+        }
+    )*)
 }
+unit_result!(Steps Level Minutes);
 
 impl From<()> for ResultType {
     fn from(_value: ()) -> Self {
@@ -91,19 +169,311 @@ impl From<()> for ResultType {
     }
 }
 
+pub type YearType = u16;
 pub type DayType = u8;
 pub type PartType = u8;
 
+/** The year of the days in this crate, used whenever no year is given */
+pub const DEFAULT_YEAR: YearType = 2019;
+
+/**
+ * An additional solver on the same input, e.g. a popular community variant of the puzzle.
+ */
+pub type ExtraSolver = fn(&str) -> RResult;
+
 pub trait DayTrait {
+    fn get_year(&self) -> YearType {
+        DEFAULT_YEAR
+    }
     fn get_day_number(&self) -> DayType;
     fn part1(&self, input: &str) -> RResult;
     fn part2(&self, input: &str) -> RResult;
+
+    /**
+     * Both parts at once, for days where part 2 builds on what part 1 had
+     * to find anyway. The runner prefers this whenever it runs both parts,
+     * `None` means the parts share nothing worth keeping.
+     */
+    fn solve_both(&self, _input: &str) -> Option<(RResult, RResult)> {
+        None
+    }
+
+    fn extras(&self) -> Vec<(&'static str, ExtraSolver)> {
+        vec![]
+    }
+}
+
+/**
+ * Both results of `DayTrait::solve_both` from the work the parts share.
+ * If that fails, both parts fail with its message.
+ */
+pub fn solve_shared<T>(
+    shared: impl FnOnce() -> anyhow::Result<T>,
+    part1: impl FnOnce(&mut T) -> RResult,
+    part2: impl FnOnce(&mut T) -> RResult,
+) -> (RResult, RResult) {
+    match shared() {
+        Ok(mut shared) => (part1(&mut shared), part2(&mut shared)),
+        Err(error) => {
+            let message = format!("{error:#}");
+            (Err(error), Err(anyhow::anyhow!(message)))
+        }
+    }
+}
+
+pub const DATA_DIR_VAR: &str = "ADVENT_DATA_DIR";
+const DEFAULT_DATA_DIR: &str = "data";
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/**
+ * Sets the directory all inputs are read from. Can only be done once,
+ * before the first input is read. Returns false if it was too late.
+ */
+pub fn set_data_dir(path: impl Into<PathBuf>) -> bool {
+    DATA_DIR.set(path.into()).is_ok()
 }
 
-fn format_path(day_num: DayType, file: &str) -> String {
-    format!("data/day{day_num:02}/{file}")
+/**
+ * The directory all inputs are read from: an explicitly set one,
+ * otherwise the one from `ADVENT_DATA_DIR`, otherwise `data`.
+ * It has a directory per year with one per day below, like `2019/day05`.
+ */
+pub fn data_dir() -> &'static Path {
+    DATA_DIR.get_or_init(|| {
+        std::env::var_os(DATA_DIR_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_DIR))
+    })
 }
 
-pub fn read_string(day_num: DayType, file: &str) -> io::Result<String> {
-    fs::read_to_string(format_path(day_num, file))
+/**
+ * A data directory without a directory for the default year is one from
+ * before there were several years, its days are those of the default year.
+ */
+fn year_dir(year: YearType) -> PathBuf {
+    let dir = data_dir().join(year.to_string());
+    if year == DEFAULT_YEAR && !dir.is_dir() {
+        data_dir().to_owned()
+    } else {
+        dir
+    }
+}
+
+fn format_path(year: YearType, day_num: DayType, file: &str) -> PathBuf {
+    year_dir(year).join(format!("day{day_num:02}")).join(file)
+}
+
+/**
+ * A data file that could not be read, with everything needed to find out why.
+ */
+#[derive(Debug, thiserror::Error)]
+pub struct DataFileError {
+    year: YearType,
+    day: DayType,
+    file: String,
+    /** Absolute, as far as that could be found out */
+    path: PathBuf,
+    /** The files the day does have, `None` if its directory is missing */
+    existing: Option<Vec<String>>,
+    #[source]
+    source: io::Error,
+}
+
+impl DataFileError {
+    fn new(year: YearType, day: DayType, file: &str, source: io::Error) -> Self {
+        let path = format_path(year, day, file);
+        let dir = format_path(year, day, "");
+        Self {
+            year,
+            day,
+            file: file.to_owned(),
+            path: std::path::absolute(&path).unwrap_or(path),
+            existing: dir
+                .is_dir()
+                .then(|| data_files_of(year, day).unwrap_or_default()),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+}
+
+impl Display for DataFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Cannot read {} of day {:02} of {} at {}",
+            self.file,
+            self.day,
+            self.year,
+            self.path.display(),
+        )?;
+        match &self.existing {
+            None => write!(
+                f,
+                " (the directory for day {:02} does not exist, set the data directory \
+                 with --data-dir or {DATA_DIR_VAR})",
+                self.day
+            ),
+            Some(files) if files.is_empty() => write!(f, " (day {:02} has no files)", self.day),
+            Some(files) => write!(f, " (day {:02} has {})", self.day, files.join(", ")),
+        }
+    }
+}
+
+/**
+ * A data file of a day of the default year, see `read_string_of` for others.
+ */
+pub fn read_string(day_num: DayType, file: &str) -> Result<String, DataFileError> {
+    read_string_of(DEFAULT_YEAR, day_num, file)
+}
+
+pub fn read_string_of(
+    year: YearType,
+    day_num: DayType,
+    file: &str,
+) -> Result<String, DataFileError> {
+    fs::read_to_string(format_path(year, day_num, file))
+        .map_err(|err| DataFileError::new(year, day_num, file, err))
+}
+
+/**
+ * The names of all files in the data directory of a day, sorted.
+ * A day without a directory simply has no files.
+ */
+pub fn data_files_of(year: YearType, day_num: DayType) -> io::Result<Vec<String>> {
+    let dir = format_path(year, day_num, "");
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/**
+ * Like `read_string_of`, the directory of the day has to exist already.
+ */
+pub fn write_string_of(
+    year: YearType,
+    day_num: DayType,
+    file: &str,
+    contents: &str,
+) -> io::Result<()> {
+    fs::write(format_path(year, day_num, file), contents)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_data_file() {
+        let Err(err) = read_string(1, "missing.txt") else {
+            panic!("missing.txt should not exist");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let message = err.to_string();
+        assert!(message.contains("day 01 of 2019"));
+        assert!(message.contains(&format!("day01{}missing.txt", std::path::MAIN_SEPARATOR)));
+        assert!(message.contains("input.txt"));
+        assert!(err.path.is_absolute());
+
+        let Err(err) = read_string(99, "input.txt") else {
+            panic!("there is no day 99");
+        };
+        assert!(err.to_string().contains(DATA_DIR_VAR));
+
+        let Err(err) = read_string_of(1999, 1, "input.txt") else {
+            panic!("there is no year 1999");
+        };
+        assert!(err
+            .path
+            .ends_with(Path::new("1999").join("day01").join("input.txt")));
+        let Err(err) = write_string_of(1999, 1, "output.txt", "") else {
+            panic!("there is no year 1999 to write to");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[derive(Debug)]
+    struct Bracketed;
+
+    impl Redactor for Bracketed {
+        fn redact(&self, answer: &str) -> String {
+            format!("[{answer}]")
+        }
+    }
+
+    #[test]
+    fn format_policies() {
+        let plain = FormatPolicy::default();
+        let fancy = FormatPolicy {
+            thousands_separator: true,
+            uppercase_text: true,
+            redactor: None,
+        };
+        let redacted = FormatPolicy {
+            redactor: Some(&Bracketed),
+            ..fancy
+        };
+
+        assert_eq!(ResultType::Integer(-1234567).format(plain), ["-1234567"]);
+        assert_eq!(ResultType::Integer(-1234567).format(fancy), ["-1,234,567"]);
+        assert_eq!(ResultType::Integer(123).format(fancy), ["123"]);
+        assert_eq!(ResultType::from(-7_i32), ResultType::Integer(-7));
+        assert_eq!(ResultType::from(7_u8), ResultType::Integer(7));
+        assert_eq!(
+            ResultType::from(i64::MAX as usize),
+            ResultType::Integer(i64::MAX)
+        );
+        assert_eq!(ResultType::from("abc").format(fancy), ["ABC"]);
+        assert!(ResultType::Nothing.format(fancy).is_empty());
+
+        assert_eq!(
+            ResultType::Integer(-1234567).format(redacted),
+            ["[-1234567]"]
+        );
+        assert_eq!(
+            ResultType::Lines(vec!["#..".to_owned(), ".#.".to_owned()]).format(redacted),
+            ["[#..\n.#.]"]
+        );
+        assert!(ResultType::Nothing.format(redacted).is_empty());
+    }
+
+    #[test]
+    fn shared_work() {
+        let mut runs = 0;
+        let (part1, part2) = solve_shared(
+            || {
+                runs += 1;
+                Ok(vec![3, 4])
+            },
+            |values| Ok(values.iter().sum::<i64>().into()),
+            |values| {
+                values.push(5);
+                Ok(values.len().into())
+            },
+        );
+        assert_eq!(runs, 1);
+        assert_eq!(part1.unwrap(), ResultType::Integer(7));
+        assert_eq!(part2.unwrap(), ResultType::Integer(3));
+
+        let (part1, part2) = solve_shared(
+            || Err::<(), _>(anyhow::anyhow!("broken")),
+            |_| Ok(().into()),
+            |_| Ok(().into()),
+        );
+        assert_eq!(part1.unwrap_err().to_string(), "broken");
+        assert_eq!(part2.unwrap_err().to_string(), "broken");
+    }
 }
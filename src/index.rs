@@ -0,0 +1,155 @@
+use crate::days::{data_files_of, day_provider, DayTrait, DayType, YearType};
+use itertools::Itertools;
+use std::{io, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown index format: {0} (known: json, markdown)")]
+pub struct UnknownFormat(String);
+
+impl FromStr for IndexFormat {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(IndexFormat::Json),
+            "markdown" | "md" => Ok(IndexFormat::Markdown),
+            _ => Err(UnknownFormat(s.to_owned())),
+        }
+    }
+}
+
+/**
+ * What the index knows about one day.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayEntry {
+    pub day: DayType,
+    pub module: String,
+    /** The example inputs found in the data directory */
+    pub examples: Vec<String>,
+    pub has_input: bool,
+    /** The names of the extra solvers, usable with `--extra` */
+    pub extras: Vec<&'static str>,
+}
+
+impl DayEntry {
+    pub fn new(day: &dyn DayTrait) -> io::Result<Self> {
+        let day_number = day.get_day_number();
+        let files = data_files_of(day.get_year(), day_number)?;
+        Ok(Self {
+            day: day_number,
+            module: day_provider::module_path(day),
+            examples: files
+                .iter()
+                .filter(|file| file.starts_with("example"))
+                .cloned()
+                .collect(),
+            has_input: files.iter().any(|file| file == "input.txt"),
+            extras: day.extras().into_iter().map(|(name, _)| name).collect(),
+        })
+    }
+}
+
+pub fn entries(year: YearType) -> anyhow::Result<Vec<DayEntry>> {
+    Ok(day_provider::get_all_days(year)?
+        .map(|day| DayEntry::new(day.as_ref()))
+        .try_collect()?)
+}
+
+pub fn render(entries: &[DayEntry], format: IndexFormat) -> String {
+    match format {
+        IndexFormat::Json => to_json(entries),
+        IndexFormat::Markdown => to_markdown(entries),
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn json_list<'a>(values: impl IntoIterator<Item = &'a str>) -> String {
+    format!("[{}]", values.into_iter().map(json_string).join(", "))
+}
+
+fn to_json(entries: &[DayEntry]) -> String {
+    let days = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "  {{\"day\": {}, \"module\": {}, \"has_input\": {}, \"examples\": {}, \"extras\": {}}}",
+                entry.day,
+                json_string(&entry.module),
+                entry.has_input,
+                json_list(entry.examples.iter().map(String::as_str)),
+                json_list(entry.extras.iter().copied()),
+            )
+        })
+        .join(",\n");
+    format!("[\n{days}\n]\n")
+}
+
+fn to_markdown(entries: &[DayEntry]) -> String {
+    let mut result = String::from("| Day | Module | Input | Examples | Extras |\n");
+    result.push_str("|----:|--------|:-----:|----------|--------|\n");
+    for entry in entries {
+        let list = |values: &[&str]| {
+            if values.is_empty() {
+                "-".to_owned()
+            } else {
+                values.iter().map(|value| format!("`{value}`")).join(", ")
+            }
+        };
+        result.push_str(&format!(
+            "| {} | [{}]({}) | {} | {} | {} |\n",
+            entry.day,
+            entry.module,
+            entry.module,
+            if entry.has_input { "yes" } else { "no" },
+            list(&entry.examples.iter().map(String::as_str).collect_vec()),
+            list(&entry.extras),
+        ));
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::{UnitResult, DEFAULT_YEAR};
+
+    #[test]
+    fn index() -> UnitResult {
+        let entries = entries(DEFAULT_YEAR)?;
+        let day17 = entries.iter().find(|entry| entry.day == 17).unwrap();
+        assert!(day17.has_input);
+        assert_eq!(day17.examples, vec!["example01.txt", "example02.txt"]);
+        assert!(day17.extras.contains(&"movement-program"));
+
+        let json = render(std::slice::from_ref(day17), IndexFormat::Json);
+        assert!(json.starts_with(
+            "[\n  {\"day\": 17, \"module\": \"src/days/day17/mod.rs\", \"has_input\": true"
+        ));
+        let markdown = render(&entries, IndexFormat::Markdown);
+        assert_eq!(markdown.lines().count(), entries.len() + 2);
+
+        assert_eq!(json_string("a\"b\\\n\t"), "\"a\\\"b\\\\\\n\\u0009\"");
+        Ok(())
+    }
+}
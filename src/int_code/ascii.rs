@@ -0,0 +1,458 @@
+#![allow(dead_code)]
+use super::{
+    ascii_frames::{Frame, FrameDelimiter, FrameReader, LineSource},
+    io_log::{default_io_log, IoLog},
+    ComputerError, IntCodeComputer,
+};
+use itertools::Itertools;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speaker {
+    Computer,
+    User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptLine {
+    pub speaker: Speaker,
+    pub text: String,
+}
+
+impl Display for TranscriptLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.speaker {
+            Speaker::Computer => write!(f, "{}", self.text),
+            Speaker::User => write!(f, "> {}", self.text),
+        }
+    }
+}
+
+/**
+ * Everything printed up to a line that looks like a prompt.
+ * `prompt` is `None` if the output ended before a prompt was seen.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    pub lines: Vec<String>,
+    pub prompt: Option<String>,
+}
+
+/**
+ * What `AsciiComputer::read_until` waits for. Both look at all the text
+ * read so far, right after every char.
+ */
+#[derive(Clone, Copy)]
+pub enum Pattern<'a> {
+    /** The text ends with this */
+    Literal(&'a str),
+    /** Anything else, like a regex search or `looks_like_prompt` */
+    Matcher(&'a dyn Fn(&str) -> bool),
+}
+
+impl Pattern<'_> {
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Pattern::Literal(literal) => text.ends_with(literal),
+            Pattern::Matcher(matcher) => matcher(text),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Pattern<'a> {
+    fn from(literal: &'a str) -> Self {
+        Pattern::Literal(literal)
+    }
+}
+
+/**
+ * The usual prompts of the puzzles are questions like `Command?` or
+ * labels like `Main:` waiting to be filled in.
+ */
+pub fn looks_like_prompt(line: &str) -> bool {
+    let line = line.trim_end();
+    line.ends_with('?') || line.ends_with(':')
+}
+
+/**
+ * A computer that talks in lines of ASCII text. Every line read or sent
+ * is kept in a transcript, so a conversation can be shown afterwards,
+ * and goes to the I/O log right away. Values that are no ASCII chars
+ * are left to be read with `read_value`.
+ */
+pub struct AsciiComputer {
+    computer: IntCodeComputer,
+    transcript: Vec<TranscriptLine>,
+    is_prompt: fn(&str) -> bool,
+    log: IoLog,
+}
+
+impl AsciiComputer {
+    pub fn new(computer: IntCodeComputer) -> Self {
+        Self {
+            computer,
+            transcript: vec![],
+            is_prompt: looks_like_prompt,
+            log: default_io_log(),
+        }
+    }
+
+    /**
+     * Instead of the default log the runner set up.
+     */
+    pub fn set_io_log(&mut self, log: IoLog) {
+        self.log = log;
+    }
+
+    /**
+     * Replaces `looks_like_prompt` for programs with unusual prompts.
+     */
+    pub fn set_prompt_detector(&mut self, is_prompt: fn(&str) -> bool) {
+        self.is_prompt = is_prompt;
+    }
+
+    pub fn computer(&mut self) -> &mut IntCodeComputer {
+        &mut self.computer
+    }
+
+    pub fn into_inner(self) -> IntCodeComputer {
+        self.computer
+    }
+
+    /**
+     * Resets the computer and starts a new transcript.
+     */
+    pub fn reset(&mut self) {
+        self.computer.reset();
+        self.transcript.clear();
+    }
+
+    /**
+     * The next line of output, `None` if the computer halted, waits for
+     * input or sent a value that is no ASCII char.
+     */
+    pub fn read_line(&mut self) -> Result<Option<String>, ComputerError> {
+        let line = self.computer.maybe_string()?;
+        if let Some(text) = &line {
+            self.record(Speaker::Computer, text);
+        }
+        Ok(line)
+    }
+
+    pub fn expect_line(&mut self) -> Result<String, ComputerError> {
+        self.read_line()?.ok_or(ComputerError::PrematureEndOfOutput)
+    }
+
+    /**
+     * Reads lines until one is exactly the prompt and returns the lines before it.
+     */
+    pub fn read_until_prompt(&mut self, prompt: &str) -> Result<Vec<String>, ComputerError> {
+        let mut lines = vec![];
+        loop {
+            let line = self.expect_line()?;
+            if line == prompt {
+                return Ok(lines);
+            }
+            lines.push(line);
+        }
+    }
+
+    /**
+     * Reads the output char by char until the pattern matches, and then up
+     * to the end of that line, which need not be there for a prompt. Returns
+     * the text without the last newline, `None` if the computer halted,
+     * waits for input or sent a value that is no ASCII char before a match.
+     * Either way, the text read is in the transcript.
+     */
+    pub fn read_until<'p>(
+        &mut self,
+        pattern: impl Into<Pattern<'p>>,
+    ) -> Result<Option<String>, ComputerError> {
+        let pattern = pattern.into();
+        let mut text = String::new();
+        let mut found = false;
+        loop {
+            let value = match self.computer.maybe_i64() {
+                Ok(Some(value)) => value,
+                Ok(None) | Err(ComputerError::WaitingForInput) => break,
+                Err(err) => return Err(err),
+            };
+            let Some(ch) = u32::try_from(value)
+                .ok()
+                .and_then(char::from_u32)
+                .filter(char::is_ascii)
+            else {
+                self.computer.unread(value);
+                break;
+            };
+            if found && ch == '\n' {
+                break;
+            }
+            text.push(ch);
+            found = found || pattern.is_match(&text);
+            if found && ch == '\n' {
+                text.pop();
+                break;
+            }
+        }
+        for line in text.lines() {
+            self.record(Speaker::Computer, line);
+        }
+        Ok(found.then_some(text))
+    }
+
+    /**
+     * Like `read_until`, but the output ending before the pattern is an error.
+     */
+    pub fn expect_prompt<'p>(
+        &mut self,
+        pattern: impl Into<Pattern<'p>>,
+    ) -> Result<String, ComputerError> {
+        self.read_until(pattern)?
+            .ok_or(ComputerError::PrematureEndOfOutput)
+    }
+
+    /**
+     * Reads up to and including the next line the prompt detector accepts,
+     * so a driver knows the program is waiting before it sends anything.
+     */
+    pub fn next_prompt(&mut self) -> Result<Prompt, ComputerError> {
+        let mut lines = vec![];
+        while let Some(line) = self.read_line()? {
+            if (self.is_prompt)(&line) {
+                return Ok(Prompt {
+                    lines,
+                    prompt: Some(line),
+                });
+            }
+            lines.push(line);
+        }
+        Ok(Prompt {
+            lines,
+            prompt: None,
+        })
+    }
+
+    /**
+     * Waits for the next prompt and answers it with a line. Returns the
+     * lines before the prompt, the output ending before one is an error.
+     */
+    pub fn answer_prompt(&mut self, line: &str) -> Result<Vec<String>, ComputerError> {
+        let Prompt { lines, prompt } = self.next_prompt()?;
+        if prompt.is_none() {
+            return Err(ComputerError::PrematureEndOfOutput);
+        }
+        self.send_line(line);
+        Ok(lines)
+    }
+
+    pub fn frames(&mut self, delimiter: FrameDelimiter) -> FrameReader<'_, Self> {
+        FrameReader::new(self, delimiter)
+    }
+
+    /**
+     * Just the next frame, `None` if the output ended before it started.
+     */
+    pub fn read_frame(
+        &mut self,
+        delimiter: FrameDelimiter,
+    ) -> Result<Option<Frame>, ComputerError> {
+        self.frames(delimiter).next().transpose()
+    }
+
+    /**
+     * A value that is no ASCII char, like the answer many programs end with.
+     */
+    pub fn read_value(&mut self) -> Result<Option<i64>, ComputerError> {
+        self.computer.maybe_i64()
+    }
+
+    pub fn expect_value(&mut self) -> Result<i64, ComputerError> {
+        self.computer.expect_i64()
+    }
+
+    /**
+     * Reads past whatever text is left to the value the program ends with.
+     * The skipped lines still go to the transcript, halting without such
+     * a value is an error.
+     */
+    pub fn expect_final_number(&mut self) -> Result<i64, ComputerError> {
+        while self.read_line()?.is_some() {}
+        self.read_value()?
+            .ok_or(ComputerError::PrematureEndOfOutput)
+    }
+
+    pub fn send_line(&mut self, line: &str) {
+        self.record(Speaker::User, line);
+        self.computer.send_string(line);
+    }
+
+    pub fn transcript(&self) -> &[TranscriptLine] {
+        &self.transcript
+    }
+
+    pub fn transcript_text(&self) -> String {
+        self.transcript.iter().join("\n")
+    }
+
+    fn record(&mut self, speaker: Speaker, text: &str) {
+        let line = TranscriptLine {
+            speaker,
+            text: text.to_owned(),
+        };
+        self.log.log(&line);
+        self.transcript.push(line);
+    }
+}
+
+impl LineSource for AsciiComputer {
+    fn next_line(&mut self) -> Result<Option<String>, ComputerError> {
+        self.read_line()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::ComputerFactory;
+
+    #[test]
+    fn conversation() -> Result<(), ComputerError> {
+        // Prints "Hi\nName?\n", echoes one char of input as a line, then prints 1234
+        let mut code = "Hi\nName?\n"
+            .chars()
+            .flat_map(|c| [104, c as i64])
+            .collect_vec();
+        let echo = code.len() + 9;
+        code.extend([3, echo as i64, 4, echo as i64, 104, 10, 104, 1234, 99, 0]);
+        let mut computer = AsciiComputer::new(ComputerFactory::new(code).build());
+
+        assert_eq!(computer.read_until_prompt("Name?")?, vec!["Hi".to_owned()]);
+        computer.send_line("X");
+        assert_eq!(computer.read_line()?, Some("X".to_owned()));
+        assert_eq!(computer.read_line()?, None);
+        assert_eq!(computer.read_value()?, Some(1234));
+        assert_eq!(computer.transcript_text(), "Hi\nName?\n> X\nX");
+
+        computer.reset();
+        assert!(computer.transcript().is_empty());
+        assert_eq!(
+            computer.next_prompt()?,
+            Prompt {
+                lines: vec!["Hi".to_owned()],
+                prompt: Some("Name?".to_owned())
+            }
+        );
+        computer.send_line("Y");
+        assert_eq!(
+            computer.next_prompt()?,
+            Prompt {
+                lines: vec!["Y".to_owned()],
+                prompt: None
+            }
+        );
+        assert!(looks_like_prompt("Main:") && !looks_like_prompt("#..#"));
+
+        computer.reset();
+        assert_eq!(computer.answer_prompt("W")?, vec!["Hi".to_owned()]);
+        assert_eq!(computer.expect_line()?, "W");
+        assert!(matches!(
+            computer.answer_prompt("V"),
+            Err(ComputerError::PrematureEndOfOutput)
+        ));
+
+        computer.reset();
+        assert_eq!(computer.expect_line()?, "Hi");
+
+        computer.reset();
+        let frame = computer.read_frame(FrameDelimiter::Size(2))?;
+        assert_eq!(
+            frame.map(|frame| frame.text()),
+            Some("Hi\nName?".to_owned())
+        );
+        computer.send_line("Z");
+        assert_eq!(computer.expect_final_number()?, 1234);
+        assert_eq!(computer.transcript_text(), "Hi\nName?\n> Z\nZ");
+
+        Ok(())
+    }
+
+    #[test]
+    fn prompts() -> Result<(), ComputerError> {
+        // Prints two lines and a question without a newline, then echoes one char
+        let mut code = "Hi\nInput instructions:\nWalk? "
+            .chars()
+            .flat_map(|c| [104, c as i64])
+            .collect_vec();
+        let echo = code.len() + 9;
+        code.extend([3, echo as i64, 4, echo as i64, 104, 10, 99, 0, 0, 0]);
+        let mut computer = AsciiComputer::new(ComputerFactory::new(code).build());
+
+        assert_eq!(
+            computer.expect_prompt("instructions")?,
+            "Hi\nInput instructions:"
+        );
+        let question = |text: &str| text.trim_end().ends_with('?');
+        assert_eq!(
+            computer.read_until(Pattern::Matcher(&question))?,
+            Some("Walk? ".to_owned())
+        );
+        computer.send_line("X");
+        assert_eq!(computer.read_until("never")?, None);
+        assert_eq!(
+            computer.transcript_text(),
+            "Hi\nInput instructions:\nWalk? \n> X\nX"
+        );
+
+        computer.reset();
+        assert!(matches!(
+            computer.expect_prompt("never"),
+            Err(ComputerError::PrematureEndOfOutput)
+        ));
+
+        computer.reset();
+        assert!(matches!(
+            computer.expect_final_number(),
+            Err(ComputerError::WaitingForInput)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn logs_io() -> Result<(), ComputerError> {
+        use crate::int_code::io_log::take_captured;
+
+        // Prints "Go?\n" and echoes one char of input as a line
+        let mut code = "Go?\n".chars().flat_map(|c| [104, c as i64]).collect_vec();
+        let echo = code.len() + 7;
+        code.extend([3, echo as i64, 4, echo as i64, 104, 10, 99, 0]);
+        let mut computer = AsciiComputer::new(ComputerFactory::new(code).build());
+        computer.set_io_log(IoLog::Capture);
+        take_captured();
+
+        computer.next_prompt()?;
+        computer.send_line("A");
+        computer.read_line()?;
+        assert_eq!(take_captured(), ["Go?", "> A", "A"]);
+        assert!(take_captured().is_empty());
+
+        computer.set_io_log(IoLog::Off);
+        computer.reset();
+        computer.next_prompt()?;
+        assert!(take_captured().is_empty());
+        assert_eq!(computer.transcript_text(), "Go?");
+
+        // The runner does this in batch mode, no other test relies on it being off
+        crate::int_code::io_log::capture_all();
+        computer.reset();
+        computer.next_prompt()?;
+        assert_eq!(take_captured(), ["Go?"]);
+
+        assert_eq!("capture".parse::<IoLog>().ok(), Some(IoLog::Capture));
+        assert_eq!("stdout".parse::<IoLog>().ok(), Some(IoLog::Stderr));
+        assert!("file".parse::<IoLog>().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+#![allow(dead_code)]
+use super::{ComputerError, IntCodeComputer};
+use crate::common::{
+    events::{self, Event},
+    grid::{Grid, GridError},
+};
+use itertools::Itertools;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDelimiter {
+    /** A frame ends with an empty line */
+    BlankLine,
+    /** A frame consists of exactly this many lines */
+    Size(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    lines: Vec<String>,
+}
+
+impl Frame {
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.iter().join("\n")
+    }
+
+    /**
+     * The frame as a grid of chars. Short lines are padded with blanks.
+     */
+    pub fn grid(&self) -> Result<Grid<char>, GridError> {
+        Grid::parse_padded(&self.text(), ' ')
+    }
+}
+
+/**
+ * Anything that hands out the ASCII output of a computer line by line.
+ * `None` means the computer halted or sent a value that is no ASCII char.
+ */
+pub trait LineSource {
+    fn next_line(&mut self) -> Result<Option<String>, ComputerError>;
+}
+
+impl LineSource for IntCodeComputer {
+    fn next_line(&mut self) -> Result<Option<String>, ComputerError> {
+        self.maybe_string()
+    }
+}
+
+/**
+ * Splits the ASCII output of a computer into frames.
+ * Stops when the computer halts or sends a value that is no ASCII char.
+ * Such a value stays available to be read from the computer afterwards.
+ */
+pub struct FrameReader<'a, S: LineSource = IntCodeComputer> {
+    computer: &'a mut S,
+    delimiter: FrameDelimiter,
+    finished: bool,
+}
+
+impl<'a, S: LineSource> FrameReader<'a, S> {
+    pub fn new(computer: &'a mut S, delimiter: FrameDelimiter) -> Self {
+        Self {
+            computer,
+            delimiter,
+            finished: false,
+        }
+    }
+
+    fn next_frame(&mut self) -> Result<Option<Frame>, ComputerError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let mut lines = vec![];
+        while let Some(line) = self.computer.next_line()? {
+            match self.delimiter {
+                FrameDelimiter::BlankLine if line.is_empty() => {
+                    if !lines.is_empty() {
+                        return Ok(Some(Frame { lines }));
+                    }
+                }
+                FrameDelimiter::BlankLine => lines.push(line),
+                FrameDelimiter::Size(size) => {
+                    lines.push(line);
+                    if lines.len() == size {
+                        return Ok(Some(Frame { lines }));
+                    }
+                }
+            }
+        }
+        self.finished = true;
+
+        if lines.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Frame { lines }))
+        }
+    }
+}
+
+impl<S: LineSource> Iterator for FrameReader<'_, S> {
+    type Item = Result<Frame, ComputerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.next_frame().transpose();
+        if let Some(Ok(frame)) = &frame {
+            events::publish_with(|| Event::Frame {
+                source: "IntCode",
+                text: frame.text(),
+            });
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::ComputerFactory;
+
+    fn printer(text: &str, trailer: Option<i64>) -> ComputerFactory {
+        let mut code = text.chars().flat_map(|c| [104, c as i64]).collect_vec();
+        if let Some(value) = trailer {
+            code.extend([104, value]);
+        }
+        code.push(99);
+        ComputerFactory::new(code)
+    }
+
+    #[test]
+    fn blank_line_frames() -> Result<(), ComputerError> {
+        let factory = printer("#.\n.#\n\n\n##\n\n", Some(1234));
+        let mut computer = factory.build();
+        let frames: Vec<_> =
+            FrameReader::new(&mut computer, FrameDelimiter::BlankLine).try_collect()?;
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].lines(), ["#.", ".#"]);
+        assert_eq!(frames[1].text(), "##");
+        assert_eq!(frames[0].grid().unwrap().width(), 2);
+        assert_eq!(computer.expect_i64()?, 1234);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sized_frames() -> Result<(), ComputerError> {
+        let factory = printer("ab\ncd\nef\n", None);
+        let mut computer = factory.build();
+        let frames: Vec<_> = FrameReader::new(&mut computer, FrameDelimiter::Size(2))
+            .map_ok(Frame::into_lines)
+            .try_collect()?;
+
+        assert_eq!(frames, vec![vec!["ab", "cd"], vec!["ef"]]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+#![allow(dead_code)]
+use super::trace::Mnemonic;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AssemblerError {
+    #[error("Line {0}: unknown mnemonic {1}")]
+    UnknownMnemonic(usize, String),
+
+    #[error("Line {0}: {1} expects {2} operands, found {3}")]
+    WrongOperandCount(usize, Mnemonic, usize, usize),
+
+    #[error("Line {0}: not a valid operand: {1}")]
+    InvalidOperand(usize, String),
+
+    #[error("Line {0}: unknown label {1}")]
+    UnknownLabel(usize, String),
+
+    #[error("Line {0}: label {1} is defined twice")]
+    DuplicateLabel(usize, String),
+
+    #[error("Line {0}: {1} cannot write to an immediate operand")]
+    ImmediateWrite(usize, Mnemonic),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl Mode {
+    fn digit(&self) -> i64 {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value<'a> {
+    Number(i64),
+    Label(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Operand<'a> {
+    mode: Mode,
+    value: Value<'a>,
+}
+
+impl<'a> Operand<'a> {
+    fn parse(line: usize, text: &'a str) -> Result<Self, AssemblerError> {
+        let (mode, value) = if let Some(value) = text.strip_prefix('#') {
+            (Mode::Immediate, value)
+        } else if let Some(value) = text.strip_prefix('@') {
+            (Mode::Relative, value)
+        } else {
+            (Mode::Position, text)
+        };
+        Ok(Self {
+            mode,
+            value: parse_value(line, value)?,
+        })
+    }
+}
+
+fn parse_value(line: usize, text: &str) -> Result<Value<'_>, AssemblerError> {
+    if let Ok(number) = text.parse() {
+        Ok(Value::Number(number))
+    } else if is_label(text) {
+        Ok(Value::Label(text))
+    } else {
+        Err(AssemblerError::InvalidOperand(line, text.to_owned()))
+    }
+}
+
+fn is_label(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Debug)]
+enum Statement<'a> {
+    Instruction(Mnemonic, Vec<Operand<'a>>),
+    Data(Vec<Value<'a>>),
+}
+
+impl Statement<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Statement::Instruction(_, operands) => operands.len() + 1,
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+fn parse_statement(line: usize, text: &str) -> Result<Statement<'_>, AssemblerError> {
+    let (name, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let arguments = rest
+        .split(',')
+        .map(str::trim)
+        .filter(|argument| !argument.is_empty())
+        .collect::<Vec<_>>();
+
+    if name == "DATA" {
+        let values = arguments
+            .into_iter()
+            .map(|argument| parse_value(line, argument))
+            .collect::<Result<_, _>>()?;
+        return Ok(Statement::Data(values));
+    }
+
+    let mnemonic = match name.parse::<Mnemonic>() {
+        Ok(Mnemonic::Illegal) | Err(_) => {
+            return Err(AssemblerError::UnknownMnemonic(line, name.to_owned()))
+        }
+        Ok(mnemonic) => mnemonic,
+    };
+    if arguments.len() != mnemonic.parameter_count() {
+        return Err(AssemblerError::WrongOperandCount(
+            line,
+            mnemonic,
+            mnemonic.parameter_count(),
+            arguments.len(),
+        ));
+    }
+    let operands = arguments
+        .into_iter()
+        .map(|argument| Operand::parse(line, argument))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(written) = mnemonic.written_parameter()
+        && operands[written].mode == Mode::Immediate
+    {
+        return Err(AssemblerError::ImmediateWrite(line, mnemonic));
+    }
+    Ok(Statement::Instruction(mnemonic, operands))
+}
+
+/**
+ * Turns a readable listing into an Intcode program.
+ *
+ * ```text
+ * ; comments start with a semicolon
+ *         IN counter          ; plain operands use position mode
+ * loop:   OUT counter
+ *         ADD counter, #-1, counter   ; '#' is immediate mode
+ *         JT counter, #loop           ; labels stand for their address
+ *         RB #5
+ *         OUT @-5                     ; '@' is relative mode
+ *         HALT
+ * counter: DATA 0             ; raw words
+ * ```
+ */
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssemblerError> {
+    let mut labels = HashMap::new();
+    let mut statements = vec![];
+    let mut address = 0;
+
+    for (idx, text) in source.lines().enumerate() {
+        let line = idx + 1;
+        let mut text = text.split(';').next().unwrap_or_default().trim();
+        while let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(AssemblerError::InvalidOperand(line, label.to_owned()));
+            }
+            if labels.insert(label, address).is_some() {
+                return Err(AssemblerError::DuplicateLabel(line, label.to_owned()));
+            }
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let statement = parse_statement(line, text)?;
+        address += statement.len() as i64;
+        statements.push((line, statement));
+    }
+
+    let resolve = |line: usize, value: &Value| match value {
+        Value::Number(number) => Ok(*number),
+        Value::Label(label) => labels
+            .get(label)
+            .copied()
+            .ok_or_else(|| AssemblerError::UnknownLabel(line, label.to_string())),
+    };
+
+    let mut program = vec![];
+    for (line, statement) in statements {
+        match statement {
+            Statement::Instruction(mnemonic, operands) => {
+                let opcode = mnemonic.opcode().expect("illegal mnemonics are rejected");
+                let modes = operands
+                    .iter()
+                    .rev()
+                    .fold(0, |modes, operand| modes * 10 + operand.mode.digit());
+                program.push(opcode + modes * 100);
+                for operand in operands {
+                    program.push(resolve(line, &operand.value)?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in values {
+                    program.push(resolve(line, &value)?);
+                }
+            }
+        }
+    }
+    Ok(program)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::{ComputerError, ComputerFactory};
+    use itertools::Itertools;
+
+    #[test]
+    fn encoding() {
+        let source = "ADD p1, #2, @3 ; all modes\np1: DATA 7, p1";
+        assert_eq!(assemble(source), Ok(vec![21001, 4, 2, 3, 7, 4]));
+
+        assert_eq!(
+            assemble("HALT\nADD #1, #2, #3"),
+            Err(AssemblerError::ImmediateWrite(2, Mnemonic::Add))
+        );
+        assert_eq!(
+            assemble("OUT 1, 2"),
+            Err(AssemblerError::WrongOperandCount(1, Mnemonic::Out, 1, 2))
+        );
+        assert_eq!(
+            assemble("JT #1, #nowhere"),
+            Err(AssemblerError::UnknownLabel(1, "nowhere".to_owned()))
+        );
+        assert_eq!(
+            assemble("MOV 1, 2"),
+            Err(AssemblerError::UnknownMnemonic(1, "MOV".to_owned()))
+        );
+        assert_eq!(
+            assemble("a: HALT\na: HALT"),
+            Err(AssemblerError::DuplicateLabel(2, "a".to_owned()))
+        );
+    }
+
+    #[test]
+    fn countdown() -> Result<(), ComputerError> {
+        let source = "
+                    IN counter
+            loop:   OUT counter
+                    ADD counter, #-1, counter
+                    JT counter, #loop
+                    RB #base
+                    OUT @2
+                    HALT
+            counter: DATA 0
+            base:   DATA 0, 0, 42
+        ";
+        let program = assemble(source).unwrap();
+        let mut computer = ComputerFactory::new(program).build();
+        computer.send_i64(3);
+        let result: Vec<_> = computer.as_iter().try_collect()?;
+
+        assert_eq!(result, vec![3, 2, 1, 42]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+#![allow(dead_code)]
+use super::{ComputerError, IntCodeComputer, Poll as ComputerPoll};
+use std::{
+    collections::VecDeque,
+    future::{poll_fn, Future},
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Debug, Default)]
+struct Shared {
+    values: VecDeque<i64>,
+    waker: Option<Waker>,
+    senders: usize,
+}
+
+/**
+ * Sends values to the `Receiver` of the same `channel`, without ever waiting.
+ * Once all clones of it are dropped, the receiver ends after the last value.
+ */
+#[derive(Debug)]
+pub struct Sender(Arc<Mutex<Shared>>);
+
+impl Sender {
+    pub fn send(&self, value: i64) {
+        let mut shared = self.0.lock().unwrap();
+        shared.values.push_back(value);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Self {
+        self.0.lock().unwrap().senders += 1;
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut shared = self.0.lock().unwrap();
+        shared.senders -= 1;
+        if shared.senders == 0
+            && let Some(waker) = shared.waker.take()
+        {
+            waker.wake();
+        }
+    }
+}
+
+/**
+ * The values sent, as an async stream that ends when all senders are gone.
+ */
+#[derive(Debug)]
+pub struct Receiver(Arc<Mutex<Shared>>);
+
+impl Receiver {
+    /** The next value if there is one right now */
+    pub fn try_recv(&mut self) -> Option<i64> {
+        self.0.lock().unwrap().values.pop_front()
+    }
+
+    /** The next value, `None` once all senders are gone and nothing is left */
+    pub async fn recv(&mut self) -> Option<i64> {
+        poll_fn(|cx| self.poll_next(cx)).await
+    }
+}
+
+/**
+ * Where an `AsyncComputer` awaits its input from. Like an async iterator,
+ * it ends with `None`.
+ */
+pub trait InputStream {
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<i64>>;
+}
+
+impl InputStream for Receiver {
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<i64>> {
+        let mut shared = self.0.lock().unwrap();
+        if let Some(value) = shared.values.pop_front() {
+            Poll::Ready(Some(value))
+        } else if shared.senders == 0 {
+            Poll::Ready(None)
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/**
+ * An unbounded channel of values for one receiving task. Does not depend on
+ * any runtime, the receiver works with every executor.
+ */
+pub fn channel() -> (Sender, Receiver) {
+    let shared = Arc::new(Mutex::new(Shared {
+        senders: 1,
+        ..Shared::default()
+    }));
+    (Sender(Arc::clone(&shared)), Receiver(shared))
+}
+
+/**
+ * Lets the other tasks of the executor run once before it continues.
+ */
+fn yield_now() -> impl Future<Output = ()> {
+    let mut yielded = false;
+    poll_fn(move |cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+}
+
+/**
+ * A computer that awaits its input from an async stream and sends its
+ * outputs on a channel, so many of them can run as tasks on any executor.
+ * Between input and output the program runs without awaiting anything,
+ * after every output it lets the other tasks have their turn.
+ */
+pub struct AsyncComputer<I> {
+    computer: IntCodeComputer,
+    input: I,
+    output: Sender,
+    idle: Option<i64>,
+}
+
+impl<I: InputStream> AsyncComputer<I> {
+    pub fn new(computer: IntCodeComputer, input: I, output: Sender) -> Self {
+        Self {
+            computer,
+            input,
+            output,
+            idle: None,
+        }
+    }
+
+    /**
+     * Instead of waiting for input, the program reads this value if there
+     * is none, like the network interfaces that read -1. The other tasks
+     * still get their turn before.
+     */
+    pub fn idle_input(self, value: i64) -> Self {
+        Self {
+            idle: Some(value),
+            ..self
+        }
+    }
+
+    /**
+     * Runs the program until it halts and hands back the computer. If the
+     * input ends while the program waits for more, that is an error.
+     */
+    pub async fn run(mut self) -> Result<IntCodeComputer, ComputerError> {
+        loop {
+            match self.computer.poll()? {
+                ComputerPoll::Output(value) => {
+                    self.output.send(value);
+                    yield_now().await;
+                }
+                ComputerPoll::NeedsInput => {
+                    let value = match self.idle {
+                        Some(idle) => {
+                            yield_now().await;
+                            poll_fn(|cx| match self.input.poll_next(cx) {
+                                Poll::Ready(value) => Poll::Ready(value),
+                                Poll::Pending => Poll::Ready(Some(idle)),
+                            })
+                            .await
+                        }
+                        None => poll_fn(|cx| self.input.poll_next(cx)).await,
+                    };
+                    let Some(value) = value else {
+                        return Err(ComputerError::WaitingForInput);
+                    };
+                    self.computer.send_i64(value);
+                }
+                ComputerPoll::Halted => return Ok(self.computer),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerFactory},
+    };
+    use std::{
+        cell::Cell,
+        pin::Pin,
+        sync::atomic::{AtomicBool, Ordering},
+        task::Wake,
+    };
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::Release);
+        }
+    }
+
+    type Task<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+    /** Takes turns polling the tasks on this thread until all are done */
+    fn run_all(mut tasks: Vec<Task>) {
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = Waker::from(Arc::clone(&flag));
+        let mut cx = Context::from_waker(&waker);
+        while !tasks.is_empty() {
+            flag.0.store(false, Ordering::Release);
+            tasks.retain_mut(|task| task.as_mut().poll(&mut cx).is_pending());
+            assert!(
+                tasks.is_empty() || flag.0.load(Ordering::Acquire),
+                "all tasks wait for each other"
+            );
+        }
+    }
+
+    #[test]
+    fn feedback_loop() -> UnitResult {
+        // Adds one to every value until it passed on 10 or more
+        let factory = ComputerFactory::new(assemble(
+            "
+            loop:   IN value
+                    ADD value, #1, value
+                    OUT value
+                    LT value, #10, flag
+                    JT flag, #loop
+                    HALT
+            value:  DATA 0
+            flag:   DATA 0
+            ",
+        )?);
+        let (first_in, mut input) = channel();
+        let mut machines = vec![];
+        for _ in 0..3 {
+            let (output, next) = channel();
+            machines.push(AsyncComputer::new(factory.build(), input, output));
+            input = next;
+        }
+        let mut receiver = input;
+        first_in.send(0);
+
+        let mut last = None;
+        let halted = Cell::new(0);
+        let mut tasks: Vec<Task> = vec![Box::pin(async {
+            // Passes on what the last machine says to the first one
+            while let Some(value) = receiver.recv().await {
+                last = Some(value);
+                first_in.send(value);
+            }
+        })];
+        for machine in machines {
+            let halted = &halted;
+            tasks.push(Box::pin(async move {
+                if machine.run().await.is_ok() {
+                    halted.set(halted.get() + 1);
+                }
+            }));
+        }
+        run_all(tasks);
+        assert_eq!(last, Some(12));
+        assert_eq!(halted.get(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn machines_are_send() {
+        fn is_send<T: Send>() {}
+        is_send::<AsyncComputer<Receiver>>();
+        is_send::<Sender>();
+    }
+
+    #[test]
+    fn idle_input() -> UnitResult {
+        let computer = ComputerFactory::new(assemble("IN 0\nOUT 0\nHALT")?).build();
+        let (sender, input) = channel();
+        let (output, mut outputs) = channel();
+        let mut result = None;
+        run_all(vec![Box::pin(async {
+            result = Some(
+                AsyncComputer::new(computer, input, output)
+                    .idle_input(-1)
+                    .run()
+                    .await,
+            );
+        })]);
+        drop(sender);
+        assert!(result.is_some_and(|result| result.is_ok()));
+        assert_eq!(outputs.try_recv(), Some(-1));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use super::{
+    memory::{Memory, SharedMemory},
+    patch::Patch,
+    Arithmetic, ComputerFactory, ExecutionMode, IntCodeComputer, Limits, PatchError, Pointer,
+};
+use std::marker::PhantomData;
+
+/**
+ * Sets up a computer before it runs, see `ComputerFactory::builder`.
+ * Unlike a `Patch`, memory set here belongs to that one computer, the
+ * program stays shared. A reset goes back to the plain program without
+ * the input and memory set here, everything else is kept as usual.
+ */
+#[derive(Clone)]
+pub struct ComputerBuilder<'a, M: Memory = SharedMemory> {
+    factory: &'a ComputerFactory,
+    /** Runs instead of the program of `factory` */
+    patched: Option<ComputerFactory>,
+    inputs: Vec<i64>,
+    memory: Vec<(Pointer, i64)>,
+    mode: Option<ExecutionMode>,
+    limits: Option<Limits>,
+    arithmetic: Option<Arithmetic>,
+    trace: bool,
+    backend: PhantomData<M>,
+}
+
+impl<'a> ComputerBuilder<'a> {
+    pub(super) fn new(factory: &'a ComputerFactory) -> Self {
+        Self {
+            factory,
+            patched: None,
+            inputs: vec![],
+            memory: vec![],
+            mode: None,
+            limits: None,
+            arithmetic: None,
+            trace: false,
+            backend: PhantomData,
+        }
+    }
+}
+
+impl<'a, M: Memory> ComputerBuilder<'a, M> {
+    /** Queued up as if sent right after building, in order */
+    pub fn input(mut self, value: i64) -> Self {
+        self.inputs.push(value);
+        self
+    }
+
+    pub fn inputs(mut self, values: impl IntoIterator<Item = i64>) -> Self {
+        self.inputs.extend(values);
+        self
+    }
+
+    /** A line of ASCII input, like `IntCodeComputer::send_string` */
+    #[allow(dead_code)]
+    pub fn line(self, line: &str) -> Self {
+        self.inputs(line.chars().map(|c| c as i64).chain([10]))
+    }
+
+    /**
+     * Runs the patched program instead, see `ComputerFactory::patch`. Unlike
+     * memory set with `set`, the patch stays when the computer is reset.
+     */
+    pub fn patch(mut self, patch: Patch) -> Result<Self, PatchError> {
+        self.patched = Some(patch.apply()?);
+        Ok(self)
+    }
+
+    /** Like `IntCodeComputer::manipulate_memory`, later values win */
+    pub fn set(mut self, address: Pointer, value: i64) -> Self {
+        self.memory.push((address, value));
+        self
+    }
+
+    /** Another memory backend, see `ComputerFactory::build_with` */
+    pub fn memory<N: Memory>(self) -> ComputerBuilder<'a, N> {
+        ComputerBuilder {
+            factory: self.factory,
+            patched: self.patched,
+            inputs: self.inputs,
+            memory: self.memory,
+            mode: self.mode,
+            limits: self.limits,
+            arithmetic: self.arithmetic,
+            trace: self.trace,
+            backend: PhantomData,
+        }
+    }
+
+    pub fn execution_mode(mut self, mode: ExecutionMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /** Instead of the default arithmetic, see `IntCodeComputer::set_arithmetic` */
+    #[allow(dead_code)]
+    pub fn arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.arithmetic = Some(arithmetic);
+        self
+    }
+
+    /** Instead of the default limits */
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /** Limits the steps, keeping the default timeout */
+    #[allow(dead_code)]
+    pub fn max_steps(self, max_steps: u64) -> Self {
+        let limits = self.limits.unwrap_or_else(super::limits::default_limits);
+        self.limits(Limits {
+            max_steps: Some(max_steps),
+            ..limits
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn trace(mut self) -> Self {
+        self.trace = true;
+        self
+    }
+
+    pub fn build(self) -> IntCodeComputer<M> {
+        let factory = self.patched.as_ref().unwrap_or(self.factory);
+        let mut computer = factory.build_with::<M>();
+        for (address, value) in self.memory {
+            computer.manipulate_memory(address, value);
+        }
+        if let Some(mode) = self.mode {
+            computer.set_execution_mode(mode);
+        }
+        if let Some(limits) = self.limits {
+            computer.set_limits(limits);
+        }
+        if let Some(arithmetic) = self.arithmetic {
+            computer.set_arithmetic(arithmetic);
+        }
+        if self.trace {
+            computer.enable_trace();
+        }
+        for value in self.inputs {
+            computer.send_i64(value);
+        }
+        computer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        days::UnitResult,
+        int_code::{
+            assembler::assemble, memory::SparseMemory, Arithmetic, ComputerError, ComputerFactory,
+            ExecutionMode, PatchError, Pointer,
+        },
+    };
+
+    #[test]
+    fn builds() -> UnitResult {
+        // Adds its input to a constant that is meant to be set from outside
+        let factory = ComputerFactory::new(assemble(
+            "
+                    IN value
+                    ADD value, constant, value
+                    OUT value
+                    HALT
+            value:  DATA 0
+            constant: DATA 0
+            ",
+        )?);
+        let constant = Pointer::new(factory.program().len() - 1);
+
+        let mut computer = factory
+            .builder()
+            .set(constant, 10)
+            .set(constant, 20)
+            .input(1)
+            .memory::<SparseMemory>()
+            .execution_mode(ExecutionMode::Compiled)
+            .trace()
+            .build();
+        assert_eq!(computer.expect_i64()?, 21);
+        assert_eq!(computer.take_trace().len(), 3);
+        assert_eq!(factory.program()[constant.as_usize()], 0);
+
+        computer.reset();
+        computer.send_i64(1);
+        assert_eq!(computer.expect_i64()?, 1);
+
+        let mut computer = factory.builder().inputs([5, 6]).max_steps(2).build();
+        assert!(matches!(
+            computer.run_till_halt().unwrap_err().cause(),
+            ComputerError::StepLimitExceeded(2)
+        ));
+
+        let mut computer = factory
+            .builder()
+            .patch(factory.patch().expect(constant, 0).set(constant, i64::MAX))?
+            .arithmetic(Arithmetic::Checked)
+            .input(1)
+            .build();
+        assert!(matches!(
+            computer.expect_i64().unwrap_err().cause(),
+            ComputerError::Overflow(..)
+        ));
+        computer.reset();
+        computer.send_i64(-1);
+        assert_eq!(computer.expect_i64()?, i64::MAX - 1);
+
+        let patched = factory.builder().patch(factory.patch().expect(constant, 1));
+        assert!(matches!(
+            patched.err(),
+            Some(PatchError::UnexpectedValue { found: 0, .. })
+        ));
+
+        Ok(())
+    }
+}
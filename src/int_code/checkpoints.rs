@@ -0,0 +1,177 @@
+#![allow(dead_code)]
+use super::{memory::Memory, snapshot::Snapshot};
+use std::collections::VecDeque;
+
+/**
+ * Snapshots taken every `interval` instructions, together with all input
+ * the program read since the oldest one. Going back to any instruction in
+ * between restores the checkpoint before it and runs forward again on the
+ * same input, see `IntCodeComputer::enable_time_travel`.
+ */
+#[derive(Debug, Clone)]
+pub(super) struct Checkpoints<M: Memory> {
+    interval: u64,
+    keep: usize,
+    snapshots: VecDeque<Snapshot<M>>,
+    /** The instruction that read the input, counted like `Snapshot::executed` */
+    inputs: VecDeque<(u64, i64)>,
+}
+
+impl<M: Memory> Checkpoints<M> {
+    pub fn new(interval: u64, keep: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            keep: keep.max(1),
+            snapshots: VecDeque::new(),
+            inputs: VecDeque::new(),
+        }
+    }
+
+    /**
+     * Keeps the settings, but forgets everything about the current run.
+     */
+    pub fn restart(self) -> Self {
+        Self::new(self.interval, self.keep)
+    }
+
+    /** Whether a snapshot is due before the instruction after `executed` ones */
+    #[inline]
+    pub fn is_due(&self, executed: u64) -> bool {
+        executed.is_multiple_of(self.interval)
+            && self
+                .snapshots
+                .back()
+                .is_none_or(|last| last.executed() < executed)
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot<M>) {
+        self.snapshots.push_back(snapshot);
+        if self.snapshots.len() > self.keep {
+            self.snapshots.pop_front();
+            if let Some(oldest) = self.snapshots.front() {
+                let oldest = oldest.executed();
+                while self.inputs.front().is_some_and(|(read, _)| *read < oldest) {
+                    self.inputs.pop_front();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn input(&mut self, executed: u64, value: i64) {
+        self.inputs.push_back((executed, value));
+    }
+
+    /** The instruction count of the oldest moment that can still be reached */
+    pub fn oldest(&self) -> Option<u64> {
+        Some(self.snapshots.front()?.executed())
+    }
+
+    /**
+     * Goes back to the latest checkpoint at or before `executed`. Returns it
+     * and the input read since, which has to be read again. Everything
+     * later is forgotten, as running forward records it again.
+     */
+    pub fn rewind(&mut self, executed: u64) -> Option<(Snapshot<M>, Vec<i64>)> {
+        let index = self
+            .snapshots
+            .iter()
+            .rposition(|snapshot| snapshot.executed() <= executed)?;
+        self.snapshots.truncate(index + 1);
+        let snapshot = self.snapshots[index].clone();
+        let first = self
+            .inputs
+            .iter()
+            .position(|(read, _)| *read >= snapshot.executed())
+            .unwrap_or(self.inputs.len());
+        let inputs = self.inputs.drain(first..).map(|(_, value)| value).collect();
+        Some((snapshot, inputs))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerError, ComputerFactory, VmEvent},
+    };
+
+    #[test]
+    fn back_and_forth() -> UnitResult {
+        // Adds up its input until it reads a zero, outputting every sum
+        let program = assemble(
+            "
+            loop:   IN value
+                    JF value, #done
+                    ADD sum, value, sum
+                    OUT sum
+                    JT #1, #loop
+            done:   OUT sum
+                    HALT
+            value:  DATA 0
+            sum:    DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        computer.enable_time_travel(3, 100);
+        for value in [1, 2, 3, 0] {
+            computer.send_i64(value);
+        }
+        let outputs = computer.as_iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(outputs, vec![1, 3, 6, 6]);
+        let end = computer.executed();
+
+        // Back to before the third addition, the input is read again from there
+        assert!(computer.travel_to(11)?);
+        assert_eq!(computer.executed(), 11);
+        let outputs = computer.as_iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(outputs, vec![6, 6]);
+        assert_eq!(computer.executed(), end);
+
+        assert!(computer.step_back()?);
+        assert_eq!(computer.step()?, VmEvent::Halted);
+        assert!(!computer.travel_to(end + 1)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn before_a_crash() -> UnitResult {
+        let program = assemble(
+            "
+                    IN value
+                    OUT value
+                    ADD value, #-5, value
+                    OUT value
+                    RB value
+                    OUT @0
+                    HALT
+            value:  DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        computer.enable_time_travel(2, 1);
+        computer.send_i64(3);
+        let err = computer
+            .as_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        assert!(matches!(
+            err.cause(),
+            ComputerError::PointerMustNoBeNegative(-2)
+        ));
+
+        // Only the latest checkpoint is kept, the first instructions are gone
+        assert!(!computer.travel_to(0)?);
+        assert!(computer.step_back()?);
+        assert_eq!(computer.relative_base(), -2);
+        assert!(computer.step().is_err());
+
+        assert!(computer.travel_to(4)?);
+        assert_eq!(computer.relative_base(), 0);
+        assert_eq!(computer.step()?, VmEvent::Continue);
+        assert!(computer.step().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+#![allow(dead_code)]
+use super::{computer_error::ComputerError, IntCodeComputer, Poll};
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+/**
+ * The order in which the computers of a cluster get their turn.
+ * A turn lasts until the computer outputs a value, waits for input or halts.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    RoundRobin,
+    /** After every turn that made progress, start again with the first listed computer */
+    Priority(Vec<usize>),
+    Random(u64),
+}
+
+pub struct Cluster {
+    computers: Vec<IntCodeComputer>,
+    links: Vec<Option<usize>>,
+    halted: Vec<bool>,
+    last_output: Vec<Option<i64>>,
+}
+
+impl Cluster {
+    pub fn new(computers: Vec<IntCodeComputer>) -> Self {
+        let len = computers.len();
+        Self {
+            computers,
+            links: vec![None; len],
+            halted: vec![false; len],
+            last_output: vec![None; len],
+        }
+    }
+
+    /**
+     * Connects every computer to the next one, and the last one to the first.
+     */
+    pub fn ring(computers: Vec<IntCodeComputer>) -> Self {
+        let mut cluster = Self::new(computers);
+        let len = cluster.computers.len();
+        for idx in 0..len {
+            cluster.connect(idx, (idx + 1) % len);
+        }
+        cluster
+    }
+
+    pub fn connect(&mut self, from: usize, to: usize) {
+        self.links[from] = Some(to);
+    }
+
+    pub fn send_i64(&mut self, node: usize, value: i64) {
+        self.computers[node].send_i64(value);
+    }
+
+    pub fn last_output(&self, node: usize) -> Option<i64> {
+        self.last_output[node]
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted.iter().all(|halted| *halted)
+    }
+
+    pub fn run(&mut self, schedule: &Schedule) -> Result<(), ComputerError> {
+        let mut rng = match schedule {
+            Schedule::Random(seed) => Some(StdRng::seed_from_u64(*seed)),
+            _ => None,
+        };
+
+        while !self.is_halted() {
+            let order = match schedule {
+                Schedule::RoundRobin => (0..self.computers.len()).collect_vec(),
+                Schedule::Priority(order) => order.clone(),
+                Schedule::Random(_) => {
+                    let mut order = (0..self.computers.len()).collect_vec();
+                    if let Some(rng) = rng.as_mut() {
+                        order.shuffle(rng);
+                    }
+                    order
+                }
+            };
+
+            let mut made_progress = false;
+            for idx in order {
+                if self.turn(idx)? {
+                    made_progress = true;
+                    if matches!(schedule, Schedule::Priority(_)) {
+                        break;
+                    }
+                }
+            }
+            if !made_progress {
+                return Err(ComputerError::ClusterDeadlock);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn turn(&mut self, idx: usize) -> Result<bool, ComputerError> {
+        if self.halted[idx] {
+            return Ok(false);
+        }
+        match self.computers[idx].poll()? {
+            Poll::Output(value) => {
+                self.last_output[idx] = Some(value);
+                if let Some(target) = self.links[idx] {
+                    self.computers[target].send_i64(value);
+                }
+                Ok(true)
+            }
+            Poll::Halted => {
+                self.halted[idx] = true;
+                Ok(true)
+            }
+            Poll::NeedsInput => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use super::{memory::Memory, ComputerError, Pointer};
+use std::sync::Arc;
+
+/**
+ * How a computer executes its program, see `IntCodeComputer::set_execution_mode`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /** Every instruction is decoded again each time it runs */
+    #[default]
+    Interpreted,
+    /**
+     * Straight runs of instructions are decoded once into blocks, which are
+     * kept until the program overwrites them. Single steps and computers
+     * that trace, profile, watch, record, keep checkpoints or track code
+     * writes still interpret.
+     */
+    Compiled,
+}
+
+/**
+ * Blocks are only compiled for code below this address. Programs never
+ * run code further out, and the blocks can be looked up in a vector.
+ */
+const CODE_LIMIT: usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl Mode {
+    fn from_digit(digit: i64) -> Option<Self> {
+        match digit {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Operand {
+    Position(Pointer),
+    Immediate(i64),
+    Relative(i64),
+    /**
+     * The program writes to the word of this operand, so it is read from
+     * memory every time the instruction runs.
+     */
+    Patched(Mode, Pointer),
+}
+
+impl Operand {
+    #[inline]
+    pub fn new(mode: Mode, value: i64) -> Result<Self, ComputerError> {
+        Ok(match mode {
+            Mode::Position => Operand::Position(Pointer::from_i64(value)?),
+            Mode::Immediate => Operand::Immediate(value),
+            Mode::Relative => Operand::Relative(value),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Target {
+    Position(Pointer),
+    Relative(i64),
+    /** See `Operand::Patched` */
+    Patched(Mode, Pointer),
+}
+
+impl Target {
+    #[inline]
+    pub fn new(mode: Mode, value: i64) -> Result<Self, ComputerError> {
+        match mode {
+            Mode::Position => Ok(Target::Position(Pointer::from_i64(value)?)),
+            Mode::Immediate => Err(ComputerError::IllegalParamMode),
+            Mode::Relative => Ok(Target::Relative(value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Op {
+    Add(Operand, Operand, Target),
+    Mul(Operand, Operand, Target),
+    Input(Target),
+    Output(Operand),
+    JumpIfTrue(Operand, Operand),
+    JumpIfFalse(Operand, Operand),
+    LessThan(Operand, Operand, Target),
+    Equals(Operand, Operand, Target),
+    RelativeBase(Operand),
+    Halt,
+}
+
+impl Op {
+    /** Everything after these might not run, or run later */
+    fn ends_block(&self) -> bool {
+        matches!(
+            self,
+            Op::Input(_) | Op::Output(_) | Op::JumpIfTrue(..) | Op::JumpIfFalse(..) | Op::Halt
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct CompiledOp {
+    pub address: Pointer,
+    /** The first word, for error reports */
+    pub instruction: i64,
+    pub next: Pointer,
+    pub op: Op,
+}
+
+struct Decoder<'a, M: Memory> {
+    memory: &'a M,
+    patched: &'a [bool],
+    next: Pointer,
+    modes: i64,
+    /** The words the decoded instructions depend on */
+    fixed: Vec<(Pointer, i64)>,
+}
+
+impl<M: Memory> Decoder<'_, M> {
+    fn is_patched(&self, address: Pointer) -> bool {
+        self.patched
+            .get(address.as_usize())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /** The next word, unless the program patches it */
+    fn word(&mut self) -> Result<i64, Pointer> {
+        let address = self.next;
+        self.next.inc();
+        if self.is_patched(address) {
+            return Err(address);
+        }
+        let word = self.memory.get(address);
+        self.fixed.push((address, word));
+        Ok(word)
+    }
+
+    fn mode(&mut self) -> Option<Mode> {
+        let mode = Mode::from_digit(self.modes % 10);
+        self.modes /= 10;
+        mode
+    }
+
+    fn operand(&mut self) -> Option<Operand> {
+        let mode = self.mode()?;
+        match self.word() {
+            Ok(value) => Operand::new(mode, value).ok(),
+            Err(address) => Some(Operand::Patched(mode, address)),
+        }
+    }
+
+    fn target(&mut self) -> Option<Target> {
+        let mode = self.mode()?;
+        match self.word() {
+            Ok(value) => Target::new(mode, value).ok(),
+            Err(_) if matches!(mode, Mode::Immediate) => None,
+            Err(address) => Some(Target::Patched(mode, address)),
+        }
+    }
+
+    /**
+     * `None` for everything the interpreter would fail on, so it can report
+     * the error as usual, and for instructions the program patches.
+     */
+    fn decode(&mut self) -> Option<CompiledOp> {
+        let address = self.next;
+        let instruction = self.word().ok().filter(|word| word.is_positive())?;
+        self.modes = instruction / 100;
+        let op = match instruction % 100 {
+            1 => Op::Add(self.operand()?, self.operand()?, self.target()?),
+            2 => Op::Mul(self.operand()?, self.operand()?, self.target()?),
+            3 => Op::Input(self.target()?),
+            4 => Op::Output(self.operand()?),
+            5 => Op::JumpIfTrue(self.operand()?, self.operand()?),
+            6 => Op::JumpIfFalse(self.operand()?, self.operand()?),
+            7 => Op::LessThan(self.operand()?, self.operand()?, self.target()?),
+            8 => Op::Equals(self.operand()?, self.operand()?, self.target()?),
+            9 => Op::RelativeBase(self.operand()?),
+            99 => Op::Halt,
+            _ => return None,
+        };
+        Some(CompiledOp {
+            address,
+            instruction,
+            next: self.next,
+            op,
+        })
+    }
+}
+
+/**
+ * Instructions that run one after the other, up to the first one that
+ * jumps, halts or talks to the outside.
+ */
+#[derive(Debug)]
+pub(super) struct Block {
+    start: usize,
+    end: usize,
+    /** The words the block was compiled from, patched operands excluded */
+    fixed: Vec<(Pointer, i64)>,
+    ops: Vec<CompiledOp>,
+}
+
+impl Block {
+    fn compile<M: Memory>(memory: &M, patched: &[bool], start: Pointer) -> Option<Self> {
+        let mut decoder = Decoder {
+            memory,
+            patched,
+            next: start,
+            modes: 0,
+            fixed: vec![],
+        };
+        let mut ops = vec![];
+        loop {
+            let address = decoder.next;
+            let fixed = decoder.fixed.len();
+            let op = (address.as_usize() < CODE_LIMIT)
+                .then(|| decoder.decode())
+                .flatten();
+            let Some(op) = op else {
+                decoder.next = address;
+                decoder.fixed.truncate(fixed);
+                break;
+            };
+            ops.push(op);
+            if op.op.ends_block() {
+                break;
+            }
+        }
+        if ops.is_empty() {
+            return None;
+        }
+        Some(Self {
+            start: start.as_usize(),
+            end: decoder.next.as_usize(),
+            fixed: decoder.fixed,
+            ops,
+        })
+    }
+
+    fn contains(&self, address: usize) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+
+    fn matches<M: Memory>(&self, memory: &M) -> bool {
+        self.fixed
+            .iter()
+            .all(|(address, word)| memory.get(*address) == *word)
+    }
+
+    #[inline]
+    pub fn ops(&self) -> &[CompiledOp] {
+        &self.ops
+    }
+}
+
+/**
+ * The blocks compiled so far, by start address. Blocks may overlap when
+ * the program jumps into the middle of one.
+ */
+#[derive(Debug, Clone, Default)]
+pub(super) struct BlockCache {
+    blocks: Vec<Option<Arc<Block>>>,
+    /** Which addresses blocks were compiled from */
+    code: Vec<bool>,
+    /** Which addresses the program wrote to after they were compiled */
+    patched: Vec<bool>,
+}
+
+impl BlockCache {
+    /** `None` if the interpreter has to handle the instruction at `start` */
+    #[inline]
+    pub fn get<M: Memory>(&mut self, memory: &M, start: Pointer) -> Option<Arc<Block>> {
+        if let Some(Some(block)) = self.blocks.get(start.as_usize()) {
+            return Some(Arc::clone(block));
+        }
+        let block = Arc::new(Block::compile(memory, &self.patched, start)?);
+        self.mark(&block);
+        if self.blocks.len() <= block.start {
+            self.blocks.resize(block.start + 1, None);
+        }
+        self.blocks[block.start] = Some(Arc::clone(&block));
+        Some(block)
+    }
+
+    fn mark(&mut self, block: &Block) {
+        if self.code.len() < block.end {
+            self.code.resize(block.end, false);
+        }
+        for (address, _) in &block.fixed {
+            self.code[address.as_usize()] = true;
+        }
+    }
+
+    /**
+     * Drops every block the write changed. Returns whether there was one.
+     * Blocks compiled later read the written word from memory instead, or
+     * leave the instruction to the interpreter if it was the opcode.
+     */
+    #[inline]
+    pub fn written(&mut self, address: Pointer) -> bool {
+        let address = address.as_usize();
+        if !self.code.get(address).copied().unwrap_or_default() {
+            return false;
+        }
+        if self.patched.len() <= address {
+            self.patched.resize(address + 1, false);
+        }
+        self.patched[address] = true;
+        self.retain(|block| !block.contains(address));
+        true
+    }
+
+    /** Keeps the blocks that are still what the memory holds, after a reset */
+    pub fn revalidate<M: Memory>(mut self, memory: &M) -> Self {
+        self.retain(|block| block.matches(memory));
+        self
+    }
+
+    fn retain(&mut self, keep: impl Fn(&Block) -> bool) {
+        let mut blocks = std::mem::take(&mut self.blocks);
+        self.code.fill(false);
+        for slot in &mut blocks {
+            match slot {
+                Some(block) if keep(block) => self.mark(block),
+                _ => *slot = None,
+            }
+        }
+        self.blocks = blocks;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::UnitResult,
+        int_code::{
+            assembler::assemble,
+            conformance::{check, IntCodeRunner},
+            ComputerError, ComputerFactory, IntCodeComputer,
+        },
+    };
+
+    struct Compiled(IntCodeComputer);
+
+    impl IntCodeRunner for Compiled {
+        fn load(program: &[i64]) -> Self {
+            let mut computer = ComputerFactory::new(program.to_vec()).build();
+            computer.set_execution_mode(ExecutionMode::Compiled);
+            Self(computer)
+        }
+
+        fn send(&mut self, value: i64) {
+            self.0.send_i64(value);
+        }
+
+        fn next_output(&mut self) -> Result<Option<i64>, ComputerError> {
+            self.0.maybe_i64()
+        }
+    }
+
+    #[test]
+    fn compiled_conforms() {
+        let failures = check::<Compiled>();
+        assert!(failures.is_empty(), "{failures:#?}");
+    }
+
+    #[test]
+    fn self_modifying() -> UnitResult {
+        // The loop counts by patching the immediate of its own output
+        let program = assemble(
+            "
+            loop:   OUT #0
+                    ADD 1, #1, 1
+                    LT 1, #3, 14
+                    JT 14, #loop
+                    HALT
+                    DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        computer.set_execution_mode(ExecutionMode::Compiled);
+        let outputs = computer.as_iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(outputs, vec![0, 1, 2]);
+
+        computer.reset();
+        let outputs = computer.as_iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(outputs, vec![0, 1, 2]);
+
+        // The second time around the output became a halt
+        let program = assemble(
+            "
+            loop:   OUT #7
+                    ADD #99, #0, 0
+                    JT #1, #loop
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        computer.set_execution_mode(ExecutionMode::Compiled);
+        let outputs = computer.as_iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(outputs, vec![7]);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+use super::{trace::Mnemonic, Pointer};
+use itertools::Itertools;
+use std::fmt::Display;
+
 #[derive(Debug, thiserror::Error)]
 pub enum ComputerError {
     #[error("Not an Int")]
@@ -14,8 +18,78 @@ pub enum ComputerError {
     PointerMustNoBeNegative(i64),
     #[error("Premature End of Output")]
     PrematureEndOfOutput,
+    #[error("Output ended after {} of {1} values", .0.len())]
+    IncompleteChunk(Vec<i64>, usize),
     #[error("Waiting for Input")]
     WaitingForInput,
     #[error("not a valid char: {0}")]
     NotAValidChar(i64),
+    #[error("Output {0} is no valid {1}")]
+    UnexpectedOutput(i64, &'static str),
+    #[error("Diagnostic check {0} failed with {1}")]
+    DiagnosticCheckFailed(usize, i64),
+    #[error("All computers of the cluster are waiting for input")]
+    ClusterDeadlock,
+    #[error("Aborted after {0} steps")]
+    StepLimitExceeded(u64),
+    #[error("Timed out after {0} steps")]
+    TimedOut(u64),
+    #[error("{1} {0} {2} does not fit into 64 bits")]
+    Overflow(Mnemonic, i64, i64),
+    #[error("Relative base {0} plus {1} does not fit into 64 bits")]
+    RelativeOverflow(i64, i64),
+    #[error("{} at {} (instruction {})", .0.cause, .0.pointer, .0.instruction)]
+    Crashed(Box<Crash>),
+}
+
+impl ComputerError {
+    /**
+     * The error itself, without the context of the instruction it happened in.
+     */
+    pub fn cause(&self) -> &ComputerError {
+        match self {
+            ComputerError::Crashed(crash) => crash.cause.cause(),
+            err => err,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutedInstruction {
+    pub address: Pointer,
+    pub instruction: i64,
+}
+
+impl Display for ExecutedInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:06} {} {}",
+            self.address.as_usize(),
+            self.instruction,
+            Mnemonic::from_instruction(self.instruction)
+        )
+    }
+}
+
+/**
+ * Where an instruction failed and what ran before it, the failing instruction last.
+ */
+#[derive(Debug)]
+pub struct Crash {
+    pub pointer: Pointer,
+    pub instruction: i64,
+    pub recent: Vec<ExecutedInstruction>,
+    pub cause: ComputerError,
+}
+
+impl Crash {
+    pub fn report(&self) -> String {
+        std::iter::once(format!(
+            "{} at {} (instruction {}), last instructions:",
+            self.cause, self.pointer, self.instruction
+        ))
+        .chain(self.recent.iter().map(|executed| format!("  {executed}")))
+        .join("\n")
+    }
 }
@@ -0,0 +1,367 @@
+#![allow(dead_code)]
+use super::{trace::Mnemonic, ComputerError, ComputerFactory, IntCodeComputer};
+use std::collections::HashSet;
+
+/**
+ * The little an Intcode implementation needs to offer to be checked by the suite.
+ */
+pub trait IntCodeRunner: Sized {
+    fn load(program: &[i64]) -> Self;
+
+    fn send(&mut self, value: i64);
+
+    /**
+     * The next output. `None` once the program halted, also on every later call.
+     */
+    fn next_output(&mut self) -> Result<Option<i64>, ComputerError>;
+}
+
+impl IntCodeRunner for IntCodeComputer {
+    fn load(program: &[i64]) -> Self {
+        ComputerFactory::new(program.to_vec()).build()
+    }
+
+    fn send(&mut self, value: i64) {
+        self.send_i64(value);
+    }
+
+    fn next_output(&mut self) -> Result<Option<i64>, ComputerError> {
+        self.maybe_i64()
+    }
+}
+
+pub type ErrorCheck = fn(&ComputerError) -> bool;
+
+/**
+ * A program with its input, the outputs it must produce and, if it
+ * is supposed to fail, the error it must fail with after those outputs.
+ */
+pub struct Case {
+    pub name: &'static str,
+    pub program: Vec<i64>,
+    pub input: Vec<i64>,
+    pub output: Vec<i64>,
+    pub error: Option<ErrorCheck>,
+}
+
+impl Case {
+    fn new(name: &'static str, program: &[i64], input: &[i64], output: &[i64]) -> Self {
+        Self {
+            name,
+            program: program.to_vec(),
+            input: input.to_vec(),
+            output: output.to_vec(),
+            error: None,
+        }
+    }
+
+    fn failing(name: &'static str, program: &[i64], output: &[i64], error: ErrorCheck) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(name, program, &[], output)
+        }
+    }
+
+    pub fn run<R: IntCodeRunner>(&self) -> Result<(), String> {
+        let mut runner = R::load(&self.program);
+        for value in &self.input {
+            runner.send(*value);
+        }
+
+        let mut output = vec![];
+        let result = loop {
+            match runner.next_output() {
+                Ok(Some(value)) => output.push(value),
+                Ok(None) => match runner.next_output() {
+                    Ok(None) => break Ok(()),
+                    Ok(Some(value)) => {
+                        return Err(format!("{}: output {value} after halting", self.name))
+                    }
+                    Err(err) => break Err(err),
+                },
+                Err(err) => break Err(err),
+            }
+        };
+
+        if output != self.output {
+            return Err(format!(
+                "{}: expected output {:?}, got {:?}",
+                self.name, self.output, output
+            ));
+        }
+        match (result, self.error) {
+            (Ok(()), None) => Ok(()),
+            (Err(err), Some(check)) if check(err.cause()) => Ok(()),
+            (Err(err), _) => Err(format!("{}: unexpected error {err:?}", self.name)),
+            (Ok(()), Some(_)) => Err(format!("{}: expected an error", self.name)),
+        }
+    }
+}
+
+const DAY05_COMPARE: [i64; 47] = [
+    3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+    1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105,
+    1, 46, 98, 99,
+];
+
+const QUINE: [i64; 16] = [
+    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+];
+
+pub fn cases() -> Vec<Case> {
+    vec![
+        Case::new("add position", &[1, 0, 0, 0, 4, 0, 99], &[], &[2]),
+        Case::new("mul immediate", &[1102, 3, 4, 7, 4, 7, 99, 0], &[], &[12]),
+        Case::new("mul position", &[2, 7, 8, 9, 4, 9, 99, 6, 7, 0], &[], &[42]),
+        Case::new("echo", &[3, 0, 4, 0, 99], &[42], &[42]),
+        Case::new(
+            "equals position",
+            &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[8],
+            &[1],
+        ),
+        Case::new(
+            "equals immediate",
+            &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
+            &[7],
+            &[0],
+        ),
+        Case::new(
+            "less than position",
+            &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[5],
+            &[1],
+        ),
+        Case::new(
+            "less than immediate",
+            &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            &[9],
+            &[0],
+        ),
+        Case::new(
+            "jump position",
+            &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+            &[0],
+            &[0],
+        ),
+        Case::new(
+            "jump immediate",
+            &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1],
+            &[3],
+            &[1],
+        ),
+        Case::new("compare below", &DAY05_COMPARE, &[7], &[999]),
+        Case::new("compare equal", &DAY05_COMPARE, &[8], &[1000]),
+        Case::new("compare above", &DAY05_COMPARE, &[9], &[1001]),
+        Case::new("quine", &QUINE, &[], &QUINE),
+        Case::new(
+            "large product",
+            &[1102, 34915192, 34915192, 7, 4, 7, 99, 0],
+            &[],
+            &[1219070632396864],
+        ),
+        Case::new(
+            "large immediate",
+            &[104, 1125899906842624, 99],
+            &[],
+            &[1125899906842624],
+        ),
+        Case::new("self modifying", &[1101, 0, 42, 5, 104, 0, 99], &[], &[42]),
+        Case::new("unset memory", &[4, 100, 99], &[], &[0]),
+        Case::new(
+            "far memory",
+            &[1101, 1, 2, 1000000, 4, 1000000, 99],
+            &[],
+            &[3],
+        ),
+        Case::new(
+            "negative relative offset",
+            &[109, 10, 204, -3, 99, 0, 0, 77],
+            &[],
+            &[77],
+        ),
+        Case::new(
+            "relative write",
+            &[109, 20, 21101, 3, 4, 1, 204, 1, 99],
+            &[],
+            &[7],
+        ),
+        Case::new(
+            "accumulated relative base",
+            &[109, 5, 109, -3, 209, 7, 204, -3, 99, 4],
+            &[],
+            &[-3],
+        ),
+        Case::new(
+            "relative base position",
+            &[9, 5, 204, 0, 99, 6, 77],
+            &[],
+            &[77],
+        ),
+        Case::new(
+            "relative arithmetic",
+            &[
+                109, 20, 22201, 0, 1, 2, 22202, 2, 1, 3, 204, 2, 204, 3, 99, 0, 0, 0, 0, 0, 3, 4,
+                0, 0,
+            ],
+            &[],
+            &[7, 28],
+        ),
+        Case::new(
+            "relative comparisons",
+            &[
+                109, 20, 22207, 0, 1, 2, 22208, 0, 0, 3, 204, 2, 204, 3, 99, 0, 0, 0, 0, 0, 3, 4,
+                0, 0,
+            ],
+            &[],
+            &[1, 1],
+        ),
+        Case::new(
+            "relative jumps",
+            &[
+                109, 30, 2205, 0, 1, 104, 0, 99, 2206, 2, 3, 104, 0, 99, 105, 1, 34, 104, 0, 99,
+                104, 1, 99, 0, 0, 0, 0, 0, 0, 0, 1, 8, 0, 14, 20,
+            ],
+            &[],
+            &[1],
+        ),
+        Case::new(
+            "relative input",
+            &[109, 9, 203, 0, 204, 0, 99],
+            &[-7],
+            &[-7],
+        ),
+        Case::failing("unknown opcode", &[104, 1, 98], &[1], |err| {
+            matches!(err, ComputerError::IllegalOperation(98))
+        }),
+        Case::failing("not an instruction", &[1105, 1, 3, -5], &[], |err| {
+            matches!(err, ComputerError::NotAnInstruction(-5))
+        }),
+        Case::failing("illegal mode", &[301, 0, 0, 0, 99], &[], |err| {
+            matches!(err, ComputerError::IllegalParamMode)
+        }),
+        Case::failing("immediate write", &[11101, 1, 1, 0, 99], &[], |err| {
+            matches!(err, ComputerError::IllegalParamMode)
+        }),
+        Case::failing("negative address", &[4, -1, 99], &[], |err| {
+            matches!(err, ComputerError::PointerMustNoBeNegative(-1))
+        }),
+        Case::failing(
+            "negative relative address",
+            &[109, -5, 204, 0, 99],
+            &[],
+            |err| matches!(err, ComputerError::PointerMustNoBeNegative(-5)),
+        ),
+        Case::failing("missing input", &[104, 1, 3, 0, 99], &[1], |err| {
+            matches!(err, ComputerError::WaitingForInput)
+        }),
+    ]
+}
+
+/**
+ * Runs every case and returns the descriptions of all that failed.
+ */
+pub fn check<R: IntCodeRunner>() -> Vec<String> {
+    cases()
+        .iter()
+        .filter_map(|case| case.run::<R>().err())
+        .collect()
+}
+
+/** An opcode, one of its parameters and the mode that parameter was given in */
+pub type ModeUse = (Mnemonic, usize, i64);
+
+/**
+ * Every mode every parameter of every opcode allows. Written parameters
+ * are never immediate.
+ */
+pub fn legal_modes() -> Vec<ModeUse> {
+    let mnemonics = (1..=9).map(Mnemonic::from_instruction);
+    mnemonics
+        .flat_map(|mnemonic| {
+            (0..mnemonic.parameter_count()).flat_map(move |parameter| {
+                (0..=2)
+                    .filter(move |mode| {
+                        *mode != 1 || mnemonic.written_parameter() != Some(parameter)
+                    })
+                    .map(move |mode| (mnemonic, parameter, mode))
+            })
+        })
+        .collect()
+}
+
+/**
+ * The modes the cases actually run on the computer, so a gap in the suite
+ * shows up as a missing entry and not as a bug found much later.
+ */
+pub fn exercised_modes() -> HashSet<ModeUse> {
+    let mut exercised = HashSet::new();
+    for case in cases() {
+        let mut computer = IntCodeComputer::load(&case.program);
+        computer.enable_trace();
+        for value in &case.input {
+            computer.send(*value);
+        }
+        while let Ok(Some(_)) = computer.next_output() {}
+        for entry in computer.take_trace() {
+            let mut modes = entry.instruction / 100;
+            for parameter in 0..entry.mnemonic.parameter_count() {
+                exercised.insert((entry.mnemonic, parameter, modes % 10));
+                modes /= 10;
+            }
+        }
+    }
+    exercised
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn computer_conforms() {
+        let failures = check::<IntCodeComputer>();
+        assert!(failures.is_empty(), "{failures:#?}");
+    }
+
+    /** Reads on after the program halted, like a computer that ignores opcode 99 */
+    struct RunsPastHalt(IntCodeComputer, bool);
+
+    impl IntCodeRunner for RunsPastHalt {
+        fn load(program: &[i64]) -> Self {
+            Self(IntCodeComputer::load(program), false)
+        }
+
+        fn send(&mut self, value: i64) {
+            self.0.send(value);
+        }
+
+        fn next_output(&mut self) -> Result<Option<i64>, ComputerError> {
+            if self.1 {
+                return Ok(Some(0));
+            }
+            let output = self.0.next_output()?;
+            self.1 = output.is_none();
+            Ok(output)
+        }
+    }
+
+    #[test]
+    fn output_after_halt_fails() {
+        let case = &cases()[0];
+        assert_eq!(
+            case.run::<RunsPastHalt>(),
+            Err(format!("{}: output 0 after halting", case.name))
+        );
+    }
+
+    #[test]
+    fn every_mode_exercised() {
+        let exercised = exercised_modes();
+        let missing = legal_modes()
+            .into_iter()
+            .filter(|mode| !exercised.contains(mode))
+            .collect::<Vec<_>>();
+        assert!(missing.is_empty(), "{missing:?}");
+    }
+}
@@ -0,0 +1,172 @@
+#![allow(dead_code)]
+use super::Pointer;
+use std::{fmt::Display, ops::Range};
+
+/**
+ * Which words of memory were ever executed as part of an instruction, its
+ * opcode or one of its parameters. Like the profile, the coverage survives
+ * a reset of the computer.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    words: Vec<bool>,
+}
+
+impl Coverage {
+    /** Called after the instruction at `address`, `length` words long, ran */
+    pub(super) fn record(&mut self, address: Pointer, length: usize) {
+        let start = address.as_usize();
+        if self.words.len() < start + length {
+            self.words.resize(start + length, false);
+        }
+        self.words[start..start + length].fill(true);
+    }
+
+    pub fn is_covered(&self, address: Pointer) -> bool {
+        self.words
+            .get(address.as_usize())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /** Adds what another run covered, to report several runs as one */
+    pub fn merge(&mut self, other: &Coverage) {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), false);
+        }
+        for (word, covered) in self.words.iter_mut().zip(&other.words) {
+            *word |= covered;
+        }
+    }
+
+    /**
+     * Splits the first `length` words, or all executed ones if there are
+     * more, into covered and uncovered ranges. Uncovered ranges hold code
+     * that never ran as well as data.
+     */
+    pub fn report(&self, length: usize) -> CoverageReport {
+        let length = length.max(self.words.len());
+        let mut covered = vec![];
+        let mut uncovered = vec![];
+        let mut start = 0;
+        while start < length {
+            let state = self.is_covered(Pointer::new(start));
+            let end = (start + 1..length)
+                .find(|address| self.is_covered(Pointer::new(*address)) != state)
+                .unwrap_or(length);
+            if state {
+                covered.push(start..end);
+            } else {
+                uncovered.push(start..end);
+            }
+            start = end;
+        }
+        CoverageReport {
+            length,
+            covered,
+            uncovered,
+        }
+    }
+}
+
+/**
+ * The ranges of a program that ran and those that did not, see `Coverage::report`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub length: usize,
+    pub covered: Vec<Range<usize>>,
+    pub uncovered: Vec<Range<usize>>,
+}
+
+impl CoverageReport {
+    pub fn covered_words(&self) -> usize {
+        self.covered.iter().map(ExactSizeIterator::len).sum()
+    }
+
+    pub fn percentage(&self) -> f64 {
+        if self.length == 0 {
+            return 0.0;
+        }
+        100.0 * self.covered_words() as f64 / self.length as f64
+    }
+}
+
+impl Display for CoverageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} of {} words executed ({:.1}%)",
+            self.covered_words(),
+            self.length,
+            self.percentage()
+        )?;
+        for (label, ranges) in [("covered", &self.covered), ("uncovered", &self.uncovered)] {
+            writeln!(f, "{label}:")?;
+            for range in ranges {
+                writeln!(
+                    f,
+                    "  {:06}-{:06} {:>6} words",
+                    range.start,
+                    range.end - 1,
+                    range.len()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerFactory},
+    };
+
+    #[test]
+    fn branches() -> UnitResult {
+        let code = assemble(
+            "
+                    IN value
+                    JF value, #zero
+                    OUT #1
+                    HALT
+            zero:   OUT #0
+                    HALT
+            value:  DATA 0
+            ",
+        )?;
+        let factory = ComputerFactory::new(code);
+        let mut computer = factory.build();
+        computer.enable_coverage();
+        computer.send_i64(0);
+        computer.run_till_halt()?;
+
+        let report = computer.coverage_report().unwrap();
+        assert_eq!(report.covered, vec![0..5, 8..11]);
+        assert_eq!(report.uncovered, vec![5..8, 11..12]);
+        assert_eq!(report.covered_words(), 8);
+        let zero = computer.coverage().unwrap().clone();
+
+        computer.reset();
+        computer.send_i64(5);
+        computer.run_till_halt()?;
+        let report = computer.coverage_report().unwrap();
+        assert_eq!(report.covered, vec![0..11]);
+        assert_eq!(report.uncovered, vec![11..12]);
+
+        let mut other = factory.build();
+        other.enable_coverage();
+        other.send_i64(1);
+        other.run_till_halt()?;
+        let mut coverage = other.coverage().unwrap().clone();
+        assert!(!coverage.is_covered(Pointer::new(8)));
+        coverage.merge(&zero);
+        assert_eq!(coverage.report(12).covered, vec![0..11]);
+        assert!(!coverage.is_covered(Pointer::new(11)));
+
+        Ok(())
+    }
+}
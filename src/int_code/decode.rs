@@ -0,0 +1,97 @@
+use super::ComputerError;
+use std::any::type_name;
+
+/**
+ * A value a program can send as a single output. `None` means the output
+ * is not a valid value of the type.
+ */
+pub trait FromOutput: Sized {
+    fn from_output(value: i64) -> Option<Self>;
+
+    #[inline]
+    fn decode(value: i64) -> Result<Self, ComputerError> {
+        Self::from_output(value).ok_or(ComputerError::UnexpectedOutput(value, type_name::<Self>()))
+    }
+}
+
+impl FromOutput for i64 {
+    #[inline]
+    fn from_output(value: i64) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl FromOutput for bool {
+    #[inline]
+    fn from_output(value: i64) -> Option<Self> {
+        match value {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl FromOutput for char {
+    #[inline]
+    fn from_output(value: i64) -> Option<Self> {
+        u8::try_from(value)
+            .ok()
+            .filter(u8::is_ascii)
+            .map(char::from)
+    }
+}
+
+/**
+ * A group of outputs that belong together, like the `x, y, tile` triples
+ * of the arcade. See `IntCodeComputer::read`.
+ */
+pub trait FromOutputs: Sized {
+    const LEN: usize;
+
+    /** Gets exactly `LEN` values */
+    fn decode(values: &[i64]) -> Result<Self, ComputerError>;
+}
+
+macro_rules! tuple_impl {
+    ($len:literal: $($t:ident $idx:tt)*) => {
+        impl<$($t: FromOutput),*> FromOutputs for ($($t,)*) {
+            const LEN: usize = $len;
+
+            #[inline]
+            fn decode(values: &[i64]) -> Result<Self, ComputerError> {
+                Ok(($($t::decode(values[$idx])?,)*))
+            }
+        }
+    };
+}
+
+tuple_impl!(1: A 0);
+tuple_impl!(2: A 0 B 1);
+tuple_impl!(3: A 0 B 1 C 2);
+tuple_impl!(4: A 0 B 1 C 2 D 3);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::ComputerFactory;
+
+    #[test]
+    fn read_tuples() -> Result<(), ComputerError> {
+        let mut computer =
+            ComputerFactory::new(vec![104, -1, 104, 65, 104, 1, 104, 7, 104, 2, 104, 3, 99])
+                .build();
+        assert_eq!(computer.read::<(i64, char, bool)>()?, Some((-1, 'A', true)));
+        assert!(matches!(
+            computer.read::<(i64, bool)>(),
+            Err(ComputerError::UnexpectedOutput(2, "bool"))
+        ));
+        assert!(matches!(
+            computer.read::<(i64, i64)>(),
+            Err(ComputerError::PrematureEndOfOutput)
+        ));
+        assert_eq!(computer.read::<(i64,)>()?, None);
+
+        Ok(())
+    }
+}
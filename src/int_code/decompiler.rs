@@ -0,0 +1,743 @@
+#![allow(dead_code)]
+use super::trace::Mnemonic;
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/**
+ * Memory cells used at least this often get a short name instead of `mem[address]`.
+ */
+const NAMED_CELL_USES: usize = 3;
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Immediate(i64),
+    Position(usize),
+    Relative(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    Always,
+    Never,
+    NonZero(Operand),
+    Zero(Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn negate(self) -> Self {
+        match self {
+            Comparison::Less => Comparison::GreaterOrEqual,
+            Comparison::GreaterOrEqual => Comparison::Less,
+            Comparison::Equal => Comparison::NotEqual,
+            Comparison::NotEqual => Comparison::Equal,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Instruction {
+    address: usize,
+    raw: i64,
+    mnemonic: Mnemonic,
+    operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /**
+     * Anything that cannot be executed, including instructions that do not
+     * fit into the program or write to an immediate operand, is `Illegal`.
+     */
+    fn decode(program: &[i64], address: usize) -> Self {
+        let raw = program[address];
+        let illegal = Self {
+            address,
+            raw,
+            mnemonic: Mnemonic::Illegal,
+            operands: vec![],
+        };
+        let mnemonic = Mnemonic::from_instruction(raw);
+        let mut modes = raw.div_euclid(100);
+        let mut operands = vec![];
+        for idx in 0..mnemonic.parameter_count() {
+            let Some(&value) = program.get(address + 1 + idx) else {
+                return illegal;
+            };
+            let operand = match modes % 10 {
+                0 if value >= 0 => Operand::Position(value as usize),
+                1 if mnemonic.written_parameter() != Some(idx) => Operand::Immediate(value),
+                2 => Operand::Relative(value),
+                _ => return illegal,
+            };
+            operands.push(operand);
+            modes /= 10;
+        }
+        if modes != 0 {
+            return illegal;
+        }
+        Self {
+            address,
+            raw,
+            mnemonic,
+            operands,
+        }
+    }
+
+    fn next(&self) -> usize {
+        self.address + 1 + self.operands.len()
+    }
+
+    fn is_jump(&self) -> bool {
+        matches!(self.mnemonic, Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse)
+    }
+
+    fn condition(&self) -> Condition {
+        match (self.mnemonic, self.operands[0]) {
+            (Mnemonic::JumpIfTrue, Operand::Immediate(value)) if value != 0 => Condition::Always,
+            (Mnemonic::JumpIfFalse, Operand::Immediate(0)) => Condition::Always,
+            (Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse, Operand::Immediate(_)) => {
+                Condition::Never
+            }
+            (Mnemonic::JumpIfTrue, operand) => Condition::NonZero(operand),
+            (_, operand) => Condition::Zero(operand),
+        }
+    }
+
+    fn is_conditional(&self) -> bool {
+        self.is_jump() && matches!(self.condition(), Condition::NonZero(_) | Condition::Zero(_))
+    }
+
+    fn is_unconditional(&self) -> bool {
+        self.is_jump() && self.condition() == Condition::Always
+    }
+
+    /** Where a jump goes, as long as it is known without running the program */
+    fn target(&self) -> Option<usize> {
+        match self.operands.get(1) {
+            Some(Operand::Immediate(target)) if self.is_jump() && *target >= 0 => {
+                Some(*target as usize)
+            }
+            _ => None,
+        }
+    }
+
+    fn written(&self) -> Option<Operand> {
+        self.mnemonic
+            .written_parameter()
+            .map(|idx| self.operands[idx])
+    }
+
+    fn read(&self) -> impl Iterator<Item = Operand> + '_ {
+        let written = self.mnemonic.written_parameter();
+        self.operands
+            .iter()
+            .enumerate()
+            .filter(move |(idx, _)| Some(*idx) != written)
+            .map(|(_, operand)| *operand)
+    }
+
+    /** The value an `ADD` or `MUL` of two immediates stores */
+    fn constant(&self) -> Option<i64> {
+        match (self.mnemonic, &self.operands[..]) {
+            (Mnemonic::Add, [Operand::Immediate(a), Operand::Immediate(b), _]) => Some(a + b),
+            (Mnemonic::Mul, [Operand::Immediate(a), Operand::Immediate(b), _]) => Some(a * b),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * One line of pseudocode and the address of the first instruction it
+ * stands for, so labels can be put in front of it.
+ */
+struct Line {
+    address: usize,
+    depth: usize,
+    text: String,
+}
+
+struct Decompiler<'a> {
+    program: &'a [i64],
+    code: BTreeMap<usize, Instruction>,
+    /** The instruction storing the return address, and the function it calls */
+    calls: HashMap<usize, usize>,
+    call_jumps: HashSet<usize>,
+    functions: BTreeSet<usize>,
+    /** Jump targets and the jumps going back to them */
+    back_jumps: HashMap<usize, Vec<usize>>,
+    /** Comparisons whose result is only used by the jump right after them */
+    folded: HashMap<usize, usize>,
+    names: BTreeMap<usize, (String, usize)>,
+    gotos: BTreeSet<usize>,
+    lines: Vec<Line>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn new(program: &'a [i64]) -> Self {
+        let mut decompiler = Self {
+            program,
+            code: BTreeMap::new(),
+            calls: HashMap::new(),
+            call_jumps: HashSet::new(),
+            functions: BTreeSet::new(),
+            back_jumps: HashMap::new(),
+            folded: HashMap::new(),
+            names: BTreeMap::new(),
+            gotos: BTreeSet::new(),
+            lines: vec![],
+        };
+        decompiler.find_code();
+        decompiler.find_loops();
+        decompiler.fold_comparisons();
+        decompiler.name_cells();
+        decompiler
+    }
+
+    /**
+     * Follows every path from address 0. A constant stored right before an
+     * unconditional jump that equals the address after the jump is taken to
+     * be a return address, so the code after calls is found as well.
+     */
+    fn find_code(&mut self) {
+        let mut pending = vec![0];
+        while !pending.is_empty() {
+            while let Some(address) = pending.pop() {
+                if address >= self.program.len() || self.code.contains_key(&address) {
+                    continue;
+                }
+                let instruction = Instruction::decode(self.program, address);
+                match instruction.mnemonic {
+                    Mnemonic::Halt | Mnemonic::Illegal => {}
+                    _ if instruction.is_jump() => {
+                        pending.extend(instruction.target());
+                        if instruction.condition() != Condition::Always {
+                            pending.push(instruction.next());
+                        }
+                    }
+                    _ => pending.push(instruction.next()),
+                }
+                self.code.insert(address, instruction);
+            }
+
+            for instruction in self.code.values() {
+                let Some(jump) = self.code.get(&instruction.next()) else {
+                    continue;
+                };
+                if let Some(function) = jump.target()
+                    && jump.is_unconditional()
+                    && instruction.constant() == Some(jump.next() as i64)
+                {
+                    self.calls.insert(instruction.address, function);
+                    self.call_jumps.insert(jump.address);
+                    self.functions.insert(function);
+                    if !self.code.contains_key(&jump.next()) {
+                        pending.push(jump.next());
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_loops(&mut self) {
+        for instruction in self.code.values() {
+            if let Some(target) = instruction.target()
+                && target <= instruction.address
+                && instruction.condition() != Condition::Never
+                && !self.call_jumps.contains(&instruction.address)
+            {
+                self.back_jumps
+                    .entry(target)
+                    .or_default()
+                    .push(instruction.address);
+            }
+        }
+    }
+
+    fn is_target(&self, address: usize) -> bool {
+        self.functions.contains(&address)
+            || self.code.values().any(|instruction| {
+                instruction.target() == Some(address)
+                    || (self.calls.contains_key(&instruction.address)
+                        && instruction.constant() == Some(address as i64))
+            })
+    }
+
+    fn fold_comparisons(&mut self) {
+        let mut reads = HashMap::<usize, usize>::new();
+        for instruction in self.code.values() {
+            for operand in instruction.read() {
+                if let Operand::Position(cell) = operand {
+                    *reads.entry(cell).or_default() += 1;
+                }
+            }
+        }
+
+        let mut candidates = HashMap::<usize, Vec<(usize, usize)>>::new();
+        for jump in self.code.values().filter(|jump| jump.is_conditional()) {
+            let (Condition::NonZero(Operand::Position(cell))
+            | Condition::Zero(Operand::Position(cell))) = jump.condition()
+            else {
+                continue;
+            };
+            let Some((_, comparison)) = self.code.range(..jump.address).next_back() else {
+                continue;
+            };
+            if matches!(comparison.mnemonic, Mnemonic::LessThan | Mnemonic::Equals)
+                && comparison.next() == jump.address
+                && comparison.written() == Some(Operand::Position(cell))
+                && !self.is_target(jump.address)
+            {
+                candidates
+                    .entry(cell)
+                    .or_default()
+                    .push((comparison.address, jump.address));
+            }
+        }
+        for (cell, pairs) in candidates {
+            if reads.get(&cell) == Some(&pairs.len()) {
+                self.folded.extend(pairs);
+            }
+        }
+    }
+
+    fn name_cells(&mut self) {
+        let mut uses = HashMap::<usize, usize>::new();
+        for instruction in self.code.values() {
+            // The flag of a folded comparison never shows up
+            let hidden = if self.folded.contains_key(&instruction.address) {
+                instruction.mnemonic.written_parameter()
+            } else if self.folded.values().contains(&instruction.address) {
+                Some(0)
+            } else {
+                None
+            };
+            for (idx, operand) in instruction.operands.iter().enumerate() {
+                if let Operand::Position(cell) = operand
+                    && hidden != Some(idx)
+                {
+                    *uses.entry(*cell).or_default() += 1;
+                }
+            }
+        }
+        self.names = uses
+            .into_iter()
+            .filter(|(_, count)| *count >= NAMED_CELL_USES)
+            .sorted_by_key(|(cell, count)| (usize::MAX - count, *cell))
+            .enumerate()
+            .map(|(idx, (cell, count))| (cell, (format!("v{}", idx + 1), count)))
+            .collect();
+    }
+
+    fn operand(&self, operand: Operand) -> String {
+        match operand {
+            Operand::Immediate(value) => value.to_string(),
+            Operand::Position(cell) => match self.names.get(&cell) {
+                Some((name, _)) => name.clone(),
+                None => format!("mem[{cell}]"),
+            },
+            Operand::Relative(offset) => format!("rb[{offset}]"),
+        }
+    }
+
+    fn comparison(&self, jump: &Instruction) -> (Operand, Comparison, Operand) {
+        let (operand, jumps_if_zero) = match jump.condition() {
+            Condition::NonZero(operand) => (operand, false),
+            Condition::Zero(operand) => (operand, true),
+            Condition::Always | Condition::Never => unreachable!("only called for conditions"),
+        };
+        let (left, comparison, right) = match self
+            .folded
+            .iter()
+            .find(|(_, folded_jump)| **folded_jump == jump.address)
+            .map(|(comparison, _)| &self.code[comparison])
+        {
+            Some(folded) => (
+                folded.operands[0],
+                match folded.mnemonic {
+                    Mnemonic::LessThan => Comparison::Less,
+                    _ => Comparison::Equal,
+                },
+                folded.operands[1],
+            ),
+            None => (operand, Comparison::NotEqual, Operand::Immediate(0)),
+        };
+        if jumps_if_zero {
+            (left, comparison.negate(), right)
+        } else {
+            (left, comparison, right)
+        }
+    }
+
+    /** The condition under which the jump is taken, or not taken with `negate` */
+    fn condition(&self, jump: &Instruction, negate: bool) -> String {
+        let (left, comparison, right) = self.comparison(jump);
+        let comparison = if negate {
+            comparison.negate()
+        } else {
+            comparison
+        };
+        format!(
+            "{} {} {}",
+            self.operand(left),
+            comparison.as_str(),
+            self.operand(right)
+        )
+    }
+
+    fn statement(&self, instruction: &Instruction) -> String {
+        let operands = &instruction.operands;
+        let op = |idx: usize| self.operand(operands[idx]);
+        match instruction.mnemonic {
+            Mnemonic::Add | Mnemonic::Mul => {
+                if let Some(value) = instruction.constant() {
+                    return format!("{} = {value}", op(2));
+                }
+                let (neutral, symbol) = if instruction.mnemonic == Mnemonic::Add {
+                    (0, "+")
+                } else {
+                    (1, "*")
+                };
+                match (operands[0], operands[1]) {
+                    (Operand::Immediate(value), _) if value == neutral => {
+                        format!("{} = {}", op(2), op(1))
+                    }
+                    (_, Operand::Immediate(value)) if value == neutral => {
+                        format!("{} = {}", op(2), op(0))
+                    }
+                    (_, Operand::Immediate(-1)) if symbol == "*" => {
+                        format!("{} = -{}", op(2), op(0))
+                    }
+                    (_, Operand::Immediate(value)) if value < 0 && symbol == "+" => {
+                        if operands[0] == operands[2] {
+                            format!("{} -= {}", op(2), -value)
+                        } else {
+                            format!("{} = {} - {}", op(2), op(0), -value)
+                        }
+                    }
+                    (first, _) if first == operands[2] => {
+                        format!("{} {symbol}= {}", op(2), op(1))
+                    }
+                    (_, second) if second == operands[2] => {
+                        format!("{} {symbol}= {}", op(2), op(0))
+                    }
+                    _ => format!("{} = {} {symbol} {}", op(2), op(0), op(1)),
+                }
+            }
+            Mnemonic::LessThan => format!("{} = {} < {}", op(2), op(0), op(1)),
+            Mnemonic::Equals => format!("{} = {} == {}", op(2), op(0), op(1)),
+            Mnemonic::In => format!("{} = input()", op(0)),
+            Mnemonic::Out => format!("output({})", op(0)),
+            Mnemonic::RelativeBase => match operands[0] {
+                Operand::Immediate(value) if value < 0 => format!("rb -= {}", -value),
+                _ => format!("rb += {}", op(0)),
+            },
+            Mnemonic::Halt => "halt".to_owned(),
+            Mnemonic::Illegal => format!("illegal {}", instruction.raw),
+            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse => self.jump(instruction),
+        }
+    }
+
+    /** A jump that is not part of any structure */
+    fn jump(&self, jump: &Instruction) -> String {
+        let goto = match (jump.target(), jump.operands[1]) {
+            (Some(target), _) => format!("goto L{target}"),
+            (None, Operand::Relative(offset)) => format!("return to rb[{offset}]"),
+            (None, operand) => format!("goto *{}", self.operand(operand)),
+        };
+        match jump.condition() {
+            Condition::Always => goto,
+            Condition::Never => "nop".to_owned(),
+            _ => format!("if ({}) {goto}", self.condition(jump, false)),
+        }
+    }
+
+    fn push(&mut self, address: usize, depth: usize, text: String) {
+        self.lines.push(Line {
+            address,
+            depth,
+            text,
+        });
+    }
+
+    fn emit(&mut self, start: usize, end: usize, depth: usize) {
+        let mut address = start;
+        while address < end
+            && let Some((_, instruction)) = self.code.range(address..end).next()
+        {
+            let instruction = instruction.clone();
+            address = self
+                .emit_at(instruction.clone(), end, depth)
+                .max(instruction.next());
+        }
+    }
+
+    /**
+     * The conditional jump deciding whether a structure is entered: the
+     * instruction itself or the one after a folded comparison.
+     */
+    fn guard(&self, instruction: &Instruction) -> Option<Instruction> {
+        let jump = match self.folded.get(&instruction.address) {
+            Some(jump) => &self.code[jump],
+            None => instruction,
+        };
+        (jump.is_conditional() && jump.target().is_some()).then(|| jump.clone())
+    }
+
+    /** Emits the structure starting with the instruction and returns where to go on */
+    fn emit_at(&mut self, instruction: Instruction, end: usize, depth: usize) -> usize {
+        let address = instruction.address;
+        let back_jump = self.back_jumps.get(&address).and_then(|jumps| {
+            jumps
+                .iter()
+                .filter(|jump| **jump < end)
+                .max()
+                .map(|jump| self.code[jump].clone())
+        });
+        if let Some(back_jump) = back_jump {
+            let after = back_jump.next();
+            if back_jump.is_unconditional() {
+                if let Some(guard) = self.guard(&instruction)
+                    && guard.target() == Some(after)
+                {
+                    let text = format!("while ({}) {{", self.condition(&guard, true));
+                    self.push(address, depth, text);
+                    self.emit(guard.next(), back_jump.address, depth + 1);
+                } else {
+                    self.push(address, depth, "loop {".to_owned());
+                    self.emit(address, back_jump.address, depth + 1);
+                }
+                self.push(address, depth, "}".to_owned());
+            } else {
+                self.push(address, depth, "do {".to_owned());
+                self.emit(address, back_jump.address, depth + 1);
+                let text = format!("}} while ({})", self.condition(&back_jump, false));
+                self.push(back_jump.address, depth, text);
+            }
+            return after;
+        }
+
+        if let Some(&function) = self.calls.get(&address) {
+            let text = match instruction.operands[2] {
+                Operand::Relative(0) => format!("call sub_{function}"),
+                store => format!("call sub_{function} (returns to {})", self.operand(store)),
+            };
+            self.push(address, depth, text);
+            return self.code[&instruction.next()].next();
+        }
+
+        if let Some(guard) = self.guard(&instruction)
+            && let Some(target) = guard.target()
+            && guard.address < target
+            && target <= end
+        {
+            let text = format!("if ({}) {{", self.condition(&guard, true));
+            self.push(address, depth, text);
+            let last = self
+                .code
+                .range(guard.next()..target)
+                .next_back()
+                .map(|(_, last)| last.clone());
+            if let Some(last) = last
+                && last.is_unconditional()
+                && !self.call_jumps.contains(&last.address)
+                && let Some(after) = last.target()
+                && target < after
+                && after <= end
+            {
+                self.emit(guard.next(), last.address, depth + 1);
+                self.push(address, depth, "} else {".to_owned());
+                self.emit(target, after, depth + 1);
+                self.push(address, depth, "}".to_owned());
+                return after;
+            }
+            self.emit(guard.next(), target, depth + 1);
+            self.push(address, depth, "}".to_owned());
+            return target;
+        }
+
+        if self.folded.contains_key(&address) {
+            return instruction.next();
+        }
+        if instruction.is_jump() {
+            if instruction.condition() == Condition::Never {
+                return instruction.next();
+            }
+            self.gotos.extend(instruction.target());
+        }
+        let text = self.statement(&instruction);
+        self.push(address, depth, text);
+        instruction.next()
+    }
+
+    fn render(mut self) -> String {
+        self.emit(0, self.program.len(), 1);
+
+        let code_words: usize = self.code.values().map(|i| i.operands.len() + 1).sum();
+        let mut result = format!(
+            "// {code_words} of {} words are code, the rest is data\n",
+            self.program.len()
+        );
+        for (cell, (name, count)) in self
+            .names
+            .iter()
+            .sorted_by_key(|(cell, (_, count))| (usize::MAX - count, **cell))
+        {
+            let initial = self.program.get(*cell).copied().unwrap_or_default();
+            result.push_str(&format!(
+                "// {name} = mem[{cell}]: {count} uses, starts as {initial}\n"
+            ));
+        }
+
+        let mut labels = self
+            .functions
+            .iter()
+            .map(|address| (*address, format!("sub_{address}:")))
+            .chain(
+                self.gotos
+                    .iter()
+                    .map(|address| (*address, format!("L{address}:"))),
+            )
+            .sorted()
+            .peekable();
+        result.push('\n');
+        for line in &self.lines {
+            while let Some((_, label)) = labels.next_if(|(address, _)| *address <= line.address) {
+                if label.starts_with("sub_") {
+                    result.push('\n');
+                }
+                result.push_str(&format!("{}{label}\n", INDENT.repeat(line.depth - 1)));
+            }
+            result.push_str(&format!("{}{}\n", INDENT.repeat(line.depth), line.text));
+        }
+        result
+    }
+}
+
+/**
+ * Turns a program into readable pseudocode. Only code reachable from
+ * address 0 is decompiled, everything else is taken to be data.
+ *
+ * Jumps are turned into `if`/`else`, `while`, `do`/`while` and endless
+ * `loop`s where they nest properly, the rest stays `goto`. A return address
+ * stored right before a jump makes a `call`, a jump to a relative address
+ * a `return`. Comparisons only used by the jump after them become its
+ * condition, and frequently used memory cells get the names `v1`, `v2`, …
+ * in order of use. `rb` is the relative base. Self modifying code is
+ * shown as it is before the program runs.
+ */
+pub fn decompile(program: &[i64]) -> String {
+    Decompiler::new(program).render()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerFactory},
+    };
+
+    #[test]
+    fn structures() -> UnitResult {
+        let program = assemble(
+            "
+                    RB #100
+                    IN count
+            loop:   LT #0, count, flag
+                    JF flag, #done
+                    ADD #ret, #0, @0
+                    JT #1, #twice
+            ret:    ADD count, #-1, count
+                    JT #1, #loop
+            done:   EQ count, #0, flag
+                    JF flag, #other
+                    OUT #1
+                    JT #1, #end
+            other:  OUT #2
+            end:    HALT
+            twice:  MUL count, #2, @1
+                    OUT @1
+                    JT #1, @0
+            count:  DATA 0
+            flag:   DATA 0
+            ",
+        )?;
+        let expected = "\
+// 49 of 51 words are code, the rest is data
+// v1 = mem[49]: 6 uses, starts as 0
+
+    rb += 100
+    v1 = input()
+    while (0 < v1) {
+        call sub_40
+        v1 -= 1
+    }
+    if (v1 == 0) {
+        output(1)
+    } else {
+        output(2)
+    }
+    halt
+
+sub_40:
+    rb[1] = v1 * 2
+    output(rb[1])
+    return to rb[0]
+";
+        assert_eq!(decompile(&program), expected);
+
+        let mut computer = ComputerFactory::new(program).build();
+        computer.send_i64(2);
+        let outputs: Vec<_> = computer.as_iter().try_collect()?;
+        assert_eq!(outputs, vec![4, 2, 1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn loops_and_gotos() -> UnitResult {
+        let program = assemble(
+            "
+            top:    IN x
+                    JF x, #skip
+                    OUT x
+                    JT x, #top
+            skip:   JT #1, #skip
+            x:      DATA 0
+            ",
+        )?;
+        let expected = "\
+// 13 of 14 words are code, the rest is data
+// v1 = mem[13]: 4 uses, starts as 0
+
+    do {
+        v1 = input()
+        if (v1 == 0) goto L10
+        output(v1)
+    } while (v1 != 0)
+L10:
+    loop {
+    }
+";
+        assert_eq!(decompile(&program), expected);
+        Ok(())
+    }
+}
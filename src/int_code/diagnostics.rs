@@ -0,0 +1,70 @@
+#![allow(dead_code)]
+use super::{
+    computer_error::ComputerError,
+    memory::{Memory, SharedMemory},
+    ComputerFactory,
+};
+use itertools::Itertools;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticReport {
+    outputs: Vec<i64>,
+}
+
+impl DiagnosticReport {
+    #[inline]
+    pub fn outputs(&self) -> &[i64] {
+        &self.outputs
+    }
+
+    /**
+     * All outputs but the last are the results of the intermediate checks.
+     * A check passed if its output is zero.
+     */
+    pub fn checks(&self) -> &[i64] {
+        match self.outputs.split_last() {
+            Some((_, checks)) => checks,
+            None => &[],
+        }
+    }
+
+    pub fn failed_checks(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        self.checks()
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, value)| *value != 0)
+    }
+
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.failed_checks().next().is_none()
+    }
+
+    pub fn final_code(&self) -> Result<i64, ComputerError> {
+        if let Some((check, value)) = self.failed_checks().next() {
+            return Err(ComputerError::DiagnosticCheckFailed(check, value));
+        }
+        self.outputs
+            .last()
+            .copied()
+            .ok_or(ComputerError::PrematureEndOfOutput)
+    }
+}
+
+pub fn run_with_input(
+    program: &ComputerFactory,
+    input: i64,
+) -> Result<DiagnosticReport, ComputerError> {
+    run_on::<SharedMemory>(program, input)
+}
+
+/** Like `run_with_input`, on a computer with the given memory backend */
+pub fn run_on<M: Memory>(
+    program: &ComputerFactory,
+    input: i64,
+) -> Result<DiagnosticReport, ComputerError> {
+    let mut computer = program.builder().input(input).memory::<M>().build();
+    let outputs = computer.as_iter().try_collect()?;
+    Ok(DiagnosticReport { outputs })
+}
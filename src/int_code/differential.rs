@@ -0,0 +1,369 @@
+#![allow(dead_code)]
+use super::{ComputerError, ComputerFactory, IntCodeComputer, Pointer, VmEvent};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/**
+ * An Intcode implementation that can be driven one instruction at a time,
+ * so two of them can be compared step by step.
+ */
+pub trait Backend {
+    fn load(program: &[i64]) -> Self;
+
+    fn send(&mut self, value: i64);
+
+    /**
+     * Executes one instruction. Waiting for input or having halted does not
+     * change anything, so those may be returned again and again.
+     */
+    fn step(&mut self) -> Result<VmEvent, ComputerError>;
+
+    fn pointer(&self) -> Pointer;
+
+    /** Every memory cell that is not zero */
+    fn memory(&self) -> BTreeMap<usize, i64>;
+}
+
+impl Backend for IntCodeComputer {
+    fn load(program: &[i64]) -> Self {
+        ComputerFactory::new(program.to_vec()).build()
+    }
+
+    fn send(&mut self, value: i64) {
+        self.send_i64(value);
+    }
+
+    fn step(&mut self) -> Result<VmEvent, ComputerError> {
+        IntCodeComputer::step(self)
+    }
+
+    fn pointer(&self) -> Pointer {
+        IntCodeComputer::pointer(self)
+    }
+
+    fn memory(&self) -> BTreeMap<usize, i64> {
+        self.memory_cells().collect()
+    }
+}
+
+/**
+ * The most direct implementation possible: every cell lives in a map and
+ * instructions are decoded from scratch every time. Slow, but easy to
+ * trust as the other side of a comparison.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Reference {
+    memory: HashMap<usize, i64>,
+    pointer: usize,
+    relative_base: i64,
+    input: VecDeque<i64>,
+    halted: bool,
+}
+
+impl Reference {
+    fn read(&self, addr: usize) -> i64 {
+        self.memory.get(&addr).copied().unwrap_or_default()
+    }
+
+    fn address(&self, offset: usize, mode: i64) -> Result<usize, ComputerError> {
+        let raw = self.read(self.pointer + offset);
+        let addr = match mode {
+            0 => raw,
+            2 => self.relative_base + raw,
+            _ => return Err(ComputerError::IllegalParamMode),
+        };
+        usize::try_from(addr).map_err(|_| ComputerError::PointerMustNoBeNegative(addr))
+    }
+
+    fn value(&self, offset: usize, mode: i64) -> Result<i64, ComputerError> {
+        if mode == 1 {
+            Ok(self.read(self.pointer + offset))
+        } else {
+            Ok(self.read(self.address(offset, mode)?))
+        }
+    }
+}
+
+impl Backend for Reference {
+    fn load(program: &[i64]) -> Self {
+        Self {
+            memory: program.iter().copied().enumerate().collect(),
+            ..Default::default()
+        }
+    }
+
+    fn send(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    fn step(&mut self) -> Result<VmEvent, ComputerError> {
+        if self.halted {
+            return Ok(VmEvent::Halted);
+        }
+        let instruction = self.read(self.pointer);
+        if instruction <= 0 {
+            return Err(ComputerError::NotAnInstruction(instruction));
+        }
+        let mode = |param: u32| instruction / 10i64.pow(param + 1) % 10;
+
+        let (next, result) = match instruction % 100 {
+            op @ (1 | 2 | 7 | 8) => {
+                let (a, b) = (self.value(1, mode(1))?, self.value(2, mode(2))?);
+                let target = self.address(3, mode(3))?;
+                let value = match op {
+                    1 => a + b,
+                    2 => a * b,
+                    7 => (a < b) as i64,
+                    _ => (a == b) as i64,
+                };
+                self.memory.insert(target, value);
+                (self.pointer + 4, VmEvent::Continue)
+            }
+            3 => {
+                let Some(value) = self.input.pop_front() else {
+                    return Ok(VmEvent::NeedsInput);
+                };
+                let target = self.address(1, mode(1))?;
+                self.memory.insert(target, value);
+                (self.pointer + 2, VmEvent::Continue)
+            }
+            4 => (self.pointer + 2, VmEvent::Output(self.value(1, mode(1))?)),
+            op @ (5 | 6) => {
+                let (test, target) = (self.value(1, mode(1))?, self.value(2, mode(2))?);
+                if (test != 0) == (op == 5) {
+                    let target = usize::try_from(target)
+                        .map_err(|_| ComputerError::PointerMustNoBeNegative(target))?;
+                    (target, VmEvent::Continue)
+                } else {
+                    (self.pointer + 3, VmEvent::Continue)
+                }
+            }
+            9 => {
+                self.relative_base += self.value(1, mode(1))?;
+                (self.pointer + 2, VmEvent::Continue)
+            }
+            99 => {
+                self.halted = true;
+                (self.pointer + 1, VmEvent::Halted)
+            }
+            op => return Err(ComputerError::IllegalOperation(op as usize)),
+        };
+        self.pointer = next;
+        Ok(result)
+    }
+
+    fn pointer(&self) -> Pointer {
+        Pointer::new(self.pointer)
+    }
+
+    fn memory(&self) -> BTreeMap<usize, i64> {
+        self.memory
+            .iter()
+            .filter(|(_, value)| **value != 0)
+            .map(|(addr, value)| (*addr, *value))
+            .collect()
+    }
+}
+
+/**
+ * What both backends agreed on.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Agreement {
+    pub steps: usize,
+    pub output: Vec<i64>,
+    pub halted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Divergence {
+    /** The step ended differently, both sides are described by their result and pointer */
+    #[error("step {step} differs: left {left}, right {right}")]
+    Step {
+        step: usize,
+        left: String,
+        right: String,
+    },
+    /** Both runs ended the same way, but left different memory behind */
+    #[error("memory at {address} differs after the run: left {left}, right {right}")]
+    Memory {
+        address: usize,
+        left: i64,
+        right: i64,
+    },
+    /** Neither side halted or waited within the limit */
+    #[error("no end after {0} steps")]
+    TooManySteps(usize),
+}
+
+fn describe<B: Backend>(backend: &B, result: &Result<VmEvent, ComputerError>) -> String {
+    match result {
+        Ok(VmEvent::Continue) => format!("continued to {}", backend.pointer()),
+        Ok(VmEvent::Output(value)) => {
+            format!("output {value} and continued to {}", backend.pointer())
+        }
+        Ok(VmEvent::NeedsInput) => format!("waited for input at {}", backend.pointer()),
+        Ok(VmEvent::Halted) => format!("halted at {}", backend.pointer()),
+        Ok(VmEvent::Breakpoint(pointer)) => format!("stopped in front of {pointer}"),
+        Ok(VmEvent::Watchpoint(hit)) => {
+            format!("hit {hit:?} and continued to {}", backend.pointer())
+        }
+        Err(err) => format!("failed with \"{}\"", err.cause()),
+    }
+}
+
+/**
+ * Runs the program on both backends in lockstep with the same input and
+ * reports the first step where they do not agree. A run ends when both
+ * halt, fail the same way or wait for input that the script does not have.
+ */
+pub fn compare<L: Backend, R: Backend>(
+    program: &[i64],
+    input: &[i64],
+    max_steps: usize,
+) -> Result<Agreement, Divergence> {
+    let mut left = L::load(program);
+    let mut right = R::load(program);
+    for value in input {
+        left.send(*value);
+        right.send(*value);
+    }
+
+    let mut output = vec![];
+    for step in 0..max_steps {
+        let left_result = left.step();
+        let right_result = right.step();
+        let left_state = describe(&left, &left_result);
+        let right_state = describe(&right, &right_result);
+        if left_state != right_state {
+            return Err(Divergence::Step {
+                step,
+                left: left_state,
+                right: right_state,
+            });
+        }
+
+        let halted = match left_result {
+            Ok(VmEvent::Continue | VmEvent::Breakpoint(_) | VmEvent::Watchpoint(_)) => continue,
+            Ok(VmEvent::Output(value)) => {
+                output.push(value);
+                continue;
+            }
+            Ok(VmEvent::Halted) => true,
+            Ok(VmEvent::NeedsInput) | Err(_) => false,
+        };
+
+        let (left, right) = (left.memory(), right.memory());
+        let mismatch = left
+            .keys()
+            .chain(right.keys())
+            .copied()
+            .filter(|address| left.get(address) != right.get(address))
+            .min();
+        if let Some(address) = mismatch {
+            return Err(Divergence::Memory {
+                address,
+                left: left.get(&address).copied().unwrap_or_default(),
+                right: right.get(&address).copied().unwrap_or_default(),
+            });
+        }
+        return Ok(Agreement {
+            steps: step + 1,
+            output,
+            halted,
+        });
+    }
+    Err(Divergence::TooManySteps(max_steps))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::{read_string, UnitResult},
+        int_code::conformance::cases,
+    };
+    use itertools::Itertools;
+
+    const MAX_STEPS: usize = 1_000_000;
+
+    #[test]
+    fn reference_agrees() -> UnitResult {
+        for case in cases() {
+            let agreement =
+                compare::<IntCodeComputer, Reference>(&case.program, &case.input, MAX_STEPS)
+                    .unwrap_or_else(|divergence| panic!("{}: {divergence}", case.name));
+            assert_eq!(agreement.output, case.output, "{}", case.name);
+        }
+
+        let program: Vec<i64> = read_string(9, "input.txt")?
+            .trim()
+            .split(',')
+            .map(str::parse)
+            .try_collect()?;
+        let agreement = compare::<IntCodeComputer, Reference>(&program, &[1], MAX_STEPS)?;
+        assert!(agreement.halted);
+        assert_eq!(agreement.output.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reports_first_divergence() {
+        /** Gets multiplication wrong, but only for large results */
+        struct Broken(Reference);
+
+        impl Backend for Broken {
+            fn load(program: &[i64]) -> Self {
+                Self(Reference::load(program))
+            }
+
+            fn send(&mut self, value: i64) {
+                self.0.send(value);
+            }
+
+            fn step(&mut self) -> Result<VmEvent, ComputerError> {
+                let instruction = self.0.read(self.0.pointer);
+                let result = self.0.step();
+                if instruction % 100 == 2 {
+                    let target = self.0.read(self.0.pointer - 1) as usize;
+                    if self.0.read(target) > 100 {
+                        self.0.memory.insert(target, 0);
+                    }
+                }
+                result
+            }
+
+            fn pointer(&self) -> Pointer {
+                self.0.pointer()
+            }
+
+            fn memory(&self) -> BTreeMap<usize, i64> {
+                self.0.memory()
+            }
+        }
+
+        let program = [1102, 20, 30, 5, 99, 0];
+        assert!(compare::<Reference, Reference>(&program, &[], 100).is_ok());
+        assert_eq!(
+            compare::<Reference, Broken>(&program, &[], 100),
+            Err(Divergence::Memory {
+                address: 5,
+                left: 600,
+                right: 0
+            })
+        );
+
+        // x = x * 3 while x < 100, then output x
+        let program = [
+            1002, 14, 3, 14, 1007, 14, 100, 15, 1005, 15, 0, 4, 14, 99, 2, 0,
+        ];
+        assert_eq!(
+            compare::<Reference, Broken>(&program, &[], 100),
+            Err(Divergence::Step {
+                step: 11,
+                left: "continued to 11".to_owned(),
+                right: "continued to 0".to_owned(),
+            })
+        );
+    }
+}
@@ -0,0 +1,183 @@
+#![allow(dead_code)]
+use super::{
+    memory::{DenseMemory, Memory, SharedMemory, SparseMemory},
+    ComputerFactory, ExecutionMode, Limits, Poll,
+};
+use itertools::Itertools;
+use rand::Rng;
+use std::panic::{self, AssertUnwindSafe};
+
+/**
+ * Enough for loops to run a while, few enough for thousands of cases a second.
+ */
+const MAX_STEPS: u64 = 2_000;
+
+/**
+ * A program and the input it gets, which the computer must run without
+ * panicking. Failing with a `ComputerError` is fine, whatever the words are.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzCase {
+    pub program: Vec<i64>,
+    pub input: Vec<i64>,
+}
+
+impl FuzzCase {
+    /**
+     * Reads the bytes a fuzzer hands out as little endian words. The first
+     * byte tells how many of them belong to the program, the rest is input.
+     */
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let Some((split, rest)) = data.split_first() else {
+            return Self::default();
+        };
+        let mut words = rest
+            .chunks(8)
+            .map(|chunk| {
+                let mut bytes = [0; 8];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                i64::from_le_bytes(bytes)
+            })
+            .collect_vec();
+        let input = words.split_off((*split as usize).min(words.len()));
+        Self {
+            program: words,
+            input,
+        }
+    }
+
+    /**
+     * Entirely random words hardly ever make it past the first instruction.
+     * These programs mostly consist of opcodes with some parameter modes,
+     * addresses in and around the program and extreme values, so they get
+     * to the paths that need guarding.
+     */
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        let len = rng.gen_range(1..40);
+        Self {
+            program: (0..len).map(|_| random_word(rng, len)).collect(),
+            input: (0..rng.gen_range(0..8))
+                .map(|_| random_word(rng, len))
+                .collect(),
+        }
+    }
+}
+
+fn random_word<R: Rng>(rng: &mut R, len: i64) -> i64 {
+    match rng.gen_range(0..10) {
+        0..=3 => {
+            let opcode = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99][rng.gen_range(0..10)];
+            // Mode 3 does not exist
+            let modes = (0..3).fold(0, |modes, _| modes * 10 + rng.gen_range(0..=3));
+            modes * 100 + opcode
+        }
+        4..=6 => rng.gen_range(-2..len + 4),
+        7 => rng.gen_range(-1_000..1_000),
+        8 => [i64::MIN, i64::MIN + 1, -1, i64::MAX - 1, i64::MAX][rng.gen_range(0..5)],
+        _ => rng.gen_range(i64::MIN..=i64::MAX),
+    }
+}
+
+/**
+ * Runs the case on every memory backend, interpreted as well as compiled.
+ * Returns the message of the first panic, the case ran fine otherwise.
+ */
+pub fn fuzz(case: &FuzzCase) -> Result<(), String> {
+    for mode in [ExecutionMode::Interpreted, ExecutionMode::Compiled] {
+        run_caught::<SharedMemory>(case, mode)?;
+        run_caught::<DenseMemory>(case, mode)?;
+        run_caught::<SparseMemory>(case, mode)?;
+    }
+    Ok(())
+}
+
+/**
+ * The entry point for a fuzzer that hands out bytes, see `FuzzCase::from_bytes`.
+ */
+pub fn fuzz_bytes(data: &[u8]) -> Result<(), String> {
+    fuzz(&FuzzCase::from_bytes(data))
+}
+
+fn run_caught<M: Memory>(case: &FuzzCase, mode: ExecutionMode) -> Result<(), String> {
+    panic::catch_unwind(AssertUnwindSafe(|| run::<M>(case, mode))).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        format!("{mode:?} on {}: {message}", std::any::type_name::<M>())
+    })
+}
+
+fn run<M: Memory>(case: &FuzzCase, mode: ExecutionMode) {
+    let mut computer = ComputerFactory::new(case.program.clone())
+        .builder()
+        .memory::<M>()
+        .execution_mode(mode)
+        .limits(Limits {
+            max_steps: Some(MAX_STEPS),
+            timeout: None,
+        })
+        .inputs(case.input.iter().copied())
+        .build();
+    while let Ok(Poll::Output(_)) = computer.poll() {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::ComputerError;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn check_random(seed: u64, cases: usize) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..cases {
+            let case = FuzzCase::random(&mut rng);
+            if let Err(message) = fuzz(&case) {
+                panic!("{message}\n{case:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn never_panics() {
+        check_random(0, 2_000);
+    }
+
+    #[test]
+    #[ignore = "long fuzz run, run with --release -- --ignored --nocapture"]
+    fn never_panics_long() {
+        check_random(1, 1_000_000);
+    }
+
+    #[test]
+    fn relative_base_overflow() {
+        for mode in [ExecutionMode::Interpreted, ExecutionMode::Compiled] {
+            for program in [
+                vec![109, i64::MAX, 204, 1, 99],
+                vec![109, -1, 109, i64::MIN, 99],
+            ] {
+                let mut computer = ComputerFactory::new(program).build();
+                computer.set_execution_mode(mode);
+                let err = computer.run_till_halt().unwrap_err();
+                assert!(matches!(err.cause(), ComputerError::RelativeOverflow(_, _)));
+            }
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(FuzzCase::from_bytes(&[]), FuzzCase::default());
+        let mut data = vec![1];
+        data.extend(1104i64.to_le_bytes());
+        data.extend([7, 0, 0]);
+        assert_eq!(
+            FuzzCase::from_bytes(&data),
+            FuzzCase {
+                program: vec![1104],
+                input: vec![7],
+            }
+        );
+        assert!(fuzz_bytes(&data).is_ok());
+    }
+}
@@ -0,0 +1,71 @@
+#![allow(dead_code)]
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+    sync::{mpsc::Receiver, Arc, Mutex},
+};
+
+/**
+ * Asked for the next value whenever the program wants input and nothing
+ * was sent to the computer. Returning `None` makes the computer wait
+ * as if there was no source at all. Sources must be `Send`, so the
+ * computer can move to another thread with it.
+ */
+pub trait InputSource: Send {
+    fn next_input(&mut self) -> Option<i64>;
+}
+
+impl<F: FnMut() -> Option<i64> + Send> InputSource for F {
+    fn next_input(&mut self) -> Option<i64> {
+        self()
+    }
+}
+
+/**
+ * Blocks until a value arrives, ends once all senders are gone.
+ */
+impl InputSource for Receiver<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.recv().ok()
+    }
+}
+
+/**
+ * Reads lines from stdin and hands them out as ASCII, newline included.
+ */
+#[derive(Debug, Default)]
+pub struct AsciiStdin {
+    pending: VecDeque<i64>,
+}
+
+impl InputSource for AsciiStdin {
+    fn next_input(&mut self) -> Option<i64> {
+        if self.pending.is_empty() {
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            if !line.ends_with('\n') {
+                line.push('\n');
+            }
+            self.pending.extend(line.chars().map(|c| c as i64));
+        }
+        self.pending.pop_front()
+    }
+}
+
+/**
+ * Shared, so cloned computers keep reading from the same source.
+ */
+#[derive(Clone)]
+pub(super) struct InputHandle(Arc<Mutex<dyn InputSource>>);
+
+impl InputHandle {
+    pub fn new(source: impl InputSource + 'static) -> Self {
+        Self(Arc::new(Mutex::new(source)))
+    }
+
+    pub fn next_input(&self) -> Option<i64> {
+        self.0.lock().unwrap().next_input()
+    }
+}
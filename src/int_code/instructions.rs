@@ -1,11 +1,78 @@
 use super::{
-    computer_error::ComputerError,
-    param_mode::ParamModeDispenser,
-    state::{State, StepResult},
-    Pointer,
+    computer_error::ComputerError, memory::Memory, param_mode::ParamModeDispenser, state::State,
+    trace::Mnemonic, Pointer, VmEvent,
 };
+use std::{str::FromStr, sync::OnceLock};
+
+/**
+ * What `ADD` and `MUL` do when the result does not fit into a cell. Cells
+ * stay `i64` either way, there are no wider ones: a program that needs
+ * more than 64 bits is told so by an error instead of computing garbage.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arithmetic {
+    /** The result wraps around, like the puzzle programs never notice */
+    #[default]
+    Wrapping,
+    /** The program stops with `ComputerError::Overflow` */
+    Checked,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown arithmetic: {0} (known: wrapping, checked)")]
+pub struct UnknownArithmetic(String);
+
+impl FromStr for Arithmetic {
+    type Err = UnknownArithmetic;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrapping" => Ok(Arithmetic::Wrapping),
+            "checked" => Ok(Arithmetic::Checked),
+            _ => Err(UnknownArithmetic(s.to_owned())),
+        }
+    }
+}
+
+static DEFAULT_ARITHMETIC: OnceLock<Arithmetic> = OnceLock::new();
+
+/**
+ * Sets the arithmetic every newly built computer starts with. Can only be
+ * done once, before the first computer is built. Returns false if it was too late.
+ */
+pub fn set_default_arithmetic(arithmetic: Arithmetic) -> bool {
+    DEFAULT_ARITHMETIC.set(arithmetic).is_ok()
+}
 
-pub fn run_instruction(state: &mut State) -> Result<StepResult, ComputerError> {
+pub fn default_arithmetic() -> Arithmetic {
+    *DEFAULT_ARITHMETIC.get_or_init(Arithmetic::default)
+}
+
+impl Arithmetic {
+    #[inline]
+    pub fn add(self, op1: i64, op2: i64) -> Result<i64, ComputerError> {
+        match self {
+            Arithmetic::Wrapping => Ok(op1.wrapping_add(op2)),
+            Arithmetic::Checked => {
+                op1.checked_add(op2)
+                    .ok_or(ComputerError::Overflow(Mnemonic::Add, op1, op2))
+            }
+        }
+    }
+
+    #[inline]
+    pub fn mul(self, op1: i64, op2: i64) -> Result<i64, ComputerError> {
+        match self {
+            Arithmetic::Wrapping => Ok(op1.wrapping_mul(op2)),
+            Arithmetic::Checked => {
+                op1.checked_mul(op2)
+                    .ok_or(ComputerError::Overflow(Mnemonic::Mul, op1, op2))
+            }
+        }
+    }
+}
+
+pub fn run_instruction<M: Memory>(state: &mut State<M>) -> Result<VmEvent, ComputerError> {
     let (code, pd) = analyze_instruction(state.get_next())?;
 
     match code {
@@ -34,150 +101,152 @@ fn analyze_instruction(instruction: i64) -> Result<(usize, ParamModeDispenser),
 }
 
 trait Instruction {
-    fn calc(state: &mut State, parameters: ParamModeDispenser)
-        -> Result<StepResult, ComputerError>;
+    fn calc<M: Memory>(
+        state: &mut State<M>,
+        parameters: ParamModeDispenser,
+    ) -> Result<VmEvent, ComputerError>;
 }
 
 struct Add;
 impl Instruction for Add {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let op1 = state.get_value(parameters.next())?;
         let op2 = state.get_value(parameters.next())?;
         let target = state.get_address(parameters.next())?;
 
-        state.set_value(target, op1 + op2);
-        Ok(StepResult::Continue)
+        state.set_value(target, state.arithmetic().add(op1, op2)?);
+        Ok(VmEvent::Continue)
     }
 }
 
 struct Mul;
 impl Instruction for Mul {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let op1 = state.get_value(parameters.next())?;
         let op2 = state.get_value(parameters.next())?;
         let target = state.get_address(parameters.next())?;
 
-        state.set_value(target, op1 * op2);
-        Ok(StepResult::Continue)
+        state.set_value(target, state.arithmetic().mul(op1, op2)?);
+        Ok(VmEvent::Continue)
     }
 }
 
 struct Stop;
 impl Instruction for Stop {
-    fn calc(
-        _state: &mut State,
+    fn calc<M: Memory>(
+        _state: &mut State<M>,
         _parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
-        Ok(StepResult::Halted)
+    ) -> Result<VmEvent, ComputerError> {
+        Ok(VmEvent::Halted)
     }
 }
 
 struct Input;
 impl Instruction for Input {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         if let Some(value) = state.get_input() {
             let target = state.get_address(parameters.next())?;
             state.set_value(target, value);
-            Ok(StepResult::Continue)
+            Ok(VmEvent::Continue)
         } else {
             state.repeat();
-            Ok(StepResult::Waiting)
+            Ok(VmEvent::NeedsInput)
         }
     }
 }
 
 struct Output;
 impl Instruction for Output {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let op1 = state.get_value(parameters.next())?;
-        Ok(StepResult::Output(op1))
+        Ok(VmEvent::Output(op1))
     }
 }
 
 struct JumpIfTrue;
 impl Instruction for JumpIfTrue {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let test = state.get_value(parameters.next())?;
         let target = state.get_value(parameters.next())?;
         if test != 0 {
             state.set_pointer(Pointer::from_i64(target)?);
         }
-        Ok(StepResult::Continue)
+        Ok(VmEvent::Continue)
     }
 }
 
 struct JumpIfFalse;
 impl Instruction for JumpIfFalse {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let test = state.get_value(parameters.next())?;
         let target = state.get_value(parameters.next())?;
         if test == 0 {
             state.set_pointer(Pointer::from_i64(target)?);
         }
-        Ok(StepResult::Continue)
+        Ok(VmEvent::Continue)
     }
 }
 
 struct LessThan;
 impl Instruction for LessThan {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let op1 = state.get_value(parameters.next())?;
         let op2 = state.get_value(parameters.next())?;
         let target = state.get_address(parameters.next())?;
 
         let result = if op1 < op2 { 1 } else { 0 };
         state.set_value(target, result);
-        Ok(StepResult::Continue)
+        Ok(VmEvent::Continue)
     }
 }
 
 struct Equals;
 impl Instruction for Equals {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let op1 = state.get_value(parameters.next())?;
         let op2 = state.get_value(parameters.next())?;
         let target = state.get_address(parameters.next())?;
 
         let result = if op1 == op2 { 1 } else { 0 };
         state.set_value(target, result);
-        Ok(StepResult::Continue)
+        Ok(VmEvent::Continue)
     }
 }
 
 struct RelativeBase;
 impl Instruction for RelativeBase {
-    fn calc(
-        state: &mut State,
+    fn calc<M: Memory>(
+        state: &mut State<M>,
         parameters: ParamModeDispenser,
-    ) -> Result<StepResult, ComputerError> {
+    ) -> Result<VmEvent, ComputerError> {
         let op1 = state.get_value(parameters.next())?;
 
-        state.adjust_relative_base(op1);
+        state.adjust_relative_base(op1)?;
 
-        Ok(StepResult::Continue)
+        Ok(VmEvent::Continue)
     }
 }
@@ -1,35 +1,482 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, sync::Arc};
 
-use super::{computer_error::ComputerError, state::State, Pointer, StepResult};
+use super::{
+    builder::ComputerBuilder,
+    checkpoints::Checkpoints,
+    compiled::ExecutionMode,
+    computer_error::ComputerError,
+    coverage::{Coverage, CoverageReport},
+    decode::FromOutputs,
+    input::{InputHandle, InputSource},
+    instructions::Arithmetic,
+    limits::Limits,
+    memory::{Memory, SharedMemory},
+    metrics::Metrics,
+    observer::Observer,
+    output_buffer::{Fill, OutputBuffer},
+    patch::{self, Patch},
+    profile::{CostModel, Profile},
+    self_modification::{ModificationReport, SelfModification},
+    session::Session,
+    snapshot::Snapshot,
+    state::State,
+    trace::{TraceEntry, TraceSink},
+    watch::{Breakpoints, Watch, Watchpoints},
+    Pointer, VmEvent,
+};
 use itertools::{Either, Itertools};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-pub struct IntCodeComputer {
-    init_memory: Vec<i64>,
-    state: State,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Poll {
+    Output(i64),
+    NeedsInput,
+    Halted,
+}
+
+/**
+ * Runs an IntCode program. The memory backend can be chosen, see
+ * `ComputerFactory::build_with`, everything else works the same with
+ * every backend.
+ */
+pub struct IntCodeComputer<M: Memory = SharedMemory> {
+    init_memory: Arc<[i64]>,
+    state: State<M>,
     peeked: VecDeque<i64>,
 }
 
-impl IntCodeComputer {
-    fn new(memory: &[i64]) -> Self {
+impl<M: Memory> Clone for IntCodeComputer<M> {
+    /** The copy only publishes metrics for what it does itself */
+    fn clone(&self) -> Self {
+        let mut state = self.state.clone();
+        state.mark_published();
+        Self {
+            init_memory: Arc::clone(&self.init_memory),
+            state,
+            peeked: self.peeked.clone(),
+        }
+    }
+}
+
+impl<M: Memory> Drop for IntCodeComputer<M> {
+    /** Computers that never halt, like most droids, are counted as well */
+    fn drop(&mut self) {
+        self.state.publish_metrics();
+    }
+}
+
+impl<M: Memory> IntCodeComputer<M> {
+    fn new(memory: Arc<[i64]>) -> Self {
         Self {
-            init_memory: Vec::from(memory),
-            state: State::new(memory),
+            state: State::new(Arc::clone(&memory)),
+            init_memory: memory,
             peeked: VecDeque::new(),
         }
     }
 
     pub fn reset(&mut self) {
-        self.state = State::new(&self.init_memory);
+        self.state.publish_metrics();
+        let profile = self.state.take_profile();
+        let coverage = self.state.take_coverage();
+        let source = self.state.take_input_source();
+        let limits = self.state.limits();
+        let watch = self.state.take_watch();
+        let breakpoints = self.state.take_breakpoints();
+        let compiled = self.state.take_compiled();
+        let arithmetic = self.state.arithmetic();
+        let checkpoints = self.state.take_checkpoints();
+        let modification = self.state.take_modification();
+        self.state = State::new(Arc::clone(&self.init_memory));
+        self.state
+            .set_checkpoints(checkpoints.map(Checkpoints::restart));
+        self.state.set_compiled(compiled);
+        self.state.set_arithmetic(arithmetic);
+        self.state.set_limits(limits);
+        self.state.set_watch(watch.map(Watchpoints::restart));
+        self.state
+            .set_breakpoints(breakpoints.map(Breakpoints::restart));
+        self.state.set_profile(profile);
+        self.state.set_coverage(coverage);
+        self.state
+            .set_modification(modification.map(SelfModification::restart));
+        self.state.set_input_source(source);
         self.peeked.clear();
     }
 
     fn run(&mut self) -> Result<Option<i64>, ComputerError> {
         loop {
-            match self.state.next_instruction()? {
-                StepResult::Continue => {}
-                StepResult::Output(value) => return Ok(Some(value)),
-                StepResult::Halted => return Ok(None),
-                StepResult::Waiting => return Err(ComputerError::WaitingForInput),
+            match self.state.next_block()? {
+                VmEvent::Continue | VmEvent::Breakpoint(_) | VmEvent::Watchpoint(_) => {}
+                VmEvent::Output(value) => return Ok(Some(value)),
+                VmEvent::Halted => return Ok(None),
+                VmEvent::NeedsInput => return Err(ComputerError::WaitingForInput),
+            }
+        }
+    }
+
+    /**
+     * Executes a single instruction. Outputs the string readers peeked at
+     * come first, one per call, without executing anything.
+     */
+    pub fn step(&mut self) -> Result<VmEvent, ComputerError> {
+        if let Some(peeked) = self.peeked.pop_front() {
+            return Ok(VmEvent::Output(peeked));
+        }
+        self.state.next_instruction()
+    }
+
+    /**
+     * In compiled mode, the computer decodes the program only once, which
+     * pays off for programs that loop a lot or are reset again and again.
+     * Compiled code that the program overwrites is compiled again. Only the
+     * speed differs, `step` and any tracing, profiling, watching or
+     * recording even work exactly like before.
+     */
+    #[allow(dead_code)]
+    pub fn set_execution_mode(&mut self, mode: ExecutionMode) {
+        self.state.set_execution_mode(mode);
+    }
+
+    /**
+     * With checked arithmetic, an `ADD` or `MUL` whose result does not fit
+     * into 64 bits stops the program with `ComputerError::Overflow` instead
+     * of leaving a wrapped result behind. Kept when the computer is reset.
+     * Computers start with the one set by `set_default_arithmetic`.
+     */
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.state.set_arithmetic(arithmetic);
+    }
+
+    /**
+     * The address of the next instruction to execute. While waiting for
+     * input, this is the input instruction.
+     */
+    pub fn pointer(&self) -> Pointer {
+        self.state.pointer()
+    }
+
+    #[allow(dead_code)]
+    pub fn relative_base(&self) -> i64 {
+        self.state.relative_base()
+    }
+
+    /**
+     * From now on `step` reports accesses to the address with `VmEvent::Watchpoint`.
+     * All other ways of running the computer do not stop there. Watching an
+     * address again replaces what was watched. Watchpoints survive a reset.
+     */
+    #[allow(dead_code)]
+    pub fn add_watchpoint(&mut self, address: Pointer, watch: Watch) {
+        self.state.watch(address, watch);
+    }
+
+    #[allow(dead_code)]
+    pub fn remove_watchpoint(&mut self, address: Pointer) {
+        self.state.unwatch(address);
+    }
+
+    /**
+     * From now on `step` stops in front of the instruction at the address
+     * with `VmEvent::Breakpoint`, once every time it gets there. Like
+     * watchpoints, only `step` stops, and breakpoints survive a reset.
+     */
+    #[allow(dead_code)]
+    pub fn add_breakpoint(&mut self, address: Pointer) {
+        self.state.set_breakpoint(address);
+    }
+
+    #[allow(dead_code)]
+    pub fn remove_breakpoint(&mut self, address: Pointer) {
+        self.state.remove_breakpoint(address);
+    }
+
+    /**
+     * Copies memory and registers, to compare them with a later snapshot
+     * using `Snapshot::diff`, or to come back to with `restore`.
+     */
+    pub fn snapshot(&self) -> Snapshot<M> {
+        self.state.snapshot()
+    }
+
+    /**
+     * Keeps a snapshot every `interval` instructions, the latest `keep` of
+     * them, and all input read since the oldest, so `travel_to` can go back.
+     * Going back replays from a snapshot, so tracing, profiling and the like
+     * see those instructions once more. After a reset the computer keeps
+     * taking snapshots, but those of the earlier run are gone.
+     */
+    #[allow(dead_code)]
+    pub fn enable_time_travel(&mut self, interval: u64, keep: usize) {
+        self.state.enable_checkpoints(interval, keep);
+    }
+
+    /** Instructions executed since the computer was built or reset */
+    #[allow(dead_code)]
+    pub fn executed(&self) -> u64 {
+        self.state.executed()
+    }
+
+    /**
+     * Instructions executed, inputs read, outputs made and memory used
+     * since the computer was built or reset. Computers also publish these
+     * with `Event::ComputerMetrics` when they halt, are reset or dropped.
+     */
+    #[allow(dead_code)]
+    pub fn metrics(&self) -> Metrics {
+        self.state.metrics()
+    }
+
+    /**
+     * Goes back to the moment after `executed` instructions, false if that
+     * lies ahead or before the oldest snapshot kept. Input read since is
+     * read again, outputs since are made again.
+     */
+    #[allow(dead_code)]
+    pub fn travel_to(&mut self, executed: u64) -> Result<bool, ComputerError> {
+        if executed > self.state.executed() {
+            return Ok(false);
+        }
+        let Some((snapshot, inputs)) = self
+            .state
+            .checkpoints_mut()
+            .and_then(|checkpoints| checkpoints.rewind(executed))
+        else {
+            return Ok(false);
+        };
+        let pending = self.state.take_input_buffer();
+        self.state.restore(&snapshot);
+        for value in inputs.into_iter().chain(pending) {
+            self.state.push_input(value);
+        }
+        self.peeked.clear();
+        while self.state.executed() < executed {
+            if self.state.next_instruction()? == VmEvent::NeedsInput {
+                return Err(ComputerError::WaitingForInput);
+            }
+        }
+        Ok(true)
+    }
+
+    /** Goes back one instruction, see `travel_to` */
+    #[allow(dead_code)]
+    pub fn step_back(&mut self) -> Result<bool, ComputerError> {
+        match self.state.executed().checked_sub(1) {
+            Some(executed) => self.travel_to(executed),
+            None => Ok(false),
+        }
+    }
+
+    /**
+     * Continues from a snapshot of this computer, to try something else from
+     * there. Pending input and outputs put back are dropped, limits, profile,
+     * watchpoints and the like stay as they are.
+     */
+    pub fn restore(&mut self, snapshot: &Snapshot<M>) {
+        self.state.restore(snapshot);
+        self.peeked.clear();
+    }
+
+    pub(super) fn memory_cells(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        self.state.memory().cells()
+    }
+
+    /**
+     * Stops the program with an error once it executed `max_steps` instructions
+     * or ran longer than the timeout, both counted from now. A stopped program
+     * continues where it was when the limits are raised.
+     * Computers start with the default limits, see `set_default_limits`.
+     */
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.state.set_limits(limits);
+    }
+
+    /**
+     * Runs until the program halts, but at most `max_steps` more instructions.
+     * The limit stays in place afterwards.
+     */
+    #[allow(dead_code)]
+    pub fn run_with_limit(&mut self, max_steps: u64) -> Result<(), ComputerError> {
+        self.set_limits(Limits {
+            max_steps: Some(max_steps),
+            ..self.state.limits()
+        });
+        self.run_till_halt()
+    }
+
+    /**
+     * The number of instructions executed since the limits were last set.
+     */
+    #[allow(dead_code)]
+    pub fn steps(&self) -> u64 {
+        self.state.steps()
+    }
+
+    /**
+     * Consulted whenever the program wants input and nothing was sent.
+     * The source stays installed when the computer is reset, and clones
+     * of the computer share it.
+     */
+    #[allow(dead_code)]
+    pub fn set_input_source(&mut self, source: impl InputSource + 'static) {
+        self.state.set_input_source(Some(InputHandle::new(source)));
+    }
+
+    /**
+     * Counts executed instructions per opcode and address from now on.
+     * Unlike the trace, the counts are kept when the computer is reset.
+     */
+    #[allow(dead_code)]
+    pub fn enable_profile(&mut self) {
+        self.state.enable_profile();
+    }
+
+    #[allow(dead_code)]
+    pub fn profile(&self) -> Option<&Profile> {
+        self.state.profile()
+    }
+
+    /**
+     * Records which words run as part of an instruction from now on. Like
+     * the profile, the coverage is kept when the computer is reset.
+     */
+    #[allow(dead_code)]
+    pub fn enable_coverage(&mut self) {
+        self.state.enable_coverage();
+    }
+
+    #[allow(dead_code)]
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.state.coverage()
+    }
+
+    /** The coverage of the program this computer was built with */
+    #[allow(dead_code)]
+    pub fn coverage_report(&self) -> Option<CoverageReport> {
+        Some(self.state.coverage()?.report(self.init_memory.len()))
+    }
+
+    /**
+     * Records from now on which words the program writes and later executes,
+     * to find out where it rewrites its own code. What was found is kept
+     * when the computer is reset.
+     */
+    #[allow(dead_code)]
+    pub fn enable_modification_tracking(&mut self) {
+        self.state.enable_modification_tracking();
+    }
+
+    #[allow(dead_code)]
+    pub fn modification(&self) -> Option<&SelfModification> {
+        self.state.modification()
+    }
+
+    #[allow(dead_code)]
+    pub fn modification_report(&self) -> Option<ModificationReport> {
+        Some(self.state.modification()?.report())
+    }
+
+    /**
+     * Enables the profile, which from now on counts cycles with these costs.
+     */
+    #[allow(dead_code)]
+    pub fn set_cost_model(&mut self, costs: CostModel) {
+        let mut profile = self.state.take_profile().unwrap_or_default();
+        profile.set_cost_model(costs);
+        self.state.set_profile(Some(profile));
+    }
+
+    /**
+     * Records every value the program reads or writes from now on, so the
+     * session can be saved and replayed later. Like the trace, the recording
+     * ends when the computer is reset.
+     */
+    #[allow(dead_code)]
+    pub fn start_recording(&mut self) {
+        self.state.start_recording();
+    }
+
+    /**
+     * Stops recording and returns what was recorded.
+     */
+    #[allow(dead_code)]
+    pub fn take_recording(&mut self) -> Option<Session> {
+        self.state.take_recording()
+    }
+
+    /**
+     * Records every executed instruction from now on, see `trace` for the format.
+     */
+    #[allow(dead_code)]
+    pub fn enable_trace(&mut self) {
+        self.state.enable_trace();
+    }
+
+    /**
+     * Like `enable_trace`, but hands every entry to the callback as soon as the
+     * instruction finished instead of keeping it.
+     */
+    #[allow(dead_code)]
+    pub fn stream_trace(&mut self, callback: impl FnMut(&TraceEntry) + Send + 'static) {
+        self.state.stream_trace(TraceSink::new(callback));
+    }
+
+    #[allow(dead_code)]
+    pub fn take_trace(&mut self) -> Vec<TraceEntry> {
+        self.state.take_trace()
+    }
+
+    /**
+     * Runs until the next output, until the computer halts or until it waits for input.
+     * Unlike the other receiving methods, missing input is not an error here.
+     * Polling again after sending input continues where it stopped.
+     */
+    pub fn poll(&mut self) -> Result<Poll, ComputerError> {
+        if let Some(peeked) = self.peeked.pop_front() {
+            return Ok(Poll::Output(peeked));
+        }
+        loop {
+            match self.state.next_block()? {
+                VmEvent::Continue | VmEvent::Breakpoint(_) | VmEvent::Watchpoint(_) => {}
+                VmEvent::Output(value) => return Ok(Poll::Output(value)),
+                VmEvent::NeedsInput => return Ok(Poll::NeedsInput),
+                VmEvent::Halted => return Ok(Poll::Halted),
+            }
+        }
+    }
+
+    /**
+     * Like `poll`, but collects outputs into `buffer` until it reaches its
+     * high-water mark. Then the computer stops until the buffer is drained,
+     * instead of running ahead of whoever consumes the outputs.
+     */
+    pub fn fill(&mut self, buffer: &mut OutputBuffer) -> Result<Fill, ComputerError> {
+        while !buffer.is_full() {
+            match self.poll()? {
+                Poll::Output(value) => buffer.push(value),
+                Poll::NeedsInput => return Ok(Fill::NeedsInput),
+                Poll::Halted => return Ok(Fill::Halted),
+            }
+        }
+        Ok(Fill::NeedsDrain)
+    }
+
+    /**
+     * Runs the program and hands every event to the observer.
+     * Returns whether the program halted, which is not the case when the
+     * observer had no input to give.
+     */
+    pub fn run_observed<O: Observer>(&mut self, observer: &mut O) -> Result<bool, O::Error> {
+        loop {
+            match self.poll()? {
+                Poll::Output(value) => observer.on_output(value)?,
+                Poll::NeedsInput => match observer.on_input_request_from(self)? {
+                    Some(value) => self.send_i64(value),
+                    None => return Ok(false),
+                },
+                Poll::Halted => {
+                    observer.on_halt()?;
+                    return Ok(true);
+                }
             }
         }
     }
@@ -43,16 +490,16 @@ impl IntCodeComputer {
     }
 
     pub fn as_iter(&mut self) -> impl Iterator<Item = Result<i64, ComputerError>> + '_ {
-        struct BlockingRunner<'b>(&'b mut IntCodeComputer);
+        struct BlockingRunner<'b, M: Memory>(&'b mut IntCodeComputer<M>);
 
-        impl<'a> BlockingRunner<'a> {
+        impl<'a, M: Memory> BlockingRunner<'a, M> {
             #[inline]
-            pub fn new(computer: &'a mut IntCodeComputer) -> BlockingRunner<'a> {
+            pub fn new(computer: &'a mut IntCodeComputer<M>) -> BlockingRunner<'a, M> {
                 Self(computer)
             }
         }
 
-        impl<'a> Iterator for BlockingRunner<'a> {
+        impl<M: Memory> Iterator for BlockingRunner<'_, M> {
             type Item = Result<i64, ComputerError>;
 
             fn next(&mut self) -> Option<Self::Item> {
@@ -74,6 +521,7 @@ impl IntCodeComputer {
     }
 
     #[inline]
+    #[allow(dead_code)]
     pub fn send_bool(&mut self, input: bool) {
         self.send_i64(if input { 1 } else { 0 })
     }
@@ -108,6 +556,7 @@ impl IntCodeComputer {
     }
 
     #[inline]
+    #[allow(dead_code)]
     pub fn maybe_i64(&mut self) -> Result<Option<i64>, ComputerError> {
         self.receive_next()
     }
@@ -122,24 +571,84 @@ impl IntCodeComputer {
     }
 
     #[inline]
+    #[allow(dead_code)]
     pub fn maybe_bool(&mut self) -> Result<Option<bool>, ComputerError> {
         Ok(self.receive_next()?.map(|value| value != 0))
     }
 
-    #[inline]
-    pub fn maybe_take_exactly(&mut self, n: usize) -> Result<Option<Vec<i64>>, ComputerError> {
-        let result: Vec<i64> = self.as_iter().take(n).try_collect()?;
-        if result.len() != n {
-            Ok(None)
-        } else {
-            Ok(Some(result))
+    /**
+     * The outputs in chunks of `len`, run for one at a time. Ends when the
+     * program halts right after a chunk. If it halts within one, the values
+     * it got come as an `IncompleteChunk` error, the last item. Any other
+     * error, like waiting for input, puts them back to be read again.
+     */
+    pub fn outputs_chunked(
+        &mut self,
+        len: usize,
+    ) -> impl Iterator<Item = Result<Vec<i64>, ComputerError>> + '_ {
+        assert!(len > 0, "chunks must not be empty");
+        let mut ended = false;
+        std::iter::from_fn(move || {
+            if ended {
+                return None;
+            }
+            let mut chunk = Vec::with_capacity(len);
+            while chunk.len() < len {
+                match self.receive_next() {
+                    Ok(Some(value)) => chunk.push(value),
+                    Ok(None) => {
+                        ended = true;
+                        return (!chunk.is_empty())
+                            .then_some(Err(ComputerError::IncompleteChunk(chunk, len)));
+                    }
+                    Err(err) => {
+                        ended = true;
+                        chunk.into_iter().rev().for_each(|value| self.unread(value));
+                        return Some(Err(err));
+                    }
+                }
+            }
+            Some(Ok(chunk))
+        })
+    }
+
+    /**
+     * Reads the next `T::LEN` outputs as one value, `None` if the program
+     * halted before the first of them. Halting in between is an error.
+     */
+    pub fn read<T: FromOutputs>(&mut self) -> Result<Option<T>, ComputerError> {
+        match self.outputs_chunked(T::LEN).next().transpose() {
+            Ok(values) => values.map(|values| T::decode(&values)).transpose(),
+            Err(ComputerError::IncompleteChunk(..)) => Err(ComputerError::PrematureEndOfOutput),
+            Err(err) => Err(err),
+        }
+    }
+
+    /**
+     * The next output without consuming it, `None` if the program halts
+     * first. Runs the program up to that output if it was not read yet.
+     */
+    #[allow(dead_code)]
+    pub fn peek_i64(&mut self) -> Result<Option<i64>, ComputerError> {
+        if let Some(peeked) = self.peeked.front() {
+            return Ok(Some(*peeked));
+        }
+        let next = self.run()?;
+        if let Some(value) = next {
+            self.peeked.push_back(value);
         }
+        Ok(next)
     }
 
-    fn push_peeked(&mut self, value: i64) {
-        self.peeked.push_back(value);
+    /**
+     * Puts a value back in front of the outputs, it is the next one read.
+     * Values unread one after the other come back in reverse order.
+     */
+    pub fn unread(&mut self, value: i64) {
+        self.peeked.push_front(value);
     }
 
+    #[allow(dead_code)]
     pub fn maybe_string_or_i64(&mut self) -> Result<Option<Either<i64, String>>, ComputerError> {
         if let Some(string) = self.maybe_string()? {
             Ok(Some(Either::Right(string)))
@@ -183,7 +692,7 @@ impl IntCodeComputer {
         }
 
         if let Some(peeked) = peeked {
-            self.push_peeked(peeked);
+            self.unread(peeked);
             Ok(None)
         } else if !got_string_data {
             Ok(None)
@@ -191,24 +700,21 @@ impl IntCodeComputer {
             Ok(Some(string))
         }
     }
-
-    pub fn expect_string_(&mut self) -> Result<String, ComputerError> {
-        if let Some(string) = self.maybe_string()? {
-            Ok(string)
-        } else {
-            Err(ComputerError::PrematureEndOfOutput)
-        }
-    }
 }
 
+/**
+ * All computers a factory builds share its program, they only copy the
+ * parts they write to.
+ */
+#[derive(Clone)]
 pub struct ComputerFactory {
-    data: Vec<i64>,
+    data: Arc<[i64]>,
 }
 
 impl ComputerFactory {
     #[inline]
     pub fn new(data: Vec<i64>) -> Self {
-        Self { data }
+        Self { data: data.into() }
     }
 
     pub fn init(input: &str) -> Result<Self, ComputerError> {
@@ -219,13 +725,554 @@ impl ComputerFactory {
         Ok(Self::new(data))
     }
 
+    /** The program every computer starts with */
+    #[inline]
+    pub fn program(&self) -> &[i64] {
+        &self.data
+    }
+
+    /** Every address where the sequence of values starts */
+    #[allow(dead_code)]
+    pub fn find(&self, sequence: &[i64]) -> Vec<Pointer> {
+        patch::find(&self.data, sequence)
+    }
+
+    /**
+     * Starts changing a copy of the program, like inserting quarters by
+     * setting address 0 to 2. The factory itself stays as it is.
+     */
+    pub fn patch(&self) -> Patch {
+        Patch::new(self.data.to_vec())
+    }
+
+    /**
+     * Sets up input, memory and settings of a computer before building it,
+     * instead of changing it one call at a time afterwards.
+     */
+    pub fn builder(&self) -> ComputerBuilder<'_> {
+        ComputerBuilder::new(self)
+    }
+
     /**
      * Creates an IntCodeComputer.
-     * This version must never wait for Input,
-     * i.e. the Input must be pushed before it is requested by this IntCodeComputer
-     * otherwise it will return an error
+     * Input is either sent before the program requests it or comes from an
+     * input source, see `IntCodeComputer::set_input_source`. Otherwise the
+     * receiving methods report the waiting as an error, only `poll` does not.
      */
     pub fn build(&self) -> IntCodeComputer {
-        IntCodeComputer::new(&self.data)
+        self.build_with()
+    }
+
+    /**
+     * Like `build`, with a memory backend other than the shared program.
+     */
+    pub fn build_with<M: Memory>(&self) -> IntCodeComputer<M> {
+        IntCodeComputer::new(Arc::clone(&self.data))
+    }
+
+    /**
+     * Runs the program once for every input, spread over all cores, and
+     * returns what `extractor` made of each run, in the order of the inputs.
+     * Every run starts on a freshly reset computer. Computers are reused by
+     * the runs on the same thread, so settings like the execution mode stick.
+     */
+    pub fn sweep<I, R, F>(&self, inputs: impl IntoIterator<Item = I>, extractor: F) -> Vec<R>
+    where
+        I: Send,
+        R: Send,
+        F: Fn(&mut IntCodeComputer, I) -> R + Sync,
+    {
+        inputs
+            .into_iter()
+            .collect_vec()
+            .into_par_iter()
+            .map_init(
+                || self.build(),
+                |computer, input| {
+                    computer.reset();
+                    extractor(computer, input)
+                },
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::{read_string, UnitResult},
+        int_code::{
+            assembler::assemble,
+            trace::Mnemonic,
+            watch::{Access, WatchHit},
+        },
+    };
+
+    fn outputs(code: Vec<i64>, input: &[i64]) -> Result<Vec<i64>, ComputerError> {
+        let mut computer = ComputerFactory::new(code).build();
+        for value in input {
+            computer.send_i64(*value);
+        }
+        computer.as_iter().try_collect()
+    }
+
+    #[test]
+    fn day09_examples() -> UnitResult {
+        let quine = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        assert_eq!(outputs(quine.clone(), &[])?, quine);
+
+        let result = outputs(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[])?;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].to_string().len(), 16);
+
+        let large = 1125899906842624;
+        assert_eq!(outputs(vec![104, large, 99], &[])?, vec![large]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn relative_mode() -> UnitResult {
+        // read into relative address, adjust base backwards, output relatively
+        let code = vec![109, 20, 203, 0, 109, -10, 204, 10, 99];
+        assert_eq!(outputs(code, &[42])?, vec![42]);
+
+        // relative base taken from memory in position mode, add in relative mode
+        let code = assemble(
+            "
+                    RB base
+                    ADD @0, @1, @2
+                    OUT @2
+                    HALT
+            base:   DATA 100
+            ",
+        )?;
+        assert_eq!(code, vec![9, 9, 22201, 0, 1, 2, 204, 2, 99, 100]);
+        let mut computer = ComputerFactory::new(code).build();
+        computer.manipulate_memory(Pointer::from_i64(100).unwrap(), 5);
+        computer.manipulate_memory(Pointer::from_i64(101).unwrap(), 7);
+        assert_eq!(computer.expect_i64()?, 12);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stays_halted() -> UnitResult {
+        let mut computer = ComputerFactory::new(vec![104, 7, 99]).build();
+        assert_eq!(computer.expect_i64()?, 7);
+        assert_eq!(computer.maybe_i64()?, None);
+        assert_eq!(computer.maybe_i64()?, None);
+
+        computer.reset();
+        assert_eq!(computer.expect_i64()?, 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn poll() -> UnitResult {
+        let code = assemble(
+            "
+                    IN value
+                    OUT value
+                    HALT
+            value:  DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(code).build();
+        assert_eq!(computer.poll()?, Poll::NeedsInput);
+        assert_eq!(computer.poll()?, Poll::NeedsInput);
+        computer.send_i64(5);
+        assert_eq!(computer.poll()?, Poll::Output(5));
+        assert_eq!(computer.poll()?, Poll::Halted);
+        assert_eq!(computer.poll()?, Poll::Halted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn input_source() -> UnitResult {
+        let code = assemble("loop: IN value\nOUT value\nJT #1, #loop\nvalue: DATA 0")?;
+        let mut computer = ComputerFactory::new(code.clone()).build();
+        let mut next = 0;
+        computer.set_input_source(move || {
+            next += 1;
+            (next <= 3).then_some(next)
+        });
+        assert_eq!(computer.expect_i64()?, 1);
+        assert_eq!(computer.expect_i64()?, 2);
+        computer.send_i64(10);
+        assert_eq!(computer.expect_i64()?, 10);
+        assert_eq!(computer.expect_i64()?, 3);
+        assert!(matches!(
+            computer.expect_i64(),
+            Err(ComputerError::WaitingForInput)
+        ));
+        computer.send_i64(4);
+        assert_eq!(computer.expect_i64()?, 4);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut computer = ComputerFactory::new(code).build();
+        computer.set_input_source(receiver);
+        std::thread::spawn(move || [7, 8].into_iter().for_each(|v| sender.send(v).unwrap()));
+        assert_eq!(computer.expect_i64()?, 7);
+        assert_eq!(computer.expect_i64()?, 8);
+        assert_eq!(computer.poll()?, Poll::NeedsInput);
+
+        Ok(())
+    }
+
+    #[test]
+    fn crash_context() {
+        let mut computer = ComputerFactory::new(vec![1101, 1, 2, 7, 104, 3, 98, 0]).build();
+        assert_eq!(computer.expect_i64().unwrap(), 3);
+        let err = computer.expect_i64().unwrap_err();
+        assert!(matches!(err.cause(), ComputerError::IllegalOperation(98)));
+
+        let ComputerError::Crashed(crash) = err else {
+            panic!("no context for {err:?}");
+        };
+        assert_eq!(crash.pointer, Pointer::new(6));
+        assert_eq!(crash.instruction, 98);
+        assert_eq!(
+            crash.report(),
+            "IllegalOperation: 98 at 6 (instruction 98), last instructions:\n  \
+             000000 1101 ADD\n  \
+             000004 104 OUT\n  \
+             000006 98 ILL"
+        );
+        assert!(matches!(
+            computer.expect_i64(),
+            Err(ComputerError::StoppedAfterError)
+        ));
+    }
+
+    #[test]
+    fn limits() -> UnitResult {
+        let code = assemble("loop: ADD count, #1, count\nJT #1, #loop\ncount: DATA 0")?;
+        let mut computer = ComputerFactory::new(code).build();
+        assert!(matches!(
+            computer.run_with_limit(100),
+            Err(ComputerError::StepLimitExceeded(100))
+        ));
+        assert_eq!(computer.get_memory_value(Pointer::new(7)), 50);
+
+        computer.reset();
+        assert_eq!(computer.steps(), 0);
+        assert!(matches!(
+            computer.run_till_halt(),
+            Err(ComputerError::StepLimitExceeded(100))
+        ));
+
+        computer.set_limits(Limits {
+            max_steps: Some(10),
+            ..Default::default()
+        });
+        assert!(computer.run_till_halt().is_err());
+        assert_eq!(computer.get_memory_value(Pointer::new(7)), 55);
+
+        computer.set_limits(Limits {
+            timeout: Some(std::time::Duration::from_millis(10)),
+            ..Default::default()
+        });
+        assert!(matches!(
+            computer.run_till_halt(),
+            Err(ComputerError::TimedOut(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn watchpoints() -> UnitResult {
+        let code = assemble(
+            "
+                    ADD score, #5, score
+                    OUT score
+                    ADD other, #1, other
+                    OUT #7
+                    HALT
+            score:  DATA 0
+            other:  DATA 0
+            ",
+        )?;
+        let score = Pointer::new(13);
+        let mut computer = ComputerFactory::new(code).build();
+        computer.add_watchpoint(score, Watch::All);
+        let hit = |access, value| {
+            VmEvent::Watchpoint(WatchHit {
+                address: score,
+                access,
+                value,
+            })
+        };
+
+        assert_eq!(computer.step()?, hit(Access::Read, 0));
+        assert_eq!(computer.step()?, hit(Access::Read, 5));
+        assert_eq!(computer.step()?, VmEvent::Output(5));
+        assert_eq!(computer.pointer(), Pointer::new(6));
+        assert_eq!(computer.step()?, VmEvent::Continue);
+
+        computer.add_watchpoint(score, Watch::Writes);
+        computer.reset();
+        assert_eq!(computer.step()?, hit(Access::Write, 5));
+        assert_eq!(computer.step()?, VmEvent::Output(5));
+
+        computer.reset();
+        assert_eq!(
+            computer.as_iter().try_collect::<_, Vec<_>, _>()?,
+            vec![5, 7]
+        );
+        computer.remove_watchpoint(score);
+        computer.reset();
+        assert_eq!(computer.step()?, VmEvent::Continue);
+
+        Ok(())
+    }
+
+    #[test]
+    fn breakpoints() -> UnitResult {
+        // Echoes its input until it reads a zero
+        let code = assemble(
+            "
+            loop:   IN value
+                    OUT value
+                    JT value, #loop
+                    HALT
+            value:  DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(code).build();
+        computer.add_breakpoint(Pointer::new(0));
+        computer.add_breakpoint(Pointer::new(2));
+
+        assert_eq!(computer.step()?, VmEvent::Breakpoint(Pointer::new(0)));
+        assert_eq!(computer.step()?, VmEvent::NeedsInput);
+        computer.send_i64(3);
+        assert_eq!(computer.step()?, VmEvent::Continue);
+        assert_eq!(computer.step()?, VmEvent::Breakpoint(Pointer::new(2)));
+        assert_eq!(computer.step()?, VmEvent::Output(3));
+        assert_eq!(computer.step()?, VmEvent::Continue);
+        assert_eq!(computer.step()?, VmEvent::Breakpoint(Pointer::new(0)));
+        assert_eq!(computer.pointer(), Pointer::new(0));
+
+        computer.reset();
+        computer.send_i64(4);
+        computer.send_i64(0);
+        assert_eq!(
+            computer.as_iter().try_collect::<_, Vec<_>, _>()?,
+            vec![4, 0]
+        );
+        computer.remove_breakpoint(Pointer::new(0));
+        computer.reset();
+        assert_eq!(computer.step()?, VmEvent::NeedsInput);
+
+        Ok(())
+    }
+
+    #[test]
+    fn single_steps() -> UnitResult {
+        let code = assemble(
+            "
+                    RB #10
+                    IN value
+                    OUT value
+                    HALT
+            value:  DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(code).build();
+        assert_eq!(computer.step()?, VmEvent::Continue);
+        assert_eq!(computer.relative_base(), 10);
+        assert_eq!(computer.pointer(), Pointer::new(2));
+
+        assert_eq!(computer.step()?, VmEvent::NeedsInput);
+        assert_eq!(computer.pointer(), Pointer::new(2));
+        computer.send_i64(5);
+        assert_eq!(computer.step()?, VmEvent::Continue);
+        assert_eq!(computer.step()?, VmEvent::Output(5));
+        assert_eq!(computer.step()?, VmEvent::Halted);
+        assert_eq!(computer.step()?, VmEvent::Halted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_observed() -> UnitResult {
+        #[derive(Default)]
+        struct Doubler {
+            asked: i64,
+            seen: Vec<i64>,
+            halted: bool,
+        }
+
+        impl Observer for Doubler {
+            type Error = ComputerError;
+
+            fn on_output(&mut self, value: i64) -> Result<(), Self::Error> {
+                self.seen.push(value);
+                Ok(())
+            }
+
+            fn on_input_request(&mut self) -> Result<Option<i64>, Self::Error> {
+                self.asked += 1;
+                Ok((self.asked <= 2).then_some(self.asked))
+            }
+
+            fn on_halt(&mut self) -> Result<(), Self::Error> {
+                self.halted = true;
+                Ok(())
+            }
+        }
+
+        let code = assemble(
+            "
+                    IN value
+                    MUL value, #2, value
+                    OUT value
+                    EQ value, #6, flag
+                    JF flag, #0
+                    HALT
+            value:  DATA 0
+            flag:   DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(code).build();
+        let mut doubler = Doubler::default();
+        assert!(!computer.run_observed(&mut doubler)?);
+        assert_eq!(doubler.seen, vec![2, 4]);
+        assert!(!doubler.halted);
+
+        computer.send_i64(3);
+        assert!(computer.run_observed(&mut doubler)?);
+        assert_eq!(doubler.seen, vec![2, 4, 6]);
+        assert!(doubler.halted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn checked_arithmetic() -> UnitResult {
+        let code = assemble(
+            "
+                    IN value
+                    MUL value, value, value
+                    OUT value
+                    ADD value, #1, value
+                    OUT value
+                    HALT
+            value:  DATA 0
+            ",
+        )?;
+        for mode in [ExecutionMode::Interpreted, ExecutionMode::Compiled] {
+            let mut computer = ComputerFactory::new(code.clone()).build();
+            computer.set_execution_mode(mode);
+            computer.send_i64(1 << 40);
+            assert_eq!(computer.expect_i64()?, 0);
+            assert_eq!(computer.expect_i64()?, 1);
+
+            computer.set_arithmetic(Arithmetic::Checked);
+            computer.reset();
+            computer.send_i64(3_037_000_499);
+            assert_eq!(computer.expect_i64()?, 9_223_372_030_926_249_001);
+            assert_eq!(computer.expect_i64()?, 9_223_372_030_926_249_002);
+
+            computer.reset();
+            computer.send_i64(1 << 40);
+            let err = computer.expect_i64().unwrap_err();
+            assert!(matches!(
+                err.cause(),
+                ComputerError::Overflow(Mnemonic::Mul, 1099511627776, 1099511627776)
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn peek_and_unread() -> UnitResult {
+        let code = assemble(
+            "
+                    OUT #72
+                    OUT #10
+                    OUT #1234
+                    HALT
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(code).build();
+        assert_eq!(computer.peek_i64()?, Some(72));
+        assert_eq!(computer.peek_i64()?, Some(72));
+        assert_eq!(computer.maybe_string()?, Some("H".to_owned()));
+        assert_eq!(computer.maybe_string()?, None);
+        assert_eq!(computer.peek_i64()?, Some(1234));
+
+        computer.unread(2);
+        computer.unread(1);
+        assert_eq!(computer.expect_i64()?, 1);
+        assert_eq!(computer.expect_i64()?, 2);
+        assert_eq!(computer.expect_i64()?, 1234);
+        assert_eq!(computer.peek_i64()?, None);
+        assert_eq!(computer.maybe_i64()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn chunked_outputs() -> UnitResult {
+        let mut computer = ComputerFactory::new(assemble(
+            "
+                    OUT #1
+                    OUT #2
+                    OUT #3
+                    OUT #4
+                    IN 100
+                    OUT 100
+                    OUT #6
+                    HALT
+            ",
+        )?)
+        .build();
+        let mut chunks = computer.outputs_chunked(3);
+        assert_eq!(chunks.next().transpose()?, Some(vec![1, 2, 3]));
+        assert!(matches!(
+            chunks.next(),
+            Some(Err(ComputerError::WaitingForInput))
+        ));
+        assert!(chunks.next().is_none());
+        drop(chunks);
+
+        // The value read before waiting is not lost
+        computer.send_i64(5);
+        let chunk = computer.outputs_chunked(2).next().transpose()?;
+        assert_eq!(chunk, Some(vec![4, 5]));
+        assert_eq!(computer.read::<(i64,)>()?, Some((6,)));
+        assert_eq!(computer.read::<(i64,)>()?, None);
+
+        computer.reset();
+        computer.send_i64(5);
+        let mut chunks = computer.outputs_chunked(4);
+        assert_eq!(chunks.next().transpose()?, Some(vec![1, 2, 3, 4]));
+        let Some(Err(ComputerError::IncompleteChunk(rest, 4))) = chunks.next() else {
+            panic!("the last chunk is not complete");
+        };
+        assert_eq!(rest, vec![5, 6]);
+        assert!(chunks.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn boost_self_test() -> UnitResult {
+        let input = read_string(9, "input.txt")?;
+        let factory = ComputerFactory::init(&input)?;
+        let mut computer = factory.build();
+        computer.send_i64(1);
+        let result: Vec<_> = computer.as_iter().try_collect()?;
+        assert_eq!(result.len(), 1, "malfunctioning opcodes: {result:?}");
+
+        Ok(())
     }
 }
@@ -0,0 +1,102 @@
+#![allow(dead_code)]
+use super::ascii::TranscriptLine;
+use crate::common::events::{self, Event};
+use std::{
+    cell::RefCell,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+/**
+ * Where an `AsciiComputer` logs every line it reads or sends, as it
+ * happens. The transcript keeps them either way, this is for watching a
+ * conversation live or keeping it when a part fails.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IoLog {
+    #[default]
+    Off,
+    /** Printed to stderr, so the lines do not mix with the results */
+    Stderr,
+    /** Published on the event bus, for whoever subscribed */
+    Events,
+    /** Kept on this thread until taken with `take_captured` */
+    Capture,
+}
+
+thread_local! {
+    static CAPTURED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+impl IoLog {
+    pub fn log(&self, line: &TranscriptLine) {
+        match self {
+            IoLog::Off => {}
+            IoLog::Stderr => eprintln!("{line}"),
+            IoLog::Events => events::publish_with(|| Event::AsciiLine {
+                text: line.to_string(),
+            }),
+            IoLog::Capture => capture(line),
+        }
+        if *self != IoLog::Capture && CAPTURE_ALL.load(Ordering::Relaxed) {
+            capture(line);
+        }
+    }
+}
+
+fn capture(line: &TranscriptLine) {
+    CAPTURED.with(|captured| captured.borrow_mut().push(line.to_string()))
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown I/O log: {0} (known: off, stderr, events, capture)")]
+pub struct UnknownIoLog(String);
+
+impl FromStr for IoLog {
+    type Err = UnknownIoLog;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(IoLog::Off),
+            // Still known by the name it had before it moved to stderr
+            "stderr" | "stdout" => Ok(IoLog::Stderr),
+            "events" => Ok(IoLog::Events),
+            "capture" => Ok(IoLog::Capture),
+            _ => Err(UnknownIoLog(s.to_owned())),
+        }
+    }
+}
+
+/**
+ * The lines captured on this thread so far, which are gone afterwards.
+ */
+pub fn take_captured() -> Vec<String> {
+    CAPTURED.with(|captured| captured.take())
+}
+
+static CAPTURE_ALL: AtomicBool = AtomicBool::new(false);
+
+/**
+ * Captures the lines of every `AsciiComputer` on top of whatever log it
+ * has, so a failing part can keep its conversation in the artifacts.
+ */
+pub fn capture_all() {
+    CAPTURE_ALL.store(true, Ordering::Relaxed);
+}
+
+static DEFAULT_IO_LOG: OnceLock<IoLog> = OnceLock::new();
+
+/**
+ * Sets the log every newly made `AsciiComputer` starts with. Can only be
+ * done once, before the first one is made. Returns false if it was too late.
+ */
+pub fn set_default_io_log(log: IoLog) -> bool {
+    DEFAULT_IO_LOG.set(log).is_ok()
+}
+
+pub fn default_io_log() -> IoLog {
+    *DEFAULT_IO_LOG.get_or_init(IoLog::default)
+}
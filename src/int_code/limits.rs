@@ -0,0 +1,81 @@
+use super::ComputerError;
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/**
+ * The deadline is only looked at when these bits of the step count are zero,
+ * asking the clock for every instruction would cost more than the instruction itself.
+ */
+const CLOCK_MASK: u64 = (1 << 12) - 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+static DEFAULT_LIMITS: OnceLock<Limits> = OnceLock::new();
+
+/**
+ * Sets the limits every newly built computer starts with. Can only be done once,
+ * before the first computer is built. Returns false if it was too late.
+ */
+pub fn set_default_limits(limits: Limits) -> bool {
+    DEFAULT_LIMITS.set(limits).is_ok()
+}
+
+pub fn default_limits() -> Limits {
+    *DEFAULT_LIMITS.get_or_init(Limits::default)
+}
+
+/**
+ * Counts the executed instructions against the limits, starting when it was created.
+ */
+#[derive(Debug, Clone)]
+pub(super) struct Budget {
+    limits: Limits,
+    steps: u64,
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            steps: 0,
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    #[inline]
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    #[inline]
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /**
+     * Accounts for one more instruction, unless the budget is used up.
+     */
+    #[inline]
+    pub fn spend(&mut self) -> Result<(), ComputerError> {
+        if let Some(max_steps) = self.limits.max_steps
+            && self.steps >= max_steps
+        {
+            return Err(ComputerError::StepLimitExceeded(self.steps));
+        }
+        if let Some(deadline) = self.deadline
+            && self.steps & CLOCK_MASK == 0
+            && Instant::now() >= deadline
+        {
+            return Err(ComputerError::TimedOut(self.steps));
+        }
+        self.steps += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use super::Pointer;
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+/**
+ * Addresses below this, or below the end of the program if it is longer,
+ * are kept in one growing vector, programs rarely touch anything beyond.
+ * Everything further out goes into a map so a single far write does not
+ * allocate gigabytes.
+ */
+const DENSE_LIMIT: usize = 1 << 16;
+
+/** The written part of a shared memory grows by at least this many words */
+const GROWTH: usize = 1 << 8;
+
+/**
+ * Where a computer keeps its memory. Instructions only read and write
+ * single cells, so a backend may trade speed for size as it likes and
+ * still runs every program.
+ */
+pub trait Memory: Debug + Clone + Send + 'static {
+    /** Holds the program from address 0 on, zero everywhere else */
+    fn new(program: Arc<[i64]>) -> Self;
+
+    fn get(&self, addr: Pointer) -> i64;
+
+    fn set(&mut self, addr: Pointer, value: i64);
+
+    /**
+     * All cells that are not zero, ordered by address.
+     */
+    fn cells(&self) -> impl Iterator<Item = (usize, i64)> + '_;
+}
+
+fn sorted_cells(cells: &HashMap<Pointer, i64>) -> Vec<(usize, i64)> {
+    let mut cells = cells
+        .iter()
+        .map(|(addr, value)| (addr.as_usize(), *value))
+        .collect::<Vec<_>>();
+    cells.sort_unstable();
+    cells
+}
+
+/**
+ * The program is shared by all computers built from it and never written.
+ * Each memory copies it only as far as it writes to it, so building a
+ * computer costs next to nothing. Reads below the copied part are as fast
+ * as with a private copy, reads beyond fall through to the program.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SharedMemory {
+    program: Arc<[i64]>,
+    dense: Vec<i64>,
+    sparse: HashMap<Pointer, i64>,
+}
+
+impl SharedMemory {
+    #[inline]
+    fn dense_limit(&self) -> usize {
+        DENSE_LIMIT.max(self.program.len())
+    }
+
+    /** Copies the program up to at least `len` words, zeros beyond its end */
+    #[cold]
+    fn grow(&mut self, len: usize) {
+        let len = len.next_multiple_of(GROWTH).min(self.dense_limit());
+        let start = self.dense.len().min(self.program.len());
+        let end = len.min(self.program.len());
+        self.dense.extend_from_slice(&self.program[start..end]);
+        self.dense.resize(len, 0);
+    }
+}
+
+impl Memory for SharedMemory {
+    fn new(program: Arc<[i64]>) -> Self {
+        Self {
+            program,
+            dense: Vec::new(),
+            sparse: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    fn get(&self, addr: Pointer) -> i64 {
+        let idx = addr.as_usize();
+        if let Some(value) = self.dense.get(idx) {
+            *value
+        } else if let Some(value) = self.program.get(idx) {
+            *value
+        } else if idx < self.dense_limit() {
+            0
+        } else {
+            self.sparse.get(&addr).copied().unwrap_or_default()
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, addr: Pointer, value: i64) {
+        let idx = addr.as_usize();
+        if idx < self.dense.len() {
+            self.dense[idx] = value;
+        } else if idx < self.dense_limit() {
+            self.grow(idx + 1);
+            self.dense[idx] = value;
+        } else {
+            self.sparse.insert(addr, value);
+        }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        let program = self.program.get(self.dense.len()..).unwrap_or_default();
+        self.dense
+            .iter()
+            .chain(program)
+            .copied()
+            .enumerate()
+            .chain(sorted_cells(&self.sparse))
+            .filter(|(_, value)| *value != 0)
+    }
+}
+
+/**
+ * A private copy of the program, which grows with every write further out
+ * up to `DENSE_LIMIT`. Cells beyond go into a map.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct DenseMemory {
+    dense: Vec<i64>,
+    sparse: HashMap<Pointer, i64>,
+}
+
+impl Memory for DenseMemory {
+    fn new(program: Arc<[i64]>) -> Self {
+        Self {
+            dense: program.to_vec(),
+            sparse: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    fn get(&self, addr: Pointer) -> i64 {
+        match self.dense.get(addr.as_usize()) {
+            Some(value) => *value,
+            None => self.sparse.get(&addr).copied().unwrap_or_default(),
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, addr: Pointer, value: i64) {
+        let idx = addr.as_usize();
+        if idx < self.dense.len() {
+            self.dense[idx] = value;
+        } else if idx < DENSE_LIMIT.max(self.dense.len()) {
+            self.dense.resize(idx + 1, 0);
+            self.dense[idx] = value;
+        } else {
+            self.sparse.insert(addr, value);
+        }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        self.dense
+            .iter()
+            .copied()
+            .enumerate()
+            .chain(sorted_cells(&self.sparse))
+            .filter(|(_, value)| *value != 0)
+    }
+}
+
+/**
+ * Every cell in a map, the program included. Slow, but its size only
+ * depends on the cells that are not zero.
+ */
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct SparseMemory {
+    cells: HashMap<Pointer, i64>,
+}
+
+impl Memory for SparseMemory {
+    fn new(program: Arc<[i64]>) -> Self {
+        Self {
+            cells: program
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| **value != 0)
+                .map(|(addr, value)| (Pointer::new(addr), *value))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    fn get(&self, addr: Pointer) -> i64 {
+        self.cells.get(&addr).copied().unwrap_or_default()
+    }
+
+    #[inline]
+    fn set(&mut self, addr: Pointer, value: i64) {
+        if value == 0 {
+            self.cells.remove(&addr);
+        } else {
+            self.cells.insert(addr, value);
+        }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        sorted_cells(&self.cells).into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dense_and_sparse() {
+        let mut memory = SharedMemory::new(Arc::from([1, 2, 3]));
+        assert_eq!(memory.get(Pointer::new(1)), 2);
+        assert_eq!(memory.get(Pointer::new(10)), 0);
+
+        memory.set(Pointer::new(10), 5);
+        assert_eq!(memory.dense.len(), GROWTH);
+        assert_eq!(memory.get(Pointer::new(10)), 5);
+        assert_eq!(memory.get(Pointer::new(9)), 0);
+        assert_eq!(memory.get(Pointer::new(1)), 2);
+
+        let far = Pointer::new(DENSE_LIMIT * 1000);
+        memory.set(far, 7);
+        assert_eq!(memory.dense.len(), GROWTH);
+        assert_eq!(memory.get(far), 7);
+        assert_eq!(memory.get(Pointer::new(DENSE_LIMIT)), 0);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let program: Arc<[i64]> = (0..600).collect();
+        let mut first = SharedMemory::new(Arc::clone(&program));
+        let mut second = SharedMemory::new(Arc::clone(&program));
+        assert!(first.dense.is_empty());
+
+        first.set(Pointer::new(300), -1);
+        assert_eq!(first.dense.len(), 2 * GROWTH);
+        assert_eq!(first.get(Pointer::new(300)), -1);
+        assert_eq!(first.get(Pointer::new(299)), 299);
+        assert_eq!(first.get(Pointer::new(599)), 599);
+        assert_eq!(second.get(Pointer::new(300)), 300);
+
+        let snapshot = first.clone();
+        first.set(Pointer::new(301), -2);
+        second.set(Pointer::new(700), -3);
+        second.set(Pointer::new(1000), -4);
+        assert_eq!(snapshot.get(Pointer::new(301)), 301);
+        assert_eq!(first.get(Pointer::new(301)), -2);
+        assert_eq!(second.get(Pointer::new(599)), 599);
+        assert_eq!(second.get(Pointer::new(650)), 0);
+        assert_eq!(second.get(Pointer::new(700)), -3);
+        assert_eq!(second.get(Pointer::new(1000)), -4);
+        assert_eq!(program[300], 300);
+
+        assert_eq!(
+            snapshot.cells().take(3).collect::<Vec<_>>(),
+            vec![(1, 1), (2, 2), (3, 3)]
+        );
+        assert_eq!(snapshot.cells().nth(299), Some((300, -1)));
+        assert_eq!(snapshot.cells().last(), Some((599, 599)));
+    }
+
+    fn exercise<M: Memory>() -> Vec<(usize, i64)> {
+        let mut memory = M::new(Arc::from([1, 0, 3]));
+        memory.set(Pointer::new(0), 0);
+        memory.set(Pointer::new(1), 2);
+        memory.set(Pointer::new(40), 4);
+        memory.set(Pointer::new(DENSE_LIMIT * 3), 5);
+        assert_eq!(memory.get(Pointer::new(2)), 3);
+        assert_eq!(memory.get(Pointer::new(39)), 0);
+        assert_eq!(memory.get(Pointer::new(DENSE_LIMIT * 3)), 5);
+        memory.cells().collect()
+    }
+
+    #[test]
+    fn backends_agree() {
+        let expected = vec![(1, 2), (2, 3), (40, 4), (DENSE_LIMIT * 3, 5)];
+        assert_eq!(exercise::<SharedMemory>(), expected);
+        assert_eq!(exercise::<DenseMemory>(), expected);
+        assert_eq!(exercise::<SparseMemory>(), expected);
+    }
+}
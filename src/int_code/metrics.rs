@@ -0,0 +1,156 @@
+use std::fmt::Display;
+
+/**
+ * What a computer did since it was built or reset, see
+ * `IntCodeComputer::metrics`. Going back in time rewinds the executed
+ * instructions, the rest counts all the work done, replays included.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub executed: u64,
+    pub inputs: u64,
+    pub outputs: u64,
+    /**
+     * The cells the program needs, up to the furthest one it wrote or the
+     * end of the program. Reading beyond does not count, as it reads zeros.
+     */
+    pub memory_cells: usize,
+}
+
+impl Metrics {
+    /** Adds up the counts of several computers, the memory is the largest of them */
+    pub fn merge(&mut self, other: &Metrics) {
+        self.executed += other.executed;
+        self.inputs += other.inputs;
+        self.outputs += other.outputs;
+        self.memory_cells = self.memory_cells.max(other.memory_cells);
+    }
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} instructions, {} inputs, {} outputs, {} memory cells",
+            self.executed, self.inputs, self.outputs, self.memory_cells
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        common::events::{self, Event},
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerFactory, ExecutionMode},
+    };
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    #[test]
+    fn counts_a_run() -> UnitResult {
+        // Echoes two inputs and writes far beyond its end
+        let program = assemble(
+            "
+                    IN 100
+                    OUT 100
+                    IN 100
+                    OUT 100
+                    ADD 100, #0, 1000
+                    HALT
+            ",
+        )?;
+        for mode in [ExecutionMode::Interpreted, ExecutionMode::Compiled] {
+            let mut computer = ComputerFactory::new(program.clone()).build();
+            computer.set_execution_mode(mode);
+            assert_eq!(computer.metrics().memory_cells, program.len());
+
+            computer.send_i64(3);
+            computer.send_i64(4);
+            assert_eq!(computer.expect_i64()?, 3);
+            // Peeking does not run the output again
+            assert_eq!(computer.peek_i64()?, Some(4));
+            computer.run_till_halt()?;
+            assert_eq!(
+                computer.metrics(),
+                Metrics {
+                    executed: 6,
+                    inputs: 2,
+                    outputs: 2,
+                    memory_cells: 1001,
+                }
+            );
+
+            computer.reset();
+            assert_eq!(computer.metrics().executed, 0);
+        }
+
+        let mut total = Metrics::default();
+        total.merge(&Metrics {
+            executed: 2,
+            inputs: 1,
+            outputs: 0,
+            memory_cells: 10,
+        });
+        total.merge(&Metrics {
+            executed: 3,
+            inputs: 0,
+            outputs: 1,
+            memory_cells: 5,
+        });
+        assert_eq!(
+            total.to_string(),
+            "5 instructions, 1 inputs, 1 outputs, 10 memory cells"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn published_once() -> UnitResult {
+        // Other tests run computers at the same time, only this thread counts
+        let seen = Arc::new(Mutex::new(vec![]));
+        let id = {
+            let seen = Arc::clone(&seen);
+            let this = thread::current().id();
+            events::bus().subscribe(move |event| {
+                if thread::current().id() == this
+                    && let Event::ComputerMetrics { .. } = event
+                {
+                    seen.lock().unwrap().push(event.to_string());
+                }
+            })
+        };
+
+        let program = assemble(
+            "
+                    IN 100
+                    OUT 100
+                    IN 100
+                    HALT
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        computer.send_i64(5);
+        assert_eq!(computer.expect_i64()?, 5);
+        let mut copy = computer.clone();
+        copy.send_i64(6);
+        copy.run_till_halt()?;
+        drop(copy);
+        drop(computer);
+        events::bus().unsubscribe(id);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                "IntCode halted after 2 instructions, 1 inputs, 0 outputs, 101 memory cells",
+                "IntCode stopped after 2 instructions, 1 inputs, 1 outputs, 101 memory cells",
+            ]
+        );
+
+        Ok(())
+    }
+}
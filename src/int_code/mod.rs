@@ -1,11 +1,51 @@
+pub mod ascii;
+pub mod ascii_frames;
+pub mod assembler;
+pub mod async_computer;
+pub mod builder;
+mod checkpoints;
+pub mod cluster;
+mod compiled;
 mod computer_error;
+pub mod conformance;
+pub mod coverage;
+mod decode;
+pub mod decompiler;
+pub mod diagnostics;
+pub mod differential;
+pub mod fuzz;
+pub mod input;
 mod instructions;
 mod int_code_computer;
+pub mod io_log;
+mod limits;
+pub mod memory;
+pub mod metrics;
+pub mod nat;
+pub mod network;
+mod observer;
+pub mod output_buffer;
 mod param_mode;
+mod patch;
 mod pointer;
+pub mod profile;
+pub mod scheduler;
+pub mod self_modification;
+pub mod session;
+pub mod snapshot;
 mod state;
+pub mod terminal;
+pub mod trace;
+mod vm_event;
+pub mod watch;
 
+pub use compiled::ExecutionMode;
 pub use computer_error::ComputerError;
-pub use int_code_computer::{ComputerFactory, IntCodeComputer};
+pub use decode::{FromOutput, FromOutputs};
+pub use instructions::{set_default_arithmetic, Arithmetic, UnknownArithmetic};
+pub use int_code_computer::{ComputerFactory, IntCodeComputer, Poll};
+pub use limits::{set_default_limits, Limits};
+pub use observer::Observer;
+pub use patch::PatchError;
 pub use pointer::Pointer;
-pub use state::StepResult;
+pub use vm_event::VmEvent;
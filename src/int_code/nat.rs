@@ -0,0 +1,203 @@
+#![allow(dead_code)]
+use super::{
+    computer_error::ComputerError,
+    network::{Destination, Packet},
+    scheduler::Scheduler,
+    ComputerFactory,
+};
+
+/**
+ * Packets sent to this address go to the NAT instead of a machine.
+ */
+pub const NAT_ADDRESS: i64 = 255;
+
+/**
+ * What a machine sends when it has no packet for anyone.
+ */
+const NO_PACKET: i64 = -1;
+
+/**
+ * A network of machines that talk in `(address, x, y)` packets. Every
+ * machine gets its address as first input and `-1` whenever it asks for a
+ * packet and there is none. The NAT keeps the last packet sent to address
+ * 255 and sends it to machine 0 once the whole network is idle.
+ */
+pub struct NetworkController {
+    scheduler: Scheduler,
+    nat: Option<(i64, i64)>,
+    first_nat: Option<(i64, i64)>,
+    last_delivered_y: Option<i64>,
+    undeliverable: Vec<Packet>,
+}
+
+impl NetworkController {
+    pub fn new(factory: &ComputerFactory, count: usize) -> Self {
+        let mut scheduler = Scheduler::new(factory, count)
+            .with_packet_size(3)
+            .with_idle_input(NO_PACKET)
+            .with_router(move |_, values| {
+                let target = values[0];
+                if (0..count as i64).contains(&target) {
+                    vec![(Destination::Machine(target as usize), values[1..].to_vec())]
+                } else {
+                    vec![(Destination::Outside, values)]
+                }
+            });
+        for machine in 0..count {
+            scheduler.send(machine, &[machine as i64]);
+        }
+        Self {
+            scheduler,
+            nat: None,
+            first_nat: None,
+            last_delivered_y: None,
+            undeliverable: vec![],
+        }
+    }
+
+    /** Sends a packet to a machine as if it came from inside the network */
+    pub fn send(&mut self, machine: usize, x: i64, y: i64) {
+        self.scheduler.send(machine, &[x, y]);
+    }
+
+    /** The packet the NAT currently holds */
+    pub fn nat(&self) -> Option<(i64, i64)> {
+        self.nat
+    }
+
+    /** Packets sent to addresses that are neither a machine nor the NAT */
+    pub fn undeliverable(&self) -> &[Packet] {
+        &self.undeliverable
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.scheduler.is_idle()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.scheduler.is_halted()
+    }
+
+    /**
+     * Gives every machine one turn. Only the last packet for the NAT in
+     * this round is kept.
+     */
+    pub fn round(&mut self) -> Result<(), ComputerError> {
+        for packet in self.scheduler.round()? {
+            match packet.values[..] {
+                [NAT_ADDRESS, x, y] => {
+                    self.nat = Some((x, y));
+                    self.first_nat.get_or_insert((x, y));
+                }
+                _ => self.undeliverable.push(packet),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn run_until_idle(&mut self) -> Result<(), ComputerError> {
+        loop {
+            self.round()?;
+            if self.is_idle() {
+                return Ok(());
+            }
+        }
+    }
+
+    /**
+     * Sends the NAT packet to machine 0 and returns it, if there is one.
+     */
+    pub fn wake_up(&mut self) -> Option<(i64, i64)> {
+        let (x, y) = self.nat?;
+        self.send(0, x, y);
+        self.last_delivered_y = Some(y);
+        Some((x, y))
+    }
+
+    /**
+     * Runs until the first packet is sent to the NAT. `None` if the network
+     * goes idle before anybody does.
+     */
+    pub fn first_nat_packet(&mut self) -> Result<Option<(i64, i64)>, ComputerError> {
+        while self.first_nat.is_none() {
+            self.round()?;
+            if self.is_idle() {
+                break;
+            }
+        }
+        Ok(self.first_nat)
+    }
+
+    /**
+     * Lets the NAT wake the network up every time it goes idle until it
+     * sends machine 0 the same Y value twice in a row. `None` if the network
+     * stays idle without a packet for the NAT.
+     */
+    pub fn first_repeated_nat_y(&mut self) -> Result<Option<i64>, ComputerError> {
+        loop {
+            self.run_until_idle()?;
+            let Some((_, y)) = self.nat else {
+                return Ok(None);
+            };
+            if self.last_delivered_y == Some(y) {
+                return Ok(Some(y));
+            }
+            self.wake_up();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{days::UnitResult, int_code::assembler::assemble};
+
+    #[test]
+    fn nat() -> UnitResult {
+        // Machine 0 starts with the packet (0, 5) for machine 1. Every machine
+        // passes the packets it gets on to the next one, the last one to the
+        // NAT, with x one larger and y one smaller, but never below 0.
+        let program = assemble(
+            "
+                    IN id
+                    ADD id, #1, next
+                    EQ next, #3, flag
+                    JF flag, #boot
+                    ADD #255, #0, next
+            boot:   JT id, #loop
+                    OUT #1
+                    OUT #0
+                    OUT #5
+            loop:   IN x
+                    EQ x, #-1, flag
+                    JT flag, #loop
+                    IN y
+                    LT #0, y, flag
+                    JF flag, #send
+                    ADD y, #-1, y
+            send:   OUT next
+                    ADD x, #1, x
+                    OUT x
+                    OUT y
+                    JT #1, #loop
+            id:     DATA 0
+            next:   DATA 0
+            flag:   DATA 0
+            x:      DATA 0
+            y:      DATA 0
+            ",
+        )?;
+        let factory = ComputerFactory::new(program);
+
+        let mut controller = NetworkController::new(&factory, 3);
+        assert_eq!(controller.first_nat_packet()?, Some((2, 3)));
+
+        let mut controller = NetworkController::new(&factory, 3);
+        assert_eq!(controller.first_repeated_nat_y()?, Some(0));
+        assert_eq!(controller.nat(), Some((8, 0)));
+        assert!(controller.undeliverable().is_empty());
+        assert!(!controller.is_halted());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,528 @@
+#![allow(dead_code)]
+use super::{
+    computer_error::ComputerError,
+    output_buffer::{Fill, OutputBuffer},
+    ComputerFactory,
+};
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/**
+ * How long a waiting machine sleeps before it checks whether the network was shut down.
+ */
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/**
+ * How long a machine sleeps before it tries again to send a packet outside
+ * when nobody received the earlier ones yet.
+ */
+const DRAIN_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    Machine(usize),
+    /** Leaves the network and can be received from the running network */
+    Outside,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub from: usize,
+    pub values: Vec<i64>,
+}
+
+/**
+ * Decides where a packet sent by a machine goes. It may rewrite the packet
+ * and send it to any number of destinations. Packets for machines that
+ * do not exist go outside.
+ */
+pub type Router = Arc<dyn Fn(usize, Vec<i64>) -> Vec<(Destination, Vec<i64>)> + Send + Sync>;
+
+/**
+ * Every machine passes its outputs to the next one, the last one sends them outside.
+ */
+pub fn chain_router(count: usize) -> Router {
+    Arc::new(move |from, values| {
+        if from + 1 < count {
+            vec![(Destination::Machine(from + 1), values)]
+        } else {
+            vec![(Destination::Outside, values)]
+        }
+    })
+}
+
+/**
+ * The last machine feeds the first one. Its packets are sent outside
+ * as well, so the results can be watched.
+ */
+pub fn ring_router(count: usize) -> Router {
+    Arc::new(move |from, values| {
+        let next = (Destination::Machine((from + 1) % count), values.clone());
+        if from + 1 == count {
+            vec![next, (Destination::Outside, values)]
+        } else {
+            vec![next]
+        }
+    })
+}
+
+/**
+ * A set of computers running the same program, each on its own thread.
+ * Every computer collects its outputs into packets, which the router
+ * delivers to the input queues of other computers.
+ */
+pub struct Network {
+    factory: Arc<ComputerFactory>,
+    inputs: Vec<Vec<i64>>,
+    packet_size: usize,
+    idle_input: Option<i64>,
+    output_limit: Option<usize>,
+    router: Router,
+}
+
+impl Network {
+    pub fn new(factory: &ComputerFactory, count: usize) -> Self {
+        Self {
+            factory: Arc::new(factory.clone()),
+            inputs: vec![vec![]; count],
+            packet_size: 1,
+            idle_input: None,
+            output_limit: None,
+            router: chain_router(count),
+        }
+    }
+
+    pub fn ring(factory: &ComputerFactory, count: usize) -> Self {
+        Self {
+            router: ring_router(count),
+            ..Self::new(factory, count)
+        }
+    }
+
+    pub fn with_router<F>(mut self, router: F) -> Self
+    where
+        F: Fn(usize, Vec<i64>) -> Vec<(Destination, Vec<i64>)> + Send + Sync + 'static,
+    {
+        self.router = Arc::new(router);
+        self
+    }
+
+    pub fn with_packet_size(mut self, packet_size: usize) -> Self {
+        self.packet_size = packet_size.max(1);
+        self
+    }
+
+    /**
+     * Instead of waiting, a machine that needs input and has none gets this value.
+     */
+    pub fn with_idle_input(mut self, value: i64) -> Self {
+        self.idle_input = Some(value);
+        self
+    }
+
+    /**
+     * At most this many packets sent outside wait to be received. A machine
+     * that has another one stops until there is room again, so a program
+     * printing faster than it is read does not fill up the memory. Packets
+     * between machines are not limited, as machines feeding each other
+     * could otherwise block each other for good.
+     */
+    pub fn with_output_limit(mut self, packets: usize) -> Self {
+        self.output_limit = Some(packets.max(1));
+        self
+    }
+
+    pub fn send(mut self, machine: usize, values: &[i64]) -> Self {
+        self.inputs[machine].extend_from_slice(values);
+        self
+    }
+
+    pub fn start(self) -> RunningNetwork {
+        let count = self.inputs.len();
+        let (outside, received) = match self.output_limit {
+            Some(limit) => {
+                let (sender, receiver) = mpsc::sync_channel(limit);
+                (Outside::Bounded(sender), receiver)
+            }
+            None => {
+                let (sender, receiver) = mpsc::channel();
+                (Outside::Unbounded(sender), receiver)
+            }
+        };
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..count).map(|_| mpsc::channel()).unzip();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handles = receivers
+            .into_iter()
+            .zip(self.inputs)
+            .enumerate()
+            .map(|(id, (input, initial))| {
+                let machine = Machine {
+                    id,
+                    factory: self.factory.clone(),
+                    initial,
+                    input,
+                    senders: senders.clone(),
+                    outside: outside.clone(),
+                    router: self.router.clone(),
+                    packet_size: self.packet_size,
+                    idle_input: self.idle_input,
+                    stop: stop.clone(),
+                };
+                thread::spawn(move || machine.run())
+            })
+            .collect();
+
+        RunningNetwork {
+            received,
+            stop,
+            handles,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Outside {
+    Unbounded(Sender<Packet>),
+    Bounded(SyncSender<Packet>),
+}
+
+impl Outside {
+    /** Waits for room if the channel is bounded, false if nobody listens anymore */
+    fn send(&self, packet: Packet, stop: &AtomicBool) -> bool {
+        match self {
+            Outside::Unbounded(sender) => sender.send(packet).is_ok(),
+            Outside::Bounded(sender) => {
+                let mut packet = packet;
+                loop {
+                    match sender.try_send(packet) {
+                        Ok(()) => return true,
+                        Err(TrySendError::Disconnected(_)) => return false,
+                        Err(TrySendError::Full(_)) if stop.load(Ordering::Relaxed) => {
+                            return false;
+                        }
+                        Err(TrySendError::Full(unsent)) => {
+                            packet = unsent;
+                            thread::sleep(DRAIN_INTERVAL);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Machine {
+    id: usize,
+    factory: Arc<ComputerFactory>,
+    initial: Vec<i64>,
+    input: Receiver<Vec<i64>>,
+    senders: Vec<Sender<Vec<i64>>>,
+    outside: Outside,
+    router: Router,
+    packet_size: usize,
+    idle_input: Option<i64>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Machine {
+    /**
+     * A machine that fails stops the whole network, as the others might
+     * wait for its packets forever.
+     */
+    fn run(self) -> Result<(), ComputerError> {
+        let result = self.run_computer();
+        if result.is_err() {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn run_computer(&self) -> Result<(), ComputerError> {
+        let mut computer = self.factory.build();
+        for value in &self.initial {
+            computer.send_i64(*value);
+        }
+        let mut packet = OutputBuffer::new(self.packet_size);
+
+        while !self.stop.load(Ordering::Relaxed) {
+            match computer.fill(&mut packet)? {
+                Fill::NeedsDrain => self.deliver(packet.drain().collect()),
+                Fill::NeedsInput => match self.receive() {
+                    Some(values) => values
+                        .into_iter()
+                        .for_each(|value| computer.send_i64(value)),
+                    None => {
+                        if let Some(idle) = self.idle_input {
+                            computer.send_i64(idle);
+                            thread::yield_now();
+                        }
+                    }
+                },
+                Fill::Halted if packet.is_empty() => break,
+                Fill::Halted => {
+                    return Err(ComputerError::IncompleteChunk(
+                        packet.drain().collect(),
+                        self.packet_size,
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn receive(&self) -> Option<Vec<i64>> {
+        if self.idle_input.is_some() {
+            self.input.try_recv().ok()
+        } else {
+            self.input.recv_timeout(WAIT_INTERVAL).ok()
+        }
+    }
+
+    fn deliver(&self, values: Vec<i64>) {
+        for (destination, values) in (self.router)(self.id, values) {
+            let sent = match destination {
+                Destination::Machine(target) if target < self.senders.len() => {
+                    self.senders[target].send(values).is_ok()
+                }
+                Destination::Machine(_) | Destination::Outside => self.outside.send(
+                    Packet {
+                        from: self.id,
+                        values,
+                    },
+                    &self.stop,
+                ),
+            };
+            // Nobody listens anymore, so the network is being torn down anyway
+            if !sent {
+                self.stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+pub struct RunningNetwork {
+    received: Receiver<Packet>,
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<Result<(), ComputerError>>>,
+}
+
+impl RunningNetwork {
+    /**
+     * Waits for the next packet sent outside. Returns `None` once every machine stopped.
+     */
+    pub fn recv(&self) -> Option<Packet> {
+        loop {
+            match self.received.recv_timeout(WAIT_INTERVAL) {
+                Ok(packet) => return Some(packet),
+                Err(RecvTimeoutError::Disconnected) => return None,
+                Err(RecvTimeoutError::Timeout) => {
+                    if self.handles.iter().all(|handle| handle.is_finished()) {
+                        return self.received.try_recv().ok();
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * Stops all machines, even those still running, and reports the first error any of them hit.
+     */
+    pub fn shutdown(mut self) -> Result<(), ComputerError> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.join_all()
+    }
+
+    /**
+     * Waits until every machine halted and returns all packets sent outside.
+     * A machine that fails or halts within a packet stops the others, and
+     * its error is returned. Never returns if machines wait for input
+     * forever, use `shutdown` then.
+     */
+    pub fn finish(mut self) -> Result<Vec<Packet>, ComputerError> {
+        let mut packets = vec![];
+        while let Some(packet) = self.recv() {
+            packets.push(packet);
+        }
+        self.join_all()?;
+        Ok(packets)
+    }
+
+    fn join_all(&mut self) -> Result<(), ComputerError> {
+        let mut result = Ok(());
+        for handle in mem::take(&mut self.handles) {
+            let outcome = handle.join().expect("machine threads do not panic");
+            if result.is_ok() {
+                result = outcome;
+            }
+        }
+        result
+    }
+}
+
+impl Drop for RunningNetwork {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::{read_string, UnitResult},
+        int_code::assembler::assemble,
+    };
+
+    #[test]
+    fn feedback_loop() -> UnitResult {
+        let input = read_string(7, "example04.txt")?;
+        let factory = ComputerFactory::init(&input)?;
+        let mut network = Network::ring(&factory, 5);
+        for (machine, phase) in [9, 8, 7, 6, 5].into_iter().enumerate() {
+            network = network.send(machine, &[phase]);
+        }
+        let packets = network.send(0, &[0]).start().finish()?;
+
+        assert!(packets.iter().all(|packet| packet.from == 4));
+        assert_eq!(
+            packets.last().map(|packet| packet.values.clone()),
+            Some(vec![139629729])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn addressed_packets() -> UnitResult {
+        // Every machine reads its id and sends (id + 1) * 100 to the machine after next,
+        // then waits for one value and sends it to address 99.
+        let program = assemble(
+            "
+                    IN id
+                    ADD id, #2, target
+                    ADD id, #1, value
+                    MUL value, #100, value
+                    OUT target
+                    OUT value
+                    IN value
+                    OUT #99
+                    OUT value
+                    HALT
+            id:     DATA 0
+            target: DATA 0
+            value:  DATA 0
+            ",
+        )?;
+        let count = 4;
+        let mut network = Network::new(&ComputerFactory::new(program), count)
+            .with_packet_size(2)
+            .with_router(move |_, values| {
+                let target = if values[0] == 99 {
+                    Destination::Outside
+                } else {
+                    Destination::Machine(values[0] as usize % count)
+                };
+                vec![(target, values[1..].to_vec())]
+            });
+        for machine in 0..count {
+            network = network.send(machine, &[machine as i64]);
+        }
+        let mut packets = network.start().finish()?;
+        packets.sort_by_key(|packet| packet.from);
+
+        assert_eq!(
+            packets,
+            vec![
+                Packet {
+                    from: 0,
+                    values: vec![300]
+                },
+                Packet {
+                    from: 1,
+                    values: vec![400]
+                },
+                Packet {
+                    from: 2,
+                    values: vec![100]
+                },
+                Packet {
+                    from: 3,
+                    values: vec![200]
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn shutdown_waiting_machines() -> UnitResult {
+        let program = assemble("loop: IN value\nOUT value\nJT #1, #loop\nvalue: DATA 0")?;
+        let running = Network::ring(&ComputerFactory::new(program), 2)
+            .send(0, &[5])
+            .start();
+        assert_eq!(
+            running.recv(),
+            Some(Packet {
+                from: 1,
+                values: vec![5]
+            })
+        );
+        running.shutdown()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn incomplete_packet() -> UnitResult {
+        // The first machine halts in the middle of a packet, the second one waits for it
+        let program = assemble(
+            "IN value\nJF value, #wait\nOUT #7\nHALT\nwait: IN value\nHALT\nvalue: DATA 0",
+        )?;
+        let result = Network::new(&ComputerFactory::new(program), 2)
+            .with_packet_size(2)
+            .send(0, &[1])
+            .send(1, &[0])
+            .start()
+            .finish();
+        assert!(matches!(
+            result,
+            Err(ComputerError::IncompleteChunk(values, 2)) if values == [7]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bounded_output() -> UnitResult {
+        // Counts up forever, much faster than anybody could read
+        let program =
+            assemble("loop: OUT value\nADD value, #1, value\nJT #1, #loop\nvalue: DATA 0")?;
+        let running = Network::new(&ComputerFactory::new(program), 1)
+            .with_output_limit(4)
+            .start();
+        thread::sleep(WAIT_INTERVAL);
+        for value in 0..10 {
+            assert_eq!(
+                running.recv(),
+                Some(Packet {
+                    from: 0,
+                    values: vec![value]
+                })
+            );
+        }
+        // The machine waits for room to send, but still notices the shutdown
+        running.shutdown()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use super::{memory::Memory, ComputerError, IntCodeComputer};
+
+/**
+ * Reacts to a running computer, see `IntCodeComputer::run_observed`.
+ * Errors of the computer itself are converted into the observer's error type.
+ */
+pub trait Observer {
+    type Error: From<ComputerError>;
+
+    fn on_output(&mut self, value: i64) -> Result<(), Self::Error>;
+
+    /**
+     * Called when the program wants input and nothing was sent.
+     * Returning `None` stops the run, the computer keeps waiting.
+     */
+    fn on_input_request(&mut self) -> Result<Option<i64>, Self::Error> {
+        Ok(None)
+    }
+
+    /**
+     * What the computer calls instead of `on_input_request`, for observers
+     * that need to look at it to decide, like running a copy of it ahead.
+     */
+    fn on_input_request_from<M: Memory>(
+        &mut self,
+        _computer: &IntCodeComputer<M>,
+    ) -> Result<Option<i64>, Self::Error> {
+        self.on_input_request()
+    }
+
+    fn on_halt(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+#![allow(dead_code)]
+use std::collections::{vec_deque, VecDeque};
+
+/**
+ * Why `IntCodeComputer::fill` stopped.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /** The buffer reached its high-water mark, nothing runs until it is drained */
+    NeedsDrain,
+    NeedsInput,
+    Halted,
+}
+
+/**
+ * Collects the outputs of a computer up to a high-water mark. Programs
+ * that print whole maps or videos would otherwise run ahead of a slow
+ * consumer and pile up everything they ever printed.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputBuffer {
+    values: VecDeque<i64>,
+    high_water: usize,
+}
+
+impl OutputBuffer {
+    pub fn new(high_water: usize) -> Self {
+        let high_water = high_water.max(1);
+        Self {
+            values: VecDeque::with_capacity(high_water),
+            high_water,
+        }
+    }
+
+    pub fn high_water(&self) -> usize {
+        self.high_water
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.values.len() >= self.high_water
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[inline]
+    pub(super) fn push(&mut self, value: i64) {
+        self.values.push_back(value);
+    }
+
+    pub fn pop(&mut self) -> Option<i64> {
+        self.values.pop_front()
+    }
+
+    /** Hands out everything buffered so far, oldest first */
+    pub fn drain(&mut self) -> vec_deque::Drain<'_, i64> {
+        self.values.drain(..)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerFactory},
+    };
+
+    #[test]
+    fn yields_when_full() -> UnitResult {
+        // Counts down from its input, then asks for the next start
+        let program = assemble(
+            "
+            start:  IN value
+            loop:   OUT value
+                    ADD value, #-1, value
+                    JT value, #loop
+                    JT #1, #start
+            value:  DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        let mut buffer = OutputBuffer::new(4);
+        computer.send_i64(10);
+
+        assert_eq!(computer.fill(&mut buffer)?, Fill::NeedsDrain);
+        assert_eq!(buffer.drain().collect::<Vec<_>>(), vec![10, 9, 8, 7]);
+        assert_eq!(computer.fill(&mut buffer)?, Fill::NeedsDrain);
+        assert_eq!(buffer.pop(), Some(6));
+        assert_eq!(computer.fill(&mut buffer)?, Fill::NeedsDrain);
+        assert_eq!(buffer.len(), 4);
+
+        // A full buffer keeps the computer from running at all
+        let executed = computer.executed();
+        assert_eq!(computer.fill(&mut buffer)?, Fill::NeedsDrain);
+        assert_eq!(computer.executed(), executed);
+        assert_eq!(buffer.pop(), Some(5));
+        assert_eq!(computer.fill(&mut buffer)?, Fill::NeedsDrain);
+        assert_eq!(buffer.drain().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+        assert_eq!(computer.fill(&mut buffer)?, Fill::NeedsInput);
+        assert!(buffer.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+#![allow(dead_code)]
+use super::{ComputerFactory, Pointer};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PatchError {
+    #[error("Address {0} is outside of the program")]
+    OutOfRange(Pointer),
+
+    #[error("Expected {expected} at address {address}, found {found}")]
+    UnexpectedValue {
+        address: Pointer,
+        expected: i64,
+        found: i64,
+    },
+
+    #[error("Sequence {0:?} not found")]
+    SequenceNotFound(Vec<i64>),
+
+    #[error("Sequence {0:?} found {1} times")]
+    AmbiguousSequence(Vec<i64>, usize),
+
+    #[error("Replacement has length {1} instead of {0}, that would move the code")]
+    LengthMismatch(usize, usize),
+}
+
+/**
+ * Every address where the sequence starts, overlapping ones included.
+ */
+pub(super) fn find(data: &[i64], sequence: &[i64]) -> Vec<Pointer> {
+    if sequence.is_empty() {
+        return vec![];
+    }
+    data.windows(sequence.len())
+        .enumerate()
+        .filter(|(_, window)| *window == sequence)
+        .map(|(address, _)| Pointer::new(address))
+        .collect()
+}
+
+/**
+ * Changes to a program before any computer runs it, see `ComputerFactory::patch`.
+ * Every step checks what it is about to change; after the first one that
+ * fails the others are skipped and `apply` reports the failure.
+ */
+#[derive(Debug, Clone)]
+pub struct Patch {
+    data: Vec<i64>,
+    error: Option<PatchError>,
+}
+
+impl Patch {
+    pub(super) fn new(data: Vec<i64>) -> Self {
+        Self { data, error: None }
+    }
+
+    fn step<F>(mut self, step: F) -> Self
+    where
+        F: FnOnce(&mut [i64]) -> Result<(), PatchError>,
+    {
+        if self.error.is_none()
+            && let Err(error) = step(&mut self.data)
+        {
+            self.error = Some(error);
+        }
+        self
+    }
+
+    /** Makes sure the program is the one the patch was written for */
+    pub fn expect(self, address: Pointer, expected: i64) -> Self {
+        self.step(|data| {
+            let found = *data
+                .get(address.as_usize())
+                .ok_or(PatchError::OutOfRange(address))?;
+            if found == expected {
+                Ok(())
+            } else {
+                Err(PatchError::UnexpectedValue {
+                    address,
+                    expected,
+                    found,
+                })
+            }
+        })
+    }
+
+    pub fn set(self, address: Pointer, value: i64) -> Self {
+        self.step(|data| {
+            let cell = data
+                .get_mut(address.as_usize())
+                .ok_or(PatchError::OutOfRange(address))?;
+            *cell = value;
+            Ok(())
+        })
+    }
+
+    /**
+     * Replaces a sequence that appears exactly once in the program. The
+     * replacement must have the same length, so no address changes.
+     */
+    pub fn replace_seq(self, sequence: &[i64], replacement: &[i64]) -> Self {
+        self.step(|data| {
+            if sequence.len() != replacement.len() {
+                return Err(PatchError::LengthMismatch(
+                    sequence.len(),
+                    replacement.len(),
+                ));
+            }
+            match find(data, sequence)[..] {
+                [address] => {
+                    let start = address.as_usize();
+                    data[start..start + replacement.len()].copy_from_slice(replacement);
+                    Ok(())
+                }
+                [] => Err(PatchError::SequenceNotFound(sequence.to_vec())),
+                ref found => Err(PatchError::AmbiguousSequence(
+                    sequence.to_vec(),
+                    found.len(),
+                )),
+            }
+        })
+    }
+
+    pub fn apply(self) -> Result<ComputerFactory, PatchError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(ComputerFactory::new(self.data)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{days::UnitResult, int_code::assembler::assemble};
+
+    #[test]
+    fn patching() -> UnitResult {
+        // Outputs the sum of two constants
+        let factory = ComputerFactory::new(assemble(
+            "
+                    ADD #12, #30, sum
+                    OUT sum
+                    HALT
+            sum:    DATA 0
+            ",
+        )?);
+        assert_eq!(factory.find(&[12, 30]), vec![Pointer::new(1)]);
+
+        let patched = factory
+            .patch()
+            .expect(Pointer::new(0), 1101)
+            .replace_seq(&[12, 30], &[100, 20])
+            .set(Pointer::new(4), 104)
+            .apply()?;
+        assert_eq!(patched.program(), &[1101, 100, 20, 7, 104, 7, 99, 0]);
+        assert_eq!(patched.build().expect_i64()?, 7);
+
+        assert_eq!(
+            factory.patch().expect(Pointer::new(0), 2).apply().err(),
+            Some(PatchError::UnexpectedValue {
+                address: Pointer::new(0),
+                expected: 2,
+                found: 1101
+            })
+        );
+        assert_eq!(
+            factory
+                .patch()
+                .set(Pointer::new(8), 1)
+                .replace_seq(&[12], &[1, 2])
+                .apply()
+                .err(),
+            Some(PatchError::OutOfRange(Pointer::new(8)))
+        );
+        assert_eq!(
+            factory.patch().replace_seq(&[7], &[8]).apply().err(),
+            Some(PatchError::AmbiguousSequence(vec![7], 2))
+        );
+
+        Ok(())
+    }
+}
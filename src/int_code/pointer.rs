@@ -24,6 +24,11 @@ impl Pointer {
         }
     }
 
+    #[inline]
+    pub fn as_usize(&self) -> usize {
+        self.0
+    }
+
     #[inline]
     pub fn inc(&mut self) {
         self.0 += 1;
@@ -0,0 +1,300 @@
+#![allow(dead_code)]
+use super::{trace::Mnemonic, Pointer};
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
+use thiserror::Error;
+
+const REPORT_SIZE: usize = 10;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Not a cost: {0} (expected MNEMONIC=CYCLES)")]
+pub struct InvalidCost(String);
+
+/**
+ * What each instruction costs in cycles. Unlike the time a run takes,
+ * the cycles are the same on every run, so they can be used to compare
+ * programs. Unless set otherwise an instruction costs a cycle for every
+ * word it is made of, `ADD` 4 and `HALT` 1.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostModel {
+    costs: HashMap<Mnemonic, u64>,
+}
+
+impl CostModel {
+    /** Every instruction costs the same, so the cycles count instructions */
+    pub fn uniform(cycles: u64) -> Self {
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 99]
+            .into_iter()
+            .map(Mnemonic::from_instruction)
+            .fold(Self::default(), |model, mnemonic| {
+                model.with_cost(mnemonic, cycles)
+            })
+    }
+
+    pub fn with_cost(mut self, mnemonic: Mnemonic, cycles: u64) -> Self {
+        self.costs.insert(mnemonic, cycles);
+        self
+    }
+
+    pub fn cost(&self, mnemonic: Mnemonic) -> u64 {
+        self.costs
+            .get(&mnemonic)
+            .copied()
+            .unwrap_or(mnemonic.parameter_count() as u64 + 1)
+    }
+}
+
+/**
+ * Costs that differ from the default, like `MUL=10, IN=5`.
+ */
+impl FromStr for CostModel {
+    type Err = InvalidCost;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|cost| !cost.is_empty())
+            .try_fold(Self::default(), |model, cost| {
+                let (mnemonic, cycles) = cost
+                    .split_once('=')
+                    .and_then(|(mnemonic, cycles)| {
+                        Some((mnemonic.trim().parse().ok()?, cycles.trim().parse().ok()?))
+                    })
+                    .ok_or_else(|| InvalidCost(cost.to_owned()))?;
+                Ok(model.with_cost(mnemonic, cycles))
+            })
+    }
+}
+
+/**
+ * Execution counts of a profiled run. Counts survive a reset of the computer,
+ * so programs that are restarted for every query are profiled as a whole.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    executed: HashMap<Pointer, (Mnemonic, u64)>,
+    leaders: HashSet<Pointer>,
+    costs: CostModel,
+}
+
+/**
+ * A straight run of instructions without jumps into or out of its middle.
+ * `end` is the address of its last instruction.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: Pointer,
+    pub end: Pointer,
+    pub length: usize,
+    pub executions: u64,
+}
+
+impl BasicBlock {
+    pub fn instructions(&self) -> u64 {
+        self.executions * self.length as u64
+    }
+}
+
+impl Profile {
+    /**
+     * Called after the instruction at `address` ran and moved the pointer to `next`.
+     */
+    pub(super) fn record(&mut self, address: Pointer, mnemonic: Mnemonic, next: Pointer) {
+        if self.executed.is_empty() {
+            self.leaders.insert(address);
+        }
+        let entry = self.executed.entry(address).or_insert((mnemonic, 0));
+        *entry = (mnemonic, entry.1 + 1);
+        if matches!(mnemonic, Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse)
+            || next.as_usize() != address.as_usize() + mnemonic.parameter_count() + 1
+        {
+            self.leaders.insert(next);
+        }
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.executed.values().map(|(_, count)| count).sum()
+    }
+
+    pub fn set_cost_model(&mut self, costs: CostModel) {
+        self.costs = costs;
+    }
+
+    /** The cycles the run took with the cost model of the profile */
+    pub fn cycles(&self) -> u64 {
+        self.cycles_with(&self.costs)
+    }
+
+    /** The cycles the run would have taken with other costs */
+    pub fn cycles_with(&self, costs: &CostModel) -> u64 {
+        self.executed
+            .values()
+            .map(|(mnemonic, count)| costs.cost(*mnemonic) * count)
+            .sum()
+    }
+
+    pub fn by_opcode(&self) -> Vec<(Mnemonic, u64)> {
+        self.executed
+            .values()
+            .into_grouping_map_by(|(mnemonic, _)| *mnemonic)
+            .fold(0, |sum, _, (_, count)| sum + count)
+            .into_iter()
+            .sorted_by_key(|(mnemonic, count)| (u64::MAX - count, mnemonic.to_string()))
+            .collect()
+    }
+
+    pub fn hot_addresses(&self, count: usize) -> Vec<(Pointer, u64)> {
+        self.executed
+            .iter()
+            .map(|(address, (_, executions))| (*address, *executions))
+            .sorted_by_key(|(address, executions)| (u64::MAX - executions, address.as_usize()))
+            .take(count)
+            .collect()
+    }
+
+    /**
+     * Blocks start at the first executed instruction, at every jump target and
+     * right after every conditional jump. They end before the next block starts
+     * or where execution never went on.
+     */
+    pub fn basic_blocks(&self) -> Vec<BasicBlock> {
+        self.leaders
+            .iter()
+            .filter_map(|start| {
+                let (_, executions) = self.executed.get(start)?;
+                let mut end = *start;
+                let mut length = 1;
+                loop {
+                    let (mnemonic, _) = self.executed[&end];
+                    if matches!(
+                        mnemonic,
+                        Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse | Mnemonic::Halt
+                    ) {
+                        break;
+                    }
+                    let next = Pointer::new(end.as_usize() + mnemonic.parameter_count() + 1);
+                    if self.leaders.contains(&next) || !self.executed.contains_key(&next) {
+                        break;
+                    }
+                    end = next;
+                    length += 1;
+                }
+                Some(BasicBlock {
+                    start: *start,
+                    end,
+                    length,
+                    executions: *executions,
+                })
+            })
+            .collect()
+    }
+
+    pub fn hot_blocks(&self, count: usize) -> Vec<BasicBlock> {
+        self.basic_blocks()
+            .into_iter()
+            .sorted_by_key(|block| (u64::MAX - block.instructions(), block.start.as_usize()))
+            .take(count)
+            .collect()
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} instructions", self.instructions())?;
+        writeln!(f, "{} cycles", self.cycles())?;
+        writeln!(f, "by opcode (instructions, cycles):")?;
+        for (mnemonic, count) in self.by_opcode() {
+            let cycles = self.costs.cost(mnemonic) * count;
+            writeln!(f, "  {mnemonic:<4} {count:>12} {cycles:>12}")?;
+        }
+        writeln!(f, "hot addresses:")?;
+        for (address, count) in self.hot_addresses(REPORT_SIZE) {
+            writeln!(f, "  {:06} {count:>12}", address.as_usize())?;
+        }
+        writeln!(f, "hot blocks:")?;
+        for block in self.hot_blocks(REPORT_SIZE) {
+            writeln!(
+                f,
+                "  {:06}-{:06} {:>3} instructions {:>10} times {:>12} total",
+                block.start.as_usize(),
+                block.end.as_usize(),
+                block.length,
+                block.executions,
+                block.instructions()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::{assembler::assemble, ComputerError, ComputerFactory};
+
+    #[test]
+    fn countdown() -> Result<(), ComputerError> {
+        let source = "
+                    IN counter
+            loop:   ADD counter, #-1, counter
+                    MUL counter, #1, copy
+                    JT counter, #loop
+                    OUT copy
+                    HALT
+            counter: DATA 0
+            copy:   DATA 0
+        ";
+        let mut computer = ComputerFactory::new(assemble(source).unwrap()).build();
+        computer.enable_profile();
+        computer.send_i64(5);
+        computer.run_till_halt()?;
+        computer.reset();
+        computer.send_i64(2);
+        computer.run_till_halt()?;
+
+        let profile = computer.profile().unwrap();
+        assert_eq!(profile.instructions(), 2 * 3 + 3 * 7);
+        assert_eq!(
+            profile.by_opcode(),
+            vec![
+                (Mnemonic::Add, 7),
+                (Mnemonic::JumpIfTrue, 7),
+                (Mnemonic::Mul, 7),
+                (Mnemonic::Halt, 2),
+                (Mnemonic::In, 2),
+                (Mnemonic::Out, 2),
+            ]
+        );
+        assert_eq!(profile.hot_addresses(1), vec![(Pointer::new(2), 7)]);
+
+        let blocks = profile.hot_blocks(REPORT_SIZE);
+        assert_eq!(
+            blocks[0],
+            BasicBlock {
+                start: Pointer::new(2),
+                end: Pointer::new(10),
+                length: 3,
+                executions: 7,
+            }
+        );
+        assert_eq!(blocks.len(), 3);
+        assert!(profile
+            .to_string()
+            .starts_with("27 instructions\n87 cycles\n"));
+
+        assert_eq!(profile.cycles_with(&CostModel::uniform(1)), 27);
+        let costs: CostModel = "MUL=10, ADD = 2".parse().unwrap();
+        assert_eq!(profile.cycles_with(&costs), 87 - 28 + 70 - 14);
+        assert_eq!(
+            "MUL=ten".parse::<CostModel>(),
+            Err(InvalidCost("MUL=ten".to_owned()))
+        );
+
+        Ok(())
+    }
+}
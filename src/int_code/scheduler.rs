@@ -0,0 +1,231 @@
+#![allow(dead_code)]
+use super::{
+    computer_error::ComputerError,
+    network::{chain_router, ring_router, Destination, Packet, Router},
+    ComputerFactory, IntCodeComputer, Poll,
+};
+use std::{mem, sync::Arc};
+
+/**
+ * The single threaded counterpart of `Network`: the same routing, but all
+ * machines take turns on one thread in a fixed order, so every run is
+ * reproducible. A turn lasts until the machine needs input it does not
+ * have or halts.
+ */
+pub struct Scheduler {
+    computers: Vec<IntCodeComputer>,
+    packets: Vec<Vec<i64>>,
+    idle: Vec<bool>,
+    halted: Vec<bool>,
+    packet_size: usize,
+    idle_input: Option<i64>,
+    router: Router,
+}
+
+impl Scheduler {
+    pub fn new(factory: &ComputerFactory, count: usize) -> Self {
+        Self {
+            computers: (0..count).map(|_| factory.build()).collect(),
+            packets: vec![vec![]; count],
+            idle: vec![false; count],
+            halted: vec![false; count],
+            packet_size: 1,
+            idle_input: None,
+            router: chain_router(count),
+        }
+    }
+
+    pub fn ring(factory: &ComputerFactory, count: usize) -> Self {
+        Self {
+            router: ring_router(count),
+            ..Self::new(factory, count)
+        }
+    }
+
+    pub fn with_router<F>(mut self, router: F) -> Self
+    where
+        F: Fn(usize, Vec<i64>) -> Vec<(Destination, Vec<i64>)> + Send + Sync + 'static,
+    {
+        self.router = Arc::new(router);
+        self
+    }
+
+    pub fn with_packet_size(mut self, packet_size: usize) -> Self {
+        self.packet_size = packet_size.max(1);
+        self
+    }
+
+    /**
+     * Instead of waiting, a machine that needs input and has none gets this value.
+     * It still counts as idle.
+     */
+    pub fn with_idle_input(mut self, value: i64) -> Self {
+        self.idle_input = Some(value);
+        self
+    }
+
+    pub fn send(&mut self, machine: usize, values: &[i64]) {
+        for value in values {
+            self.computers[machine].send_i64(*value);
+        }
+        self.idle[machine] = false;
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted.iter().all(|halted| *halted)
+    }
+
+    /**
+     * Every machine either halted or asked for input without getting any.
+     * Nothing will happen anymore unless some input is sent from outside.
+     */
+    pub fn is_idle(&self) -> bool {
+        self.idle
+            .iter()
+            .zip(&self.halted)
+            .all(|(idle, halted)| *idle || *halted)
+    }
+
+    /**
+     * Gives every machine one turn and returns the packets sent outside.
+     */
+    pub fn round(&mut self) -> Result<Vec<Packet>, ComputerError> {
+        let mut outside = vec![];
+        for machine in 0..self.computers.len() {
+            self.turn(machine, &mut outside)?;
+        }
+        Ok(outside)
+    }
+
+    /**
+     * Runs rounds until the network is idle or halted and returns all packets sent outside.
+     */
+    pub fn run_until_idle(&mut self) -> Result<Vec<Packet>, ComputerError> {
+        let mut outside = vec![];
+        loop {
+            outside.extend(self.round()?);
+            if self.is_idle() {
+                return Ok(outside);
+            }
+        }
+    }
+
+    fn turn(&mut self, machine: usize, outside: &mut Vec<Packet>) -> Result<(), ComputerError> {
+        if self.halted[machine] {
+            return Ok(());
+        }
+        loop {
+            match self.computers[machine].poll()? {
+                Poll::Output(value) => {
+                    self.packets[machine].push(value);
+                    if self.packets[machine].len() == self.packet_size {
+                        let packet = mem::take(&mut self.packets[machine]);
+                        self.deliver(machine, packet, outside);
+                    }
+                }
+                Poll::NeedsInput => {
+                    self.idle[machine] = true;
+                    if let Some(idle) = self.idle_input {
+                        self.computers[machine].send_i64(idle);
+                    }
+                    return Ok(());
+                }
+                Poll::Halted => {
+                    self.halted[machine] = true;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn deliver(&mut self, from: usize, values: Vec<i64>, outside: &mut Vec<Packet>) {
+        for (destination, values) in (self.router)(from, values) {
+            match destination {
+                Destination::Machine(target) if target < self.computers.len() => {
+                    self.send(target, &values)
+                }
+                Destination::Machine(_) | Destination::Outside => {
+                    outside.push(Packet { from, values })
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::{read_string, UnitResult},
+        int_code::assembler::assemble,
+    };
+
+    #[test]
+    fn feedback_loop() -> UnitResult {
+        let input = read_string(7, "example04.txt")?;
+        let mut scheduler = Scheduler::ring(&ComputerFactory::init(&input)?, 5);
+        for (machine, phase) in [9, 8, 7, 6, 5].into_iter().enumerate() {
+            scheduler.send(machine, &[phase]);
+        }
+        scheduler.send(0, &[0]);
+        let packets = scheduler.run_until_idle()?;
+
+        assert!(scheduler.is_halted());
+        assert_eq!(
+            packets.last().map(|packet| packet.values.clone()),
+            Some(vec![139629729])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn idle_detection() -> UnitResult {
+        // Every machine forwards each value it gets to the machine with the next id
+        let program = assemble(
+            "
+                    IN id
+                    ADD id, #1, next
+            loop:   IN value
+                    EQ value, #-1, flag
+                    JT flag, #loop
+                    OUT next
+                    OUT value
+                    JT #1, #loop
+            id:     DATA 0
+            next:   DATA 0
+            value:  DATA 0
+            flag:   DATA 0
+            ",
+        )?;
+        let count = 3;
+        let mut scheduler = Scheduler::new(&ComputerFactory::new(program), count)
+            .with_packet_size(2)
+            .with_idle_input(-1)
+            .with_router(|_, values| {
+                vec![(
+                    Destination::Machine(values[0] as usize),
+                    values[1..].to_vec(),
+                )]
+            });
+        for machine in 0..count {
+            scheduler.send(machine, &[machine as i64]);
+        }
+
+        assert!(scheduler.run_until_idle()?.is_empty());
+        for value in [7, 8] {
+            scheduler.send(0, &[value]);
+            assert!(!scheduler.is_idle());
+            assert_eq!(
+                scheduler.run_until_idle()?,
+                vec![Packet {
+                    from: 2,
+                    values: vec![value]
+                }]
+            );
+        }
+        assert!(!scheduler.is_halted());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+#![allow(dead_code)]
+use super::Pointer;
+use itertools::Itertools;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    ops::Range,
+};
+
+/**
+ * Which words of memory the program wrote to and later executed as part of
+ * an instruction, and which instructions wrote them. Writes from outside,
+ * like patches, do not count. Like the coverage, the modified words survive
+ * a reset of the computer, the writes of the run before do not.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SelfModification {
+    /** Every word the program wrote to, with the instruction that did last */
+    writes: HashMap<Pointer, Pointer>,
+    /** Words executed after a write, with the instruction that wrote them */
+    modified: BTreeMap<usize, Pointer>,
+    /** The instruction running right now */
+    running: Option<Pointer>,
+    /** What it wrote, which only counts once it is done */
+    pending: Vec<Pointer>,
+}
+
+impl SelfModification {
+    /** Keeps what was modified, but forgets the writes of the current run */
+    pub(super) fn restart(self) -> Self {
+        Self {
+            modified: self.modified,
+            ..Self::default()
+        }
+    }
+
+    /** Called before the instruction at `address` runs */
+    #[inline]
+    pub(super) fn start(&mut self, address: Pointer) {
+        self.running = Some(address);
+    }
+
+    #[inline]
+    pub(super) fn write(&mut self, address: Pointer) {
+        if self.running.is_some() {
+            self.pending.push(address);
+        }
+    }
+
+    /**
+     * Called after the instruction that started ran, `length` words long.
+     * What it wrote into itself only counts the next time it runs.
+     */
+    pub(super) fn executed(&mut self, length: usize) {
+        let Some(running) = self.running else {
+            return;
+        };
+        let start = running.as_usize();
+        for address in start..start + length {
+            if let Some(writer) = self.writes.get(&Pointer::new(address)) {
+                self.modified.insert(address, *writer);
+            }
+        }
+    }
+
+    /** Called after every instruction, whether it ran or not */
+    #[inline]
+    pub(super) fn stop(&mut self) {
+        if let Some(writer) = self.running.take() {
+            for address in self.pending.drain(..) {
+                self.writes.insert(address, writer);
+            }
+        }
+    }
+
+    pub fn is_modified(&self, address: Pointer) -> bool {
+        self.modified.contains_key(&address.as_usize())
+    }
+
+    /**
+     * The modified words as ranges of consecutive addresses, each with the
+     * instructions that last wrote to it.
+     */
+    pub fn report(&self) -> ModificationReport {
+        let mut ranges: Vec<ModifiedRange> = vec![];
+        for (address, writer) in &self.modified {
+            match ranges.last_mut() {
+                Some(last) if last.words.end == *address => {
+                    last.words.end += 1;
+                    last.writers.push(*writer);
+                }
+                _ => ranges.push(ModifiedRange {
+                    words: *address..address + 1,
+                    writers: vec![*writer],
+                }),
+            }
+        }
+        for range in &mut ranges {
+            range
+                .writers
+                .sort_unstable_by_key(|writer| writer.as_usize());
+            range.writers.dedup();
+        }
+        ModificationReport { ranges }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifiedRange {
+    pub words: Range<usize>,
+    pub writers: Vec<Pointer>,
+}
+
+/**
+ * The code a program rewrote before running it, see `SelfModification::report`.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModificationReport {
+    pub ranges: Vec<ModifiedRange>,
+}
+
+impl ModificationReport {
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn modified_words(&self) -> usize {
+        self.ranges.iter().map(|range| range.words.len()).sum()
+    }
+}
+
+impl Display for ModificationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no executed word was written by the program");
+        }
+        match self.modified_words() {
+            1 => writeln!(f, "1 executed word was written by the program:")?,
+            words => writeln!(f, "{words} executed words were written by the program:")?,
+        }
+        for range in &self.ranges {
+            writeln!(
+                f,
+                "  {:06}-{:06} written by {}",
+                range.words.start,
+                range.words.end - 1,
+                range
+                    .writers
+                    .iter()
+                    .map(|writer| format!("{:06}", writer.as_usize()))
+                    .join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerFactory},
+    };
+
+    #[test]
+    fn patched_output() -> UnitResult {
+        // Counts by patching the immediate of its own output, the flag it
+        // jumps on is written but never executed
+        let program = assemble(
+            "
+            loop:   OUT #0
+                    ADD 1, #1, 1
+                    LT 1, #3, 14
+                    JT 14, #loop
+                    HALT
+                    DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        computer.enable_modification_tracking();
+        computer.manipulate_memory(Pointer::new(13), 99);
+        let outputs = computer.as_iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(outputs, vec![0, 1, 2]);
+
+        let report = computer.modification_report().unwrap();
+        assert_eq!(
+            report.ranges,
+            vec![ModifiedRange {
+                words: 1..2,
+                writers: vec![Pointer::new(2)],
+            }]
+        );
+        assert!(!computer
+            .modification()
+            .unwrap()
+            .is_modified(Pointer::new(13)));
+
+        computer.reset();
+        assert_eq!(computer.modification_report().unwrap().modified_words(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rewrites_itself() -> UnitResult {
+        // The first instruction writes its own target every time, which only
+        // counts from the second time on. The last round turns an output into a halt.
+        let program = assemble(
+            "
+            loop:   ADD 3, #0, 3
+                    JT flag, #done
+                    ADD #1, #0, flag
+                    JT #1, #loop
+            done:   MUL #33, #3, end
+            end:    OUT #1
+            flag:   DATA 0
+            ",
+        )?;
+        let mut computer = ComputerFactory::new(program).build();
+        computer.enable_modification_tracking();
+        assert!(computer.as_iter().next().is_none());
+
+        let report = computer.modification_report().unwrap();
+        assert_eq!(
+            report.ranges,
+            vec![
+                ModifiedRange {
+                    words: 3..4,
+                    writers: vec![Pointer::new(0)],
+                },
+                ModifiedRange {
+                    words: 18..19,
+                    writers: vec![Pointer::new(14)],
+                }
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "2 executed words were written by the program:\n  000003-000003 written by 000000\n  000018-000018 written by 000014\n"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+#![allow(dead_code)]
+use super::{ComputerError, IntCodeComputer, Poll};
+use std::{fmt::Display, fs, io, path::Path, str::FromStr};
+
+/**
+ * A value the program read or wrote, in the order the program did it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoEvent {
+    Input(i64),
+    Output(i64),
+}
+
+impl Display for IoEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoEvent::Input(value) => write!(f, "in {value}"),
+            IoEvent::Output(value) => write!(f, "out {value}"),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+
+    #[error("Line {0} is no session event: {1}")]
+    NotAnEvent(usize, String),
+
+    #[error("Computer error: {0}")]
+    ComputerError(#[from] ComputerError),
+
+    #[error("Replay differs at event {event}: expected {expected:?}, the program did {actual:?}")]
+    Diverged {
+        event: usize,
+        expected: Option<IoEvent>,
+        actual: Poll,
+    },
+}
+
+/**
+ * Every input and output of a computer, see `IntCodeComputer::start_recording`.
+ * Stored as text, one event per line.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    events: Vec<IoEvent>,
+}
+
+impl Session {
+    pub(super) fn push(&mut self, event: IoEvent) {
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[IoEvent] {
+        &self.events
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = i64> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            IoEvent::Input(value) => Some(*value),
+            IoEvent::Output(_) => None,
+        })
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = i64> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            IoEvent::Input(_) => None,
+            IoEvent::Output(value) => Some(*value),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SessionError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /**
+     * Feeds the recorded inputs to the computer whenever it asks for one and
+     * checks that it produces exactly the recorded outputs in between.
+     * Returns whether the program halted; it may also end up waiting for
+     * input when the session was recorded before the end.
+     */
+    pub fn replay(&self, computer: &mut IntCodeComputer) -> Result<bool, SessionError> {
+        let mut events = self.events.iter().copied().enumerate().peekable();
+        loop {
+            let actual = computer.poll()?;
+            let next = events.peek().copied();
+            match (actual, next.map(|(_, event)| event)) {
+                (Poll::Output(value), Some(IoEvent::Output(expected))) if value == expected => {}
+                (Poll::NeedsInput, Some(IoEvent::Input(value))) => computer.send_i64(value),
+                (Poll::Halted, None) => return Ok(true),
+                (Poll::NeedsInput, None) => return Ok(false),
+                (actual, expected) => {
+                    return Err(SessionError::Diverged {
+                        event: next.map_or(self.events.len(), |(idx, _)| idx),
+                        expected,
+                        actual,
+                    })
+                }
+            }
+            events.next();
+        }
+    }
+}
+
+impl Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{event}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Session {
+    type Err = SessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let events = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                let event = match line.trim().split_once(' ') {
+                    Some(("in", value)) => value.parse().map(IoEvent::Input),
+                    Some(("out", value)) => value.parse().map(IoEvent::Output),
+                    _ => return Err(SessionError::NotAnEvent(idx + 1, line.to_owned())),
+                };
+                event.map_err(|_| SessionError::NotAnEvent(idx + 1, line.to_owned()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { events })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::ComputerFactory;
+
+    #[test]
+    fn record_and_replay() -> Result<(), SessionError> {
+        // Outputs twice every input until it reads a zero
+        let factory = ComputerFactory::new(vec![
+            3, 15, 1006, 15, 14, 1002, 15, 2, 16, 4, 16, 1105, 1, 0, 99, 0, 0,
+        ]);
+
+        let mut computer = factory.build();
+        computer.start_recording();
+        for value in [3, 5, 0] {
+            computer.send_i64(value);
+        }
+        computer.run_till_halt()?;
+        let session = computer.take_recording().unwrap();
+        assert_eq!(session.inputs().collect::<Vec<_>>(), vec![3, 5, 0]);
+        assert_eq!(session.outputs().collect::<Vec<_>>(), vec![6, 10]);
+
+        let text = session.to_string();
+        assert_eq!(text, "in 3\nout 6\nin 5\nout 10\nin 0\n");
+        let session: Session = text.parse()?;
+        assert!(session.replay(&mut factory.build())?);
+
+        let tampered: Session = text.replace("out 10", "out 11").parse()?;
+        assert!(matches!(
+            tampered.replay(&mut factory.build()),
+            Err(SessionError::Diverged {
+                event: 3,
+                expected: Some(IoEvent::Output(11)),
+                actual: Poll::Output(10)
+            })
+        ));
+
+        let unfinished: Session = "in 3\nout 6\n".parse()?;
+        assert!(!unfinished.replay(&mut factory.build())?);
+
+        Ok(())
+    }
+}
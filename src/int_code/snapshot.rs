@@ -0,0 +1,241 @@
+#![allow(dead_code)]
+use super::{
+    memory::{Memory, SharedMemory},
+    state::RunningState,
+    Pointer,
+};
+use std::{cmp::Ordering, fmt::Display};
+
+/**
+ * The memory and registers of a computer at one moment, see
+ * `IntCodeComputer::snapshot`.
+ */
+#[derive(Debug, Clone)]
+pub struct Snapshot<M: Memory = SharedMemory> {
+    memory: M,
+    pointer: Pointer,
+    relative_base: i64,
+    executed: u64,
+    running: RunningState,
+}
+
+/** A cell that holds a different value in the later snapshot */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellChange {
+    pub address: Pointer,
+    pub old: i64,
+    pub new: i64,
+}
+
+/**
+ * What happened between two snapshots of the same run.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryDiff {
+    /** Instructions executed in between, negative if the later snapshot is the older one */
+    pub instructions: i64,
+    pub pointer: (Pointer, Pointer),
+    pub relative_base: (i64, i64),
+    /** Ordered by address */
+    pub changes: Vec<CellChange>,
+}
+
+impl<M: Memory> Snapshot<M> {
+    pub(super) fn new(
+        memory: &M,
+        pointer: Pointer,
+        relative_base: i64,
+        executed: u64,
+        running: RunningState,
+    ) -> Self {
+        Self {
+            memory: memory.clone(),
+            pointer,
+            relative_base,
+            executed,
+            running,
+        }
+    }
+
+    pub(super) fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    pub(super) fn running(&self) -> RunningState {
+        self.running
+    }
+
+    pub fn get(&self, address: Pointer) -> i64 {
+        self.memory.get(address)
+    }
+
+    pub fn pointer(&self) -> Pointer {
+        self.pointer
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    /** Instructions the computer executed since it was built or reset */
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    /**
+     * Every cell that changed from this snapshot to the later one. Cells
+     * that changed and changed back in between do not show up.
+     */
+    pub fn diff(&self, later: &Snapshot<M>) -> MemoryDiff {
+        let mut old = self.memory.cells().peekable();
+        let mut new = later.memory.cells().peekable();
+        let mut changes = vec![];
+        loop {
+            let (address, old, new) = match (old.peek(), new.peek()) {
+                (None, None) => break,
+                (Some(&(address, value)), None) => {
+                    old.next();
+                    (address, value, 0)
+                }
+                (None, Some(&(address, value))) => {
+                    new.next();
+                    (address, 0, value)
+                }
+                (Some(&(old_address, old_value)), Some(&(new_address, new_value))) => {
+                    match old_address.cmp(&new_address) {
+                        Ordering::Less => {
+                            old.next();
+                            (old_address, old_value, 0)
+                        }
+                        Ordering::Greater => {
+                            new.next();
+                            (new_address, 0, new_value)
+                        }
+                        Ordering::Equal => {
+                            old.next();
+                            new.next();
+                            (old_address, old_value, new_value)
+                        }
+                    }
+                }
+            };
+            if old != new {
+                changes.push(CellChange {
+                    address: Pointer::new(address),
+                    old,
+                    new,
+                });
+            }
+        }
+
+        MemoryDiff {
+            instructions: later.executed as i64 - self.executed as i64,
+            pointer: (self.pointer, later.pointer),
+            relative_base: (self.relative_base, later.relative_base),
+            changes,
+        }
+    }
+}
+
+impl MemoryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn get(&self, address: Pointer) -> Option<&CellChange> {
+        self.changes
+            .binary_search_by_key(&address.as_usize(), |change| change.address.as_usize())
+            .ok()
+            .map(|index| &self.changes[index])
+    }
+}
+
+impl Display for MemoryDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} instructions", self.instructions)?;
+        writeln!(f, "ip: {} -> {}", self.pointer.0, self.pointer.1)?;
+        writeln!(
+            f,
+            "rb: {} -> {}",
+            self.relative_base.0, self.relative_base.1
+        )?;
+        writeln!(f, "{} cells changed", self.changes.len())?;
+        for change in &self.changes {
+            writeln!(
+                f,
+                "  {:>6}: {:>12} -> {}",
+                change.address.as_usize(),
+                change.old,
+                change.new
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        days::UnitResult,
+        int_code::{assembler::assemble, ComputerFactory},
+    };
+
+    #[test]
+    fn diff() -> UnitResult {
+        // Counts down from the input, keeping the steps in a far cell
+        let mut computer = ComputerFactory::new(assemble(
+            "
+                    IN counter
+            loop:   ADD counter, #-1, counter
+                    ADD 100000, #1, 100000
+                    OUT counter
+                    JT counter, #loop
+                    HALT
+            counter: DATA 0
+            ",
+        )?)
+        .build();
+        computer.send_i64(3);
+        let start = computer.snapshot();
+        assert_eq!(computer.expect_i64()?, 2);
+        let first = computer.snapshot();
+        computer.run_till_halt()?;
+        let end = computer.snapshot();
+
+        let diff = start.diff(&first);
+        assert_eq!(diff.instructions, 4);
+        assert_eq!(diff.pointer, (Pointer::new(0), Pointer::new(12)));
+        let counter = Pointer::new(16);
+        assert_eq!(
+            diff.changes,
+            vec![
+                CellChange {
+                    address: counter,
+                    old: 0,
+                    new: 2
+                },
+                CellChange {
+                    address: Pointer::new(100_000),
+                    old: 0,
+                    new: 1
+                },
+            ]
+        );
+
+        let diff = first.diff(&end);
+        assert_eq!(diff.instructions, 10);
+        assert_eq!(
+            diff.get(counter),
+            Some(&CellChange {
+                address: counter,
+                old: 2,
+                new: 0
+            })
+        );
+        assert_eq!(end.diff(&start).instructions, -14);
+        assert!(end.diff(&end).is_empty());
+
+        Ok(())
+    }
+}
@@ -1,115 +1,670 @@
-use super::computer_error::ComputerError;
+use super::checkpoints::Checkpoints;
+use super::compiled::{BlockCache, CompiledOp, ExecutionMode, Op, Operand, Target};
+use super::computer_error::{ComputerError, Crash, ExecutedInstruction};
+use super::coverage::Coverage;
+use super::input::InputHandle;
+use super::instructions::{self, default_arithmetic, Arithmetic};
+use super::limits::{default_limits, Budget, Limits};
+use super::memory::{Memory, SharedMemory};
+use super::metrics::Metrics;
 use super::param_mode::ParamMode;
-use super::{instructions, Pointer};
-use std::collections::{HashMap, VecDeque};
-
-pub enum StepResult {
-    Continue,
-    Output(i64),
-    Waiting,
-    Halted,
-}
+use super::profile::Profile;
+use super::self_modification::SelfModification;
+use super::session::{IoEvent, Session};
+use super::snapshot::Snapshot;
+use super::trace::{Mnemonic, TraceEntry, TraceEvent, TraceRecorder, TraceSink};
+use super::watch::{Access, Breakpoints, Watch, Watchpoints};
+use super::{Pointer, VmEvent};
+use crate::common::events::{self, Event};
+use std::{collections::VecDeque, sync::Arc};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum RunningState {
     Running,
     Waiting,
+    Halted,
     Error,
 }
 
-pub struct State {
-    memory: HashMap<Pointer, i64>,
+/**
+ * How many of the last executed instructions are kept for error reports.
+ */
+const HISTORY_SIZE: usize = 16;
+
+#[derive(Debug, Clone)]
+struct History {
+    entries: [ExecutedInstruction; HISTORY_SIZE],
+    next: usize,
+    len: usize,
+}
+
+impl History {
+    fn new() -> Self {
+        Self {
+            entries: [ExecutedInstruction {
+                address: Pointer::default(),
+                instruction: 0,
+            }; HISTORY_SIZE],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, address: Pointer, instruction: i64) {
+        self.entries[self.next] = ExecutedInstruction {
+            address,
+            instruction,
+        };
+        self.next = (self.next + 1) % HISTORY_SIZE;
+        self.len = (self.len + 1).min(HISTORY_SIZE);
+    }
+
+    /** Oldest first */
+    fn to_vec(&self) -> Vec<ExecutedInstruction> {
+        let start = (self.next + HISTORY_SIZE - self.len) % HISTORY_SIZE;
+        (0..self.len)
+            .map(|offset| self.entries[(start + offset) % HISTORY_SIZE])
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct State<M: Memory = SharedMemory> {
+    memory: M,
     pointer: Pointer,
     relative_base: i64,
     running: RunningState,
     input_buffer: VecDeque<i64>,
+    input_source: Option<InputHandle>,
+    trace: Option<TraceRecorder>,
+    profile: Option<Profile>,
+    coverage: Option<Coverage>,
+    history: History,
+    executed: u64,
+    budget: Budget,
+    watch: Option<Watchpoints>,
+    breakpoints: Option<Breakpoints>,
+    recording: Option<Session>,
+    compiled: Option<BlockCache>,
+    arithmetic: Arithmetic,
+    checkpoints: Option<Checkpoints<M>>,
+    modification: Option<SelfModification>,
+    inputs: u64,
+    outputs: u64,
+    /** One beyond the furthest address written, at least the program length */
+    memory_cells: usize,
+    /** The part of the metrics already published */
+    reported: Metrics,
 }
 
-impl State {
-    pub fn new(memory: &[i64]) -> State {
-        let memory = memory
-            .iter()
-            .copied()
-            .enumerate()
-            .map(|(p, data)| (Pointer::new(p), data))
-            .collect();
+/**
+ * What a compiled instruction leaves to the block that runs it.
+ */
+enum Flow {
+    Next,
+    /** The instruction overwrote compiled code, possibly the block itself */
+    CodeChanged,
+    Stop(VmEvent),
+}
 
+impl<M: Memory> State<M> {
+    pub fn new(memory: Arc<[i64]>) -> Self {
         Self {
-            memory,
+            memory_cells: memory.len(),
+            memory: M::new(memory),
             pointer: Pointer::default(),
             relative_base: 0,
             running: RunningState::Running,
             input_buffer: VecDeque::new(),
+            input_source: None,
+            trace: None,
+            profile: None,
+            coverage: None,
+            history: History::new(),
+            executed: 0,
+            budget: Budget::new(default_limits()),
+            watch: None,
+            breakpoints: None,
+            recording: None,
+            compiled: None,
+            arithmetic: default_arithmetic(),
+            checkpoints: None,
+            modification: None,
+            inputs: 0,
+            outputs: 0,
+            reported: Metrics::default(),
         }
     }
 
-    pub fn next_instruction(&mut self) -> Result<StepResult, ComputerError> {
+    pub fn next_instruction(&mut self) -> Result<VmEvent, ComputerError> {
         match self.running {
             RunningState::Running => {}
             RunningState::Waiting => {
-                if self.input_buffer.is_empty() {
-                    return Ok(StepResult::Waiting);
+                if self.input_buffer.is_empty() && self.input_source.is_none() {
+                    return Ok(VmEvent::NeedsInput);
                 }
                 self.running = RunningState::Running;
             }
+            RunningState::Halted => return Ok(VmEvent::Halted),
             RunningState::Error => return Err(ComputerError::StoppedAfterError),
         }
+        if let Some(watch) = &mut self.watch
+            && let Some(value) = watch.take_pending_output()
+        {
+            return Ok(VmEvent::Output(value));
+        }
+        if let Some(breakpoints) = &mut self.breakpoints
+            && breakpoints.stops_at(self.pointer)
+        {
+            return Ok(VmEvent::Breakpoint(self.pointer));
+        }
+        if let Some(checkpoints) = &self.checkpoints
+            && checkpoints.is_due(self.executed)
+        {
+            let snapshot = self.snapshot();
+            if let Some(checkpoints) = &mut self.checkpoints {
+                checkpoints.push(snapshot);
+            }
+        }
+        self.budget.spend()?;
 
-        match instructions::run_instruction(self) {
-            Ok(StepResult::Continue) => Ok(StepResult::Continue),
-            Ok(StepResult::Waiting) => {
+        if let Some(trace) = &mut self.trace {
+            trace.start(self.pointer);
+        }
+
+        let address = self.pointer;
+        let instruction = self.get_value_at(address);
+        let mnemonic =
+            (self.profile.is_some() || self.coverage.is_some() || self.modification.is_some())
+                .then(|| Mnemonic::from_instruction(instruction));
+        if let Some(modification) = &mut self.modification {
+            modification.start(address);
+        }
+        let result = instructions::run_instruction(self);
+        match result {
+            Ok(VmEvent::NeedsInput) => {}
+            Ok(VmEvent::Output(_)) => {
+                self.history.push(address, instruction);
+                self.executed += 1;
+                self.outputs += 1;
+            }
+            _ => {
+                self.history.push(address, instruction);
+                self.executed += 1;
+            }
+        }
+        if !matches!(result, Ok(VmEvent::NeedsInput))
+            && let Some(breakpoints) = &mut self.breakpoints
+        {
+            breakpoints.executed();
+        }
+        if let Some(trace) = &mut self.trace {
+            match result {
+                Ok(VmEvent::Output(value)) => {
+                    trace.event(TraceEvent::Output(value));
+                    trace.finish();
+                }
+                Ok(VmEvent::Continue | VmEvent::Halted | VmEvent::Watchpoint(_)) => trace.finish(),
+                Ok(VmEvent::NeedsInput | VmEvent::Breakpoint(_)) | Err(_) => trace.discard(),
+            }
+        }
+
+        if let Some(recording) = &mut self.recording
+            && let Ok(VmEvent::Output(value)) = result
+        {
+            recording.push(IoEvent::Output(value));
+        }
+
+        if let Some(mnemonic) = mnemonic
+            && matches!(
+                result,
+                Ok(VmEvent::Continue | VmEvent::Output(_) | VmEvent::Halted)
+            )
+        {
+            if let Some(profile) = &mut self.profile {
+                profile.record(address, mnemonic, self.pointer);
+            }
+            if let Some(coverage) = &mut self.coverage {
+                coverage.record(address, mnemonic.parameter_count() + 1);
+            }
+            if let Some(modification) = &mut self.modification {
+                modification.executed(mnemonic.parameter_count() + 1);
+            }
+        }
+        if let Some(modification) = &mut self.modification {
+            modification.stop();
+        }
+
+        let result = match (result, self.watch.as_mut().and_then(Watchpoints::take_hit)) {
+            (Ok(VmEvent::Continue), Some(hit)) => Ok(VmEvent::Watchpoint(hit)),
+            (Ok(VmEvent::Output(value)), Some(hit)) => {
+                if let Some(watch) = &mut self.watch {
+                    watch.hold_output(value);
+                }
+                Ok(VmEvent::Watchpoint(hit))
+            }
+            (result, _) => result,
+        };
+
+        match result {
+            Ok(VmEvent::Continue) => Ok(VmEvent::Continue),
+            Ok(VmEvent::NeedsInput) => {
                 self.running = RunningState::Waiting;
-                Ok(StepResult::Waiting)
+                Ok(VmEvent::NeedsInput)
+            }
+            Ok(VmEvent::Output(value)) => Ok(VmEvent::Output(value)),
+            Ok(VmEvent::Breakpoint(pointer)) => Ok(VmEvent::Breakpoint(pointer)),
+            Ok(VmEvent::Watchpoint(hit)) => Ok(VmEvent::Watchpoint(hit)),
+            Ok(VmEvent::Halted) => {
+                self.halt();
+                Ok(VmEvent::Halted)
+            }
+            Err(err) => Err(self.crash(address, instruction, err)),
+        }
+    }
+
+    fn crash(&mut self, pointer: Pointer, instruction: i64, cause: ComputerError) -> ComputerError {
+        self.running = RunningState::Error;
+        ComputerError::Crashed(Box::new(Crash {
+            pointer,
+            instruction,
+            recent: self.history.to_vec(),
+            cause,
+        }))
+    }
+
+    /**
+     * Like `next_instruction`, but runs compiled blocks until the program
+     * outputs, waits or halts, if the computer compiles and nothing needs
+     * to see every instruction. May return `Continue` after anything from
+     * one instruction to many blocks.
+     */
+    pub fn next_block(&mut self) -> Result<VmEvent, ComputerError> {
+        if self.compiled.is_none()
+            || self.running != RunningState::Running
+            || self.trace.is_some()
+            || self.profile.is_some()
+            || self.coverage.is_some()
+            || self.watch.is_some()
+            || self.breakpoints.is_some()
+            || self.recording.is_some()
+            || self.checkpoints.is_some()
+            || self.modification.is_some()
+        {
+            return self.next_instruction();
+        }
+
+        loop {
+            let block = match &mut self.compiled {
+                Some(cache) => cache.get(&self.memory, self.pointer),
+                None => None,
+            };
+            let Some(block) = block else {
+                return self.next_instruction();
+            };
+            for op in block.ops() {
+                self.budget.spend()?;
+                self.pointer = op.next;
+                let flow = match self.execute(op) {
+                    Ok(Flow::Stop(VmEvent::NeedsInput)) => {
+                        self.pointer = op.address;
+                        self.running = RunningState::Waiting;
+                        return Ok(VmEvent::NeedsInput);
+                    }
+                    Ok(flow) => flow,
+                    Err(err) => {
+                        self.history.push(op.address, op.instruction);
+                        self.executed += 1;
+                        return Err(self.crash(op.address, op.instruction, err));
+                    }
+                };
+                self.history.push(op.address, op.instruction);
+                self.executed += 1;
+                match flow {
+                    Flow::Next => {}
+                    Flow::CodeChanged => break,
+                    Flow::Stop(VmEvent::Halted) => {
+                        self.halt();
+                        return Ok(VmEvent::Halted);
+                    }
+                    Flow::Stop(result) => return Ok(result),
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn execute(&mut self, op: &CompiledOp) -> Result<Flow, ComputerError> {
+        match op.op {
+            Op::Add(op1, op2, target) => {
+                let value = self
+                    .arithmetic
+                    .add(self.operand(op1)?, self.operand(op2)?)?;
+                self.write(target, value)
+            }
+            Op::Mul(op1, op2, target) => {
+                let value = self
+                    .arithmetic
+                    .mul(self.operand(op1)?, self.operand(op2)?)?;
+                self.write(target, value)
+            }
+            Op::Input(target) => match self.get_input() {
+                Some(value) => self.write(target, value),
+                None => Ok(Flow::Stop(VmEvent::NeedsInput)),
+            },
+            Op::Output(op1) => {
+                let value = self.operand(op1)?;
+                self.outputs += 1;
+                Ok(Flow::Stop(VmEvent::Output(value)))
+            }
+            Op::JumpIfTrue(test, target) => {
+                let (test, target) = (self.operand(test)?, self.operand(target)?);
+                if test != 0 {
+                    self.pointer = Pointer::from_i64(target)?;
+                }
+                Ok(Flow::Next)
             }
-            Ok(StepResult::Output(value)) => Ok(StepResult::Output(value)),
-            Ok(StepResult::Halted) => Ok(StepResult::Halted),
-            Err(err) => {
-                self.running = RunningState::Error;
-                Err(err)
+            Op::JumpIfFalse(test, target) => {
+                let (test, target) = (self.operand(test)?, self.operand(target)?);
+                if test == 0 {
+                    self.pointer = Pointer::from_i64(target)?;
+                }
+                Ok(Flow::Next)
+            }
+            Op::LessThan(op1, op2, target) => {
+                let value = (self.operand(op1)? < self.operand(op2)?) as i64;
+                self.write(target, value)
+            }
+            Op::Equals(op1, op2, target) => {
+                let value = (self.operand(op1)? == self.operand(op2)?) as i64;
+                self.write(target, value)
+            }
+            Op::RelativeBase(op1) => {
+                self.relative_base = self.relative(self.operand(op1)?)?;
+                Ok(Flow::Next)
             }
+            Op::Halt => Ok(Flow::Stop(VmEvent::Halted)),
         }
     }
 
+    #[inline]
+    fn operand(&self, operand: Operand) -> Result<i64, ComputerError> {
+        match operand {
+            Operand::Position(address) => Ok(self.memory.get(address)),
+            Operand::Immediate(value) => Ok(value),
+            Operand::Relative(offset) => {
+                Ok(self.memory.get(Pointer::from_i64(self.relative(offset)?)?))
+            }
+            Operand::Patched(mode, word) => {
+                self.operand(Operand::new(mode, self.memory.get(word))?)
+            }
+        }
+    }
+
+    #[inline]
+    fn write(&mut self, target: Target, value: i64) -> Result<Flow, ComputerError> {
+        let address = match target {
+            Target::Position(address) => address,
+            Target::Relative(offset) => Pointer::from_i64(self.relative(offset)?)?,
+            Target::Patched(mode, word) => {
+                return self.write(Target::new(mode, self.memory.get(word))?, value);
+            }
+        };
+        self.memory_cells = self.memory_cells.max(address.as_usize() + 1);
+        self.memory.set(address, value);
+        if self
+            .compiled
+            .as_mut()
+            .is_some_and(|cache| cache.written(address))
+        {
+            Ok(Flow::CodeChanged)
+        } else {
+            Ok(Flow::Next)
+        }
+    }
+
+    pub fn set_execution_mode(&mut self, mode: ExecutionMode) {
+        match mode {
+            ExecutionMode::Interpreted => self.compiled = None,
+            ExecutionMode::Compiled => {
+                self.compiled.get_or_insert_with(BlockCache::default);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    pub fn take_compiled(&mut self) -> Option<BlockCache> {
+        self.compiled.take()
+    }
+
+    /** Keeps the blocks that still match the memory */
+    pub fn set_compiled(&mut self, compiled: Option<BlockCache>) {
+        self.compiled = compiled.map(|cache| cache.revalidate(&self.memory));
+    }
+
+    pub fn pointer(&self) -> Pointer {
+        self.pointer
+    }
+
+    #[allow(dead_code)]
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    pub fn watch(&mut self, address: Pointer, watch: Watch) {
+        self.watch
+            .get_or_insert_with(Watchpoints::default)
+            .set(address, watch);
+    }
+
+    pub fn set_breakpoint(&mut self, address: Pointer) {
+        self.breakpoints
+            .get_or_insert_with(Breakpoints::default)
+            .set(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: Pointer) {
+        if let Some(breakpoints) = &mut self.breakpoints {
+            breakpoints.remove(address);
+        }
+    }
+
+    pub fn take_breakpoints(&mut self) -> Option<Breakpoints> {
+        self.breakpoints.take()
+    }
+
+    pub fn set_breakpoints(&mut self, breakpoints: Option<Breakpoints>) {
+        self.breakpoints = breakpoints;
+    }
+
+    pub fn take_watch(&mut self) -> Option<Watchpoints> {
+        self.watch.take()
+    }
+
+    pub fn set_watch(&mut self, watch: Option<Watchpoints>) {
+        self.watch = watch;
+    }
+
+    pub fn unwatch(&mut self, address: Pointer) {
+        if let Some(watch) = &mut self.watch {
+            watch.remove(address);
+        }
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Session::default());
+    }
+
+    pub fn take_recording(&mut self) -> Option<Session> {
+        self.recording.take()
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.budget.limits()
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.budget = Budget::new(limits);
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.budget.steps()
+    }
+
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    fn halt(&mut self) {
+        self.running = RunningState::Halted;
+        self.publish_metrics();
+    }
+
+    /**
+     * Publishes what the computer did since the metrics were published
+     * last, if it did anything at all.
+     */
+    pub fn publish_metrics(&mut self) {
+        let metrics = self.metrics();
+        let reported = std::mem::replace(&mut self.reported, metrics);
+        let instructions = metrics.executed.saturating_sub(reported.executed);
+        let inputs = metrics.inputs - reported.inputs;
+        let outputs = metrics.outputs - reported.outputs;
+        if instructions == 0 && inputs == 0 && outputs == 0 {
+            return;
+        }
+        let halted = self.running == RunningState::Halted;
+        events::publish_with(|| Event::ComputerMetrics {
+            halted,
+            instructions,
+            inputs,
+            outputs,
+            memory_cells: metrics.memory_cells,
+        });
+    }
+
+    /** Leaves everything done so far to be published by someone else */
+    pub fn mark_published(&mut self) {
+        self.reported = self.metrics();
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            executed: self.executed,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            memory_cells: self.memory_cells,
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot<M> {
+        Snapshot::new(
+            &self.memory,
+            self.pointer,
+            self.relative_base,
+            self.executed,
+            self.running,
+        )
+    }
+
+    /**
+     * Back to the moment of the snapshot. Input not read yet is dropped,
+     * compiled blocks are kept as far as they still match the memory.
+     */
+    pub fn restore(&mut self, snapshot: &Snapshot<M>) {
+        self.memory = snapshot.memory().clone();
+        self.pointer = snapshot.pointer();
+        self.relative_base = snapshot.relative_base();
+        self.executed = snapshot.executed();
+        self.running = snapshot.running();
+        self.input_buffer.clear();
+        self.history = History::new();
+        let compiled = self.compiled.take();
+        self.set_compiled(compiled);
+    }
+
     #[inline]
     pub fn get_value_at(&self, pointer: Pointer) -> i64 {
-        self.memory.get(&pointer).copied().unwrap_or_default()
+        self.memory.get(pointer)
     }
 
     pub fn get_next(&mut self) -> i64 {
         let value = self.get_value_at(self.pointer);
         self.pointer.inc();
+        if let Some(trace) = &mut self.trace {
+            trace.word(value);
+        }
         value
     }
 
     pub fn get_value(&mut self, pm: ParamMode) -> Result<i64, ComputerError> {
         let value = self.get_next();
         match pm {
-            ParamMode::Position => Ok(self.get_value_at(Pointer::from_i64(value)?)),
-            ParamMode::Relative => {
-                Ok(self.get_value_at(Pointer::from_i64(self.relative_base + value)?))
-            }
+            ParamMode::Position => self.read_traced(Pointer::from_i64(value)?),
+            ParamMode::Relative => self.read_traced(Pointer::from_i64(self.relative(value)?)?),
             ParamMode::Immediate => Ok(value),
             ParamMode::Illegal => Err(ComputerError::IllegalParamMode),
         }
     }
 
+    fn read_traced(&mut self, addr: Pointer) -> Result<i64, ComputerError> {
+        let value = self.get_value_at(addr);
+        self.trace_event(TraceEvent::Read(addr, value));
+        if let Some(watch) = &mut self.watch {
+            watch.check(addr, Access::Read, value);
+        }
+        Ok(value)
+    }
+
     #[inline]
     pub fn get_address(&mut self, pm: ParamMode) -> Result<Pointer, ComputerError> {
         let value = self.get_next();
         match pm {
             ParamMode::Position => Pointer::from_i64(value),
-            ParamMode::Relative => Pointer::from_i64(self.relative_base + value),
+            ParamMode::Relative => Pointer::from_i64(self.relative(value)?),
             ParamMode::Immediate | ParamMode::Illegal => Err(ComputerError::IllegalParamMode),
         }
     }
 
     pub fn set_value(&mut self, addr: Pointer, value: i64) {
-        self.memory.insert(addr, value);
+        self.trace_event(TraceEvent::Write(addr, value));
+        if let Some(watch) = &mut self.watch {
+            watch.check(addr, Access::Write, value);
+        }
+        if let Some(cache) = &mut self.compiled {
+            cache.written(addr);
+        }
+        if let Some(modification) = &mut self.modification {
+            modification.write(addr);
+        }
+        self.memory_cells = self.memory_cells.max(addr.as_usize() + 1);
+        self.memory.set(addr, value);
     }
 
     #[inline]
     pub fn get_input(&mut self) -> Option<i64> {
-        self.input_buffer.pop_front()
+        let value = match self.input_buffer.pop_front() {
+            Some(value) => value,
+            None => self.input_source.as_ref()?.next_input()?,
+        };
+        self.trace_event(TraceEvent::Input(value));
+        if let Some(recording) = &mut self.recording {
+            recording.push(IoEvent::Input(value));
+        }
+        if let Some(checkpoints) = &mut self.checkpoints {
+            checkpoints.input(self.executed, value);
+        }
+        self.inputs += 1;
+        Some(value)
     }
 
     #[inline]
@@ -117,15 +672,133 @@ impl State {
         self.input_buffer.push_back(value);
     }
 
+    pub fn set_input_source(&mut self, source: Option<InputHandle>) {
+        self.input_source = source;
+    }
+
+    pub fn take_input_source(&mut self) -> Option<InputHandle> {
+        self.input_source.take()
+    }
+
     pub fn repeat(&mut self) {
         self.pointer.dec();
     }
 
     pub fn set_pointer(&mut self, target: Pointer) {
+        self.trace_event(TraceEvent::Jump(target));
         self.pointer = target
     }
 
-    pub fn adjust_relative_base(&mut self, relative_base: i64) {
-        self.relative_base += relative_base
+    pub fn adjust_relative_base(&mut self, relative_base: i64) -> Result<(), ComputerError> {
+        self.relative_base = self.relative(relative_base)?;
+        self.trace_event(TraceEvent::RelativeBase(self.relative_base));
+        Ok(())
+    }
+
+    /** The relative base moved by `offset`, which may not overflow */
+    #[inline]
+    fn relative(&self, offset: i64) -> Result<i64, ComputerError> {
+        self.relative_base
+            .checked_add(offset)
+            .ok_or(ComputerError::RelativeOverflow(self.relative_base, offset))
+    }
+
+    #[inline]
+    fn trace_event(&mut self, event: TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.event(event);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(TraceRecorder::default);
+    }
+
+    #[allow(dead_code)]
+    pub fn stream_trace(&mut self, sink: TraceSink) {
+        self.trace = Some(TraceRecorder::streaming(sink));
+    }
+
+    #[allow(dead_code)]
+    pub fn enable_profile(&mut self) {
+        self.profile.get_or_insert_with(Profile::default);
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    pub fn set_profile(&mut self, profile: Option<Profile>) {
+        self.profile = profile;
+    }
+
+    #[allow(dead_code)]
+    pub fn enable_coverage(&mut self) {
+        self.coverage.get_or_insert_with(Coverage::default);
+    }
+
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    pub fn enable_checkpoints(&mut self, interval: u64, keep: usize) {
+        self.checkpoints = Some(Checkpoints::new(interval, keep));
+    }
+
+    pub fn checkpoints_mut(&mut self) -> Option<&mut Checkpoints<M>> {
+        self.checkpoints.as_mut()
+    }
+
+    pub fn take_checkpoints(&mut self) -> Option<Checkpoints<M>> {
+        self.checkpoints.take()
+    }
+
+    pub fn set_checkpoints(&mut self, checkpoints: Option<Checkpoints<M>>) {
+        self.checkpoints = checkpoints;
+    }
+
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    pub fn take_input_buffer(&mut self) -> VecDeque<i64> {
+        std::mem::take(&mut self.input_buffer)
+    }
+
+    pub fn enable_modification_tracking(&mut self) {
+        self.modification = Some(SelfModification::default());
+    }
+
+    pub fn modification(&self) -> Option<&SelfModification> {
+        self.modification.as_ref()
+    }
+
+    pub fn take_modification(&mut self) -> Option<SelfModification> {
+        self.modification.take()
+    }
+
+    pub fn set_modification(&mut self, modification: Option<SelfModification>) {
+        self.modification = modification;
+    }
+
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    pub fn set_coverage(&mut self, coverage: Option<Coverage>) {
+        self.coverage = coverage;
+    }
+
+    #[allow(dead_code)]
+    pub fn take_trace(&mut self) -> Vec<TraceEntry> {
+        self.trace
+            .as_mut()
+            .map(TraceRecorder::take)
+            .unwrap_or_default()
     }
 }
@@ -0,0 +1,91 @@
+use super::{ascii::AsciiComputer, ComputerError};
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TerminalError {
+    #[error("Computer error: {0}")]
+    ComputerError(#[from] ComputerError),
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+}
+
+/**
+ * How a session ended.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEnd {
+    /** The program halted */
+    Halted,
+    /** The program wanted more input than there was */
+    EndOfInput,
+}
+
+/**
+ * Lets somebody talk to an ASCII program: its lines go to `output` and
+ * whenever it waits for input, the next line of `input` is sent.
+ * Values that are no ASCII chars are shown on their own line. With `echo`
+ * every line sent is written to `output` as well, so a session fed from a
+ * file reads like one typed by hand.
+ */
+pub fn play(
+    computer: &mut AsciiComputer,
+    mut input: impl BufRead,
+    mut output: impl Write,
+    echo: bool,
+) -> Result<SessionEnd, TerminalError> {
+    loop {
+        match computer.read_line() {
+            Ok(Some(line)) => writeln!(output, "{line}")?,
+            Ok(None) => match computer.read_value()? {
+                Some(value) => writeln!(output, "[{value}]")?,
+                None => return Ok(SessionEnd::Halted),
+            },
+            Err(err) if matches!(err.cause(), ComputerError::WaitingForInput) => {
+                write!(output, "> ")?;
+                output.flush()?;
+                let mut line = String::new();
+                if input.read_line(&mut line)? == 0 {
+                    writeln!(output)?;
+                    return Ok(SessionEnd::EndOfInput);
+                }
+                let line = line.trim_end_matches(['\r', '\n']);
+                if echo {
+                    writeln!(output, "{line}")?;
+                }
+                computer.send_line(line);
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::ComputerFactory;
+    use itertools::Itertools;
+
+    #[test]
+    fn session() -> Result<(), TerminalError> {
+        // Prints "Go?\n", echoes two chars of input, then prints 1234
+        let mut code = "Go?\n".chars().flat_map(|c| [104, c as i64]).collect_vec();
+        let cell = code.len() + 11;
+        code.extend([3, cell as i64, 4, cell as i64]);
+        code.extend([3, cell as i64, 4, cell as i64, 104, 1234, 99, 0]);
+        let factory = ComputerFactory::new(code);
+
+        let mut output = vec![];
+        let mut computer = AsciiComputer::new(factory.build());
+        let end = play(&mut computer, "a\n".as_bytes(), &mut output, true)?;
+        assert_eq!(end, SessionEnd::Halted);
+        assert_eq!(String::from_utf8_lossy(&output), "Go?\n> a\na\n[1234]\n");
+
+        let mut output = vec![];
+        let mut computer = AsciiComputer::new(factory.build());
+        let end = play(&mut computer, "".as_bytes(), &mut output, false)?;
+        assert_eq!(end, SessionEnd::EndOfInput);
+        assert_eq!(String::from_utf8_lossy(&output), "Go?\n> \n");
+
+        Ok(())
+    }
+}
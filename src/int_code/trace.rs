@@ -0,0 +1,433 @@
+#![allow(dead_code)]
+use super::Pointer;
+use crate::common::diff::render_diff;
+use itertools::Itertools;
+use std::{
+    fmt::{Debug, Display},
+    io::{self, BufRead, Write},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TraceError {
+    #[error("IO Error")]
+    IoError(#[from] io::Error),
+
+    #[error("Not a valid trace line: {0}")]
+    InvalidLine(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mnemonic {
+    Add,
+    Mul,
+    In,
+    Out,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    RelativeBase,
+    Halt,
+    Illegal,
+}
+
+impl Mnemonic {
+    pub fn from_instruction(instruction: i64) -> Self {
+        match instruction.rem_euclid(100) {
+            1 => Mnemonic::Add,
+            2 => Mnemonic::Mul,
+            3 => Mnemonic::In,
+            4 => Mnemonic::Out,
+            5 => Mnemonic::JumpIfTrue,
+            6 => Mnemonic::JumpIfFalse,
+            7 => Mnemonic::LessThan,
+            8 => Mnemonic::Equals,
+            9 => Mnemonic::RelativeBase,
+            99 => Mnemonic::Halt,
+            _ => Mnemonic::Illegal,
+        }
+    }
+
+    pub fn opcode(&self) -> Option<i64> {
+        match self {
+            Mnemonic::Add => Some(1),
+            Mnemonic::Mul => Some(2),
+            Mnemonic::In => Some(3),
+            Mnemonic::Out => Some(4),
+            Mnemonic::JumpIfTrue => Some(5),
+            Mnemonic::JumpIfFalse => Some(6),
+            Mnemonic::LessThan => Some(7),
+            Mnemonic::Equals => Some(8),
+            Mnemonic::RelativeBase => Some(9),
+            Mnemonic::Halt => Some(99),
+            Mnemonic::Illegal => None,
+        }
+    }
+
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => 3,
+            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse => 2,
+            Mnemonic::In | Mnemonic::Out | Mnemonic::RelativeBase => 1,
+            Mnemonic::Halt | Mnemonic::Illegal => 0,
+        }
+    }
+
+    /**
+     * The parameter the instruction writes to, if any. It can never be immediate.
+     */
+    pub fn written_parameter(&self) -> Option<usize> {
+        match self {
+            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => Some(2),
+            Mnemonic::In => Some(0),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Mnemonic::Add => "ADD",
+            Mnemonic::Mul => "MUL",
+            Mnemonic::In => "IN",
+            Mnemonic::Out => "OUT",
+            Mnemonic::JumpIfTrue => "JT",
+            Mnemonic::JumpIfFalse => "JF",
+            Mnemonic::LessThan => "LT",
+            Mnemonic::Equals => "EQ",
+            Mnemonic::RelativeBase => "RB",
+            Mnemonic::Halt => "HALT",
+            Mnemonic::Illegal => "ILL",
+        }
+    }
+}
+
+impl Display for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = TraceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ADD" => Ok(Mnemonic::Add),
+            "MUL" => Ok(Mnemonic::Mul),
+            "IN" => Ok(Mnemonic::In),
+            "OUT" => Ok(Mnemonic::Out),
+            "JT" => Ok(Mnemonic::JumpIfTrue),
+            "JF" => Ok(Mnemonic::JumpIfFalse),
+            "LT" => Ok(Mnemonic::LessThan),
+            "EQ" => Ok(Mnemonic::Equals),
+            "RB" => Ok(Mnemonic::RelativeBase),
+            "HALT" => Ok(Mnemonic::Halt),
+            "ILL" => Ok(Mnemonic::Illegal),
+            _ => Err(TraceError::InvalidLine(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    /** A parameter in position or relative mode was resolved to this value */
+    Read(Pointer, i64),
+    Write(Pointer, i64),
+    Jump(Pointer),
+    RelativeBase(i64),
+    Input(i64),
+    Output(i64),
+}
+
+impl Display for TraceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceEvent::Read(addr, value) => write!(f, "read {addr} {value}"),
+            TraceEvent::Write(addr, value) => write!(f, "write {addr} {value}"),
+            TraceEvent::Jump(addr) => write!(f, "jump {addr}"),
+            TraceEvent::RelativeBase(base) => write!(f, "base {base}"),
+            TraceEvent::Input(value) => write!(f, "in {value}"),
+            TraceEvent::Output(value) => write!(f, "out {value}"),
+        }
+    }
+}
+
+impl FromStr for TraceEvent {
+    type Err = TraceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TraceError::InvalidLine(s.to_owned());
+        let parts = s.split_whitespace().collect_vec();
+        let int = |idx: usize| -> Result<i64, TraceError> {
+            parts
+                .get(idx)
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(invalid)
+        };
+        let pointer = |idx: usize| Pointer::from_i64(int(idx)?).map_err(|_| invalid());
+
+        match (parts.first().copied(), parts.len()) {
+            (Some("read"), 3) => Ok(TraceEvent::Read(pointer(1)?, int(2)?)),
+            (Some("write"), 3) => Ok(TraceEvent::Write(pointer(1)?, int(2)?)),
+            (Some("jump"), 2) => Ok(TraceEvent::Jump(pointer(1)?)),
+            (Some("base"), 2) => Ok(TraceEvent::RelativeBase(int(1)?)),
+            (Some("in"), 2) => Ok(TraceEvent::Input(int(1)?)),
+            (Some("out"), 2) => Ok(TraceEvent::Output(int(1)?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/**
+ * One executed instruction. A line of the textual format looks like
+ * `000012 1101 ADD 3 4 5 | write 5 7`: the address, the raw instruction,
+ * its mnemonic, the raw parameters and all effects of the instruction.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub address: Pointer,
+    pub instruction: i64,
+    pub mnemonic: Mnemonic,
+    pub operands: Vec<i64>,
+    pub events: Vec<TraceEvent>,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:06} {} {}",
+            self.address.as_usize(),
+            self.instruction,
+            self.mnemonic
+        )?;
+        for operand in &self.operands {
+            write!(f, " {operand}")?;
+        }
+        for event in &self.events {
+            write!(f, " | {event}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TraceEntry {
+    type Err = TraceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TraceError::InvalidLine(s.to_owned());
+        let mut sections = s.split(" | ");
+        let mut words = sections.next().ok_or_else(invalid)?.split_whitespace();
+
+        let address = words
+            .next()
+            .and_then(|addr| addr.parse().ok())
+            .map(Pointer::new)
+            .ok_or_else(invalid)?;
+        let instruction = words
+            .next()
+            .and_then(|instr| instr.parse().ok())
+            .ok_or_else(invalid)?;
+        let mnemonic: Mnemonic = words.next().ok_or_else(invalid)?.parse()?;
+        if mnemonic != Mnemonic::from_instruction(instruction) {
+            return Err(invalid());
+        }
+        let operands = words
+            .map(|word| word.parse().map_err(|_| invalid()))
+            .try_collect()?;
+        let events = sections.map(|event| event.parse()).try_collect()?;
+
+        Ok(Self {
+            address,
+            instruction,
+            mnemonic,
+            operands,
+            events,
+        })
+    }
+}
+
+/**
+ * Gets every finished entry right away instead of collecting them.
+ */
+type TraceCallback = dyn FnMut(&TraceEntry) + Send;
+
+#[derive(Clone)]
+pub(super) struct TraceSink(Arc<Mutex<TraceCallback>>);
+
+impl TraceSink {
+    pub fn new(callback: impl FnMut(&TraceEntry) + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(callback)))
+    }
+}
+
+impl Debug for TraceSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TraceSink")
+    }
+}
+
+/**
+ * Collects the entries while the computer is running. Words and events are
+ * gathered for the current instruction and only become an entry once the
+ * instruction completed, so an input instruction that has to wait is not recorded.
+ */
+#[derive(Debug, Default, Clone)]
+pub(super) struct TraceRecorder {
+    entries: Vec<TraceEntry>,
+    current: Option<(Pointer, Vec<i64>, Vec<TraceEvent>)>,
+    sink: Option<TraceSink>,
+}
+
+impl TraceRecorder {
+    pub fn streaming(sink: TraceSink) -> Self {
+        Self {
+            sink: Some(sink),
+            ..Default::default()
+        }
+    }
+
+    pub fn start(&mut self, address: Pointer) {
+        self.current = Some((address, vec![], vec![]));
+    }
+
+    pub fn word(&mut self, word: i64) {
+        if let Some((_, words, _)) = &mut self.current {
+            words.push(word);
+        }
+    }
+
+    pub fn event(&mut self, event: TraceEvent) {
+        if let Some((_, _, events)) = &mut self.current {
+            events.push(event);
+        }
+    }
+
+    pub fn finish(&mut self) {
+        let Some((address, words, events)) = self.current.take() else {
+            return;
+        };
+        let Some((instruction, operands)) = words.split_first() else {
+            return;
+        };
+        let entry = TraceEntry {
+            address,
+            instruction: *instruction,
+            mnemonic: Mnemonic::from_instruction(*instruction),
+            operands: operands.to_vec(),
+            events,
+        };
+        match &self.sink {
+            Some(TraceSink(callback)) => (callback.lock().unwrap())(&entry),
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn discard(&mut self) {
+        self.current = None;
+    }
+
+    pub fn take(&mut self) -> Vec<TraceEntry> {
+        std::mem::take(&mut self.entries)
+    }
+}
+
+pub fn write_trace<W: Write>(writer: &mut W, trace: &[TraceEntry]) -> io::Result<()> {
+    for entry in trace {
+        writeln!(writer, "{entry}")?;
+    }
+    Ok(())
+}
+
+pub fn read_trace<R: BufRead>(reader: R) -> Result<Vec<TraceEntry>, TraceError> {
+    reader
+        .lines()
+        .filter_ok(|line| !line.trim().is_empty())
+        .map(|line| line?.parse())
+        .try_collect()
+}
+
+/**
+ * Shows where two traces diverge, with a few equal lines around every change.
+ */
+pub fn diff_traces(left: &[TraceEntry], right: &[TraceEntry]) -> String {
+    let left = left.iter().map(|entry| entry.to_string()).collect_vec();
+    let right = right.iter().map(|entry| entry.to_string()).collect_vec();
+    render_diff(&left, &right, Some(2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::int_code::ComputerFactory;
+
+    #[test]
+    fn round_trip() -> Result<(), TraceError> {
+        let factory = ComputerFactory::new(vec![3, 9, 1002, 9, 3, 9, 4, 9, 99, 0]);
+        let mut computer = factory.build();
+        computer.enable_trace();
+        computer.send_i64(7);
+        assert_eq!(computer.expect_i64().unwrap(), 21);
+        computer.run_till_halt().unwrap();
+        let trace = computer.take_trace();
+
+        let mut buffer = vec![];
+        write_trace(&mut buffer, &trace)?;
+        let text = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            text,
+            "000000 3 IN 9 | in 7 | write 9 7\n\
+             000002 1002 MUL 9 3 9 | read 9 7 | write 9 21\n\
+             000006 4 OUT 9 | read 9 21 | out 21\n\
+             000008 99 HALT\n"
+        );
+
+        assert_eq!(read_trace(text.as_bytes())?, trace);
+
+        Ok(())
+    }
+
+    #[test]
+    fn streaming() {
+        let lines = Arc::new(Mutex::new(vec![]));
+        let collected = lines.clone();
+        let factory = ComputerFactory::new(vec![1101, 2, 3, 5, 104, 0, 99]);
+        let mut computer = factory.build();
+        computer.stream_trace(move |entry| collected.lock().unwrap().push(entry.to_string()));
+        computer.run_till_halt().unwrap();
+
+        assert_eq!(
+            *lines.lock().unwrap(),
+            vec![
+                "000000 1101 ADD 2 3 5 | write 5 5",
+                "000004 104 OUT 5 | out 5",
+                "000006 99 HALT"
+            ]
+        );
+        assert!(computer.take_trace().is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        let factory = ComputerFactory::new(vec![3, 9, 1002, 9, 3, 9, 4, 9, 99, 0]);
+        let traces = [7, 8]
+            .into_iter()
+            .map(|input| {
+                let mut computer = factory.build();
+                computer.enable_trace();
+                computer.send_i64(input);
+                computer.run_till_halt().unwrap();
+                computer.take_trace()
+            })
+            .collect_vec();
+
+        let diff = diff_traces(&traces[0], &traces[1]);
+        assert!(diff.contains("- 000000 3 IN 9 | in 7 | write 9 7"));
+        assert!(diff.contains("+ 000000 3 IN 9 | in 8 | write 9 8"));
+        assert!(diff.contains("  000008 99 HALT"));
+        assert_eq!(diff_traces(&traces[0], &traces[0]), "@ 4 equal lines");
+    }
+}
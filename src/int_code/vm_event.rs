@@ -0,0 +1,26 @@
+use super::{watch::WatchHit, Pointer};
+
+/**
+ * What a single step of a computer did. The interpreter, the compiled
+ * blocks and `IntCodeComputer::step` all report with it, the ways of
+ * running a computer that do not stop for breakpoints and watchpoints
+ * simply go on after those.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmEvent {
+    Continue,
+    Output(i64),
+    /** Nothing was executed, the next instruction needs input */
+    NeedsInput,
+    Halted,
+    /**
+     * Nothing was executed, the next instruction is at a breakpoint. The
+     * step after this executes it.
+     */
+    Breakpoint(Pointer),
+    /**
+     * The instruction accessed a watched address. If it also had an output,
+     * that comes with the next step, without executing anything.
+     */
+    Watchpoint(WatchHit),
+}
@@ -0,0 +1,144 @@
+#![allow(dead_code)]
+use super::Pointer;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/**
+ * Which accesses to an address pause the computer.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watch {
+    Reads,
+    Writes,
+    All,
+}
+
+impl Watch {
+    fn covers(&self, access: Access) -> bool {
+        matches!(
+            (self, access),
+            (Watch::All, _) | (Watch::Reads, Access::Read) | (Watch::Writes, Access::Write)
+        )
+    }
+}
+
+/**
+ * A watched address was accessed. For reads, `value` is what was read,
+ * for writes what was written.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    pub address: Pointer,
+    pub access: Access,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Watchpoints {
+    watched: HashMap<Pointer, Watch>,
+    hit: Option<WatchHit>,
+    /** An output that was held back to report a hit first */
+    pending_output: Option<i64>,
+}
+
+impl Watchpoints {
+    pub fn set(&mut self, address: Pointer, watch: Watch) {
+        self.watched.insert(address, watch);
+    }
+
+    /**
+     * Keeps what is watched, but forgets everything about the current run.
+     */
+    pub fn restart(self) -> Self {
+        Self {
+            watched: self.watched,
+            ..Default::default()
+        }
+    }
+
+    pub fn remove(&mut self, address: Pointer) {
+        self.watched.remove(&address);
+    }
+
+    /**
+     * Only the first watched access of an instruction is kept.
+     */
+    #[inline]
+    pub fn check(&mut self, address: Pointer, access: Access, value: i64) {
+        if self.hit.is_none()
+            && self
+                .watched
+                .get(&address)
+                .is_some_and(|watch| watch.covers(access))
+        {
+            self.hit = Some(WatchHit {
+                address,
+                access,
+                value,
+            });
+        }
+    }
+
+    pub fn take_hit(&mut self) -> Option<WatchHit> {
+        self.hit.take()
+    }
+
+    pub fn hold_output(&mut self, value: i64) {
+        self.pending_output = Some(value);
+    }
+
+    pub fn take_pending_output(&mut self) -> Option<i64> {
+        self.pending_output.take()
+    }
+}
+
+/**
+ * The addresses of instructions to stop in front of.
+ */
+#[derive(Debug, Clone, Default)]
+pub(super) struct Breakpoints {
+    addresses: HashSet<Pointer>,
+    /** Reported already, so the next step executes it */
+    passed: Option<Pointer>,
+}
+
+impl Breakpoints {
+    pub fn set(&mut self, address: Pointer) {
+        self.addresses.insert(address);
+    }
+
+    pub fn remove(&mut self, address: Pointer) {
+        self.addresses.remove(&address);
+    }
+
+    pub fn restart(self) -> Self {
+        Self {
+            addresses: self.addresses,
+            passed: None,
+        }
+    }
+
+    /**
+     * Whether to stop in front of the instruction at `pointer`. Only once,
+     * until `executed` is called.
+     */
+    #[inline]
+    pub fn stops_at(&mut self, pointer: Pointer) -> bool {
+        if self.passed != Some(pointer) && self.addresses.contains(&pointer) {
+            self.passed = Some(pointer);
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    pub fn executed(&mut self) {
+        self.passed = None;
+    }
+}
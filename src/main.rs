@@ -2,86 +2,300 @@
 #![feature(slice_group_by)]
 #![feature(let_chains)]
 #![feature(associated_type_defaults)]
-use days::{day_provider, read_string, DayTrait, DayType, PartType, ResultType, UnitResult};
-use std::{env, time};
+// Only `common::unsafe_utils` may allow it again
+#![deny(unsafe_code)]
+use common::{
+    events::{self, Event},
+    viz::{self, set_default_theme, Theme},
+};
+use days::{
+    day_provider, read_string_of, set_data_dir, DayTrait, DayType, FormatPolicy, PartType, RResult,
+    ResultType, UnitResult, YearType, DEFAULT_YEAR,
+};
+use index::IndexFormat;
+use int_code::{
+    ascii::AsciiComputer,
+    io_log::{self, set_default_io_log, IoLog},
+    metrics::Metrics,
+    set_default_arithmetic, set_default_limits, terminal, Arithmetic, ComputerFactory, Limits,
+};
+use itertools::Itertools;
+use notify::{NotifyTarget, Summary};
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    sync::Mutex,
+    time,
+};
 
+mod artifacts;
+mod clipboard;
 mod common;
 mod days;
+mod index;
 mod int_code;
 mod macros;
+mod notify;
+mod redact;
 
-fn output(day: DayType, part: PartType, result: ResultType, time: time::Duration) {
-    match result {
-        ResultType::Integer(value) => {
-            println!(
-                "Day {:02} part {}: {} ({})",
-                day,
-                part,
-                value,
-                time.as_secs_f64()
-            );
-        }
-        ResultType::String(value) => {
-            println!(
-                "Day {:02} part {}: {} ({})",
-                day,
-                part,
-                value,
-                time.as_secs_f32()
-            );
-        }
-        ResultType::Lines(value) => {
-            println!(
-                "Day {:02} part {}: {} ({})",
-                day,
-                part,
-                value[0],
-                time.as_secs_f32()
-            );
-            for line in &value[1..] {
-                println!("               {line}");
-            }
-        }
-        ResultType::Nothing => {}
+/**
+ * `time` is `None` for a part that was solved together with the one before.
+ */
+fn output(
+    day: DayType,
+    label: &str,
+    result: ResultType,
+    time: Option<time::Duration>,
+    options: &Options,
+) {
+    let lines = result.format(options.format);
+    let Some((first, rest)) = lines.split_first() else {
+        return;
+    };
+    let time = match time {
+        Some(time) => time.as_secs_f64().to_string(),
+        None => "shared".to_owned(),
+    };
+    println!("Day {day:02} {label}: {first} ({time})");
+    for line in rest {
+        println!("               {line}");
     }
 }
 
-fn run_part(day: &dyn DayTrait, is_part1: bool, input: &str) -> anyhow::Result<time::Duration> {
+fn run_part(
+    day: &dyn DayTrait,
+    is_part1: bool,
+    input: &str,
+    options: &Options,
+) -> anyhow::Result<time::Duration> {
+    artifacts::take_recorded();
+    io_log::take_captured();
     let now = time::Instant::now();
     let result = if is_part1 {
-        day.part1(input)?
+        day.part1(input)
     } else {
-        day.part2(input)?
+        day.part2(input)
     };
+    let elapsed = now.elapsed();
+    report_part(day, is_part1, result, Some(elapsed), input, options)?;
+    Ok(elapsed)
+}
 
-    if matches!(result, ResultType::Nothing) {
-        Ok(time::Duration::ZERO)
-    } else {
-        let elapsed = now.elapsed();
+/**
+ * Prints the result of a part, or writes the artifacts of its failure.
+ */
+fn report_part(
+    day: &dyn DayTrait,
+    is_part1: bool,
+    result: RResult,
+    elapsed: Option<time::Duration>,
+    input: &str,
+    options: &Options,
+) -> anyhow::Result<()> {
+    let result = match result {
+        Ok(result) => result,
+        Err(error) if options.batch => {
+            let base = options
+                .artifacts_dir
+                .as_deref()
+                .unwrap_or(ARTIFACTS_DIR.as_ref());
+            let part = if is_part1 { 1 } else { 2 };
+            let io = io_log::take_captured();
+            if !io.is_empty() {
+                artifacts::record("io", io.iter().join("\n"));
+            }
+            match artifacts::write_failure(
+                base,
+                day.get_year(),
+                day.get_day_number(),
+                part,
+                input,
+                &error,
+            ) {
+                Ok(dir) => eprintln!("Failure artifacts written to {}", dir.display()),
+                Err(io_error) => eprintln!("Could not write failure artifacts: {io_error}"),
+            }
+            return Err(error);
+        }
+        Err(error) => return Err(error),
+    };
+
+    if !matches!(result, ResultType::Nothing) {
         output(
             day.get_day_number(),
-            if is_part1 { 1 } else { 2 },
+            &format!("part {}", if is_part1 { 1 } else { 2 }),
             result,
             elapsed,
+            options,
         );
-        Ok(elapsed)
     }
+    Ok(())
+}
+
+/**
+ * Runs both parts through `DayTrait::solve_both`, if the day has it.
+ */
+fn run_both(
+    day: &dyn DayTrait,
+    input: &str,
+    options: &Options,
+) -> Option<anyhow::Result<time::Duration>> {
+    artifacts::take_recorded();
+    io_log::take_captured();
+    let now = time::Instant::now();
+    let (result1, result2) = day.solve_both(input)?;
+    let elapsed = now.elapsed();
+    let reported1 = report_part(day, true, result1, Some(elapsed), input, options);
+    if reported1.is_err() && !options.batch {
+        return Some(reported1.map(|_| elapsed));
+    }
+    let reported2 = report_part(day, false, result2, None, input, options);
+    Some(reported1.and(reported2).map(|_| elapsed))
+}
+
+fn read_input(day: &dyn DayTrait, options: &Options) -> anyhow::Result<String> {
+    if options.clipboard {
+        Ok(clipboard::read()?)
+    } else {
+        Ok(read_string_of(
+            day.get_year(),
+            day.get_day_number(),
+            "input.txt",
+        )?)
+    }
+}
+
+fn run_extra(day: &dyn DayTrait, name: &str, options: &Options) -> anyhow::Result<()> {
+    let Some((_, solver)) = day.extras().into_iter().find(|(extra, _)| *extra == name) else {
+        let known = day.extras().into_iter().map(|(extra, _)| extra).join(", ");
+        Err(ParamError::UnknownExtra(name.to_owned(), known))?
+    };
+    let input = read_input(day, options)?;
+    let now = time::Instant::now();
+    let result = solver(&input)?;
+    output(
+        day.get_day_number(),
+        &format!("extra {name}"),
+        result,
+        Some(now.elapsed()),
+        options,
+    );
+    print_vm_stats(day, options);
+    Ok(())
 }
 
-fn run(day: &dyn DayTrait, part1: bool, part2: bool) -> anyhow::Result<time::Duration> {
-    let input = read_string(day.get_day_number(), "input.txt")?;
+/**
+ * Runs the input of the day as an ASCII program connected to the terminal.
+ */
+fn run_interactive(day: &dyn DayTrait, options: &Options) -> anyhow::Result<()> {
+    let input = read_input(day, options)?;
+    let mut computer = AsciiComputer::new(ComputerFactory::init(&input)?.build());
+    let stdin = io::stdin();
+    let echo = !stdin.is_terminal();
+    let end = terminal::play(&mut computer, stdin.lock(), io::stdout().lock(), echo)?;
+    if end == terminal::SessionEnd::EndOfInput {
+        eprintln!("Input ended, the program was still waiting");
+    }
+    Ok(())
+}
+
+/**
+ * What the computers did since the current day started, added up, with
+ * how many runs there were and how many of them halted. Only collected
+ * with `--vm-stats`.
+ */
+static VM_STATS: Mutex<Option<(usize, usize, Metrics)>> = Mutex::new(None);
+
+fn collect_vm_stats(event: &Event) {
+    if let Event::ComputerMetrics {
+        halted,
+        instructions,
+        inputs,
+        outputs,
+        memory_cells,
+    } = event
+    {
+        let mut stats = VM_STATS.lock().unwrap();
+        let (runs, halts, total) = stats.get_or_insert_default();
+        *runs += 1;
+        if *halted {
+            *halts += 1;
+        }
+        total.merge(&Metrics {
+            executed: *instructions,
+            inputs: *inputs,
+            outputs: *outputs,
+            memory_cells: *memory_cells,
+        });
+    }
+}
+
+fn print_vm_stats(day: &dyn DayTrait, options: &Options) {
+    if !options.vm_stats {
+        return;
+    }
+    if let Some((runs, halts, total)) = VM_STATS.lock().unwrap().take() {
+        println!(
+            "Day {:02} IntCode: {runs} run(s), {halts} halted, {total}",
+            day.get_day_number()
+        );
+    }
+}
+
+fn run(
+    day: &dyn DayTrait,
+    part1: bool,
+    part2: bool,
+    options: &Options,
+) -> anyhow::Result<time::Duration> {
+    let input = read_input(day, options)?;
+    VM_STATS.lock().unwrap().take();
+    let elapsed = run_parts(day, part1, part2, &input, options);
+    print_vm_stats(day, options);
+    elapsed
+}
+
+fn run_parts(
+    day: &dyn DayTrait,
+    part1: bool,
+    part2: bool,
+    input: &str,
+    options: &Options,
+) -> anyhow::Result<time::Duration> {
+    if part1
+        && part2
+        && let Some(elapsed) = run_both(day, input, options)
+    {
+        return elapsed;
+    }
     let elapsed1 = if part1 {
-        run_part(day, true, &input)?
+        run_part(day, true, input, options)
     } else {
-        time::Duration::ZERO
+        Ok(time::Duration::ZERO)
     };
+    // in batch mode part 2 still gets its own result and artifacts
+    if elapsed1.is_err() && !options.batch {
+        return elapsed1;
+    }
     let elapsed2 = if part2 {
-        run_part(day, false, &input)?
+        run_part(day, false, input, options)
     } else {
-        time::Duration::ZERO
+        Ok(time::Duration::ZERO)
     };
 
-    Ok(elapsed1 + elapsed2)
+    Ok(elapsed1? + elapsed2?)
+}
+
+/**
+ * A failing notification is reported but does not fail the run.
+ */
+fn notify(summary: &Summary, options: &Options) {
+    if let Some(target) = &options.notify
+        && let Err(error) = notify::send(target, summary)
+    {
+        eprintln!("Could not send the notification: {error}");
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -91,34 +305,314 @@ enum ParamError {
 
     #[error("Unknown Part: {0}")]
     UnknownPart(PartType),
+
+    #[error("Unknown Option: {0}")]
+    UnknownOption(String),
+
+    #[error("Missing value for Option: {0}")]
+    MissingValue(String),
+
+    #[error("Not a valid year: {0}")]
+    InvalidYear(String),
+
+    #[error("Data directory was already set")]
+    DataDirAlreadySet,
+
+    #[error("Unknown extra: {0} (known: {1})")]
+    UnknownExtra(String, String),
+
+    #[error("An extra needs exactly one day without a part")]
+    ExtraWithoutDay,
+
+    #[error("Reading the clipboard needs exactly one day")]
+    ClipboardWithoutDay,
+
+    #[error("Computer limits were already set")]
+    LimitsAlreadySet,
+
+    #[error(transparent)]
+    UnknownIndexFormat(#[from] index::UnknownFormat),
+
+    #[error(transparent)]
+    UnknownNotifyTarget(#[from] notify::UnknownTarget),
+
+    #[error(transparent)]
+    UnknownTheme(#[from] viz::UnknownTheme),
+
+    #[error("The theme was already set")]
+    ThemeAlreadySet,
+
+    #[error(transparent)]
+    UnknownIoLog(#[from] io_log::UnknownIoLog),
+
+    #[error("The I/O log was already set")]
+    IoLogAlreadySet,
+
+    #[error(transparent)]
+    UnknownArithmetic(#[from] int_code::UnknownArithmetic),
+
+    #[error("The arithmetic was already set")]
+    ArithmeticAlreadySet,
+
+    #[error("Interactive mode needs exactly one day without a part")]
+    InteractiveWithoutDay,
+
+    #[error("The index covers all days, no day can be given")]
+    IndexWithDay,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum RunError {
+    #[error("{0} day(s) failed")]
+    DaysFailed(usize),
+}
+
+const ARTIFACTS_DIR: &str = "artifacts";
+
+#[derive(Debug, Default)]
+struct Options {
+    format: FormatPolicy,
+    /** The event whose days run, the default year if not given */
+    year: Option<YearType>,
+    data_dir: Option<PathBuf>,
+    artifacts_dir: Option<PathBuf>,
+    extra: Option<String>,
+    /** Print an index of all days instead of running them */
+    index: Option<IndexFormat>,
+    /** Play the input of a day as an ASCII program on the terminal */
+    interactive: bool,
+    /** Read the input from the clipboard instead of the data directory */
+    clipboard: bool,
+    /** Applies to every IntCode computer, so a runaway program aborts the day */
+    limits: Limits,
+    /** Set when running all days, failures are then recorded and skipped */
+    batch: bool,
+    /** Who to tell about the results when the days are done */
+    notify: Option<NotifyTarget>,
+    /** Log the events days and computers publish to stderr */
+    events: bool,
+    /** Print what the IntCode computers of a day did after its results */
+    vm_stats: bool,
+    /** How days color what they render */
+    theme: Option<Theme>,
+    /** Where ASCII programs log what they print and are sent */
+    io_log: Option<IoLog>,
+    /** What every IntCode computer does when `ADD` or `MUL` overflow */
+    arithmetic: Option<Arithmetic>,
+}
+
+impl Options {
+    fn year(&self) -> YearType {
+        self.year.unwrap_or(DEFAULT_YEAR)
+    }
+
+    /**
+     * Days are given as positional parameters or with `--day`, both like
+     * `5` or `5/1`. A leading `run` is allowed as well, which is just what
+     * happens without it.
+     */
+    fn parse(params: &[String]) -> Result<(Self, Vec<String>), ParamError> {
+        let mut options = Options::default();
+        let mut positional = vec![];
+        let mut params = params.iter();
+        while let Some(param) = params.next() {
+            match param.as_str() {
+                "--separators" => options.format.thousands_separator = true,
+                "--uppercase" => options.format.uppercase_text = true,
+                "--redact" => options.format.redactor = Some(redact::salted()),
+                "--clipboard" => options.clipboard = true,
+                "--interactive" => options.interactive = true,
+                "--events" => options.events = true,
+                "--vm-stats" => options.vm_stats = true,
+                "--max-steps" => {
+                    let Some(steps) = params.next().and_then(|steps| steps.parse().ok()) else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.limits.max_steps = Some(steps);
+                }
+                "--timeout" => {
+                    let Some(seconds) = params.next().and_then(|seconds| {
+                        time::Duration::try_from_secs_f64(seconds.parse().ok()?).ok()
+                    }) else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.limits.timeout = Some(seconds);
+                }
+                "--year" => {
+                    let Some(year) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    let Ok(year) = year.parse() else {
+                        return Err(ParamError::InvalidYear(year.to_owned()));
+                    };
+                    options.year = Some(year);
+                }
+                "--day" => {
+                    let Some(day) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    positional.push(day.to_owned());
+                }
+                "--data-dir" => {
+                    let Some(dir) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.data_dir = Some(PathBuf::from(dir));
+                }
+                "--extra" => {
+                    let Some(name) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.extra = Some(name.to_owned());
+                }
+                "--index" => {
+                    let Some(format) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.index = Some(format.parse()?);
+                }
+                "--theme" => {
+                    let Some(theme) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.theme = Some(theme.parse()?);
+                }
+                "--arithmetic" => {
+                    let Some(arithmetic) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.arithmetic = Some(arithmetic.parse()?);
+                }
+                "--io-log" => {
+                    let Some(log) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.io_log = Some(log.parse()?);
+                }
+                "--notify" => {
+                    let Some(target) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.notify = Some(target.parse()?);
+                }
+                "--artifacts-dir" => {
+                    let Some(dir) = params.next() else {
+                        return Err(ParamError::MissingValue(param.to_owned()));
+                    };
+                    options.artifacts_dir = Some(PathBuf::from(dir));
+                }
+                option if option.starts_with("--") => {
+                    return Err(ParamError::UnknownOption(option.to_owned()))
+                }
+                "run" if positional.is_empty() => {}
+                _ => positional.push(param.to_owned()),
+            }
+        }
+        Ok((options, positional))
+    }
 }
 
 fn run_on_parameters(params: &[String]) -> UnitResult {
+    let (options, params) = Options::parse(params)?;
+    if let Some(dir) = &options.data_dir
+        && !set_data_dir(dir)
+    {
+        Err(ParamError::DataDirAlreadySet)?;
+    }
+    if options.limits != Limits::default() && !set_default_limits(options.limits) {
+        Err(ParamError::LimitsAlreadySet)?;
+    }
+    if let Some(theme) = options.theme
+        && !set_default_theme(theme)
+    {
+        Err(ParamError::ThemeAlreadySet)?;
+    }
+    if let Some(log) = options.io_log
+        && !set_default_io_log(log)
+    {
+        Err(ParamError::IoLogAlreadySet)?;
+    }
+    if let Some(arithmetic) = options.arithmetic
+        && !set_default_arithmetic(arithmetic)
+    {
+        Err(ParamError::ArithmeticAlreadySet)?;
+    }
+    if options.events {
+        events::bus().subscribe(|event| eprintln!("{event}"));
+    }
+    if options.vm_stats {
+        events::bus().subscribe(collect_vm_stats);
+    }
+    if options.clipboard && params.len() != 1 {
+        Err(ParamError::ClipboardWithoutDay)?;
+    }
+    if let Some(format) = options.index {
+        if !params.is_empty() {
+            Err(ParamError::IndexWithDay)?;
+        }
+        print!(
+            "{}",
+            index::render(&index::entries(options.year())?, format)
+        );
+        return Ok(());
+    }
+    if options.interactive {
+        let [day_str] = &params[..] else {
+            Err(ParamError::InteractiveWithoutDay)?
+        };
+        let day = day_provider::get_day(options.year(), day_str.parse()?)?;
+        return run_interactive(day.as_ref(), &options);
+    }
+    if let Some(name) = &options.extra {
+        let [day_str] = &params[..] else {
+            Err(ParamError::ExtraWithoutDay)?
+        };
+        let day = day_provider::get_day(options.year(), day_str.parse()?)?;
+        return run_extra(day.as_ref(), name, &options);
+    }
     match params.len() {
         0 => {
-            let mut runtime = time::Duration::ZERO;
-            for day in day_provider::get_all_days() {
-                runtime += run(day.as_ref(), true, true)?;
+            let options = Options {
+                batch: true,
+                ..options
+            };
+            io_log::capture_all();
+            let mut summary = Summary::new(options.year());
+            for day in day_provider::get_all_days(options.year())? {
+                let result = run(day.as_ref(), true, true, &options);
+                if let Err(error) = &result {
+                    eprintln!("Day {:02} failed: {error:#}", day.get_day_number());
+                }
+                summary.add(day.get_day_number(), &result);
             }
             println!();
-            println!("Runtime: {}", runtime.as_secs_f32());
+            println!("Runtime: {}", summary.runtime().as_secs_f32());
+            notify(&summary, &options);
+            let failed = summary.failed();
+            if failed > 0 {
+                Err(RunError::DaysFailed(failed))?;
+            }
         }
         1 => {
             let mut parts = params[0].split('/');
             if let Some(day_str) = parts.next() {
                 let day_number = day_str.parse::<DayType>()?;
-                let day = day_provider::get_day(day_number)?;
+                let day = day_provider::get_day(options.year(), day_number)?;
 
-                if let Some(part_str) = parts.next() {
+                let result = if let Some(part_str) = parts.next() {
                     match part_str.parse::<PartType>()? {
-                        1 => run(day.as_ref(), true, false)?,
-                        2 => run(day.as_ref(), false, true)?,
+                        1 => run(day.as_ref(), true, false, &options),
+                        2 => run(day.as_ref(), false, true, &options),
                         p => Err(ParamError::UnknownPart(p))?,
-                    };
+                    }
                 } else {
-                    let runtime = run(day.as_ref(), true, true)?;
-                    println!("Runtime: {}", runtime.as_secs_f32());
-                }
+                    run(day.as_ref(), true, true, &options)
+                        .inspect(|runtime| println!("Runtime: {}", runtime.as_secs_f32()))
+                };
+                let mut summary = Summary::new(options.year());
+                summary.add(day_number, &result);
+                notify(&summary, &options);
+                result?;
             }
         }
         n => Err(ParamError::TooManyParameters(n))?,
@@ -0,0 +1,220 @@
+use crate::days::{DayType, YearType};
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+#[cfg_attr(not(feature = "notify"), allow(dead_code))]
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[cfg_attr(feature = "notify", allow(dead_code))]
+    #[error("Notifications are not compiled in, build with --features notify")]
+    Disabled,
+
+    #[error("No notification tool found (tried {0})")]
+    NoTool(String),
+
+    #[error("{0} failed: {1}")]
+    ToolFailed(&'static str, String),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown notification target: {0} (known: desktop, http://…, https://…)")]
+pub struct UnknownTarget(String);
+
+/**
+ * Where to report that a run finished.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifyTarget {
+    Desktop,
+    /** The summary is posted as plain text */
+    Webhook(String),
+}
+
+impl FromStr for NotifyTarget {
+    type Err = UnknownTarget;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "desktop" {
+            Ok(NotifyTarget::Desktop)
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(NotifyTarget::Webhook(s.to_owned()))
+        } else {
+            Err(UnknownTarget(s.to_owned()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    Solved(Duration),
+    Failed(String),
+}
+
+/**
+ * How every day of a run went.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    year: YearType,
+    days: Vec<(DayType, Outcome)>,
+}
+
+impl Summary {
+    pub fn new(year: YearType) -> Self {
+        Self { year, days: vec![] }
+    }
+
+    pub fn add(&mut self, day: DayType, result: &anyhow::Result<Duration>) {
+        let outcome = match result {
+            Ok(elapsed) => Outcome::Solved(*elapsed),
+            Err(error) => Outcome::Failed(format!("{error:#}")),
+        };
+        self.days.push((day, outcome));
+    }
+
+    pub fn runtime(&self) -> Duration {
+        self.days
+            .iter()
+            .filter_map(|(_, outcome)| match outcome {
+                Outcome::Solved(elapsed) => Some(*elapsed),
+                Outcome::Failed(_) => None,
+            })
+            .sum()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.days
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, Outcome::Failed(_)))
+            .count()
+    }
+
+    #[cfg_attr(not(feature = "notify"), allow(dead_code))]
+    pub fn title(&self) -> String {
+        match self.failed() {
+            0 => format!("Advent {}: {} day(s) done", self.year, self.days.len()),
+            failed => format!(
+                "Advent {}: {failed} of {} day(s) failed",
+                self.year,
+                self.days.len()
+            ),
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Day  Time (s)  Result")?;
+        for (day, outcome) in &self.days {
+            match outcome {
+                Outcome::Solved(elapsed) => {
+                    writeln!(f, " {day:02}  {:8.3}  ok", elapsed.as_secs_f64())?
+                }
+                Outcome::Failed(error) => writeln!(f, " {day:02}  {:>8}  {error}", "-")?,
+            }
+        }
+        write!(f, "Runtime: {:.3}", self.runtime().as_secs_f64())
+    }
+}
+
+/**
+ * Tells the target that the run is over. Like the clipboard, this is done
+ * by external programs: `notify-send` or `osascript` for the desktop and
+ * `curl` for webhooks.
+ */
+#[cfg(feature = "notify")]
+pub fn send(target: &NotifyTarget, summary: &Summary) -> Result<(), NotifyError> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let title = summary.title();
+    let body = summary.to_string();
+    let run = |tool: &'static str, args: &[&str], stdin: Option<&str>| {
+        let mut child = Command::new(tool)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .ok()?;
+        if let Some(stdin) = stdin
+            && let Some(mut pipe) = child.stdin.take()
+        {
+            pipe.write_all(stdin.as_bytes()).ok()?;
+        }
+        let output = child.wait_with_output().ok()?;
+        Some(if output.status.success() {
+            Ok(())
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+            Err(NotifyError::ToolFailed(tool, error))
+        })
+    };
+
+    let result = match target {
+        NotifyTarget::Desktop => {
+            let script = format!(
+                "display notification {:?} with title {:?}",
+                body.replace('\n', " / "),
+                title
+            );
+            run("notify-send", &[&title, &body], None)
+                .or_else(|| run("osascript", &["-e", &script], None))
+                .ok_or_else(|| NotifyError::NoTool("notify-send, osascript".to_owned()))
+        }
+        NotifyTarget::Webhook(url) => {
+            let message = format!("{title}\n\n{body}\n");
+            run(
+                "curl",
+                &[
+                    "--silent",
+                    "--show-error",
+                    "--fail",
+                    "--max-time",
+                    "10",
+                    "--header",
+                    "Content-Type: text/plain; charset=utf-8",
+                    "--data-binary",
+                    "@-",
+                    url,
+                ],
+                Some(&message),
+            )
+            .ok_or_else(|| NotifyError::NoTool("curl".to_owned()))
+        }
+    };
+    result?
+}
+
+#[cfg(not(feature = "notify"))]
+pub fn send(_target: &NotifyTarget, _summary: &Summary) -> Result<(), NotifyError> {
+    Err(NotifyError::Disabled)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut summary = Summary::new(2019);
+        summary.add(1, &Ok(Duration::from_millis(1500)));
+        summary.add(18, &Err(anyhow::anyhow!("no path")));
+        summary.add(20, &Ok(Duration::from_millis(250)));
+
+        assert_eq!(summary.failed(), 1);
+        assert_eq!(summary.runtime(), Duration::from_millis(1750));
+        assert_eq!(summary.title(), "Advent 2019: 1 of 3 day(s) failed");
+        assert_eq!(
+            summary.to_string(),
+            "Day  Time (s)  Result\n 01     1.500  ok\n 18         -  no path\n 20     0.250  ok\nRuntime: 1.750"
+        );
+
+        assert_eq!(
+            "desktop".parse::<NotifyTarget>().ok(),
+            Some(NotifyTarget::Desktop)
+        );
+        assert!("ftp://example.com".parse::<NotifyTarget>().is_err());
+    }
+}
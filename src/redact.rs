@@ -0,0 +1,46 @@
+use crate::days::Redactor;
+use std::{collections::hash_map::RandomState, hash::BuildHasher, sync::OnceLock};
+
+/**
+ * Redacts answers with SipHash under a random key that is drawn once per
+ * run and never shown. Equal answers look the same within one run, but
+ * without the key nobody can hash small numbers until one matches.
+ */
+#[derive(Debug)]
+pub struct SaltedHash(RandomState);
+
+impl SaltedHash {
+    fn new() -> Self {
+        Self(RandomState::new())
+    }
+}
+
+impl Redactor for SaltedHash {
+    fn redact(&self, answer: &str) -> String {
+        format!("<redacted {:016x}>", self.0.hash_one(answer))
+    }
+}
+
+/**
+ * The redactor of this run.
+ */
+pub fn salted() -> &'static SaltedHash {
+    static SALTED: OnceLock<SaltedHash> = OnceLock::new();
+    SALTED.get_or_init(SaltedHash::new)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn salted_hash() {
+        let redactor = salted();
+        let hidden = redactor.redact("1234567");
+        assert!(!hidden.contains("1234567"));
+        assert_eq!(hidden, salted().redact("1234567"));
+        assert_ne!(hidden, redactor.redact("1234568"));
+
+        assert_ne!(hidden, SaltedHash::new().redact("1234567"));
+    }
+}